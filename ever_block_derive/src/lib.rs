@@ -0,0 +1,138 @@
+/*
+* Copyright (C) 2019-2023 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    parse_macro_input, parse_quote, Data, DeriveInput, Error, Fields, Generics, Index, LitInt,
+    Member, Result,
+};
+
+/// Derives `Serializable` and `Deserializable` for structs written as plain sequence of fields.
+/// Optional constructor tag is written before the fields: `#[tlb(tag = 0xcc26, bits = 16)]`.
+/// Struct must implement `Default` to be deserialized.
+#[proc_macro_derive(TlbSerializable, attributes(tlb))]
+pub fn derive_tlb_serializable(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input).unwrap_or_else(Error::into_compile_error).into()
+}
+
+struct Tag {
+    value: u32,
+    bits: usize,
+}
+
+fn parse_tag(input: &DeriveInput) -> Result<Option<Tag>> {
+    let mut value = None;
+    let mut bits = None;
+    for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("tlb")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("tag") {
+                value = Some(meta.value()?.parse::<LitInt>()?.base10_parse::<u32>()?);
+            } else if meta.path.is_ident("bits") {
+                bits = Some(meta.value()?.parse::<LitInt>()?.base10_parse::<usize>()?);
+            } else {
+                return Err(meta.error("expected `tag` or `bits`"))
+            }
+            Ok(())
+        })?;
+    }
+    match (value, bits) {
+        (None, None) => Ok(None),
+        (Some(value), Some(bits)) => {
+            if bits == 0 || bits > 32 {
+                return Err(Error::new_spanned(&input.ident, "tag length must be from 1 to 32 bits"))
+            }
+            if bits < 32 && value >> bits != 0 {
+                return Err(Error::new_spanned(&input.ident, format!("tag {:#x} doesn't fit into {} bits", value, bits)))
+            }
+            Ok(Some(Tag { value, bits }))
+        }
+        _ => Err(Error::new_spanned(&input.ident, "both `tag` and `bits` must be set"))
+    }
+}
+
+fn add_bound(generics: &Generics, bound: TokenStream2) -> Generics {
+    let mut generics = generics.clone();
+    for param in generics.type_params_mut() {
+        param.bounds.push(parse_quote!(#bound));
+    }
+    generics
+}
+
+fn expand(input: DeriveInput) -> Result<TokenStream2> {
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => return Err(Error::new_spanned(&input.ident, "TlbSerializable can be derived only for structs"))
+    };
+    let members: Vec<(Member, String)> = match fields {
+        Fields::Named(fields) => fields.named.iter().map(|field| {
+            let ident = field.ident.clone().expect("named field");
+            let name = ident.to_string();
+            (Member::Named(ident), name)
+        }).collect(),
+        Fields::Unnamed(fields) => (0..fields.unnamed.len()).map(|i| {
+            (Member::Unnamed(Index::from(i)), i.to_string())
+        }).collect(),
+        Fields::Unit => Vec::new(),
+    };
+    let tag = parse_tag(&input)?;
+
+    let ident = &input.ident;
+    let type_name = ident.to_string();
+
+    let write_tag = tag.as_ref().map(|Tag { value, bits }| quote! {
+        ::ever_block::IBitstring::append_bits(cell, #value as usize, #bits)?;
+    });
+    let write_fields = members.iter().map(|(member, _)| quote! {
+        ::ever_block::Serializable::write_to(&self.#member, cell)?;
+    });
+    let read_tag = tag.as_ref().map(|Tag { value, bits }| quote! {
+        let tag = slice.get_next_int(#bits)? as u32;
+        if tag != #value {
+            return Err(::ever_block::BlockError::InvalidConstructorTag {
+                t: tag,
+                s: std::any::type_name::<Self>().to_string()
+            }.into())
+        }
+    });
+    let read_fields = members.iter().map(|(member, name)| quote! {
+        ::ever_block::WithDeserializeContext::with_ctx(
+            ::ever_block::Deserializable::read_from(&mut self.#member, slice), #type_name, #name
+        )?;
+    });
+
+    let ser_generics = add_bound(&input.generics, quote!(::ever_block::Serializable));
+    let (ser_impl, ser_ty, ser_where) = ser_generics.split_for_impl();
+    let de_generics = add_bound(&input.generics, quote!(::ever_block::Deserializable));
+    let (de_impl, de_ty, de_where) = de_generics.split_for_impl();
+
+    Ok(quote! {
+        impl #ser_impl ::ever_block::Serializable for #ident #ser_ty #ser_where {
+            fn write_to(&self, cell: &mut ::ever_block::BuilderData) -> ::ever_block::Result<()> {
+                #write_tag
+                #(#write_fields)*
+                Ok(())
+            }
+        }
+
+        impl #de_impl ::ever_block::Deserializable for #ident #de_ty #de_where {
+            fn read_from(&mut self, slice: &mut ::ever_block::SliceData) -> ::ever_block::Result<()> {
+                #read_tag
+                #(#read_fields)*
+                Ok(())
+            }
+        }
+    })
+}
@@ -0,0 +1,62 @@
+/*
+* Copyright (C) 2019-2023 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+//! Prints accounts difference between two shard states.
+//!
+//! cargo run --example diff_states -- <old_state.boc> <new_state.boc>
+
+use ever_block::{
+    fail, read_single_root_boc, Deserializable, Result, ShardStateUnsplit,
+};
+
+fn read_state(path: &str) -> Result<ShardStateUnsplit> {
+    let root = read_single_root_boc(std::fs::read(path)?)?;
+    ShardStateUnsplit::construct_from_cell(root)
+}
+
+fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.len() != 2 {
+        fail!("usage: diff_states <old_state.boc> <new_state.boc>")
+    }
+    let old_state = read_state(&args[0])?;
+    let new_state = read_state(&args[1])?;
+    println!("old: {} seq_no {}", old_state.shard(), old_state.seq_no());
+    println!("new: {} seq_no {}", new_state.shard(), new_state.seq_no());
+
+    let (mut added, mut removed, mut changed) = (0, 0, 0);
+    old_state.read_accounts()?.scan_diff_with_aug(&new_state.read_accounts()?, |id, old, new| {
+        match (old, new) {
+            (None, Some((_, aug))) => {
+                added += 1;
+                println!("+ {:x} balance {}", id, aug.balance());
+            }
+            (Some((_, aug)), None) => {
+                removed += 1;
+                println!("- {:x} balance {}", id, aug.balance());
+            }
+            (Some((old, old_aug)), Some((new, new_aug))) => {
+                changed += 1;
+                println!(
+                    "* {:x} balance {} -> {} last_trans_lt {} -> {}",
+                    id, old_aug.balance(), new_aug.balance(), old.last_trans_lt(), new.last_trans_lt()
+                );
+            }
+            (None, None) => ()
+        }
+        Ok(true)
+    })?;
+    println!("added: {}, removed: {}, changed: {}", added, removed, changed);
+    println!("total balance: {} -> {}", old_state.total_balance(), new_state.total_balance());
+    Ok(())
+}
@@ -0,0 +1,55 @@
+/*
+* Copyright (C) 2019-2023 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+//! Dumps block from BOC file as JSON together with its transactions and messages.
+//!
+//! cargo run --example dump_block --features json -- src/tests/data/<block>.boc
+
+use ever_block::{
+    fail, read_single_root_boc, Block, Deserializable, HashmapAugType, Result,
+};
+
+fn dump_block(path: &str) -> Result<()> {
+    let root = read_single_root_boc(std::fs::read(path)?)?;
+    let block = Block::construct_from_cell(root)?;
+
+    let mut transactions = vec!();
+    let extra = block.read_extra()?;
+    extra.read_account_blocks()?.iterate_objects(|account_block| {
+        account_block.transaction_iterate(|tr| {
+            transactions.push(serde_json::to_value(&tr)?);
+            Ok(true)
+        })?;
+        Ok(true)
+    })?;
+
+    let mc_extra = extra.read_custom()?;
+    let json = serde_json::json!({
+        "block": block,
+        "mc_block_extra": mc_extra,
+        "transactions": transactions,
+    });
+    println!("{}", serde_json::to_string_pretty(&json)?);
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.is_empty() {
+        fail!("usage: dump_block <block.boc>...")
+    }
+    for path in &args {
+        dump_block(path)?;
+    }
+    Ok(())
+}
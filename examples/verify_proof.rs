@@ -0,0 +1,65 @@
+/*
+* Copyright (C) 2019-2023 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+//! Builds Merkle proofs for every transaction of the block and checks them
+//! the same way light client does - against block root hash only.
+//!
+//! cargo run --example verify_proof -- src/tests/data/<block>.boc
+
+use ever_block::{
+    check_block_info_proof, check_transaction_proof, fail, read_single_root_boc, write_boc,
+    Block, Deserializable, HashmapAugType, MerkleProof, Result, Serializable, UsageTree,
+};
+
+fn verify_block(path: &str) -> Result<()> {
+    let root = read_single_root_boc(std::fs::read(path)?)?;
+    let block_id = root.repr_hash();
+    let block = Block::construct_from_cell(root.clone())?;
+
+    // block info proof
+    let usage_tree = UsageTree::with_root(root.clone());
+    Block::construct_from_cell(usage_tree.root_cell())?.read_info()?;
+    let proof = MerkleProof::create_by_usage_tree(&root, usage_tree)?;
+    let proof_block = Block::construct_from_cell(proof.proof.clone())?;
+    let info = check_block_info_proof(&proof_block, &proof.hash, &block_id)?;
+    println!("block {:x} seq_no {} shard {}", block_id, info.seq_no(), info.shard());
+
+    // transaction proofs
+    let mut count = 0;
+    block.read_extra()?.read_account_blocks()?.iterate_objects(|account_block| {
+        account_block.transaction_iterate(|tr| {
+            let proof = block.build_transaction_proof(tr.account_id(), tr.logical_time())?;
+            // the proof travels as BOC
+            let boc = write_boc(&proof.serialize()?)?;
+            let proof = MerkleProof::construct_from_bytes(&boc)?;
+            check_transaction_proof(&proof, &tr, &block_id)?;
+            println!("  transaction {:x} lt {} proof {} bytes: OK", tr.account_id(), tr.logical_time(), boc.len());
+            count += 1;
+            Ok(true)
+        })?;
+        Ok(true)
+    })?;
+    println!("  {} transaction proofs checked", count);
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.is_empty() {
+        fail!("usage: verify_proof <block.boc>...")
+    }
+    for path in &args {
+        verify_block(path)?;
+    }
+    Ok(())
+}
@@ -113,6 +113,39 @@ impl StorageUsed {
         Ok(used)
     }
 
+    /// Recomputes storage used by the account walking its storage cells: every unique cell
+    /// is counted once, public cells are unique cells of public libraries of the account
+    pub fn compute_for_account(account: &Account) -> Result<StorageUsed> {
+        let stuff = match account.stuff() {
+            Some(stuff) => stuff,
+            None => return Ok(Self::default())
+        };
+        let mut used = Self::calculate_for_struct(&stuff.storage)?;
+        if let Some(state_init) = account.state_init() {
+            used.public_cells = Self::count_public_cells(state_init)?;
+        }
+        Ok(used)
+    }
+
+    /// Recomputes storage used by the state init the same way as for account
+    pub fn compute_for_state_init(state_init: &StateInit) -> Result<StorageUsed> {
+        let mut used = Self::calculate_for_struct(state_init)?;
+        used.public_cells = Self::count_public_cells(state_init)?;
+        Ok(used)
+    }
+
+    fn count_public_cells(state_init: &StateInit) -> Result<VarUInteger7> {
+        let mut public = Self::default();
+        let mut hashes = HashSet::new();
+        state_init.library.iterate(|lib| {
+            if lib.public {
+                public.calculate_for_cell(&mut hashes, &lib.root);
+            }
+            Ok(true)
+        })?;
+        Ok(public.cells)
+    }
+
     fn calculate_for_cell(&mut self, hashes: &mut HashSet<UInt256>, cell: &Cell) {
         if hashes.insert(cell.repr_hash()) {
             self.cells.add_checked(1);
@@ -200,6 +233,13 @@ impl StorageUsedShort {
     pub fn append(&mut self, root_cell: &Cell) {
         Self::calculate_for_cell(self, &mut HashSet::new(), root_cell);
     }
+
+    /// Storage of the message paid with forwarding fees: root cell is not counted
+    pub fn calculate_for_message_cell(root_cell: &Cell) -> Result<StorageUsedShort> {
+        let mut used = Self::default();
+        used.append(root_cell);
+        Self::with_values_checked(used.cells() - 1, used.bits() - root_cell.bit_length() as u64)
+    }
 }
 
 impl Serializable for StorageUsedShort {
@@ -782,6 +822,20 @@ impl Account {
         }
     }
 
+    /// Checks cells and bits of the stored storage stat against recomputed ones,
+    /// public cells are not checked because update_storage_stat doesn't count them
+    pub fn check_storage_used(&self) -> Result<()> {
+        if let Some(info) = self.storage_info() {
+            let used = StorageUsed::compute_for_account(self)?;
+            if used.cells() != info.used.cells() || used.bits() != info.used.bits() {
+                fail!(BlockError::InvalidData(format!(
+                    "account storage stat {} doesn't match recomputed {}", info.used, used
+                )))
+            }
+        }
+        Ok(())
+    }
+
     #[cfg(test)]
     /// getting statistic using storage for calculate storage/transfer fee
     fn get_storage_stat(&self) -> Result<StorageUsed> {
@@ -0,0 +1,224 @@
+/*
+* Copyright (C) 2019-2023 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+//! Generators of structurally valid values for property tests and fuzzing
+
+use crate::{
+    accounts::AccountStatus,
+    bintree::BinTree,
+    blocks::BlockIdExt,
+    common_message::CommonMessage,
+    master::{FutureSplitMerge, McBlockExtra, ShardDescr},
+    messages::{
+        ExtOutMessageHeader, ExternalInboundMessageHeader, InternalMessageHeader, Message,
+        MsgAddressExt, MsgAddressInt,
+    },
+    shard::{ShardIdent, MASTERCHAIN_ID, MAX_SPLIT_DEPTH},
+    transactions::{HashUpdate, Transaction, TransactionDescr},
+    types::{CurrencyCollection, Grams, InRefValue},
+    AccountId, BuilderData, SliceData, UInt256,
+};
+use ::arbitrary::{Arbitrary, Error, Result, Unstructured};
+
+#[cfg(test)]
+#[path = "tests/test_arbitrary_types.rs"]
+mod tests;
+
+const MAX_BODY_BITS: usize = 800;
+const MAX_OTHER_CURRENCIES: usize = 3;
+const MAX_OUT_MSGS: usize = 3;
+
+fn check<T>(result: crate::Result<T>) -> Result<T> {
+    result.map_err(|_| Error::IncorrectFormat)
+}
+
+fn arbitrary_workchain_id(u: &mut Unstructured) -> Result<i32> {
+    if u.ratio(1, 4)? {
+        Ok(MASTERCHAIN_ID)
+    } else {
+        u.int_in_range(0..=i8::MAX as i32)
+    }
+}
+
+fn arbitrary_address(u: &mut Unstructured) -> Result<MsgAddressInt> {
+    let workchain_id = arbitrary_workchain_id(u)? as i8;
+    let address = AccountId::from(u.arbitrary::<[u8; 32]>()?);
+    check(MsgAddressInt::with_standart(None, workchain_id, address))
+}
+
+fn arbitrary_body(u: &mut Unstructured) -> Result<Option<SliceData>> {
+    if !u.arbitrary()? {
+        return Ok(None)
+    }
+    let bits = u.int_in_range(0..=MAX_BODY_BITS)?;
+    let data = u.bytes((bits + 7) / 8)?.to_vec();
+    let builder = check(BuilderData::with_raw(data, bits))?;
+    check(SliceData::load_builder(builder)).map(Some)
+}
+
+impl<'a> Arbitrary<'a> for UInt256 {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        u.arbitrary::<[u8; 32]>().map(UInt256::from)
+    }
+}
+
+impl<'a> Arbitrary<'a> for ShardIdent {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let workchain_id = arbitrary_workchain_id(u)?;
+        if workchain_id == MASTERCHAIN_ID {
+            return Ok(ShardIdent::masterchain())
+        }
+        let prefix_len = u.int_in_range(0..=MAX_SPLIT_DEPTH)?;
+        check(ShardIdent::with_prefix_len(prefix_len, workchain_id, u.arbitrary()?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for BlockIdExt {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(BlockIdExt::with_params(u.arbitrary()?, u.arbitrary()?, u.arbitrary()?, u.arbitrary()?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for CurrencyCollection {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut value = CurrencyCollection::with_grams(u.arbitrary()?);
+        for _ in 0..u.int_in_range(0..=MAX_OTHER_CURRENCIES)? {
+            let other = u.int_in_range(1..=u128::MAX)?;
+            check(value.set_other(u.arbitrary()?, other))?;
+        }
+        Ok(value)
+    }
+}
+
+impl<'a> Arbitrary<'a> for FutureSplitMerge {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(match u.int_in_range(0..=2)? {
+            0 => FutureSplitMerge::None,
+            1 => FutureSplitMerge::Split { split_utime: u.arbitrary()?, interval: u.arbitrary()? },
+            _ => FutureSplitMerge::Merge { merge_utime: u.arbitrary()?, interval: u.arbitrary()? },
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for ShardDescr {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let start_lt = u.arbitrary::<u64>()? >> 1;
+        let end_lt = start_lt + u.arbitrary::<u32>()? as u64;
+        let mut descr = ShardDescr::with_params(
+            u.arbitrary()?, start_lt, end_lt, u.arbitrary()?, u.arbitrary()?
+        );
+        descr.reg_mc_seqno = u.arbitrary()?;
+        descr.file_hash = u.arbitrary()?;
+        match u.int_in_range(0..=2)? {
+            0 => (),
+            1 => descr.before_split = true,
+            _ => descr.before_merge = true,
+        }
+        descr.want_split = u.arbitrary()?;
+        descr.want_merge = !descr.want_split && u.arbitrary()?;
+        descr.nx_cc_updated = u.arbitrary()?;
+        descr.next_catchain_seqno = u.arbitrary()?;
+        descr.next_validator_shard = u.arbitrary::<ShardIdent>()?.shard_prefix_with_tag();
+        descr.min_ref_mc_seqno = u.arbitrary()?;
+        descr.gen_utime = u.arbitrary()?;
+        descr.fees_collected = u.arbitrary()?;
+        descr.funds_created = u.arbitrary()?;
+        Ok(descr)
+    }
+}
+
+impl<'a> Arbitrary<'a> for Message {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut msg = match u.int_in_range(0..=2)? {
+            0 => {
+                let mut header = InternalMessageHeader::with_addresses_and_bounce(
+                    arbitrary_address(u)?,
+                    arbitrary_address(u)?,
+                    u.arbitrary()?,
+                    u.arbitrary()?,
+                );
+                header.bounced = header.bounce && u.arbitrary()?;
+                header.fwd_fee = Grams::from(u.arbitrary::<u64>()?);
+                let mut msg = Message::with_int_header(header);
+                msg.set_at_and_lt(u.arbitrary()?, u.arbitrary()?);
+                msg
+            }
+            1 => Message::with_ext_in_header(ExternalInboundMessageHeader {
+                src: MsgAddressExt::AddrNone,
+                dst: arbitrary_address(u)?,
+                import_fee: Grams::from(u.arbitrary::<u64>()?),
+            }),
+            _ => {
+                let header = ExtOutMessageHeader::with_addresses(arbitrary_address(u)?, MsgAddressExt::AddrNone);
+                let mut msg = Message::with_ext_out_header(header);
+                msg.set_at_and_lt(u.arbitrary()?, u.arbitrary()?);
+                msg
+            }
+        };
+        if let Some(body) = arbitrary_body(u)? {
+            msg.set_body(body);
+        }
+        Ok(msg)
+    }
+}
+
+impl<'a> Arbitrary<'a> for AccountStatus {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(match u.int_in_range(0..=3)? {
+            0 => AccountStatus::AccStateUninit,
+            1 => AccountStatus::AccStateFrozen,
+            2 => AccountStatus::AccStateActive,
+            _ => AccountStatus::AccStateNonexist,
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for Transaction {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let account_addr = AccountId::from(u.arbitrary::<[u8; 32]>()?);
+        let mut tr = Transaction::with_address_and_status(account_addr, u.arbitrary()?);
+        check(tr.write_description(&TransactionDescr::default()))?;
+        if u.arbitrary()? {
+            check(tr.write_in_msg(Some(&CommonMessage::Std(u.arbitrary()?))))?;
+        }
+        for _ in 0..u.int_in_range(0..=MAX_OUT_MSGS)? {
+            check(tr.add_out_message(&CommonMessage::Std(u.arbitrary()?)))?;
+        }
+        tr.set_logical_time(u.arbitrary()?);
+        tr.set_now(u.arbitrary()?);
+        tr.set_prev_trans_hash(u.arbitrary()?);
+        tr.set_prev_trans_lt(u.arbitrary()?);
+        tr.set_end_status(u.arbitrary()?);
+        tr.set_total_fees(u.arbitrary()?);
+        check(tr.write_state_update(&HashUpdate::with_hashes(u.arbitrary()?, u.arbitrary()?)))?;
+        Ok(tr)
+    }
+}
+
+impl<'a> Arbitrary<'a> for McBlockExtra {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut extra = McBlockExtra::default();
+        let workchains = u.int_in_range(1..=2)?;
+        for workchain_id in 0..workchains {
+            let tree = check(BinTree::with_item(&u.arbitrary::<ShardDescr>()?))?;
+            check(extra.shards.set(&workchain_id, &InRefValue(tree)))?;
+            let shard = check(ShardIdent::with_workchain_id(workchain_id))?;
+            if u.arbitrary()? {
+                let (left, right) = (u.arbitrary::<ShardDescr>()?, u.arbitrary::<ShardDescr>()?);
+                check(extra.shards.split_shard(&shard, |_| Ok((left, right))))?;
+            }
+            check(extra.fees.store_shard_fees(&shard, u.arbitrary()?, u.arbitrary()?))?;
+        }
+        Ok(extra)
+    }
+}
@@ -12,10 +12,10 @@
 
 
 use crate::{
-    fail, error::BlockError,
+    error, fail, error::BlockError, shard::ShardIdent,
     Augmentable, BuilderData, Cell, Deserializable, IBitstring, Result, Serializable, SliceData
 };
-use std::marker::PhantomData;
+use std::{collections::HashMap, marker::PhantomData};
 
 #[cfg(test)]
 #[path = "tests/test_bintree.rs"]
@@ -265,6 +265,43 @@ impl<X: Default + Serializable + Deserializable> BinTree<X> {
         })
     }
 
+    /// Builds the tree in one pass with leaves for given shards of one workchain,
+    /// shards must cover the whole workchain without overlapping
+    pub fn from_map(mut items: HashMap<ShardIdent, X>) -> Result<Self> {
+        let workchain_id = items.keys().next()
+            .ok_or_else(|| error!(BlockError::InvalidArg("no shards are given".to_string())))?
+            .workchain_id();
+        if let Some(shard) = items.keys().find(|shard| shard.workchain_id() != workchain_id) {
+            fail!(BlockError::InvalidArg(format!(
+                "shard {} is not in the workchain {}", shard, workchain_id
+            )))
+        }
+        let builder = Self::build_subtree(&mut items, ShardIdent::full(workchain_id))?;
+        if let Some(shard) = items.keys().next() {
+            fail!(BlockError::InvalidArg(format!("shard {} overlaps with other shards", shard)))
+        }
+        Ok(Self {
+            data: SliceData::load_builder(builder)?,
+            phantom: PhantomData::<X>,
+        })
+    }
+
+    fn build_subtree(items: &mut HashMap<ShardIdent, X>, shard: ShardIdent) -> Result<BuilderData> {
+        if let Some(value) = items.remove(&shard) {
+            let mut leaf = false.write_to_new_cell()?;
+            value.write_to(&mut leaf)?;
+            return Ok(leaf)
+        }
+        if items.is_empty() || !shard.can_split() {
+            fail!(BlockError::InvalidArg(format!("shard {} is not covered by given shards", shard)))
+        }
+        let (left, right) = shard.split()?;
+        let mut fork = true.write_to_new_cell()?;
+        fork.checked_append_reference(Self::build_subtree(items, left)?.into_cell()?)?;
+        fork.checked_append_reference(Self::build_subtree(items, right)?.into_cell()?)?;
+        Ok(fork)
+    }
+
     /// Splits item by calling splitter function, returns false if item was not found
     pub fn split(
         &mut self,
@@ -279,6 +316,21 @@ impl<X: Default + Serializable + Deserializable> BinTree<X> {
         }
     }
 
+    /// Same as `split` but fails if depth of the new items would exceed `max_depth`
+    pub fn split_with_max_depth(
+        &mut self,
+        key: SliceData,
+        max_depth: usize,
+        splitter: impl FnOnce(X) -> Result<(X, X)>
+    ) -> Result<bool> {
+        if key.remaining_bits() >= max_depth {
+            fail!(BlockError::InvalidArg(format!(
+                "item at depth {} can't be split: max depth is {}", key.remaining_bits(), max_depth
+            )))
+        }
+        self.split(key, splitter)
+    }
+
     /// Merge 2 items in fork by calling merger function, returns false if fork was not found
     pub fn merge(
         &mut self,
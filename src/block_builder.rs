@@ -0,0 +1,246 @@
+/*
+* Copyright (C) 2019-2023 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use crate::{
+    blocks::{Block, BlockExtra, BlockIdExt, BlockInfo, ValueFlow},
+    config_params::{BlockLimits, ConfigParams, ParamLimitIndex},
+    error::BlockError,
+    inbound_messages::{InMsg, InMsgDescr},
+    master::McBlockExtra,
+    merkle_update::MerkleUpdate,
+    outbound_messages::{OutMsg, OutMsgDescr},
+    signature::BlockProof,
+    transactions::{ShardAccountBlocks, Transaction},
+    write_boc, AccountId, Cell, Result, SerdeContext, Serializable, UInt256, error, fail,
+};
+use std::collections::HashMap;
+
+#[cfg(test)]
+#[path = "tests/test_block_builder.rs"]
+mod tests;
+
+/// Resources used by the block being built
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct BlockUsage {
+    /// Estimated size of the block data: sum of bits of all added items without deduplication
+    pub bytes: u64,
+    pub gas: u64,
+    pub lt_delta: u64,
+}
+
+/// Serialized block with its id and proof without signatures
+#[derive(Clone, Debug)]
+pub struct BuiltBlock {
+    pub block: Block,
+    pub root: Cell,
+    pub boc: Vec<u8>,
+    pub id: BlockIdExt,
+    pub proof: BlockProof,
+}
+
+/// Accumulates parts of the block the way collator produces them:
+/// transactions go into account blocks, messages into descriptors.
+/// Logical time of transactions must grow for every account and
+/// block usage must stay below hard limits from ConfigParam 22/23.
+#[derive(Clone, Debug)]
+pub struct BlockBuilder {
+    ctx: SerdeContext,
+    global_id: i32,
+    info: BlockInfo,
+    limits: BlockLimits,
+    value_flow: ValueFlow,
+    in_msg_descr: InMsgDescr,
+    out_msg_descr: OutMsgDescr,
+    account_blocks: ShardAccountBlocks,
+    state_update: Option<MerkleUpdate>,
+    mc_extra: Option<McBlockExtra>,
+    rand_seed: UInt256,
+    created_by: UInt256,
+    last_lts: HashMap<AccountId, u64>,
+    end_lt: u64,
+    usage: BlockUsage,
+}
+
+impl BlockBuilder {
+    /// Limits are taken from the config for masterchain or basechain depending on the shard
+    pub fn with_config(global_id: i32, info: BlockInfo, config: &ConfigParams) -> Result<Self> {
+        let limits = config.block_limits(info.shard().is_masterchain())?;
+        Ok(Self::with_limits(global_id, info, limits))
+    }
+
+    pub fn with_limits(global_id: i32, info: BlockInfo, limits: BlockLimits) -> Self {
+        Self::with_serde_context(SerdeContext::empty(), global_id, info, limits)
+    }
+
+    pub fn with_serde_context(ctx: SerdeContext, global_id: i32, info: BlockInfo, limits: BlockLimits) -> Self {
+        let end_lt = info.start_lt();
+        Self {
+            ctx,
+            global_id,
+            info,
+            limits,
+            value_flow: ValueFlow::default(),
+            in_msg_descr: InMsgDescr::with_serde_opts(ctx.opts()),
+            out_msg_descr: OutMsgDescr::with_serde_opts(ctx.opts()),
+            account_blocks: ShardAccountBlocks::with_serde_opts(ctx.opts()),
+            state_update: None,
+            mc_extra: None,
+            rand_seed: UInt256::default(),
+            created_by: UInt256::default(),
+            last_lts: HashMap::new(),
+            end_lt,
+            usage: BlockUsage::default(),
+        }
+    }
+
+    pub fn info(&self) -> &BlockInfo { &self.info }
+    pub fn info_mut(&mut self) -> &mut BlockInfo { &mut self.info }
+
+    pub fn limits(&self) -> &BlockLimits { &self.limits }
+    pub fn usage(&self) -> &BlockUsage { &self.usage }
+
+    /// Logical time after the last added transaction and its outbound messages
+    pub fn end_lt(&self) -> u64 { self.end_lt }
+
+    pub fn value_flow(&self) -> &ValueFlow { &self.value_flow }
+    pub fn value_flow_mut(&mut self) -> &mut ValueFlow { &mut self.value_flow }
+    pub fn set_value_flow(&mut self, value_flow: ValueFlow) { self.value_flow = value_flow }
+
+    pub fn set_state_update(&mut self, state_update: MerkleUpdate) { self.state_update = Some(state_update) }
+    pub fn set_mc_extra(&mut self, mc_extra: McBlockExtra) { self.mc_extra = Some(mc_extra) }
+    pub fn set_rand_seed(&mut self, rand_seed: UInt256) { self.rand_seed = rand_seed }
+    pub fn set_created_by(&mut self, created_by: UInt256) { self.created_by = created_by }
+
+    /// The highest level of limits reached by any of block parameters
+    pub fn limits_level(&self) -> ParamLimitIndex {
+        Self::classify(&self.limits, &self.usage)
+    }
+
+    fn classify(limits: &BlockLimits, usage: &BlockUsage) -> ParamLimitIndex {
+        let bytes = limits.bytes().classify(clamp(usage.bytes));
+        let gas = limits.gas().classify(clamp(usage.gas));
+        let lt_delta = limits.lt_delta().classify(clamp(usage.lt_delta));
+        bytes.max(gas).max(lt_delta)
+    }
+
+    fn check_usage(&self, usage: BlockUsage) -> Result<()> {
+        if Self::classify(&self.limits, &usage) == ParamLimitIndex::Hard {
+            fail!(BlockError::InvalidOperation(format!(
+                "block limits are exceeded: {} bytes, {} gas, lt delta {}",
+                usage.bytes, usage.gas, usage.lt_delta
+            )))
+        }
+        Ok(())
+    }
+
+    /// Adds transaction to the account block. Transaction must not be older
+    /// than the block start and than the previous transaction of the account.
+    pub fn add_transaction(&mut self, transaction: &Transaction) -> Result<()> {
+        let lt = transaction.logical_time();
+        let account_id = transaction.account_id();
+        if lt < self.info.start_lt() {
+            fail!(BlockError::InvalidArg(format!(
+                "transaction lt {} is less than block start lt {}", lt, self.info.start_lt()
+            )))
+        }
+        if let Some(last_lt) = self.last_lts.get(account_id) {
+            if lt <= *last_lt {
+                fail!(BlockError::InvalidArg(format!(
+                    "transaction lt {} of account {:x} is not greater than previous one {}",
+                    lt, account_id, last_lt
+                )))
+            }
+        }
+        let cell = transaction.serialize_with_opts(self.ctx.opts())?;
+        let end_lt = self.end_lt.max(lt + transaction.msg_count() as u64 + 1);
+        let usage = BlockUsage {
+            bytes: self.usage.bytes + cell_bytes(&cell),
+            gas: self.usage.gas + transaction.gas_used().unwrap_or_default(),
+            lt_delta: end_lt - self.info.start_lt(),
+        };
+        self.check_usage(usage)?;
+        self.account_blocks.add_serialized_transaction(transaction, &cell)?;
+        self.last_lts.insert(account_id.clone(), lt);
+        self.end_lt = end_lt;
+        self.usage = usage;
+        Ok(())
+    }
+
+    pub fn add_in_msg(&mut self, in_msg: &InMsg) -> Result<()> {
+        let bytes = cell_bytes(&in_msg.serialize_with_opts(self.ctx.opts())?);
+        self.add_bytes(bytes)?;
+        self.in_msg_descr.insert(in_msg)
+    }
+
+    pub fn add_out_msg(&mut self, out_msg: &OutMsg) -> Result<()> {
+        let bytes = cell_bytes(&out_msg.serialize_with_opts(self.ctx.opts())?);
+        self.add_bytes(bytes)?;
+        self.out_msg_descr.insert(out_msg)
+    }
+
+    fn add_bytes(&mut self, bytes: u64) -> Result<()> {
+        let usage = BlockUsage { bytes: self.usage.bytes + bytes, ..self.usage };
+        self.check_usage(usage)?;
+        self.usage = usage;
+        Ok(())
+    }
+
+    /// Serializes the block, end lt of the info is set by added transactions
+    pub fn build(mut self) -> Result<BuiltBlock> {
+        let state_update = self.state_update.take().ok_or_else(|| error!(
+            BlockError::InvalidOperation("state update of the block is not set".to_string())
+        ))?;
+        if self.mc_extra.is_some() && !self.info.shard().is_masterchain() {
+            fail!(BlockError::InvalidData(format!(
+                "shard block {} can't contain McBlockExtra", self.info.shard()
+            )))
+        }
+        self.info.set_end_lt(self.end_lt.max(self.info.start_lt() + 1));
+
+        let mut extra = BlockExtra::with_serde_context(self.ctx);
+        extra.write_in_msg_descr(&self.in_msg_descr)?;
+        extra.write_out_msg_descr(&self.out_msg_descr)?;
+        extra.write_account_blocks(&self.account_blocks)?;
+        extra.write_custom(self.mc_extra.as_ref())?;
+        extra.rand_seed = self.rand_seed;
+        extra.created_by = self.created_by;
+
+        let block = Block::with_serde_context(
+            self.ctx,
+            self.global_id,
+            &self.info,
+            &self.value_flow,
+            &state_update,
+            None,
+            &extra,
+        )?;
+        let root = block.serialize_with_opts(self.ctx.opts())?;
+        let boc = write_boc(&root)?;
+        let id = BlockIdExt::with_params(
+            self.info.shard().clone(),
+            self.info.seq_no(),
+            root.repr_hash(),
+            UInt256::calc_file_hash(&boc),
+        );
+        let proof = BlockProof::with_params(id.clone(), block.build_header_proof()?.serialize()?, None);
+        Ok(BuiltBlock { block, root, boc, id, proof })
+    }
+}
+
+fn cell_bytes(cell: &Cell) -> u64 {
+    (cell.tree_bits_count() + 7) / 8
+}
+
+fn clamp(value: u64) -> u32 {
+    value.min(u32::MAX as u64) as u32
+}
@@ -12,28 +12,28 @@
 */
 
 use crate::{
-    config_params::{CatchainConfig, GlobalVersion},
+    config_params::{CatchainConfig, ConfigParams, ConsensusConfig, GlobalVersion},
     define_HashmapE,
     error::BlockError,
-    inbound_messages::InMsgDescr,
+    inbound_messages::{InMsg, InMsgDescr},
     master::{BlkMasterInfo, McBlockExtra},
     merkle_update::MerkleUpdate,
     merkle_proof::MerkleProof,
-    outbound_messages::OutMsgDescr,
+    outbound_messages::{OutMsg, OutMsgDescr},
     OutMsgQueueInfo,
     shard::ShardIdent,
     signature::BlockSignatures,
-    transactions::ShardAccountBlocks,
+    transactions::{ShardAccountBlocks, Transaction},
     types::{ChildCell, CurrencyCollection, Grams, InRefValue, UnixTime32, AddSub},
-    validators::ValidatorSet, VarUInteger32,
+    validators::{ValidatorBaseInfo, ValidatorSet}, VarUInteger32,
     Deserializable, Serializable,
     error, fail, AccountId, BuilderData, Cell, ExceptionCode, IBitstring,
-    RefShardBlocks, Result, SliceData, UInt256,
+    HashmapAugType, HashmapType, RefShardBlocks, Result, SerdeContext, SliceData, UInt256, UsageTree,
     SERDE_OPTS_COMMON_MESSAGE, SERDE_OPTS_EMPTY
 };
 use std::{
     borrow::Cow, cmp::Ordering, fmt::{self, Display, Formatter}, io::{Cursor, Write},
-    str::FromStr
+    str::FromStr, sync::OnceLock
 };
 
 #[cfg(test)]
@@ -94,6 +94,18 @@ impl BlockIdExt {
     pub fn file_hash(&self) -> &UInt256 {
         &self.file_hash
     }
+
+    /// Key for the deterministic ordering of block candidates:
+    /// workchain, shard prefix with tag, seq_no, root and file hashes
+    pub fn collation_order_key(&self) -> (i32, u64, u32, UInt256, UInt256) {
+        (
+            self.shard_id.workchain_id(),
+            self.shard_id.shard_prefix_with_tag(),
+            self.seq_no,
+            self.root_hash.clone(),
+            self.file_hash.clone(),
+        )
+    }
 }
 
 impl Serializable for BlockIdExt {
@@ -127,50 +139,111 @@ impl Display for BlockIdExt {
     }
 }
 
+impl BlockIdExt {
+    /// Canonical form `(wc,shard,seqno):root_hash:file_hash`
+    pub fn to_canonical_string(&self) -> String {
+        format!("{}:{:X}:{:X}", self.to_short_string(), self.root_hash, self.file_hash)
+    }
+
+    /// Short form `(wc,shard,seqno)` without hashes
+    pub fn to_short_string(&self) -> String {
+        format!("({},{},{})",
+            self.shard_id.workchain_id(),
+            self.shard_id.shard_prefix_as_str_with_tag(),
+            self.seq_no)
+    }
+
+    /// Parses the short form `(wc,shard,seqno)` without hashes
+    pub fn parse_short(s: &str) -> Result<(ShardIdent, u32)> {
+        let (shard, seq_no, tail) = Self::parse_short_prefix(s, s.trim())?;
+        if !tail.is_empty() {
+            return Err(Self::parse_error(s, format!("unexpected {:?} after the short block id", tail)))
+        }
+        Ok((shard, seq_no))
+    }
+
+    fn parse_short_prefix<'a>(s: &str, text: &'a str) -> Result<(ShardIdent, u32, &'a str)> {
+        let (body, tail) = text.strip_prefix('(')
+            .and_then(|text| text.find(')').map(|end| (&text[..end], &text[end + 1..])))
+            .ok_or_else(|| Self::parse_error(s, "expected (workchain,shard,seqno)"))?;
+        let parts: Vec<_> = body.split(',').map(str::trim).collect();
+        if parts.len() != 3 {
+            return Err(Self::parse_error(
+                s, format!("expected 3 comma separated parts in parentheses but found {}", parts.len())
+            ))
+        }
+        let workchain_id: i32 = parts[0].parse()
+            .map_err(|err| Self::parse_error(s, format!("invalid workchain id {:?}: {}", parts[0], err)))?;
+        let shard = Self::parse_shard(s, workchain_id, parts[1])?;
+        let seq_no: u32 = parts[2].parse()
+            .map_err(|err| Self::parse_error(s, format!("invalid seqno {:?}: {}", parts[2], err)))?;
+        Ok((shard, seq_no, tail))
+    }
+
+    fn parse_shard(s: &str, workchain_id: i32, shard: &str) -> Result<ShardIdent> {
+        let prefix = u64::from_str_radix(shard, 16)
+            .map_err(|err| Self::parse_error(s, format!("invalid shard {:?}: {}", shard, err)))?;
+        if prefix == 0 {
+            return Err(Self::parse_error(s, format!("invalid shard {:?}: prefix has no tag bit", shard)))
+        }
+        ShardIdent::with_tagged_prefix(workchain_id, prefix)
+            .map_err(|err| Self::parse_error(s, format!("invalid shard {:?}: {}", shard, err)))
+    }
+
+    fn parse_hash(s: &str, name: &str, hash: &str) -> Result<UInt256> {
+        hash.trim().parse()
+            .map_err(|err| Self::parse_error(s, format!("invalid {} {:?}: {}", name, hash, err)))
+    }
+
+    fn parse_error(s: &str, reason: impl Display) -> crate::Error {
+        error!(BlockError::InvalidArg(format!("can't parse block id {:?}: {}", s, reason)))
+    }
+
+    /// Legacy form `(wc:shard, seqno, rh root_hash, fh file_hash)`
+    fn from_legacy_str(s: &str) -> Result<Self> {
+        let parts: Vec<_> = s.trim().trim_start_matches('(').trim_end_matches(')').split(',').collect();
+        if parts.len() != 4 {
+            return Err(Self::parse_error(s, format!("expected 4 comma separated parts but found {}", parts.len())))
+        }
+        let (workchain_id, shard) = parts[0].trim().split_once(':')
+            .ok_or_else(|| Self::parse_error(s, format!("expected workchain:shard but found {:?}", parts[0])))?;
+        let workchain_id: i32 = workchain_id.trim().parse()
+            .map_err(|err| Self::parse_error(s, format!("invalid workchain id {:?}: {}", workchain_id, err)))?;
+        let shard_id = Self::parse_shard(s, workchain_id, shard.trim())?;
+        let seq_no: u32 = parts[1].trim().parse()
+            .map_err(|err| Self::parse_error(s, format!("invalid seqno {:?}: {}", parts[1], err)))?;
+        let root_hash = parts[2].trim().strip_prefix("rh ")
+            .ok_or_else(|| Self::parse_error(s, format!("expected rh before root hash in {:?}", parts[2])))?;
+        let file_hash = parts[3].trim().strip_prefix("fh ")
+            .ok_or_else(|| Self::parse_error(s, format!("expected fh before file hash in {:?}", parts[3])))?;
+        Ok(Self::with_params(
+            shard_id,
+            seq_no,
+            Self::parse_hash(s, "root hash", root_hash)?,
+            Self::parse_hash(s, "file hash", file_hash)?,
+        ))
+    }
+}
+
+/// Parses the canonical form `(wc,shard,seqno):root_hash:file_hash`
+/// and the legacy one `(wc:shard, seqno, rh root_hash, fh file_hash)`
 impl FromStr for BlockIdExt {
     type Err = crate::Error;
 
     fn from_str(s: &str) -> Result<Self> {
-        // (0:1800000000000000, 1203696, rh 59b6e56610aa5df5e8ee4cc5f1081cd5d08473f10e0899f7763d580b2a635f90, fh 1b4d177339538562d10166d87823783b7e747ee80d85d033459928fd0605a126)
-        let mut parts = s.trim_start_matches('(').trim_end_matches(')').split(',');
-        let shard_parts = parts
-            .next()
-            .ok_or_else(|| error!("Can't read shard ident from {}", s))?
-            .trim();
-        let mut shard_parts = shard_parts.split(':');
-        let workchain_id: i32 = shard_parts
-            .next()
-            .ok_or_else(|| error!("Can't read workchain_id from {}", s))?
-            .trim()
-            .parse()
-            .map_err(|e| error!("Can't read workchain_id from {}: {}", s, e))?;
-        let shard = u64::from_str_radix(
-            shard_parts.next().ok_or_else(|| error!("Can't read shard from {}", s))?.trim(),
-            16
-        ).map_err(|e| error!("Can't read shard from {}: {}", s, e))?;
-        let seq_no: u32 = parts
-            .next()
-            .ok_or_else(|| error!("Can't read seq_no from {}", s))?
-            .trim()
-            .parse()
-            .map_err(|e| error!("Can't read seq_no from {}: {}", s, e))?;
-        let root_hash = parts
-            .next()
-            .ok_or_else(|| error!("Can't read root_hash from {}", s))?
-            .trim_start_matches(" rh ")
-            .parse()
-            .map_err(|e| error!("Can't read root_hash from {}: {}", s, e))?;
-        let file_hash = parts
-            .next()
-            .ok_or_else(|| error!("Can't read file_hash from {}", s))?
-            .trim_start_matches(" fh ")
-            .parse()
-            .map_err(|e| error!("Can't read file_hash from {}: {}", s, e))?;
+        if s.contains(" rh ") {
+            return Self::from_legacy_str(s)
+        }
+        let (shard_id, seq_no, tail) = Self::parse_short_prefix(s, s.trim())?;
+        let hashes = tail.strip_prefix(':')
+            .ok_or_else(|| Self::parse_error(s, "expected :root_hash:file_hash after the short block id"))?;
+        let (root_hash, file_hash) = hashes.split_once(':')
+            .ok_or_else(|| Self::parse_error(s, "expected root_hash:file_hash"))?;
         Ok(Self::with_params(
-            ShardIdent::with_tagged_prefix(workchain_id, shard)?,
+            shard_id,
             seq_no,
-            root_hash,
-            file_hash,
+            Self::parse_hash(s, "root hash", root_hash)?,
+            Self::parse_hash(s, "file hash", file_hash)?,
         ))
     }
 }
@@ -640,6 +713,22 @@ impl Deserializable for OutQueueUpdate {
     }
 }
 
+impl OutQueueUpdate {
+    /// Builds update of the out queue part addressed to the workchain
+    /// (with processed info) between two shard states,
+    /// see `OutMsgQueueInfo::prepare_update_for_wc`
+    pub fn with_states(
+        old_shard_state_root: &Cell,
+        old_shard_state_usage_tree: &UsageTree,
+        new_shard_state_root: &Cell,
+        workchain_id: i32,
+    ) -> Result<Self> {
+        OutMsgQueueInfo::prepare_update_for_wc(
+            old_shard_state_root, old_shard_state_usage_tree, new_shard_state_root, workchain_id
+        )
+    }
+}
+
 impl Serializable for OutQueueUpdate {
     fn write_to(&self, builder: &mut BuilderData) -> Result<()> {
         self.is_empty.write_to(builder)?;
@@ -844,6 +933,47 @@ impl Block {
         })
     }
 
+    /// Constructs block with all nested structures serialized with options of the context.
+    /// Fails if block extra was constructed with other options.
+    pub fn with_serde_context(
+        ctx: SerdeContext,
+        global_id: i32,
+        info: &BlockInfo,
+        value_flow: &ValueFlow,
+        state_update: &MerkleUpdate,
+        out_msg_queue_updates: Option<OutQueueUpdates>,
+        extra: &BlockExtra,
+    ) -> Result<Self> {
+        ctx.check::<BlockExtra>(extra.serde_opts())?;
+        Ok(Block {
+            global_id,
+            info: ctx.child_with_struct(info)?,
+            value_flow: ctx.child_with_struct(value_flow)?,
+            extra: ctx.child_with_struct(extra)?,
+            state_update: ctx.child_with_struct(state_update)?,
+            out_msg_queue_updates,
+        })
+    }
+
+    /// Serialization options the block was constructed or read with
+    pub fn serde_opts(&self) -> u8 {
+        self.extra.serde_opts()
+    }
+
+    /// Out queue update for the workchain, `None` if block has no updates for it
+    pub fn read_out_queue_update_for(&self, workchain_id: i32) -> Result<Option<OutQueueUpdate>> {
+        match &self.out_msg_queue_updates {
+            Some(updates) => updates.get(&workchain_id),
+            None => Ok(None)
+        }
+    }
+
+    pub fn write_out_queue_update_for(&mut self, workchain_id: i32, update: &OutQueueUpdate) -> Result<()> {
+        self.out_msg_queue_updates
+            .get_or_insert_with(OutQueueUpdates::default)
+            .set(&workchain_id, update)
+    }
+
     pub fn global_id(&self) -> i32 {
         self.global_id
     }
@@ -927,6 +1057,215 @@ impl Block {
             )))?
             .read_cur_validator_set_and_cc_conf()
     }
+
+    /// Iterates transactions of the account reading only its account block.
+    /// Returns false if iteration was stopped by `p`, true if it ran to completion
+    /// (including the case when the block has no transactions of the account)
+    pub fn iterate_transactions_for_account<F>(&self, account_id: &AccountId, p: F) -> Result<bool>
+    where F: FnMut(Transaction) -> Result<bool> {
+        match self.read_extra()?.read_account_blocks()?.get_serialized(account_id.clone())? {
+            Some(account_block) => account_block.transaction_iterate(p),
+            None => Ok(true)
+        }
+    }
+
+    /// Checks that message descriptors agree with each other and with the value flow:
+    /// every immediately processed outbound message is reimported with the same
+    /// envelope and forwarding fee, and total imported and exported values
+    /// of the descriptors are equal to those of `ValueFlow`
+    pub fn check_msg_descriptors(&self) -> Result<()> {
+        let extra = self.read_extra()?;
+        let in_msg_descr = extra.read_in_msg_descr()?;
+        let out_msg_descr = extra.read_out_msg_descr()?;
+        let value_flow = self.read_value_flow()?;
+        let mut errors = Vec::new();
+        out_msg_descr.iterate_with_keys(|key: UInt256, out_msg| {
+            let out_msg = match out_msg {
+                OutMsg::Immediate(out_msg) => out_msg,
+                _ => return Ok(true)
+            };
+            let in_msg = match in_msg_descr.get(&key)? {
+                Some(InMsg::Immediate(in_msg)) => in_msg,
+                Some(in_msg) => {
+                    errors.push(format!("immediate outbound message {:x} is imported as {}", key, in_msg));
+                    return Ok(true)
+                }
+                None => {
+                    errors.push(format!("immediate outbound message {:x} is not imported", key));
+                    return Ok(true)
+                }
+            };
+            if out_msg.read_reimport_message()? != InMsg::Immediate(in_msg.clone()) {
+                errors.push(format!("reimport of message {:x} differs from inbound descriptor", key));
+            }
+            if in_msg.envelope_message_hash() != out_msg.out_message_cell().repr_hash() {
+                errors.push(format!("message {:x} is reimported with another envelope", key));
+            }
+            let env = out_msg.read_out_message()?;
+            if in_msg.fwd_fee() != env.fwd_fee_remaining() {
+                errors.push(format!(
+                    "fwd fee {} of reimported message {:x} != remaining fwd fee {} of its envelope",
+                    in_msg.fwd_fee(), key, env.fwd_fee_remaining()
+                ));
+            }
+            Ok(true)
+        })?;
+        in_msg_descr.iterate_with_keys(|key: UInt256, in_msg| {
+            if let InMsg::Immediate(_) = in_msg {
+                match out_msg_descr.get(&key)? {
+                    Some(OutMsg::Immediate(_)) => (),
+                    _ => errors.push(format!("immediate inbound message {:x} is not exported", key))
+                }
+            }
+            Ok(true)
+        })?;
+        let imported = &in_msg_descr.full_import_fees().value_imported;
+        if *imported != value_flow.imported {
+            errors.push(format!(
+                "imported value {} of InMsgDescr != imported {} of value flow", imported, value_flow.imported
+            ));
+        }
+        let exported = out_msg_descr.full_exported();
+        if *exported != value_flow.exported {
+            errors.push(format!(
+                "exported value {} of OutMsgDescr != exported {} of value flow", exported, value_flow.exported
+            ));
+        }
+        if !errors.is_empty() {
+            fail!(BlockError::InvalidData(format!("message descriptors are inconsistent: {}", errors.join("; "))))
+        }
+        Ok(())
+    }
+
+    /// Builds Merkle proof of the transaction inclusion into the block.
+    /// Proof contains block info and path through account blocks to the transaction
+    pub fn build_transaction_proof(&self, account: &AccountId, lt: u64) -> Result<MerkleProof> {
+        let block_root = self.serialize_with_opts(self.serde_opts())?;
+        let usage_tree = UsageTree::with_root(block_root.clone());
+        let block = Block::construct_from_cell(usage_tree.root_cell())?;
+
+        block.read_info()?;
+
+        block
+            .read_extra()?
+            .read_account_blocks()?
+            .get_serialized(account.clone())?
+            .ok_or_else(|| error!(BlockError::NotFound(
+                format!("account block {:x} in block", account)
+            )))?
+            .transactions()
+            .get(&lt)?
+            .ok_or_else(|| error!(BlockError::NotFound(
+                format!("transaction {:x} with lt {} in block", account, lt)
+            )))?;
+
+        MerkleProof::create_by_usage_tree(&block_root, usage_tree)
+    }
+
+    /// Builds Merkle proof of the block header as used in `BlockProof`.
+    /// Proof contains block info and state update, for key blocks also full config.
+    pub fn build_header_proof(&self) -> Result<MerkleProof> {
+        let block_root = self.serialize_with_opts(self.serde_opts())?;
+        let usage_tree = UsageTree::with_root(block_root.clone());
+        let block = Block::construct_from_cell(usage_tree.root_cell())?;
+
+        let info = block.read_info()?;
+        block.read_state_update()?;
+        let mut config_root = None;
+        if info.key_block() {
+            if let Some(mc_extra) = block.read_extra()?.read_custom()? {
+                config_root = mc_extra.config().and_then(|config| config.config_params.data().map(|c| c.repr_hash()));
+            }
+        }
+
+        MerkleProof::create_with_subtrees(
+            &block_root,
+            |hash| usage_tree.contains(hash),
+            |hash| config_root.as_ref() == Some(hash),
+        )
+    }
+
+    /// Collects validator related data of the key block checking that
+    /// validator sets follow each other and are actual for the block
+    pub fn extract_key_block_data(&self) -> Result<KeyBlockData> {
+        let info = self.read_info()?;
+        if !info.shard().is_masterchain() || !info.key_block() {
+            fail!(BlockError::InvalidArg(format!(
+                "block {}:{} is not a key block", info.shard(), info.seq_no()
+            )))
+        }
+        let config = self
+            .read_extra()?
+            .read_custom()?
+            .and_then(|extra| extra.config().cloned())
+            .ok_or_else(|| error!(BlockError::InvalidData(format!(
+                "key block {} has no config", info.seq_no()
+            ))))?;
+        let gen_utime = info.gen_utime().as_u32();
+        let prev_validators = match config.prev_validator_set_present()? {
+            true => Some(config.prev_validator_set()?),
+            false => None
+        };
+        let cur_validators = config.validator_set()?;
+        let next_validators = match config.next_validator_set_present()? {
+            true => Some(config.next_validator_set()?),
+            false => None
+        };
+        if cur_validators.list().is_empty() {
+            fail!(BlockError::InvalidData(format!("key block {} has empty validator set", info.seq_no())))
+        }
+        if cur_validators.utime_since() > gen_utime {
+            fail!(BlockError::InvalidData(format!(
+                "current validator set of key block {} is active since {} but block is generated at {}",
+                info.seq_no(), cur_validators.utime_since(), gen_utime
+            )))
+        }
+        if let Some(prev) = prev_validators.as_ref() {
+            if prev.utime_since() >= cur_validators.utime_since() {
+                fail!(BlockError::InvalidData(format!(
+                    "previous validator set of key block {} is not older than current one", info.seq_no()
+                )))
+            }
+        }
+        if let Some(next) = next_validators.as_ref() {
+            if next.utime_since() <= cur_validators.utime_since() {
+                fail!(BlockError::InvalidData(format!(
+                    "next validator set of key block {} is not newer than current one", info.seq_no()
+                )))
+            }
+        }
+        Ok(KeyBlockData {
+            seq_no: info.seq_no(),
+            gen_utime,
+            prev_validators,
+            cur_validators,
+            next_validators,
+            catchain_config: config.catchain_config()?,
+            consensus_config: config.consensus_config()?,
+            validator_info: ValidatorBaseInfo::with_params(
+                info.gen_validator_list_hash_short(), info.gen_catchain_seqno()
+            ),
+            config,
+        })
+    }
+}
+
+/// Data of the key block needed to follow the chain of key blocks
+#[derive(Clone, Debug)]
+pub struct KeyBlockData {
+    pub seq_no: u32,
+    pub gen_utime: u32,
+    pub config: ConfigParams,
+    /// ConfigParam 33 or 32
+    pub prev_validators: Option<ValidatorSet>,
+    /// ConfigParam 35 or 34
+    pub cur_validators: ValidatorSet,
+    /// ConfigParam 37 or 36
+    pub next_validators: Option<ValidatorSet>,
+    pub catchain_config: CatchainConfig,
+    pub consensus_config: ConsensusConfig,
+    /// Validators which signed the block
+    pub validator_info: ValidatorBaseInfo,
 }
 
 impl Ord for Block {
@@ -965,11 +1304,15 @@ impl BlockExtra {
     pub fn new() -> Self { Self::default() }
 
     pub fn with_common_msg_support() -> BlockExtra {
-        let opts = SERDE_OPTS_COMMON_MESSAGE;
+        Self::with_serde_context(SerdeContext::common_message())
+    }
+
+    /// Constructs extra with empty descriptors serialized with options of the context
+    pub fn with_serde_context(ctx: SerdeContext) -> BlockExtra {
         BlockExtra {
-            in_msg_descr: ChildCell::with_serde_opts(opts),
-            out_msg_descr: ChildCell::with_serde_opts(opts),
-            account_blocks: ChildCell::with_serde_opts(opts),
+            in_msg_descr: empty_child(ctx, InMsgDescr::with_serde_opts(ctx.opts())),
+            out_msg_descr: empty_child(ctx, OutMsgDescr::with_serde_opts(ctx.opts())),
+            account_blocks: empty_child(ctx, ShardAccountBlocks::with_serde_opts(ctx.opts())),
             rand_seed: UInt256::rand(),
             created_by: UInt256::default(), // TODO: Need to fill?
             custom: None,
@@ -977,6 +1320,11 @@ impl BlockExtra {
         }
     }
 
+    /// Serialization options of nested descriptors
+    pub fn serde_opts(&self) -> u8 {
+        self.in_msg_descr.serde_opts()
+    }
+
     pub fn read_in_msg_descr(&self) -> Result<InMsgDescr> {
         self.in_msg_descr.read_struct()
     }
@@ -1094,15 +1442,38 @@ impl Deserializable for BlockExtra {
     }
 }
 
+/// Empty descriptor serialized with options of the context, so the child
+/// is not replaced by default descriptor without options on serialization
+fn empty_child<T: Serializable + Deserializable>(ctx: SerdeContext, empty: T) -> ChildCell<T> {
+    ctx.child_with_struct(&empty).unwrap_or_else(|_| ctx.child())
+}
+
+/// Cell of the descriptor: not filled one is serialized as empty with options of the extra,
+/// filled one must have been serialized with the same options
+fn extra_child_cell<T: Serializable + Deserializable>(
+    child: &ChildCell<T>,
+    opts: u8,
+    empty: impl FnOnce(u8) -> T,
+) -> Result<Cell> {
+    if child.empty() {
+        return empty(opts).serialize_with_opts(opts)
+    }
+    SerdeContext::with_opts(opts).check::<T>(child.serde_opts())?;
+    Ok(child.cell())
+}
+
 fn serialize_blockextra(
     extra: &BlockExtra,
     cell: &mut BuilderData,
     tag: u32,
+    opts: u8,
 ) -> Result<()> {
     cell.append_u32(tag)?;
-    cell.checked_append_reference(extra.in_msg_descr.cell())?;
-    cell.checked_append_reference(extra.out_msg_descr.cell())?;
-    cell.checked_append_reference(extra.account_blocks.cell())?;
+    cell.checked_append_reference(extra_child_cell(&extra.in_msg_descr, opts, InMsgDescr::with_serde_opts)?)?;
+    cell.checked_append_reference(extra_child_cell(&extra.out_msg_descr, opts, OutMsgDescr::with_serde_opts)?)?;
+    cell.checked_append_reference(
+        extra_child_cell(&extra.account_blocks, opts, ShardAccountBlocks::with_serde_opts)?
+    )?;
     extra.rand_seed.write_to(cell)?;
     extra.created_by.write_to(cell)?;
 
@@ -1123,7 +1494,7 @@ impl Serializable for BlockExtra {
         } else {
             BLOCK_EXTRA_TAG_2
         };
-        serialize_blockextra(self, cell, tag)
+        serialize_blockextra(self, cell, tag, SERDE_OPTS_EMPTY)
     }
 
     fn write_with_opts(&self, cell:&mut BuilderData, opts: u8) -> Result<()> {
@@ -1131,7 +1502,7 @@ impl Serializable for BlockExtra {
             return self.write_to(cell);
         }
         if opts & SERDE_OPTS_COMMON_MESSAGE != 0 {
-            serialize_blockextra(self, cell, BLOCK_EXTRA_TAG_3)
+            serialize_blockextra(self, cell, BLOCK_EXTRA_TAG_3, opts)
         } else {
             fail!(BlockError::UnsupportedSerdeOptions(
                 std::any::type_name::<Self>().to_string(),
@@ -1145,15 +1516,56 @@ define_HashmapE!{CopyleftRewards, 256, Grams}
 
 impl CopyleftRewards {
     pub fn add_copyleft_reward(&mut self, reward_address: &AccountId, reward: &Grams) -> Result<()> {
-        if let Some(mut value) = self.get(reward_address)? {
-            value.add(reward)?;
-            self.set(reward_address, &value)?;
-        } else {
-            self.set(reward_address, reward)?;
-        }
+        self.add_reward(reward_address, reward)
+    }
+
+    /// Accumulated reward of the address, zero if it has none
+    pub fn reward_of(&self, address: &AccountId) -> Result<Grams> {
+        Ok(self.get(address)?.unwrap_or_default())
+    }
+
+    pub fn add_reward(&mut self, address: &AccountId, reward: &Grams) -> Result<()> {
+        let mut value = self.reward_of(address)?;
+        value.add(reward)?;
+        self.set(address, &value)?;
         Ok(())
     }
 
+    /// Iterates rewards from the largest one, equal rewards are ordered by address
+    pub fn iterate_sorted_by_amount<F>(&self, mut func: F) -> Result<bool>
+    where F: FnMut(AccountId, Grams) -> Result<bool> {
+        let mut rewards = Vec::new();
+        self.iterate_with_keys(|address: AccountId, value| {
+            rewards.push((address, value));
+            Ok(true)
+        })?;
+        rewards.sort_by(|(address1, value1), (address2, value2)| {
+            value2.cmp(value1).then_with(|| address1.cmp(address2))
+        });
+        for (address, value) in rewards {
+            if !func(address, value)? {
+                return Ok(false)
+            }
+        }
+        Ok(true)
+    }
+
+    /// Removes rewards reached the threshold and returns them to be paid out
+    /// as it is done by `merge_rewards_with_threshold`
+    pub fn split_above_threshold(&mut self, threshold: &Grams) -> Result<Vec<(AccountId, Grams)>> {
+        let mut send_rewards = Vec::new();
+        self.iterate_with_keys(|address: AccountId, value| {
+            if &value >= threshold {
+                send_rewards.push((address, value));
+            }
+            Ok(true)
+        })?;
+        for (address, _) in &send_rewards {
+            self.remove(address)?;
+        }
+        Ok(send_rewards)
+    }
+
     pub fn merge_rewards(&mut self, other: &Self) -> Result<()> {
         // if map size is big, iterating will be long
         other.iterate_with_keys(|key: AccountId, mut value| {
@@ -1273,6 +1685,103 @@ impl ValueFlow {
         self.mesh_exported.iterate(|_value| Ok(true))?;
         Ok(())
     }
+
+    /// Checks the balance equation
+    /// from_prev_blk + imported + fees_imported + created + minted + recovered ==
+    /// to_next_blk + exported + fees_collected
+    /// for grams and every extra currency. Also checks that minted and recovered
+    /// are used only in masterchain and that copyleft rewards are covered by collected fees.
+    pub fn validate(&self, is_masterchain: bool) -> Result<()> {
+        let mut errors = Vec::new();
+        if !is_masterchain {
+            for (name, value) in [("minted", &self.minted), ("recovered", &self.recovered)] {
+                if !value.is_zero()? {
+                    errors.push(format!("{} is {} in non-masterchain block", name, value));
+                }
+            }
+        }
+        let inflow = [
+            ("from_prev_blk", &self.from_prev_blk),
+            ("imported", &self.imported),
+            ("fees_imported", &self.fees_imported),
+            ("created", &self.created),
+            ("minted", &self.minted),
+            ("recovered", &self.recovered),
+        ];
+        let outflow = [
+            ("to_next_blk", &self.to_next_blk),
+            ("exported", &self.exported),
+            ("fees_collected", &self.fees_collected),
+        ];
+        let total_in = Self::sum_flow(&inflow)?;
+        let total_out = Self::sum_flow(&outflow)?;
+        if total_in.grams != total_out.grams {
+            errors.push(format!(
+                "grams inflow {} ({}) != outflow {} ({})",
+                total_in.grams, Self::describe_flow(&inflow, None)?,
+                total_out.grams, Self::describe_flow(&outflow, None)?
+            ));
+        }
+        let mut currencies = std::collections::BTreeSet::new();
+        for collection in [&total_in, &total_out] {
+            collection.other.iterate_keys(|key: u32| {
+                currencies.insert(key);
+                Ok(true)
+            })?;
+        }
+        for key in currencies {
+            let value_in = total_in.get_other(key)?.unwrap_or_else(VarUInteger32::zero);
+            let value_out = total_out.get_other(key)?.unwrap_or_else(VarUInteger32::zero);
+            if value_in != value_out {
+                errors.push(format!(
+                    "extra currency {} inflow {} ({}) != outflow {} ({})",
+                    key, value_in, Self::describe_flow(&inflow, Some(key))?,
+                    value_out, Self::describe_flow(&outflow, Some(key))?
+                ));
+            }
+        }
+        let mut rewards = Grams::zero();
+        self.copyleft_rewards.iterate(|reward| {
+            if !rewards.add(&reward)? {
+                fail!(BlockError::InvalidData("copyleft rewards overflow".to_string()))
+            }
+            Ok(true)
+        })?;
+        if rewards > self.fees_collected.grams {
+            errors.push(format!(
+                "copyleft rewards total {} exceeds fees_collected {}", rewards, self.fees_collected.grams
+            ));
+        }
+        if !errors.is_empty() {
+            fail!(BlockError::InvalidData(format!("value flow is not balanced: {}", errors.join("; "))))
+        }
+        Ok(())
+    }
+
+    fn sum_flow(components: &[(&str, &CurrencyCollection)]) -> Result<CurrencyCollection> {
+        let mut total = CurrencyCollection::default();
+        for (_, value) in components {
+            total = total.checked_add(value)?;
+        }
+        Ok(total)
+    }
+
+    /// Lists non-zero components for grams or given extra currency
+    fn describe_flow(components: &[(&str, &CurrencyCollection)], currency: Option<u32>) -> Result<String> {
+        let mut parts = Vec::new();
+        for (name, value) in components {
+            match currency {
+                None if !value.grams.is_zero() => parts.push(format!("{} {}", name, value.grams)),
+                Some(key) => if let Some(other) = value.get_other(key)? {
+                    if !other.is_zero() {
+                        parts.push(format!("{} {}", name, other));
+                    }
+                }
+                None => ()
+            }
+        }
+        Ok(parts.join(" + "))
+    }
 }
 
 /*
@@ -1538,6 +2047,144 @@ impl Deserializable for Block {
     }
 }
 
+/// Block wrapper which parses only the outer block cell on construction.
+/// Nested structures are deserialized on first access and memoized,
+/// so reading of `gen_utime` or `seq_no` doesn't touch block extra at all.
+#[derive(Debug)]
+pub struct LazyBlock {
+    cell: Cell,
+    tag: u32,
+    global_id: i32,
+    opts: u8,
+    info: OnceLock<BlockInfo>,
+    value_flow: OnceLock<ValueFlow>,
+    state_update: OnceLock<MerkleUpdate>,
+    out_msg_queue_updates: OnceLock<Option<OutQueueUpdates>>,
+    extra: OnceLock<BlockExtra>,
+}
+
+fn lazy_read<T>(lock: &OnceLock<T>, read: impl FnOnce() -> Result<T>) -> Result<&T> {
+    if let Some(value) = lock.get() {
+        return Ok(value)
+    }
+    let _ = lock.set(read()?);
+    lock.get().ok_or_else(|| error!("value is not initialized"))
+}
+
+impl LazyBlock {
+    pub fn with_cell(cell: Cell) -> Result<Self> {
+        let mut slice = SliceData::load_cell_ref(&cell)?;
+        let tag = slice.get_next_u32()?;
+        let opts = match tag {
+            BLOCK_TAG_1 | BLOCK_TAG_2 => SERDE_OPTS_EMPTY,
+            BLOCK_TAG_3 => SERDE_OPTS_COMMON_MESSAGE,
+            _ => fail!(
+                BlockError::InvalidConstructorTag {
+                    t: tag,
+                    s: "Block".to_string()
+                }
+            )
+        };
+        let global_id = slice.get_next_i32()?;
+        if cell.references_count() != 4 {
+            fail!(BlockError::InvalidData(format!(
+                "block cell must have 4 references but has {}", cell.references_count()
+            )))
+        }
+        Ok(Self {
+            cell,
+            tag,
+            global_id,
+            opts,
+            info: OnceLock::new(),
+            value_flow: OnceLock::new(),
+            state_update: OnceLock::new(),
+            out_msg_queue_updates: OnceLock::new(),
+            extra: OnceLock::new(),
+        })
+    }
+
+    pub fn cell(&self) -> &Cell {
+        &self.cell
+    }
+
+    pub fn hash(&self) -> UInt256 {
+        self.cell.repr_hash()
+    }
+
+    pub fn global_id(&self) -> i32 {
+        self.global_id
+    }
+
+    pub fn serde_opts(&self) -> u8 {
+        self.opts
+    }
+
+    pub fn info_cell(&self) -> Result<Cell> {
+        self.cell.reference(0)
+    }
+
+    pub fn value_flow_cell(&self) -> Result<Cell> {
+        self.cell.reference(1)
+    }
+
+    pub fn extra_cell(&self) -> Result<Cell> {
+        self.cell.reference(3)
+    }
+
+    pub fn read_info(&self) -> Result<&BlockInfo> {
+        lazy_read(&self.info, || {
+            BlockInfo::construct_from_cell_with_opts(self.info_cell()?, self.opts)
+        })
+    }
+
+    pub fn read_value_flow(&self) -> Result<&ValueFlow> {
+        lazy_read(&self.value_flow, || {
+            ValueFlow::construct_from_cell_with_opts(self.value_flow_cell()?, self.opts)
+        })
+    }
+
+    fn state_update_slice(&self) -> Result<(Cell, Option<SliceData>)> {
+        let cell = self.cell.reference(2)?;
+        if self.tag == BLOCK_TAG_1 {
+            Ok((cell, None))
+        } else {
+            let mut slice = SliceData::load_cell(cell)?;
+            Ok((slice.checked_drain_reference()?, Some(slice)))
+        }
+    }
+
+    pub fn read_state_update(&self) -> Result<&MerkleUpdate> {
+        lazy_read(&self.state_update, || {
+            let (cell, _) = self.state_update_slice()?;
+            MerkleUpdate::construct_from_cell_with_opts(cell, self.opts)
+        })
+    }
+
+    pub fn read_out_msg_queue_updates(&self) -> Result<Option<&OutQueueUpdates>> {
+        lazy_read(&self.out_msg_queue_updates, || {
+            match self.state_update_slice()?.1 {
+                Some(mut slice) => {
+                    let updates = OutQueueUpdates::construct_from_with_opts(&mut slice, self.opts)?;
+                    Ok(Some(updates).filter(|updates| !updates.is_empty()))
+                }
+                None => Ok(None)
+            }
+        }).map(|updates| updates.as_ref())
+    }
+
+    pub fn read_extra(&self) -> Result<&BlockExtra> {
+        lazy_read(&self.extra, || {
+            BlockExtra::construct_from_cell_with_opts(self.extra_cell()?, self.opts)
+        })
+    }
+
+    /// Fully deserializes block
+    pub fn to_block(&self) -> Result<Block> {
+        Block::construct_from_cell(self.cell.clone())
+    }
+}
+
 fn serialize_block(
     block: &Block,
     builder: &mut BuilderData,
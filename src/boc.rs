@@ -13,18 +13,20 @@
 use std::{
     collections::{HashMap, HashSet, hash_map},
     io::{Read, Write, Seek, SeekFrom, Cursor},
-    sync::Arc, ops::Deref,
+    sync::{Arc, Mutex, OnceLock}, ops::Deref,
     path::{PathBuf,Path},
     fmt::Debug,
     fs::File,
 };
 
 use crate::{
-    cell::{self, Cell, DataCell, SHA256_SIZE, DEPTH_SIZE, MAX_DATA_BYTES, MAX_SAFE_DEPTH},
+    cell::{self, Cell, CellInterner, DataCell, SHA256_SIZE, DEPTH_SIZE, MAX_DATA_BYTES, MAX_SAFE_DEPTH},
     ByteOrderRead, UInt256, Result, Status, fail, error, MAX_REFERENCES_COUNT, full_len, CellType, 
-    MAX_BIG_DATA_BYTES, CellImpl, crc32_digest, Crc32,
+    MAX_BIG_DATA_BYTES, CellImpl, crc32_digest, Crc32, Sha256, sha256_digest,
 };
 use smallvec::SmallVec;
+#[cfg(feature = "mmap")]
+pub use memmap2::Mmap;
 
 
 
@@ -460,6 +462,20 @@ impl<'a, S: OrderedCellsStorage> BocWriter<'a, S> {
         self.write_ex(dest, false, false, None, None)
     }
 
+    /// Writes BOC and returns its file hash computed on the fly
+    pub fn write_ex_with_file_hash<T: Write>(
+        self,
+        dest: &mut T,
+        include_index: bool,
+        include_crc: bool,
+        custom_ref_size: Option<usize>,
+        custom_offset_size: Option<usize>,
+    ) -> Result<UInt256> {
+        let mut dest = IoSha256Filter::new(dest);
+        self.write_ex(&mut dest, include_index, include_crc, custom_ref_size, custom_offset_size)?;
+        Ok(dest.finalize())
+    }
+
     pub fn write_ex<T: Write>(
         self,
         dest: &mut T,
@@ -715,6 +731,8 @@ pub struct BocReader<'a> {
     done_cells: Box<dyn DoneCellsStorage>,
     max_depth: u16,
     allow_big_cells: bool,
+    expected_file_hash: Option<UInt256>,
+    interner: Option<Arc<CellInterner>>,
 }
 
 impl<'a> Default for BocReader<'a> {
@@ -725,19 +743,82 @@ impl<'a> Default for BocReader<'a> {
             done_cells: Box::<HashMap::<u32, Cell>>::default(),
             max_depth: MAX_SAFE_DEPTH,
             allow_big_cells: false,
+            expected_file_hash: None,
+            interner: None,
         }
     }
 }
 
 pub fn read_boc(data: impl AsRef<[u8]>) -> Result<BocReaderResult> {
+    #[cfg(feature = "compression")]
+    if is_compressed_boc(data.as_ref()) {
+        return read_compressed_boc(data.as_ref())
+    }
     let mut cursor = Cursor::new(data);
     BocReader::new().read(&mut cursor)
 }
 
+#[cfg(feature = "compression")]
+const BOC_ZSTD_TAG: u32 = 0x7a737462; // "zstb"
+
+#[cfg(feature = "compression")]
+pub fn is_compressed_boc(data: &[u8]) -> bool {
+    data.len() >= 4 && data[..4] == BOC_ZSTD_TAG.to_be_bytes()
+}
+
+/// Compressed BOC: tag, file hash of the uncompressed BOC and zstd frame
+#[cfg(feature = "compression")]
+pub fn write_boc_compressed(root_cell: &Cell, level: i32) -> Result<Vec<u8>> {
+    let boc = write_boc(root_cell)?;
+    let mut result = Vec::with_capacity(boc.len() / 2);
+    result.extend_from_slice(&BOC_ZSTD_TAG.to_be_bytes());
+    result.extend_from_slice(&sha256_digest(&boc));
+    zstd::stream::copy_encode(&boc[..], &mut result, level)?;
+    Ok(result)
+}
+
+/// Decompresses BOC on the fly checking its file hash
+#[cfg(feature = "compression")]
+pub fn read_compressed_boc(data: &[u8]) -> Result<BocReaderResult> {
+    if !is_compressed_boc(data) || data.len() < 4 + SHA256_SIZE {
+        fail!("data is not a compressed BOC")
+    }
+    let file_hash = UInt256::from_slice(&data[4..4 + SHA256_SIZE]);
+    let mut decoder = zstd::stream::read::Decoder::new(&data[4 + SHA256_SIZE..])?;
+    let result = BocReader::new()
+        .set_expected_file_hash(file_hash)
+        .read_stream(&mut decoder)?;
+    if decoder.read(&mut [0])? != 0 {
+        fail!("compressed BOC has extra data after the bag of cells")
+    }
+    Ok(result)
+}
+
 pub fn read_single_root_boc(data: impl AsRef<[u8]>) -> Result<Cell> {
     read_boc(data)?.withdraw_single_root()
 }
 
+/// Maps the BOC file into memory read-only.
+///
+/// # Safety
+///
+/// Cells loaded by [read_mapped_boc] refer to the mapped pages directly,
+/// so the file must not be modified or truncated while the map or any of these
+/// cells is alive: cells would observe changed data and reading a truncated page
+/// terminates the process with SIGBUS.
+#[cfg(feature = "mmap")]
+pub unsafe fn map_boc_file(path: impl AsRef<Path>) -> Result<Arc<Mmap>> {
+    let file = File::open(path)?;
+    Ok(Arc::new(Mmap::map(&file)?))
+}
+
+/// Reads BOC from the memory mapped file without copying cells data,
+/// every cell holds the map, so it is unmapped when the last cell is dropped
+#[cfg(feature = "mmap")]
+pub fn read_mapped_boc(data: Arc<Mmap>) -> Result<BocReaderResult> {
+    BocReader::new().read_mapped(data)
+}
+
 impl<'a> BocReader<'a> {
     pub fn new() -> Self { Self::default() }
 
@@ -766,23 +847,71 @@ impl<'a> BocReader<'a> {
         self
     }
 
-    pub fn read<T: Read + Seek>(&mut self, src: &mut T) -> Result<BocReaderResult> {
-        #[cfg(not(target_family = "wasm"))]
-        let now = std::time::Instant::now();
+    /// File hash (sha256 of the whole BOC) is computed while reading
+    /// and checked when all cells are constructed
+    pub fn set_expected_file_hash(mut self, file_hash: UInt256) -> Self {
+        self.expected_file_hash = Some(file_hash);
+        self
+    }
+
+    /// Loaded cells are replaced with equal ones already present in the pool
+    pub fn set_cell_interner(mut self, interner: Arc<CellInterner>) -> Self {
+        self.interner = Some(interner);
+        self
+    }
+
+    fn make_cell(&self, cell: DataCell) -> Cell {
+        let cell = Cell::with_cell_impl(cell);
+        match &self.interner {
+            Some(interner) => interner.intern(cell),
+            None => cell
+        }
+    }
 
+    pub fn read<T: Read + Seek>(&mut self, src: &mut T) -> Result<BocReaderResult> {
         let position = src.stream_position()?;
         let src_full_len = src.seek(SeekFrom::End(0))? - position;
         src.seek(SeekFrom::Start(position))?;
+        self.read_with_hash_check(src, Some(src_full_len))
+    }
+
+    /// Reads BOC sequentially from the source which doesn't support seeking
+    /// (socket, pipe, decompressor). Raw cells are kept in the indexed cells storage
+    /// so huge states can be read using disk-backed storages.
+    pub fn read_stream<T: Read>(&mut self, src: &mut T) -> Result<BocReaderResult> {
+        self.read_with_hash_check(src, None)
+    }
+
+    fn read_with_hash_check<T: Read>(&mut self, src: &mut T, src_full_len: Option<u64>) -> Result<BocReaderResult> {
+        match self.expected_file_hash.clone() {
+            Some(expected) => {
+                let mut src = IoSha256Filter::new(src);
+                let result = self.read_sequentially(&mut src, src_full_len)?;
+                let file_hash = src.finalize();
+                if file_hash != expected {
+                    fail!("file hash mismatch, expected: {:x}, actual: {:x}", expected, file_hash)
+                }
+                Ok(result)
+            }
+            None => self.read_sequentially(src, src_full_len)
+        }
+    }
+
+    fn read_sequentially<T: Read>(&mut self, src: &mut T, src_full_len: Option<u64>) -> Result<BocReaderResult> {
+        #[cfg(not(target_family = "wasm"))]
+        let now = std::time::Instant::now();
 
         // TODO do not compute crc if header says crc isn't included
         let mut src = IoCrcFilter::new_reader(src);
 
         let header = self.read_header(&mut src)?;
-        let header_len = src.stream_position()? - position;
+        let header_len = src.processed();
 
         check_abort(self.abort)?;
 
-        Self::precheck_cells_tree_len(&header, header_len, src_full_len, true)?;
+        if let Some(src_full_len) = src_full_len {
+            Self::precheck_cells_tree_len(&header, header_len, src_full_len, true)?;
+        }
 
         // Skip index
         if header.index_included {
@@ -793,15 +922,16 @@ impl<'a> BocReader<'a> {
         // Read cells
         #[cfg(not(target_family = "wasm"))]
         let now1 = std::time::Instant::now();
-        let mut actual_data_size = src.stream_position()?;
+        let cells_start = src.processed();
         let mut remaining_big_cells = header.big_cells_count;
         for cell_index in 0..header.cells_count {
             check_abort(self.abort)?;
+            let rest = (header.tot_cells_size as u64).saturating_sub(src.processed() - cells_start);
             let raw_cell = Self::read_raw_cell(
-                &mut src, header.ref_size, cell_index, header.cells_count, &mut remaining_big_cells)?;
+                &mut src, header.ref_size, cell_index, header.cells_count, &mut remaining_big_cells, rest)?;
             self.indexed_cells.insert(cell_index as u32, raw_cell)?;
         }
-        actual_data_size = src.stream_position()? - actual_data_size;
+        let actual_data_size = src.processed() - cells_start;
         if actual_data_size as usize != header.tot_cells_size {
             fail!("actual data size disagrees with the size from header")
         }
@@ -819,7 +949,8 @@ impl<'a> BocReader<'a> {
                 refs.push(self.done_cells.get(raw_cell.refs[i])?)
             }
             let cell = DataCell::with_raw_data(refs, raw_cell.data, Some(self.max_depth))?;
-            self.done_cells.insert(cell_index as u32, Cell::with_cell_impl(cell))?;
+            let cell = self.make_cell(cell);
+            self.done_cells.insert(cell_index as u32, cell)?;
         }
         #[cfg(not(target_family = "wasm"))]
         let constructing_time = now1.elapsed().as_millis();
@@ -864,9 +995,38 @@ impl<'a> BocReader<'a> {
     }
 
     pub fn read_inmem(&mut self, data: Arc<Vec<u8>>) -> Result<BocReaderResult> {
+        let max_depth = self.max_depth;
+        self.read_inmem_ex(
+            data.deref(),
+            |refs, offset| DataCell::with_external_data(refs, &data, offset, Some(max_depth))
+        )
+    }
+
+    /// Reads BOC from the memory mapped file, cells refer to the mapped data without copying
+    #[cfg(feature = "mmap")]
+    pub fn read_mapped(&mut self, data: Arc<Mmap>) -> Result<BocReaderResult> {
+        let max_depth = self.max_depth;
+        self.read_inmem_ex(
+            data.deref(),
+            |refs, offset| DataCell::with_mapped_data(refs, &data, offset, Some(max_depth))
+        )
+    }
+
+    fn read_inmem_ex(
+        &mut self,
+        data: &[u8],
+        construct_cell: impl Fn(Vec<Cell>, usize) -> Result<DataCell>,
+    ) -> Result<BocReaderResult> {
         #[cfg(not(target_family = "wasm"))]
         let now = std::time::Instant::now();
-        let mut src = Cursor::new(data.deref());
+        let mut src = Cursor::new(data);
+
+        if let Some(expected) = &self.expected_file_hash {
+            let file_hash = UInt256::from(sha256_digest(data));
+            if &file_hash != expected {
+                fail!("file hash mismatch, expected: {:x}, actual: {:x}", expected, file_hash)
+            }
+        }
     
         let header = self.read_header(&mut src)?;
 
@@ -925,14 +1085,15 @@ impl<'a> BocReader<'a> {
                 refs.push(child.clone());
             }
     
-            let cell = DataCell::with_external_data(refs, &data, offset, Some(self.max_depth))?;
+            let cell = construct_cell(refs, offset)?;
             if cell.cell_type() == CellType::Big {
                 if remaining_big_cells == 0 {
                     fail!("Big cell is not allowed");
                 }
                 remaining_big_cells -= 1;
             }
-            self.done_cells.insert(cell_index as u32, Cell::with_cell_impl(cell))?;
+            let cell = self.make_cell(cell);
+            self.done_cells.insert(cell_index as u32, cell)?;
         }
         #[cfg(not(target_family = "wasm"))]
         let constructing_time = now1.elapsed().as_millis();
@@ -1150,7 +1311,8 @@ impl<'a> BocReader<'a> {
         cell_index: usize,
         cells_count: usize,
         remaining_big_cells: &mut usize,
-    ) -> Result<RawCell> where T: Read + Rest {
+        rest: u64,
+    ) -> Result<RawCell> where T: Read {
         let mut refs = [0; 4];
         let mut data;
         let mut d1d2 = [0_u8; 2];
@@ -1164,8 +1326,7 @@ impl<'a> BocReader<'a> {
             if len > MAX_BIG_DATA_BYTES {
                 fail!("big cell data length {} is too big", len);
             }
-            let rest = src.rest()?;
-            if len > rest as usize {
+            if len as u64 > rest {
                 fail!("big cell data length {} is too big (data rest is {})", len, rest);
             }
 
@@ -1260,17 +1421,193 @@ impl<'a> BocReader<'a> {
     }
 }
 
+/// Index of the bag of cells for random access: only the offset of every cell
+/// is computed up front, cells are constructed on demand and cached.
+/// The map of cell hashes is built on the first lookup by hash.
+/// The offset table from the file is used if BOC has it.
+pub struct BocIndex {
+    data: Arc<Vec<u8>>,
+    header: BocHeader,
+    offsets: Vec<usize>,
+    cells: Mutex<HashMap<u32, Cell>>,
+    indexes: OnceLock<HashMap<UInt256, u32>>,
+    max_depth: u16,
+}
+
+impl BocIndex {
+    pub fn with_data(data: Arc<Vec<u8>>) -> Result<Self> {
+        let mut src = Cursor::new(data.deref());
+        let header = BocReader::new().read_header(&mut src)?;
+        BocReader::precheck_cells_tree_len(&header, src.position(), data.len() as u64, false)?;
+
+        let cells_count = header.cells_count;
+        let mut offsets = Vec::with_capacity(cells_count);
+        if header.index_included {
+            let index_start = src.position() as usize;
+            let cells_start = index_start + cells_count * header.offset_size;
+            offsets.push(cells_start);
+            for i in 0..cells_count - 1 {
+                let o = index_start + i * header.offset_size;
+                let mut offset = Cursor::new(&data[o..o + header.offset_size])
+                    .read_be_uint(header.offset_size)? as usize;
+                if header.has_cache_bits {
+                    offset >>= 1;
+                }
+                offsets.push(cells_start + offset);
+            }
+        } else {
+            for _ in 0..cells_count {
+                offsets.push(src.position() as usize);
+                BocReader::skip_cell(&mut src, header.ref_size)?;
+            }
+        }
+
+        Ok(Self {
+            data,
+            header,
+            offsets,
+            cells: Mutex::new(HashMap::new()),
+            indexes: OnceLock::new(),
+            max_depth: MAX_SAFE_DEPTH,
+        })
+    }
+
+    pub fn header(&self) -> &BocHeader {
+        &self.header
+    }
+
+    pub fn cells_count(&self) -> usize {
+        self.offsets.len()
+    }
+
+    pub fn root_hashes(&self) -> Result<Vec<UInt256>> {
+        Ok(self.load_roots()?.iter().map(|root| root.repr_hash()).collect())
+    }
+
+    pub fn contains(&self, hash: &UInt256) -> Result<bool> {
+        Ok(self.indexes()?.contains_key(hash))
+    }
+
+    /// Constructs the cell with its subtree
+    pub fn load_cell(&self, hash: &UInt256) -> Result<Cell> {
+        match self.indexes()?.get(hash) {
+            Some(index) => self.load_cell_by_index(*index),
+            None => fail!("Cell {:x} was not found", hash)
+        }
+    }
+
+    pub fn load_roots(&self) -> Result<Vec<Cell>> {
+        Self::roots_indexes(&self.header).into_iter()
+            .map(|index| self.load_cell_by_index(index))
+            .collect()
+    }
+
+    /// Constructs the cell with its subtree, reusing and caching already constructed cells
+    pub fn load_cell_by_index(&self, index: u32) -> Result<Cell> {
+        if index as usize >= self.offsets.len() {
+            fail!("Cell #{} was not found", index)
+        }
+        let mut cells = self.cells.lock().map_err(|_| error!("cells cache is poisoned"))?;
+        // references always point to cells with greater indexes,
+        // so subtree is constructed from the greatest index to the least one
+        let mut subtree = HashSet::new();
+        let mut stack = vec![index];
+        while let Some(cell_index) = stack.pop() {
+            if !cells.contains_key(&cell_index) && subtree.insert(cell_index) {
+                stack.extend(Self::read_refs(&self.data, self.offsets[cell_index as usize], &self.header, cell_index as usize)?);
+            }
+        }
+        let mut subtree = subtree.into_iter().collect::<Vec<_>>();
+        subtree.sort_unstable_by(|a, b| b.cmp(a));
+
+        for cell_index in subtree {
+            let offset = self.offsets[cell_index as usize];
+            let mut refs = Vec::new();
+            for r in Self::read_refs(&self.data, offset, &self.header, cell_index as usize)? {
+                refs.push(cells.get(&r).cloned().ok_or_else(|| error!("Cell #{} was not found", r))?);
+            }
+            let cell = DataCell::with_external_data(refs, &self.data, offset, Some(self.max_depth))?;
+            cells.insert(cell_index, Cell::with_cell_impl(cell));
+        }
+        cells.get(&index).cloned().ok_or_else(|| error!("Cell #{} was not found", index))
+    }
+
+    fn indexes(&self) -> Result<&HashMap<UInt256, u32>> {
+        if let Some(indexes) = self.indexes.get() {
+            return Ok(indexes)
+        }
+        let _ = self.indexes.set(self.build_indexes()?);
+        self.indexes.get().ok_or_else(|| error!("index of hashes is not initialized"))
+    }
+
+    fn build_indexes(&self) -> Result<HashMap<UInt256, u32>> {
+        let cells_count = self.offsets.len();
+        // count parents to drop every constructed cell as soon as all its parents are done
+        let mut parents = vec![0_u32; cells_count];
+        for (cell_index, offset) in self.offsets.iter().enumerate() {
+            for r in Self::read_refs(&self.data, *offset, &self.header, cell_index)? {
+                parents[r as usize] += 1;
+            }
+        }
+
+        let mut cells = HashMap::new();
+        let mut indexes = HashMap::with_capacity(cells_count);
+        for cell_index in (0..cells_count).rev() {
+            let offset = self.offsets[cell_index];
+            let mut refs = Vec::new();
+            for r in Self::read_refs(&self.data, offset, &self.header, cell_index)? {
+                let child = cells.get(&r).cloned().ok_or_else(|| error!("Cell #{} was not found", r))?;
+                parents[r as usize] -= 1;
+                if parents[r as usize] == 0 {
+                    cells.remove(&r);
+                }
+                refs.push(child);
+            }
+            let cell = DataCell::with_external_data(refs, &self.data, offset, Some(self.max_depth))?;
+            let cell = Cell::with_cell_impl(cell);
+            indexes.entry(cell.repr_hash()).or_insert(cell_index as u32);
+            if parents[cell_index] != 0 {
+                cells.insert(cell_index as u32, cell);
+            }
+        }
+        Ok(indexes)
+    }
+
+    fn read_refs(data: &[u8], offset: usize, header: &BocHeader, cell_index: usize) -> Result<SmallVec<[u32; 4]>> {
+        if data.len() <= offset {
+            fail!("Invalid data: data too short or index is invalid");
+        }
+        let refs = BocReader::read_refs_indexes(
+            &mut Cursor::new(&data[offset..]), header.ref_size, cell_index, header.cells_count
+        )?;
+        if let Some(r) = refs.iter().find(|r| **r as usize >= header.cells_count) {
+            fail!("reference out of range, cells_count: {}, ref: {}", header.cells_count, r)
+        }
+        Ok(refs)
+    }
+
+    fn roots_indexes(header: &BocHeader) -> Vec<u32> {
+        if header.roots_indexes.is_empty() {
+            vec![0]
+        } else {
+            header.roots_indexes.clone()
+        }
+    }
+}
+
 /// Wraps I/O operations and computes CRC32-C of the data being processed
 struct IoCrcFilter<'a, T> {
     io_object: &'a mut T,
-    hasher: Crc32<'a>
+    hasher: Crc32<'a>,
+    processed: u64,
 }
 
 impl<'a, T: Write> IoCrcFilter<'a, T> {
     pub fn new_writer(io_object: &'a mut T) -> Self {
         IoCrcFilter{ 
             io_object,
-            hasher: Crc32::new()
+            hasher: Crc32::new(),
+            processed: 0,
         }
     }
 
@@ -1286,7 +1623,8 @@ impl<'a, T: Read> IoCrcFilter<'a, T> {
     pub fn new_reader(io_object: &'a mut T) -> Self {
         IoCrcFilter{ 
             io_object,
-            hasher: Crc32::new()
+            hasher: Crc32::new(),
+            processed: 0,
         }
     }
 
@@ -1300,17 +1638,19 @@ impl<'a, T: Read> IoCrcFilter<'a, T> {
     }
 }
 
-impl<'a, T> IoCrcFilter<'a, T> where T: Seek {
-    fn stream_position(&mut self) -> Result<u64> {
-        let p = self.io_object.stream_position()?;
-        Ok(p)
+impl<'a, T> IoCrcFilter<'a, T> {
+    /// Count of bytes passed through the filter
+    fn processed(&self) -> u64 {
+        self.processed
     }
 }
 
 impl<'a, T> Write for IoCrcFilter<'a, T> where T: Write {
     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
-        self.hasher.update(buf);
-        self.io_object.write(buf)
+        let len = self.io_object.write(buf)?;
+        self.hasher.update(&buf[..len]);
+        self.processed += len as u64;
+        Ok(len)
     }
 
     fn flush(&mut self) -> std::io::Result<()> {
@@ -1320,22 +1660,49 @@ impl<'a, T> Write for IoCrcFilter<'a, T> where T: Write {
 
 impl<'a, T> Read for IoCrcFilter<'a, T> where T: Read {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
-        let res = self.io_object.read(buf);
-        self.hasher.update(buf);
-        res
+        let len = self.io_object.read(buf)?;
+        self.hasher.update(&buf[..len]);
+        self.processed += len as u64;
+        Ok(len)
     }
 }
 
-trait Rest {
-    fn rest(&mut self) -> Result<u64>;
+/// Wraps I/O operations and computes SHA256 (file hash) of all the data being processed
+struct IoSha256Filter<'a, T> {
+    io_object: &'a mut T,
+    hasher: Sha256,
 }
 
-impl<'a, T> Rest for IoCrcFilter<'a, T> where T: Seek {
-    fn rest(&mut self) -> Result<u64> {
-        let p = self.io_object.stream_position()?;
-        let rest = self.io_object.seek(SeekFrom::End(0))? - p;
-        self.io_object.seek(SeekFrom::Start(p))?;
-        Ok(rest)
+impl<'a, T> IoSha256Filter<'a, T> {
+    fn new(io_object: &'a mut T) -> Self {
+        Self {
+            io_object,
+            hasher: Sha256::new(),
+        }
+    }
+
+    fn finalize(self) -> UInt256 {
+        UInt256::from(self.hasher.finalize())
+    }
+}
+
+impl<'a, T> Write for IoSha256Filter<'a, T> where T: Write {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let len = self.io_object.write(buf)?;
+        self.hasher.update(&buf[..len]);
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.io_object.flush()
+    }
+}
+
+impl<'a, T> Read for IoSha256Filter<'a, T> where T: Read {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let len = self.io_object.read(buf)?;
+        self.hasher.update(&buf[..len]);
+        Ok(len)
     }
 }
 
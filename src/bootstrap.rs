@@ -0,0 +1,174 @@
+/*
+* Copyright (C) 2019-2023 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use crate::{
+    blocks::{Block, BlockIdExt},
+    config_params::ConfigParams,
+    error::BlockError,
+    master::ShardHashes,
+    merkle_proof::MerkleProof,
+    shard::{ShardStateUnsplit, MASTERCHAIN_ID, SHARD_FULL},
+    signature::BlockProof,
+    Deserializable, error, fail, read_single_root_boc, Result, UInt256,
+};
+
+#[cfg(test)]
+#[path = "tests/test_bootstrap.rs"]
+mod tests;
+
+/// Verified data of the masterchain state
+#[derive(Clone, Debug)]
+pub struct TrustedMcState {
+    pub block_id: BlockIdExt,
+    pub config: ConfigParams,
+    pub shards: ShardHashes,
+}
+
+/// Light client bootstrap: starting from the zerostate with known hashes
+/// it walks through the chain of key block proofs (every key block is signed
+/// by validators from the previous one) and finally checks the proof of
+/// a recent masterchain block with the proof of its state.
+pub struct TrustedStateBootstrap {
+    zerostate_id: BlockIdExt,
+    last_key_block_id: BlockIdExt,
+    config: ConfigParams,
+}
+
+impl TrustedStateBootstrap {
+    /// Zerostate is checked against both root and file hashes
+    pub fn with_zerostate(zerostate_id: BlockIdExt, zerostate_boc: &[u8]) -> Result<Self> {
+        if !zerostate_id.shard().is_masterchain() || zerostate_id.seq_no() != 0 {
+            fail!(BlockError::InvalidArg(format!("{} is not a masterchain zerostate id", zerostate_id)))
+        }
+        if UInt256::calc_file_hash(zerostate_boc) != zerostate_id.file_hash {
+            fail!(BlockError::WrongHash)
+        }
+        let root = read_single_root_boc(zerostate_boc)?;
+        if root.repr_hash() != zerostate_id.root_hash {
+            fail!(BlockError::WrongHash)
+        }
+        let config = ShardStateUnsplit::construct_from_cell(root)?
+            .read_custom()?
+            .ok_or_else(|| error!(BlockError::InvalidData("zerostate has no McStateExtra".to_string())))?
+            .config
+            .clone();
+        Ok(Self {
+            last_key_block_id: zerostate_id.clone(),
+            zerostate_id,
+            config,
+        })
+    }
+
+    pub fn zerostate_id(&self) -> &BlockIdExt {
+        &self.zerostate_id
+    }
+
+    pub fn last_key_block_id(&self) -> &BlockIdExt {
+        &self.last_key_block_id
+    }
+
+    /// Config of the last trusted key block (or zerostate)
+    pub fn config(&self) -> &ConfigParams {
+        &self.config
+    }
+
+    /// Checks proof of the next key block and takes its config as trusted
+    pub fn add_key_block_proof(&mut self, proof: &BlockProof) -> Result<()> {
+        let block = self.check_block_proof(proof)?;
+        if !block.read_info()?.key_block() {
+            fail!(BlockError::InvalidData(format!("block {} is not a key block", proof.proof_for)))
+        }
+        let config = block
+            .read_extra()?
+            .read_custom()?
+            .and_then(|extra| extra.config().cloned())
+            .ok_or_else(|| error!(BlockError::InvalidData(
+                format!("proof of key block {} doesn't contain config", proof.proof_for)
+            )))?;
+        self.config = config;
+        self.last_key_block_id = proof.proof_for.clone();
+        Ok(())
+    }
+
+    /// Checks proof of the masterchain block and proof of its state,
+    /// the block must follow the last trusted key block
+    pub fn finish(self, block_proof: &BlockProof, state_proof: &MerkleProof) -> Result<TrustedMcState> {
+        let block = self.check_block_proof(block_proof)?;
+        let state_update = block.read_state_update()?;
+        if state_proof.hash != state_update.new_hash {
+            fail!(BlockError::WrongMerkleProof(
+                format!("state proof is not for the state of block {}", block_proof.proof_for)
+            ))
+        }
+        let state: ShardStateUnsplit = state_proof.virtualize()?;
+        if state.seq_no() != block_proof.proof_for.seq_no() || !state.shard().is_masterchain() {
+            fail!(BlockError::WrongMerkleProof(
+                format!("state proof is not for the state of block {}", block_proof.proof_for)
+            ))
+        }
+        let extra = state
+            .read_custom()?
+            .ok_or_else(|| error!(BlockError::InvalidData("state has no McStateExtra".to_string())))?;
+        Ok(TrustedMcState {
+            block_id: block_proof.proof_for.clone(),
+            config: extra.config,
+            shards: extra.shards,
+        })
+    }
+
+    /// Checks merkle proof and signatures of validators from the last trusted key block,
+    /// returns virtualized block
+    fn check_block_proof(&self, proof: &BlockProof) -> Result<Block> {
+        let id = &proof.proof_for;
+        if !id.shard().is_masterchain() {
+            fail!(BlockError::InvalidArg(format!("{} is not a masterchain block", id)))
+        }
+        if id.seq_no() <= self.last_key_block_id.seq_no() {
+            fail!(BlockError::InvalidArg(
+                format!("{} is not newer than the last key block {}", id, self.last_key_block_id)
+            ))
+        }
+        let merkle_proof = MerkleProof::construct_from_cell(proof.root.clone())?;
+        if merkle_proof.hash != id.root_hash {
+            fail!(BlockError::WrongMerkleProof(format!("proof is not for the block {}", id)))
+        }
+        let block: Block = merkle_proof.virtualize()?;
+        let info = block.read_info()?;
+        if info.seq_no() != id.seq_no() || !info.shard().is_masterchain() {
+            fail!(BlockError::WrongMerkleProof(format!("proof contains wrong info for the block {}", id)))
+        }
+        if info.prev_key_block_seqno() != self.last_key_block_id.seq_no() {
+            fail!(BlockError::InvalidData(format!(
+                "previous key block of {} is {} but the last trusted key block is {}",
+                id, info.prev_key_block_seqno(), self.last_key_block_id
+            )))
+        }
+
+        let signatures = proof.signatures.as_ref()
+            .ok_or_else(|| error!(BlockError::InvalidData(format!("proof of {} has no signatures", id))))?;
+        let (validators, hash_short) = self.config.validator_set()?.calc_subset(
+            &self.config.catchain_config()?,
+            SHARD_FULL,
+            MASTERCHAIN_ID,
+            info.gen_catchain_seqno(),
+            info.gen_utime(),
+        )?;
+        if signatures.validator_info.catchain_seqno != info.gen_catchain_seqno()
+            || signatures.validator_info.validator_list_hash_short != hash_short
+            || info.gen_validator_list_hash_short() != hash_short {
+            fail!(BlockError::InvalidData(format!("block {} is signed by wrong validator set", id)))
+        }
+        proof.check_signatures_with_list(&validators, 0)?;
+        Ok(block)
+    }
+}
@@ -0,0 +1,144 @@
+/*
+* Copyright (C) 2019-2023 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use crate::{cell::{Cell, CellImpl}, UInt256};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, Weak, atomic::{AtomicU64, Ordering}},
+};
+
+#[cfg(test)]
+#[path = "tests/test_interner.rs"]
+mod tests;
+
+const SHARDS_COUNT: usize = 32;
+const MIN_PURGE_THRESHOLD: usize = 1024;
+
+lazy_static::lazy_static! {
+    static ref GLOBAL_INTERNER: Arc<CellInterner> = Arc::new(CellInterner::new());
+}
+
+struct InternerShard {
+    cells: HashMap<UInt256, Weak<dyn CellImpl>>,
+    purge_threshold: usize,
+}
+
+impl Default for InternerShard {
+    fn default() -> Self {
+        Self {
+            cells: HashMap::new(),
+            purge_threshold: MIN_PURGE_THRESHOLD,
+        }
+    }
+}
+
+impl InternerShard {
+    fn purge(&mut self) -> usize {
+        let before = self.cells.len();
+        self.cells.retain(|_, cell| cell.strong_count() != 0);
+        self.purge_threshold = (self.cells.len() * 2).max(MIN_PURGE_THRESHOLD);
+        before - self.cells.len()
+    }
+}
+
+/// Cells statistics of the interner
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct CellInternerStats {
+    /// Cells replaced with already loaded ones
+    pub hits: u64,
+    /// Cells added to the pool
+    pub misses: u64,
+}
+
+/// Pool of loaded cells: cells equal by representation hash are replaced
+/// with the one already loaded, so they share the same allocation.
+/// Pool holds only weak references, entries of dropped cells are removed
+/// when the pool grows twice since the last purge or on `purge` call.
+/// Cells have to be interned from leaves to the root (as `BocReader` does)
+/// to share whole subtrees.
+pub struct CellInterner {
+    shards: Vec<Mutex<InternerShard>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl Default for CellInterner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CellInterner {
+    pub fn new() -> Self {
+        Self {
+            shards: (0..SHARDS_COUNT).map(|_| Mutex::new(InternerShard::default())).collect(),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Process-wide pool
+    pub fn global() -> Arc<CellInterner> {
+        GLOBAL_INTERNER.clone()
+    }
+
+    /// Returns loaded cell with the same representation hash or stores the given one.
+    /// Virtualized cells are returned as is because their hashes differ from the stored ones
+    pub fn intern(&self, cell: Cell) -> Cell {
+        if cell.virtualization() != 0 {
+            return cell
+        }
+        let hash = cell.repr_hash();
+        let mut shard = self.shard(&hash);
+        if let Some(existing) = shard.cells.get(&hash).and_then(|cell| cell.upgrade()) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Cell::with_cell_impl_arc(existing)
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        shard.cells.insert(hash, Arc::downgrade(cell.cell_impl()));
+        if shard.cells.len() >= shard.purge_threshold {
+            shard.purge();
+        }
+        cell
+    }
+
+    /// Removes entries of dropped cells, returns count of removed entries
+    pub fn purge(&self) -> usize {
+        self.shards.iter().map(|shard| Self::lock(shard).purge()).sum()
+    }
+
+    /// Count of entries including not yet purged ones
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(|shard| Self::lock(shard).cells.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn stats(&self) -> CellInternerStats {
+        CellInternerStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+
+    fn shard(&self, hash: &UInt256) -> std::sync::MutexGuard<'_, InternerShard> {
+        Self::lock(&self.shards[hash.as_slice()[0] as usize % SHARDS_COUNT])
+    }
+
+    fn lock(shard: &Mutex<InternerShard>) -> std::sync::MutexGuard<'_, InternerShard> {
+        // pool contains only weak references, so it stays consistent even after panic
+        shard.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
@@ -18,6 +18,8 @@ use std::{
     sync::{Arc, Weak, atomic::{AtomicU64, Ordering}}
 };
 use num::{FromPrimitive, ToPrimitive};
+#[cfg(feature = "mmap")]
+use memmap2::Mmap;
 
 pub const SHA256_SIZE: usize = 32;
 pub const DEPTH_SIZE: usize = 2;
@@ -1046,6 +1048,22 @@ enum CellBuffer {
     External{
         buf: Arc<Vec<u8>>,
         offset: usize,
+    },
+    #[cfg(feature = "mmap")]
+    Mapped{
+        buf: MappedBuffer,
+        offset: usize,
+    },
+}
+
+#[cfg(feature = "mmap")]
+#[derive(Clone, Debug)]
+struct MappedBuffer(Arc<Mmap>);
+
+#[cfg(feature = "mmap")]
+impl PartialEq for MappedBuffer {
+    fn eq(&self, other: &Self) -> bool {
+        self.0[..] == other.0[..]
     }
 }
 
@@ -1053,19 +1071,25 @@ impl CellBuffer {
     pub fn data(&self) -> &[u8] {
         match &self {
             CellBuffer::Local(d) => d,
-            CellBuffer::External{ buf, offset} => &buf[*offset..*offset + full_len(&buf[*offset..])]
+            CellBuffer::External{ buf, offset} => &buf[*offset..*offset + full_len(&buf[*offset..])],
+            #[cfg(feature = "mmap")]
+            CellBuffer::Mapped{ buf, offset} => &buf.0[*offset..*offset + full_len(&buf.0[*offset..])],
         }
     }
     pub fn unbounded_data(&self) -> &[u8] {
         match &self {
             CellBuffer::Local(d) => d,
-            CellBuffer::External{ buf, offset} => &buf[*offset..]
+            CellBuffer::External{ buf, offset} => &buf[*offset..],
+            #[cfg(feature = "mmap")]
+            CellBuffer::Mapped{ buf, offset} => &buf.0[*offset..],
         }
     }
     pub fn unbounded_data_mut(&mut self) -> Result<&mut [u8]> {
         match self {
             CellBuffer::Local(d) => Ok(d),
-            CellBuffer::External{ buf: _, offset: _} => fail!("Can't change extarnal buffer")
+            CellBuffer::External{ buf: _, offset: _} => fail!("Can't change extarnal buffer"),
+            #[cfg(feature = "mmap")]
+            CellBuffer::Mapped{ buf: _, offset: _} => fail!("Can't change mapped buffer"),
         }
     }
 }
@@ -1148,6 +1172,22 @@ impl CellData {
         })
     }
 
+    /// Cell data refers to the memory mapped file without copying
+    #[cfg(feature = "mmap")]
+    pub fn with_mapped_data(buffer: &Arc<Mmap>, offset: usize) -> Result<Self> {
+
+        check_cell_buf(&buffer[offset..], true)?;
+
+        let allocate_for_hashes = (!store_hashes(&buffer[offset..])) as usize * (level(&buffer[offset..]) as usize + 1);
+        Ok(Self{
+            buf: CellBuffer::Mapped{
+                buf: MappedBuffer(buffer.clone()),
+                offset,
+            },
+            hashes_depths: Vec::with_capacity(allocate_for_hashes)
+        })
+    }
+
     pub fn with_raw_data(data: Vec<u8>) -> Result<Self> {
 
         check_cell_buf(&data, false)?;
@@ -1422,6 +1462,17 @@ impl DataCell {
         Self::construct_cell(cell_data, references, max_depth)
     }
 
+    #[cfg(feature = "mmap")]
+    pub fn with_mapped_data(
+        references: Vec<Cell>,
+        buffer: &Arc<Mmap>,
+        offset: usize,
+        max_depth: Option<u16>,
+    ) -> Result<DataCell> {
+        let cell_data = CellData::with_mapped_data(buffer, offset)?;
+        Self::construct_cell(cell_data, references, max_depth)
+    }
+
     pub fn with_raw_data(
         references: Vec<Cell>,
         data: Vec<u8>,
@@ -1959,6 +2010,10 @@ pub use self::builder::*;
 mod builder_operations;
 
 pub use self::builder_operations::*;
+
+mod interner;
+
+pub use self::interner::*;
 use smallvec::SmallVec;
 
 pub(crate) fn to_hex_string(data: impl AsRef<[u8]>, len: usize, lower: bool) -> String {
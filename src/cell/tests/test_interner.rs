@@ -0,0 +1,70 @@
+/*
+* Copyright (C) 2019-2023 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use super::*;
+use crate::{write_boc, BocReader, BuilderData, IBitstring};
+
+fn build_cell(value: u32) -> Cell {
+    let mut child = BuilderData::new();
+    child.append_u32(value).unwrap();
+    let mut root = BuilderData::new();
+    root.append_u32(value + 1).unwrap();
+    root.checked_append_reference(child.into_cell().unwrap()).unwrap();
+    root.into_cell().unwrap()
+}
+
+#[test]
+fn test_intern_cells() {
+    let interner = CellInterner::new();
+    let cell1 = interner.intern(build_cell(1));
+    let cell2 = interner.intern(build_cell(1));
+    let other = interner.intern(build_cell(2));
+    assert!(Arc::ptr_eq(cell1.cell_impl(), cell2.cell_impl()));
+    assert!(!Arc::ptr_eq(cell1.cell_impl(), other.cell_impl()));
+    assert_eq!(interner.stats(), CellInternerStats { hits: 1, misses: 2 });
+    assert_eq!(interner.len(), 2);
+
+    drop(cell1);
+    assert_eq!(interner.purge(), 0);
+    drop(cell2);
+    drop(other);
+    assert_eq!(interner.purge(), 2);
+    assert!(interner.is_empty());
+}
+
+#[test]
+fn test_boc_reader_with_interner() {
+    let boc = write_boc(&build_cell(10)).unwrap();
+    let interner = Arc::new(CellInterner::new());
+    let read = || BocReader::new()
+        .set_cell_interner(interner.clone())
+        .read_inmem(Arc::new(boc.clone())).unwrap()
+        .withdraw_single_root().unwrap();
+    let root1 = read();
+    let root2 = read();
+    assert!(Arc::ptr_eq(root1.cell_impl(), root2.cell_impl()));
+    assert_eq!(interner.stats(), CellInternerStats { hits: 2, misses: 2 });
+
+    let plain = BocReader::new().read_inmem(Arc::new(boc)).unwrap().withdraw_single_root().unwrap();
+    assert!(!Arc::ptr_eq(root1.cell_impl(), plain.cell_impl()));
+    assert_eq!(plain, root1);
+}
+
+#[test]
+fn test_interner_shard_purge_threshold() {
+    // new shard doesn't purge on the first insert
+    let mut shard = InternerShard::default();
+    assert_eq!(shard.purge_threshold, MIN_PURGE_THRESHOLD);
+    assert_eq!(shard.purge(), 0);
+    assert_eq!(shard.purge_threshold, MIN_PURGE_THRESHOLD);
+}
@@ -0,0 +1,218 @@
+/*
+* Copyright (C) 2019-2023 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use crate::{Cell, CellType, Result, UInt256};
+use std::{collections::{HashSet, VecDeque}, io::Write};
+
+#[cfg(test)]
+#[path = "tests/test_cell_graph.rs"]
+mod tests;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum GraphFormat {
+    /// Graphviz
+    Dot,
+    GraphML,
+    /// One JSON object per line for every node and edge
+    JsonL,
+}
+
+/// Limits of the exported part of the cells tree
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CellGraphOptions {
+    /// Cells deeper than this (root has depth 0) are not exported
+    pub max_depth: usize,
+    /// Max count of exported unique cells
+    pub max_cells: usize,
+}
+
+impl Default for CellGraphOptions {
+    fn default() -> Self {
+        Self {
+            max_depth: usize::MAX,
+            max_cells: usize::MAX,
+        }
+    }
+}
+
+/// Counts of exported nodes and edges
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct CellGraphStat {
+    pub nodes: usize,
+    pub edges: usize,
+    /// Count of exported nodes which have some not exported children
+    pub truncated: usize,
+}
+
+struct Node {
+    cell: Cell,
+    hash: UInt256,
+    /// Exported children with their indexes among the cell references
+    children: Vec<(usize, UInt256)>,
+    truncated: bool,
+}
+
+fn cell_type_name(cell_type: CellType) -> &'static str {
+    match cell_type {
+        CellType::Ordinary => "ordinary",
+        CellType::PrunedBranch => "pruned_branch",
+        CellType::LibraryReference => "library_reference",
+        CellType::MerkleProof => "merkle_proof",
+        CellType::MerkleUpdate => "merkle_update",
+        CellType::Big => "big",
+        CellType::Unknown => "unknown",
+    }
+}
+
+/// Collects unique cells in breadth-first order within the limits
+fn collect_nodes(root: &Cell, options: &CellGraphOptions) -> Result<Vec<Node>> {
+    let mut nodes = Vec::new();
+    let mut visited = HashSet::new();
+    let mut queue = VecDeque::new();
+    if options.max_cells > 0 {
+        visited.insert(root.repr_hash());
+        queue.push_back((root.clone(), 0));
+    }
+    while let Some((cell, depth)) = queue.pop_front() {
+        let mut node = Node {
+            hash: cell.repr_hash(),
+            cell: cell.clone(),
+            children: Vec::with_capacity(cell.references_count()),
+            truncated: false,
+        };
+        for i in 0..cell.references_count() {
+            let child = cell.reference(i)?;
+            let child_hash = child.repr_hash();
+            if visited.contains(&child_hash) {
+                node.children.push((i, child_hash));
+            } else if depth < options.max_depth && visited.len() < options.max_cells {
+                visited.insert(child_hash.clone());
+                node.children.push((i, child_hash));
+                queue.push_back((child, depth + 1));
+            } else {
+                node.truncated = true;
+            }
+        }
+        nodes.push(node);
+    }
+    Ok(nodes)
+}
+
+/// Exports DAG of cells (each unique cell once) with hashes, bit lengths
+/// and types of the cells for analysis with standard graph tools
+pub fn export_cell_graph(
+    root: &Cell,
+    format: GraphFormat,
+    options: &CellGraphOptions,
+    dest: &mut impl Write,
+) -> Result<CellGraphStat> {
+    let nodes = collect_nodes(root, options)?;
+    let mut stat = CellGraphStat::default();
+    match format {
+        GraphFormat::Dot => {
+            writeln!(dest, "digraph cells {{")?;
+            for node in &nodes {
+                let cell = &node.cell;
+                let shape = if cell.cell_type() == CellType::Ordinary { "box" } else { "octagon" };
+                let style = if node.truncated { ", style=dashed" } else { "" };
+                writeln!(
+                    dest,
+                    "  \"{:x}\" [label=\"{}\\n{} bits, {} refs\\n{}\", shape={}{}];",
+                    node.hash, &node.hash.to_hex_string()[..8], cell.bit_length(),
+                    cell.references_count(), cell_type_name(cell.cell_type()), shape, style
+                )?;
+            }
+            for node in &nodes {
+                for (i, child) in &node.children {
+                    writeln!(dest, "  \"{:x}\" -> \"{:x}\" [label=\"{}\"];", node.hash, child, i)?;
+                }
+            }
+            writeln!(dest, "}}")?;
+        }
+        GraphFormat::GraphML => {
+            writeln!(dest, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+            writeln!(dest, "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">")?;
+            for (id, kind, attr_type) in [
+                ("bits", "node", "int"),
+                ("refs", "node", "int"),
+                ("cell_type", "node", "string"),
+                ("level", "node", "int"),
+                ("depth", "node", "int"),
+                ("truncated", "node", "boolean"),
+                ("index", "edge", "int"),
+            ] {
+                writeln!(
+                    dest,
+                    "  <key id=\"{}\" for=\"{}\" attr.name=\"{}\" attr.type=\"{}\"/>",
+                    id, kind, id, attr_type
+                )?;
+            }
+            writeln!(dest, "  <graph id=\"cells\" edgedefault=\"directed\">")?;
+            for node in &nodes {
+                let cell = &node.cell;
+                writeln!(dest, "    <node id=\"{:x}\">", node.hash)?;
+                writeln!(dest, "      <data key=\"bits\">{}</data>", cell.bit_length())?;
+                writeln!(dest, "      <data key=\"refs\">{}</data>", cell.references_count())?;
+                writeln!(dest, "      <data key=\"cell_type\">{}</data>", cell_type_name(cell.cell_type()))?;
+                writeln!(dest, "      <data key=\"level\">{}</data>", cell.level())?;
+                writeln!(dest, "      <data key=\"depth\">{}</data>", cell.repr_depth())?;
+                writeln!(dest, "      <data key=\"truncated\">{}</data>", node.truncated)?;
+                writeln!(dest, "    </node>")?;
+            }
+            for node in &nodes {
+                for (i, child) in &node.children {
+                    writeln!(
+                        dest,
+                        "    <edge source=\"{:x}\" target=\"{:x}\"><data key=\"index\">{}</data></edge>",
+                        node.hash, child, i
+                    )?;
+                }
+            }
+            writeln!(dest, "  </graph>")?;
+            writeln!(dest, "</graphml>")?;
+        }
+        GraphFormat::JsonL => {
+            for node in &nodes {
+                let cell = &node.cell;
+                let json = serde_json::json!({
+                    "type": "node",
+                    "hash": node.hash.to_hex_string(),
+                    "bits": cell.bit_length(),
+                    "refs": cell.references_count(),
+                    "cell_type": cell_type_name(cell.cell_type()),
+                    "level": cell.level(),
+                    "depth": cell.repr_depth(),
+                    "truncated": node.truncated,
+                });
+                writeln!(dest, "{}", json)?;
+            }
+            for node in &nodes {
+                for (i, child) in &node.children {
+                    let json = serde_json::json!({
+                        "type": "edge",
+                        "from": node.hash.to_hex_string(),
+                        "to": child.to_hex_string(),
+                        "index": i,
+                    });
+                    writeln!(dest, "{}", json)?;
+                }
+            }
+        }
+    }
+    for node in &nodes {
+        stat.nodes += 1;
+        stat.edges += node.children.len();
+        stat.truncated += node.truncated as usize;
+    }
+    Ok(stat)
+}
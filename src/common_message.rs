@@ -1,5 +1,5 @@
 use crate::{
-    error::BlockError, messages::Message, Deserializable, Serializable, SERDE_OPTS_COMMON_MESSAGE,
+    error::BlockError, messages::Message, Deserializable, SemanticEq, Serializable, SERDE_OPTS_COMMON_MESSAGE,
     SERDE_OPTS_EMPTY, error, fail, Error, BuilderData, IBitstring, Result, SliceData
 };
 
@@ -132,3 +132,9 @@ impl Deserializable for CommonMessage {
         Ok(())
     }
 }
+
+impl SemanticEq for CommonMessage {
+    fn semantic_eq(&self, other: &Self) -> bool {
+        self == other
+    }
+}
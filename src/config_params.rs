@@ -12,6 +12,7 @@
 */
 
 use crate::{
+    accounts::{StorageUsed, StorageUsedShort},
     define_HashmapE,
     error::BlockError,
     dictionary::hashmapaug::HashmapAugType,
@@ -163,7 +164,18 @@ impl ConfigParams {
         };
         Ok(addr)
     }
-    // TODO 4 dns_root_addr
+    pub fn dns_root_address(&self) -> Result<UInt256> {
+        match self.config(4)? {
+            Some(ConfigParamEnum::ConfigParam4(param)) => Ok(param.dns_root_addr),
+            _ => fail!("no dns root address in config")
+        }
+    }
+    pub fn owner_address(&self) -> Result<UInt256> {
+        match self.config(5)? {
+            Some(ConfigParamEnum::ConfigParam5(param)) => Ok(param.owner_addr),
+            _ => fail!("no owner address in config")
+        }
+    }
     pub fn mint_prices(&self) -> Result<ConfigParam6> {
         match self.config(6)? {
             Some(ConfigParamEnum::ConfigParam6(cp)) => Ok(cp),
@@ -188,14 +200,30 @@ impl ConfigParams {
             _ => fail!("no mandatory params in config")
         }
     }
-    // TODO 11 ConfigVotingSetup
+    pub fn critical_params(&self) -> Result<MandatoryParams> {
+        match self.config(10)? {
+            Some(ConfigParamEnum::ConfigParam10(cp)) => Ok(cp.critical_params),
+            _ => fail!("no critical params in config")
+        }
+    }
+    pub fn config_voting_setup(&self) -> Result<ConfigVotingSetup> {
+        match self.config(11)? {
+            Some(ConfigParamEnum::ConfigParam11(param)) => Ok(param),
+            _ => fail!("no config 11 (config voting setup)")
+        }
+    }
     pub fn workchains(&self) -> Result<Workchains> {
         match self.config(12)? {
             Some(ConfigParamEnum::ConfigParam12(param)) => Ok(param.workchains),
             _ => fail!("Workchains not found in config")
         }
     }
-    // TODO 13 compliant pricing
+    pub fn complaint_pricing(&self) -> Result<ConfigParam13> {
+        match self.config(13)? {
+            Some(ConfigParamEnum::ConfigParam13(param)) => Ok(param),
+            _ => fail!("no config 13 (complaint pricing)")
+        }
+    }
     pub fn block_create_fees(&self, masterchain: bool) -> Result<Grams> {
         match self.config(14)? {
             Some(ConfigParamEnum::ConfigParam14(param)) => if masterchain {
@@ -206,6 +234,12 @@ impl ConfigParams {
             _ => fail!("no block create fee parameter")
         }
     }
+    pub fn block_create_fees_config(&self) -> Result<BlockCreateFees> {
+        match self.config(14)? {
+            Some(ConfigParamEnum::ConfigParam14(param)) => Ok(param.block_create_fees),
+            _ => fail!("no block create fee parameter")
+        }
+    }
     pub fn elector_params(&self) -> Result<ConfigParam15> {
         match self.config(15)? {
             Some(ConfigParamEnum::ConfigParam15(param)) => Ok(param),
@@ -224,8 +258,6 @@ impl ConfigParams {
             _ => fail!("no stakes params in config")
         }
     }
-    // TODO 16 validators count
-    // TODO 17 stakes config
     pub fn storage_prices(&self) -> Result<ConfigParam18> {
         match self.config(18)? {
             Some(ConfigParamEnum::ConfigParam18(param)) => Ok(param),
@@ -242,6 +274,10 @@ impl ConfigParams {
         }
         fail!("Gas prices not found")
     }
+    /// Gas fee with prices of masterchain (ConfigParam20) or workchain (ConfigParam21)
+    pub fn compute_gas_fee(&self, is_masterchain: bool, gas_used: u64) -> Result<Grams> {
+        self.gas_prices(is_masterchain)?.compute_fee(gas_used)
+    }
     pub fn block_limits(&self, masterchain: bool) -> Result<BlockLimits> {
         if masterchain {
             if let Some(ConfigParamEnum::ConfigParam22(param)) = self.config(22)? {
@@ -274,7 +310,6 @@ impl ConfigParams {
             _ => fail!("no ConsensusConfig in config_params")
         }
     }
-    // TODO 29 consensus config
     pub fn fundamental_smc_addr(&self) -> Result<FundamentalSmcAddresses> {
         match self.config(31)? {
             Some(ConfigParamEnum::ConfigParam31(param)) => Ok(param.fundamental_smc_addr),
@@ -342,8 +377,19 @@ impl ConfigParams {
             _ =>  fail!("wrong config 44 (suspended addresses)")
         }
     }
-    // TODO 39 validator signed temp keys
-    // ConfigParam58(MeshConfig),
+    pub fn validator_signed_temp_keys(&self) -> Result<Option<ValidatorKeys>> {
+        match self.config(39)? {
+            Some(ConfigParamEnum::ConfigParam39(param)) => Ok(Some(param.validator_keys)),
+            None => Ok(None),
+            _ =>  fail!("wrong config 39 (validator signed temp keys)")
+        }
+    }
+    pub fn slashing_config(&self) -> Result<SlashingConfig> {
+        match self.config(40)? {
+            Some(ConfigParamEnum::ConfigParam40(param)) => Ok(param.slashing_config),
+            _ => fail!("no config 40 (slashing config)")
+        }
+    }
     pub fn mesh_config(&self) -> Result<Option<MeshConfig>> {
         match self.config(58)? {
             Some(ConfigParamEnum::ConfigParam58(mc)) => Ok(Some(mc)),
@@ -359,9 +405,158 @@ impl ConfigParams {
     }
 }
 
-#[derive(Clone, Copy, Debug)]
-#[repr(u64)]
-pub enum GlobalCapabilities {
+//
+// Typed setters
+//
+impl ConfigParams {
+    pub fn set_config_address(&mut self, config_addr: UInt256) -> Result<()> {
+        self.set_config(ConfigParamEnum::ConfigParam0(ConfigParam0 { config_addr }))
+    }
+    pub fn set_elector_address(&mut self, elector_addr: UInt256) -> Result<()> {
+        self.set_config(ConfigParamEnum::ConfigParam1(ConfigParam1 { elector_addr }))
+    }
+    pub fn set_minter_address(&mut self, minter_addr: UInt256) -> Result<()> {
+        self.set_config(ConfigParamEnum::ConfigParam2(ConfigParam2 { minter_addr }))
+    }
+    pub fn set_fee_collector_address(&mut self, fee_collector_addr: UInt256) -> Result<()> {
+        self.set_config(ConfigParamEnum::ConfigParam3(ConfigParam3 { fee_collector_addr }))
+    }
+    pub fn set_dns_root_address(&mut self, dns_root_addr: UInt256) -> Result<()> {
+        self.set_config(ConfigParamEnum::ConfigParam4(ConfigParam4 { dns_root_addr }))
+    }
+    pub fn set_owner_address(&mut self, owner_addr: UInt256) -> Result<()> {
+        self.set_config(ConfigParamEnum::ConfigParam5(ConfigParam5 { owner_addr }))
+    }
+    pub fn set_mint_prices(&mut self, prices: ConfigParam6) -> Result<()> {
+        self.set_config(ConfigParamEnum::ConfigParam6(prices))
+    }
+    pub fn set_to_mint(&mut self, to_mint: ExtraCurrencyCollection) -> Result<()> {
+        self.set_config(ConfigParamEnum::ConfigParam7(ConfigParam7 { to_mint }))
+    }
+    pub fn set_global_version(&mut self, global_version: GlobalVersion) -> Result<()> {
+        self.set_config(ConfigParamEnum::ConfigParam8(ConfigParam8 { global_version }))
+    }
+    pub fn set_mandatory_params(&mut self, mandatory_params: MandatoryParams) -> Result<()> {
+        self.set_config(ConfigParamEnum::ConfigParam9(ConfigParam9 { mandatory_params }))
+    }
+    pub fn set_critical_params(&mut self, critical_params: MandatoryParams) -> Result<()> {
+        self.set_config(ConfigParamEnum::ConfigParam10(ConfigParam10 { critical_params }))
+    }
+    pub fn set_config_voting_setup(&mut self, setup: ConfigVotingSetup) -> Result<()> {
+        self.set_config(ConfigParamEnum::ConfigParam11(setup))
+    }
+    pub fn set_workchains(&mut self, workchains: Workchains) -> Result<()> {
+        self.set_config(ConfigParamEnum::ConfigParam12(ConfigParam12 { workchains }))
+    }
+    pub fn set_complaint_pricing(&mut self, pricing: ConfigParam13) -> Result<()> {
+        self.set_config(ConfigParamEnum::ConfigParam13(pricing))
+    }
+    pub fn set_block_create_fees(&mut self, block_create_fees: BlockCreateFees) -> Result<()> {
+        self.set_config(ConfigParamEnum::ConfigParam14(ConfigParam14 { block_create_fees }))
+    }
+    pub fn set_elector_params(&mut self, params: ConfigParam15) -> Result<()> {
+        self.set_config(ConfigParamEnum::ConfigParam15(params))
+    }
+    pub fn set_validators_count(&mut self, count: ConfigParam16) -> Result<()> {
+        self.set_config(ConfigParamEnum::ConfigParam16(count))
+    }
+    pub fn set_stakes_config(&mut self, stakes: ConfigParam17) -> Result<()> {
+        self.set_config(ConfigParamEnum::ConfigParam17(stakes))
+    }
+    pub fn set_storage_prices(&mut self, prices: ConfigParam18) -> Result<()> {
+        self.set_config(ConfigParamEnum::ConfigParam18(prices))
+    }
+    pub fn set_gas_prices(&mut self, is_masterchain: bool, prices: GasLimitsPrices) -> Result<()> {
+        if is_masterchain {
+            self.set_config(ConfigParamEnum::ConfigParam20(prices))
+        } else {
+            self.set_config(ConfigParamEnum::ConfigParam21(prices))
+        }
+    }
+    pub fn set_block_limits(&mut self, masterchain: bool, limits: BlockLimits) -> Result<()> {
+        if masterchain {
+            self.set_config(ConfigParamEnum::ConfigParam22(limits))
+        } else {
+            self.set_config(ConfigParamEnum::ConfigParam23(limits))
+        }
+    }
+    pub fn set_fwd_prices(&mut self, is_masterchain: bool, prices: MsgForwardPrices) -> Result<()> {
+        if is_masterchain {
+            self.set_config(ConfigParamEnum::ConfigParam24(prices))
+        } else {
+            self.set_config(ConfigParamEnum::ConfigParam25(prices))
+        }
+    }
+    pub fn set_catchain_config(&mut self, catchain_config: CatchainConfig) -> Result<()> {
+        self.set_config(ConfigParamEnum::ConfigParam28(catchain_config))
+    }
+    pub fn set_consensus_config(&mut self, consensus_config: ConsensusConfig) -> Result<()> {
+        self.set_config(ConfigParamEnum::ConfigParam29(ConfigParam29 { consensus_config }))
+    }
+    pub fn set_delector_parameters(&mut self, params: DelectorParams) -> Result<()> {
+        self.set_config(ConfigParamEnum::ConfigParam30(params))
+    }
+    pub fn set_fundamental_smc_addr(&mut self, fundamental_smc_addr: FundamentalSmcAddresses) -> Result<()> {
+        self.set_config(ConfigParamEnum::ConfigParam31(ConfigParam31 { fundamental_smc_addr }))
+    }
+    pub fn set_prev_validator_set(&mut self, prev_validators: ValidatorSet) -> Result<()> {
+        self.set_config(ConfigParamEnum::ConfigParam32(ConfigParam32 { prev_validators }))
+    }
+    pub fn set_prev_temp_validator_set(&mut self, prev_temp_validators: ValidatorSet) -> Result<()> {
+        self.set_config(ConfigParamEnum::ConfigParam33(ConfigParam33 { prev_temp_validators }))
+    }
+    pub fn set_validator_set(&mut self, cur_validators: ValidatorSet) -> Result<()> {
+        self.set_config(ConfigParamEnum::ConfigParam34(ConfigParam34 { cur_validators }))
+    }
+    pub fn set_temp_validator_set(&mut self, cur_temp_validators: ValidatorSet) -> Result<()> {
+        self.set_config(ConfigParamEnum::ConfigParam35(ConfigParam35 { cur_temp_validators }))
+    }
+    pub fn set_next_validator_set(&mut self, next_validators: ValidatorSet) -> Result<()> {
+        self.set_config(ConfigParamEnum::ConfigParam36(ConfigParam36 { next_validators }))
+    }
+    pub fn set_next_temp_validator_set(&mut self, next_temp_validators: ValidatorSet) -> Result<()> {
+        self.set_config(ConfigParamEnum::ConfigParam37(ConfigParam37 { next_temp_validators }))
+    }
+    pub fn set_validator_signed_temp_keys(&mut self, validator_keys: ValidatorKeys) -> Result<()> {
+        self.set_config(ConfigParamEnum::ConfigParam39(ConfigParam39 { validator_keys }))
+    }
+    pub fn set_slashing_config(&mut self, slashing_config: SlashingConfig) -> Result<()> {
+        self.set_config(ConfigParamEnum::ConfigParam40(ConfigParam40 { slashing_config }))
+    }
+    pub fn set_copyleft_config(&mut self, copyleft: ConfigCopyleft) -> Result<()> {
+        self.set_config(ConfigParamEnum::ConfigParam42(copyleft))
+    }
+    pub fn set_suspended_addresses(&mut self, addresses: SuspendedAddresses) -> Result<()> {
+        self.set_config(ConfigParamEnum::ConfigParam44(addresses))
+    }
+    pub fn set_mesh_config(&mut self, mesh_config: MeshConfig) -> Result<()> {
+        self.set_config(ConfigParamEnum::ConfigParam58(mesh_config))
+    }
+}
+
+/// Set of global capabilities from ConfigParam8. Unknown bits are preserved as is,
+/// so capabilities enabled by newer versions survive the round trip through this type.
+#[derive(Clone, Copy, Default, Eq, Hash, PartialEq)]
+pub struct GlobalCapabilities(u64);
+
+macro_rules! define_capabilities {
+    ( $( $(#[$attr:meta])* $name:ident = $value:expr, )* ) => {
+        #[allow(non_upper_case_globals)]
+        impl GlobalCapabilities {
+            $( $(#[$attr])* pub const $name: Self = Self($value); )*
+
+            /// Named capabilities known to this version of the crate
+            pub fn known() -> Vec<(&'static str, Self)> {
+                #[allow(unused_mut)]
+                let mut known = Vec::new();
+                $( $(#[$attr])* known.push((stringify!($name), Self::$name)); )*
+                known
+            }
+        }
+    };
+}
+
+define_capabilities! {
     CapNone                   = 0,
     CapIhrEnabled             = 0x0000_0000_0001,
     CapCreateStatsEnabled     = 0x0000_0000_0002,
@@ -403,10 +598,81 @@ pub enum GlobalCapabilities {
     CapNoSplitOutQueue        = 0x0008_0000_0000, // Don't split out queue on shard splitting
     CapUndeletableAccounts    = 0x0010_0000_0000, // Don't delete frozen accounts
     CapTvmV20                 = 0x0020_0000_0000, // BLS instructions
-    CapDuePaymentFix          = 0x0040_0000_0000, // No due payments on credit phase and add payed dues to storage fee in TVM 
+    CapDuePaymentFix          = 0x0040_0000_0000, // No due payments on credit phase and add payed dues to storage fee in TVM
     CapCommonMessage          = 0x0080_0000_0000,
 }
 
+impl GlobalCapabilities {
+    pub const fn empty() -> Self { Self(0) }
+    pub const fn from_bits(bits: u64) -> Self { Self(bits) }
+    pub const fn bits(self) -> u64 { self.0 }
+    pub const fn is_empty(self) -> bool { self.0 == 0 }
+    /// All bits of `other` are set
+    pub const fn contains(self, other: Self) -> bool { self.0 & other.0 == other.0 }
+    /// Any bit of `other` is set
+    pub const fn intersects(self, other: Self) -> bool { self.0 & other.0 != 0 }
+    pub fn insert(&mut self, other: Self) { self.0 |= other.0 }
+    pub fn remove(&mut self, other: Self) { self.0 &= !other.0 }
+    pub fn set(&mut self, other: Self, enabled: bool) {
+        if enabled {
+            self.insert(other)
+        } else {
+            self.remove(other)
+        }
+    }
+    /// Bits which have no name in this version of the crate
+    pub fn unknown_bits(self) -> u64 {
+        Self::known().into_iter().fold(self.0, |bits, (_, cap)| bits & !cap.0)
+    }
+}
+
+impl From<u64> for GlobalCapabilities {
+    fn from(bits: u64) -> Self { Self(bits) }
+}
+
+impl From<GlobalCapabilities> for u64 {
+    fn from(caps: GlobalCapabilities) -> Self { caps.0 }
+}
+
+impl std::ops::BitOr for GlobalCapabilities {
+    type Output = Self;
+    fn bitor(self, other: Self) -> Self { Self(self.0 | other.0) }
+}
+
+impl std::ops::BitOrAssign for GlobalCapabilities {
+    fn bitor_assign(&mut self, other: Self) { self.0 |= other.0 }
+}
+
+impl std::ops::BitAnd for GlobalCapabilities {
+    type Output = Self;
+    fn bitand(self, other: Self) -> Self { Self(self.0 & other.0) }
+}
+
+/// Prints names of the set capabilities joined by ` | ` and unknown bits in hex
+impl std::fmt::Display for GlobalCapabilities {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let mut names: Vec<String> = Self::known().into_iter()
+            .filter(|(_, cap)| !cap.is_empty() && self.contains(*cap))
+            .map(|(name, _)| name.to_string())
+            .collect();
+        let unknown = self.unknown_bits();
+        if unknown != 0 {
+            names.push(format!("{:#x}", unknown));
+        }
+        if names.is_empty() {
+            write!(f, "CapNone")
+        } else {
+            write!(f, "{}", names.join(" | "))
+        }
+    }
+}
+
+impl std::fmt::Debug for GlobalCapabilities {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        std::fmt::Display::fmt(self, f)
+    }
+}
+
 impl ConfigParams {
     pub fn get_lt_align(&self) -> u64 {
         1_000_000
@@ -432,6 +698,16 @@ impl ConfigParams {
             Err(_) => false
         }
     }
+    /// All capabilities of the set are enabled
+    pub fn supports(&self, capabilities: GlobalCapabilities) -> bool {
+        match self.get_global_version() {
+            Ok(gb) => gb.supports(capabilities),
+            Err(_) => capabilities.is_empty()
+        }
+    }
+    pub fn global_capabilities(&self) -> GlobalCapabilities {
+        GlobalCapabilities::from_bits(self.capabilities())
+    }
     pub fn capabilities(&self) -> u64 {
         match self.get_global_version() {
             Ok(gb) => gb.capabilities,
@@ -519,6 +795,66 @@ impl ConfigParams {
     }
 }
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ConfigParamChangeKind {
+    Added,
+    Removed,
+    Modified,
+}
+
+/// Change of one config param between two configs,
+/// typed values are present only for known and correctly parsed params
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ConfigParamChange {
+    pub index: u32,
+    pub old_cell: Option<Cell>,
+    pub new_cell: Option<Cell>,
+    pub old_value: Option<ConfigParamEnum>,
+    pub new_value: Option<ConfigParamEnum>,
+}
+
+impl ConfigParamChange {
+    pub fn kind(&self) -> ConfigParamChangeKind {
+        match (&self.old_cell, &self.new_cell) {
+            (None, Some(_)) => ConfigParamChangeKind::Added,
+            (Some(_), None) => ConfigParamChangeKind::Removed,
+            _ => ConfigParamChangeKind::Modified,
+        }
+    }
+
+    fn typed_value(cell: &Option<Cell>, index: u32) -> Option<ConfigParamEnum> {
+        match ConfigParamEnum::construct_from_cell_and_number(cell.clone()?, index) {
+            Ok(ConfigParamEnum::ConfigParamAny(..)) | Err(_) => None,
+            Ok(value) => Some(value)
+        }
+    }
+}
+
+impl ConfigParams {
+    /// Reports params which were added, removed or modified in the new config
+    /// ordered by index
+    pub fn diff(&self, new: &ConfigParams) -> Result<Vec<ConfigParamChange>> {
+        let mut changes = Vec::new();
+        self.config_params.scan_diff(&new.config_params, |mut key, old_slice, new_slice| {
+            let index = key.get_next_u32()?;
+            let old_cell = old_slice.and_then(|slice| slice.reference_opt(0));
+            let new_cell = new_slice.and_then(|slice| slice.reference_opt(0));
+            if old_cell != new_cell {
+                changes.push(ConfigParamChange {
+                    index,
+                    old_value: ConfigParamChange::typed_value(&old_cell, index),
+                    new_value: ConfigParamChange::typed_value(&new_cell, index),
+                    old_cell,
+                    new_cell,
+                });
+            }
+            Ok(true)
+        })?;
+        changes.sort_by_key(|change| change.index);
+        Ok(changes)
+    }
+}
+
 impl Deserializable for ConfigParams {
     fn read_from(&mut self, cell: &mut SliceData) -> Result<()> {
         self.config_addr.read_from(cell)?;
@@ -924,7 +1260,20 @@ pub struct GlobalVersion {
 impl GlobalVersion {
     pub fn new() -> Self { Self::default() }
     pub fn has_capability(&self, capability: GlobalCapabilities) -> bool {
-        (self.capabilities & (capability as u64)) != 0
+        GlobalCapabilities::from_bits(self.capabilities).intersects(capability)
+    }
+    /// All capabilities of the set are enabled
+    pub fn supports(&self, capabilities: GlobalCapabilities) -> bool {
+        GlobalCapabilities::from_bits(self.capabilities).contains(capabilities)
+    }
+    pub fn global_capabilities(&self) -> GlobalCapabilities {
+        GlobalCapabilities::from_bits(self.capabilities)
+    }
+    /// Enables or disables capabilities keeping other bits including unknown ones
+    pub fn set_capability(&mut self, capabilities: GlobalCapabilities, enabled: bool) {
+        let mut caps = GlobalCapabilities::from_bits(self.capabilities);
+        caps.set(capabilities, enabled);
+        self.capabilities = caps.bits();
     }
 }
 
@@ -1323,6 +1672,38 @@ impl ConfigParam18 {
         };
         self.map.set(&index, sp)
     }
+
+    /// Storage fee due for the period from `last_paid` till `now`: every epoch
+    /// is charged by its own prices, the sum is rounded up as the executor does
+    pub fn storage_fee(&self, stats: &StorageUsed, last_paid: u32, now: u32, is_masterchain: bool) -> Result<Grams> {
+        let prices = self.prices()?;
+        if now <= last_paid || last_paid == 0 || prices.is_empty() || now <= prices[0].utime_since {
+            return Ok(Grams::zero())
+        }
+        let mut fee = 0u128;
+        let mut last_paid = last_paid;
+        for (i, epoch) in prices.iter().enumerate() {
+            let end = match prices.get(i + 1) {
+                Some(next) => next.utime_since.min(now),
+                None => now
+            };
+            let since = epoch.utime_since.max(last_paid);
+            if end < since {
+                continue
+            }
+            let (bit_price, cell_price) = match is_masterchain {
+                true => (epoch.mc_bit_price_ps, epoch.mc_cell_price_ps),
+                false => (epoch.bit_price_ps, epoch.cell_price_ps)
+            };
+            fee = (stats.bits() as u128 * bit_price as u128)
+                .checked_add(stats.cells() as u128 * cell_price as u128)
+                .and_then(|price| price.checked_mul((end - since) as u128))
+                .and_then(|price| fee.checked_add(price))
+                .ok_or_else(|| error!(BlockError::InvalidOperation("storage fee overflow".to_string())))?;
+            last_paid = end;
+        }
+        Grams::new((fee + 0xffff) >> 16)
+    }
 }
 
 
@@ -1430,6 +1811,30 @@ impl GasLimitsPrices {
         }
         result
     }
+
+    /// Gas fee in nanograms, see `calc_gas_fee`
+    pub fn compute_fee(&self, gas_used: u64) -> Result<Grams> {
+        Grams::new(self.calc_gas_fee(gas_used))
+    }
+
+    /// Price of the first flat_gas_limit gas paid by any computation
+    pub fn flat_fee(&self) -> Grams {
+        Grams::from(self.flat_gas_price)
+    }
+
+    /// Gas bought for `amount` nanograms limited by gas_limit, see `calc_gas`
+    pub fn gas_for_grams(&self, amount: &Grams) -> u64 {
+        self.calc_gas(amount.as_u128()).min(self.gas_limit)
+    }
+
+    /// Maximum gas of computation for the account `balance`:
+    /// special accounts get special_gas_limit regardless of the balance
+    pub fn max_gas(&self, balance: &Grams, is_special: bool) -> u64 {
+        match is_special {
+            true => self.special_gas_limit,
+            false => self.gas_for_grams(balance)
+        }
+    }
 }
 
 const GAS_PRICES_TAG: u8 = 0xDD;
@@ -1537,6 +1942,32 @@ pub struct MsgForwardPrices {
 
 impl MsgForwardPrices {
     pub fn new() -> Self { Self::default() }
+
+    /// Forwarding fee of the message with `cells` and `bits` not counting its root cell:
+    /// lump_price + ceil((bit_price * bits + cell_price * cells) / 2^16)
+    pub fn fwd_fee(&self, cells: u64, bits: u64) -> Result<Grams> {
+        let fee = self.lump_price as u128 + ((
+            self.bit_price as u128 * bits as u128 +
+            self.cell_price as u128 * cells as u128 + 0xffff
+        ) >> 16);
+        Grams::new(fee)
+    }
+
+    pub fn fwd_fee_for_storage(&self, storage: &StorageUsedShort) -> Result<Grams> {
+        self.fwd_fee(storage.cells(), storage.bits())
+    }
+
+    /// fwd_fee * ihr_price_factor / 2^16
+    pub fn ihr_fee(&self, fwd_fee: &Grams) -> Result<Grams> {
+        Grams::new((fwd_fee.as_u128() * self.ihr_price_factor as u128) >> 16)
+    }
+
+    /// Splits forwarding fee into the part collected by validators of the current
+    /// shard (fwd_fee * first_frac / 2^16) and the rest carried by the message
+    pub fn first_frac_split(&self, fwd_fee: &Grams) -> Result<(Grams, Grams)> {
+        let mine = (fwd_fee.as_u128() * self.first_frac as u128) >> 16;
+        Ok((Grams::new(mine)?, Grams::new(fwd_fee.as_u128() - mine)?))
+    }
 }
 
 const MSG_FWD_PRICES_TAG: u8 = 0xEA;
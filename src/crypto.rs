@@ -14,10 +14,11 @@
 use crate::{
     fail, Result, base64_decode, base64_encode, ed25519_create_expanded_private_key, 
     ed25519_create_private_key, ed25519_create_public_key, ed25519_expand_private_key, 
-    ed25519_generate_private_key, ed25519_verify, ed25519_sign, Ed25519ExpandedPrivateKey, 
-    Ed25519PrivateKey, sha256_digest_slices, x25519_shared_secret
+    ed25519_generate_private_key, ed25519_verify, ed25519_verify_batch, ed25519_sign,
+    Ed25519ExpandedPrivateKey, Ed25519PrivateKey, sha256_digest_slices, x25519_shared_secret,
+    ED25519_PUBLIC_KEY_LENGTH, ED25519_SIGNATURE_LENGTH
 };
-use std::{convert::TryInto, fmt::{self, Debug, Display, Formatter}, sync::Arc};
+use std::{convert::TryInto, fmt::{self, Debug, Display, Formatter}, sync::{Arc, RwLock}};
 use super::bls::{BLS_PUBLIC_KEY_LEN, BLS_SECRET_KEY_LEN, BLS_KEY_MATERIAL_LEN};
 
 pub trait KeyOption: Sync + Send + Debug {
@@ -158,7 +159,7 @@ impl Ed25519KeyOption {
 
     // Calculate key ID
     fn calc_id(type_id: i32, pub_key: &[u8; Self::PUB_KEY_SIZE]) -> Arc<KeyId> {
-        let data = sha256_digest_slices(&[&type_id.to_le_bytes(), pub_key]);
+        let data = crypto_provider().sha256(&[&type_id.to_le_bytes(), pub_key]);
         KeyId::from_data(data)
     }
 
@@ -199,7 +200,7 @@ impl KeyOption for Ed25519KeyOption {
 
     /// Verify signature
     fn verify(&self, data: &[u8], signature: &[u8]) -> Result<()> {
-        ed25519_verify(self.pub_key()?, data, signature)
+        crypto_provider().ed25519_verify(self.pub_key()?.try_into()?, data, signature.try_into()?)
     }
 
     /// Calculate shared secret
@@ -380,3 +381,74 @@ impl KeyOptionJson {
         &self.type_id
     }
 }
+
+/// Backend of cryptographic primitives used to verify signatures of blocks and key ids.
+/// The default one is pure Rust, other backends can be installed with `set_crypto_provider`.
+/// Only ed25519 verification and `Ed25519KeyOption::calc_id` go through the provider,
+/// cell hashes and other digests are always computed with the built-in sha256
+pub trait CryptoProvider: Sync + Send {
+    fn ed25519_verify(
+        &self,
+        pub_key: &[u8; ED25519_PUBLIC_KEY_LENGTH],
+        data: &[u8],
+        signature: &[u8; ED25519_SIGNATURE_LENGTH]
+    ) -> Result<()>;
+    /// Verifies all signatures, by default one by one
+    fn ed25519_verify_batch(
+        &self,
+        messages: &[&[u8]],
+        pub_keys: &[&[u8; ED25519_PUBLIC_KEY_LENGTH]],
+        signatures: &[&[u8; ED25519_SIGNATURE_LENGTH]]
+    ) -> Result<()> {
+        if messages.len() != pub_keys.len() || messages.len() != signatures.len() {
+            fail!("batch verification needs equal count of messages, keys and signatures")
+        }
+        for ((data, pub_key), signature) in messages.iter().zip(pub_keys).zip(signatures) {
+            self.ed25519_verify(pub_key, data, signature)?;
+        }
+        Ok(())
+    }
+    /// SHA-256 of the concatenated slices
+    fn sha256(&self, data: &[&[u8]]) -> [u8; 32];
+}
+
+/// Pure Rust implementation based on ed25519-dalek and sha2
+#[derive(Debug, Default)]
+pub struct DefaultCryptoProvider;
+
+impl CryptoProvider for DefaultCryptoProvider {
+    fn ed25519_verify(
+        &self,
+        pub_key: &[u8; ED25519_PUBLIC_KEY_LENGTH],
+        data: &[u8],
+        signature: &[u8; ED25519_SIGNATURE_LENGTH]
+    ) -> Result<()> {
+        ed25519_verify(pub_key, data, signature)
+    }
+    fn ed25519_verify_batch(
+        &self,
+        messages: &[&[u8]],
+        pub_keys: &[&[u8; ED25519_PUBLIC_KEY_LENGTH]],
+        signatures: &[&[u8; ED25519_SIGNATURE_LENGTH]]
+    ) -> Result<()> {
+        ed25519_verify_batch(messages, pub_keys, signatures)
+    }
+    fn sha256(&self, data: &[&[u8]]) -> [u8; 32] {
+        sha256_digest_slices(data)
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref CRYPTO_PROVIDER: RwLock<Arc<dyn CryptoProvider>> = RwLock::new(Arc::new(DefaultCryptoProvider));
+}
+
+/// Currently installed crypto backend
+pub fn crypto_provider() -> Arc<dyn CryptoProvider> {
+    CRYPTO_PROVIDER.read().unwrap_or_else(|err| err.into_inner()).clone()
+}
+
+/// Installs the crypto backend for the whole process, returns the previous one
+pub fn set_crypto_provider(provider: Arc<dyn CryptoProvider>) -> Arc<dyn CryptoProvider> {
+    let mut current = CRYPTO_PROVIDER.write().unwrap_or_else(|err| err.into_inner());
+    std::mem::replace(&mut *current, provider)
+}
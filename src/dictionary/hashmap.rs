@@ -435,6 +435,32 @@ macro_rules! define_HashmapE {
                 Ok(())
             }
 
+            /// removes items not satisfying the predicate in one pass keeping the rest of the tree
+            pub fn retain<K, F>(&mut self, mut predicate: F) -> Result<()>
+            where K: Deserializable, F: FnMut(&K, &$x_type) -> Result<bool> {
+                let opts = self.1;
+                $crate::HashmapRemover::hashmap_filter(&mut self.0, |key, mut slice| {
+                    let key = K::construct_from(&mut $crate::SliceData::load_bitstring(key.clone())?)?;
+                    let value = <$x_type>::construct_from_with_opts(&mut slice, opts)?;
+                    if predicate(&key, &value)? {
+                        Ok($crate::HashmapFilterResult::Accept)
+                    } else {
+                        Ok($crate::HashmapFilterResult::Remove)
+                    }
+                })
+            }
+
+            /// Lower bound of items count and the exact count if it is not greater than
+            /// LEN_HINT_LIMIT, unlike len() it doesn't walk through big dictionaries
+            pub fn len_hint(&self) -> Result<(usize, Option<usize>)> {
+                let count = self.count($crate::LEN_HINT_LIMIT + 1)?;
+                if count > $crate::LEN_HINT_LIMIT {
+                    Ok(($crate::LEN_HINT_LIMIT, None))
+                } else {
+                    Ok((count, Some(count)))
+                }
+            }
+
             pub fn export_keys<K: Deserializable>(&self) -> Result<Vec<K>> {
                 let mut keys = Vec::new();
                 self.iterate_keys(|key: K| {
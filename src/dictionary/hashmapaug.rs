@@ -91,6 +91,53 @@ macro_rules! define_HashmapAugE {
                     opts: $crate::SERDE_OPTS_EMPTY,
                 })
             }
+            /// keys of all items
+            pub fn keys(&self) -> Result<Vec<$k_type>> {
+                let mut keys = Vec::new();
+                $crate::HashmapAugType::iterate_slices_with_keys(self, |key: $k_type, _| {
+                    keys.push(key);
+                    Ok(true)
+                })?;
+                Ok(keys)
+            }
+            /// all items without augmentation
+            pub fn values(&self) -> Result<Vec<$x_type>> {
+                let mut values = Vec::new();
+                $crate::HashmapAugType::iterate_objects(self, |value: $x_type| {
+                    values.push(value);
+                    Ok(true)
+                })?;
+                Ok(values)
+            }
+            pub fn contains_key(&self, key: &$k_type) -> Result<bool> {
+                Ok($crate::HashmapAugType::get_raw(self, key)?.is_some())
+            }
+            /// removes items not satisfying the predicate, root augmentation is recalculated
+            pub fn retain<F>(&mut self, mut predicate: F) -> Result<()>
+            where F: FnMut(&$k_type, &$x_type) -> Result<bool> {
+                let mut removed = Vec::new();
+                $crate::HashmapAugType::iterate_with_keys(self, |key: $k_type, value: $x_type| {
+                    if !predicate(&key, &value)? {
+                        removed.push(key);
+                    }
+                    Ok(true)
+                })?;
+                for key in removed {
+                    let key = $crate::Serializable::write_to_bitstring_with_opts(&key, self.opts)?;
+                    $crate::HashmapRemover::remove(self, key)?;
+                }
+                Ok(())
+            }
+            /// Lower bound of items count and the exact count if it is not greater than
+            /// LEN_HINT_LIMIT, unlike len() it doesn't walk through big dictionaries
+            pub fn len_hint(&self) -> Result<(usize, Option<usize>)> {
+                let count = $crate::HashmapType::count(self, $crate::LEN_HINT_LIMIT + 1)?;
+                if count > $crate::LEN_HINT_LIMIT {
+                    Ok(($crate::LEN_HINT_LIMIT, None))
+                } else {
+                    Ok((count, Some(count)))
+                }
+            }
             /// split map by key
             pub fn split(&self, key: &SliceData) -> Result<(Self, Self)> {
                 let (left, right) = $crate::HashmapType::hashmap_split(self, key)?;
@@ -474,6 +521,28 @@ pub trait HashmapAugType<
             None => Ok(None)
         }
     }
+    /// gets item with aug for the next (or previous) key, `eq` allows the key itself
+    fn find_leaf(&self, key: &K, next: bool, eq: bool, signed: bool) -> Result<Option<(K, X, Y)>> {
+        let root = match self.data() {
+            Some(root) => root.clone(),
+            None => return Ok(None)
+        };
+        let key = key.write_to_bitstring_with_opts(self.serde_opts())?;
+        let mut path = BuilderData::new();
+        let next_index = if next { 0 } else { 1 };
+        let result = super::find_leaf::<Self>(
+            root, &mut path, self.bit_len(), key, next_index, eq, signed, &mut 0
+        )?;
+        match result {
+            Some(mut val) => {
+                let key = K::construct_from_with_opts(&mut SliceData::load_bitstring(path)?, self.serde_opts())?;
+                let aug = Y::construct_from_with_opts(&mut val, self.serde_opts())?;
+                let val = X::construct_from_with_opts(&mut val, self.serde_opts())?;
+                Ok(Some((key, val, aug)))
+            }
+            None => Ok(None)
+        }
+    }
 
     // /// Checks if HashmapAugE is empty
     // fn is_empty(&self) -> bool {
@@ -32,6 +32,9 @@ pub type Leaf = Result<Option<SliceData>>;
 pub const ADD: u8 = 0x01;
 pub const REPLACE: u8 = 0x02;
 
+/// Items are counted up to this limit by len_hint() of generated dictionaries
+pub const LEN_HINT_LIMIT: usize = 1024;
+
 fn hm_label_same(key: &SliceData, same: bool, max_len: usize) -> Result<BuilderData> {
     let len = key.remaining_bits();
     debug_assert!(len <= max_len && max_len <= 1023);
@@ -15,7 +15,7 @@
 #![allow(clippy::type_complexity)]
 #![allow(clippy::vec_init_then_push)]
 use super::*;
-use crate::{error, define_HashmapAugE, IBitstring, hm_label, HashmapSubtree, AddSub, Grams};
+use crate::{error, define_HashmapAugE, IBitstring, hm_label, HashmapSubtree, AddSub, Grams, LEN_HINT_LIMIT};
 use std::fmt;
 
 #[derive(Eq, Clone, Debug, Default, PartialEq)]
@@ -658,3 +658,34 @@ fn test_hashmap_add_remove() {
     hashmap.del(&1).unwrap();
     assert_eq!(hashmap.root_extra(), &0);
 }
+
+#[test]
+fn test_generated_dictionary_accessors() {
+    let mut map = MyHashmap::default();
+    assert_eq!(map.len_hint().unwrap(), (0, Some(0)));
+    for key in 0..10u8 {
+        map.set(&key, &(key * 2), &key).unwrap();
+    }
+    assert_eq!(map.keys().unwrap(), (0..10).collect::<Vec<u8>>());
+    assert_eq!(map.values().unwrap(), (0..10).map(|key| key * 2).collect::<Vec<u8>>());
+    assert!(map.contains_key(&9).unwrap());
+    assert!(!map.contains_key(&10).unwrap());
+    assert_eq!(map.len_hint().unwrap(), (10, Some(10)));
+    assert_eq!(*map.root_extra(), 9);
+
+    map.retain(|key, _| Ok(*key < 5)).unwrap();
+    assert_eq!(map.keys().unwrap(), (0..5).collect::<Vec<u8>>());
+    assert_eq!(*map.root_extra(), 4);
+
+    let mut dict = crate::ExtraCurrencyCollection::default();
+    for key in 0..LEN_HINT_LIMIT as u32 + 10 {
+        dict.set(&key, &crate::VarUInteger32::from_two_u128(0, key as u128).unwrap()).unwrap();
+    }
+    assert_eq!(dict.len_hint().unwrap(), (LEN_HINT_LIMIT, None));
+    dict.retain(|key: &u32, _| Ok(*key % 100 == 0)).unwrap();
+    assert_eq!(dict.len_hint().unwrap(), (11, Some(11)));
+    assert_eq!(dict.export_keys::<u32>().unwrap(), (0..11).map(|key| key * 100).collect::<Vec<u32>>());
+    assert_eq!(dict.export_vector().unwrap()[1], crate::VarUInteger32::from_two_u128(0, 100).unwrap());
+    assert!(dict.check_key(&1000u32).unwrap());
+    assert!(!dict.check_key(&1001u32).unwrap());
+}
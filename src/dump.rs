@@ -0,0 +1,392 @@
+/*
+* Copyright (C) 2019-2023 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+//! Human-readable dumps of blocks and states.
+//! Output is deterministic: dictionaries are walked in key order.
+
+use crate::{
+    blocks::{Block, BlockExtra, BlockInfo, ValueFlow},
+    config_params::ConfigParams,
+    dictionary::hashmapaug::HashmapAugType,
+    master::{McBlockExtra, McStateExtra, ShardHashes},
+    merkle_update::MerkleUpdate,
+    shard::{ShardIdent, ShardStateUnsplit},
+    transactions::Transaction,
+    types::InRefValue,
+    HashmapType, Result, UInt256,
+};
+use std::fmt::{self, Write};
+
+#[cfg(test)]
+#[path = "tests/test_dump.rs"]
+mod tests;
+
+const INDENT: &str = "  ";
+
+/// What to include into a dump
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct DumpOptions {
+    /// Nested sections deeper than this are folded into "name: ..."
+    pub max_depth: usize,
+    pub show_messages: bool,
+    pub show_config: bool,
+}
+
+impl Default for DumpOptions {
+    fn default() -> Self {
+        Self {
+            max_depth: usize::MAX,
+            show_messages: false,
+            show_config: false,
+        }
+    }
+}
+
+/// Writes indented "name: value" lines grouped into nested sections
+pub struct DumpWriter<'a> {
+    out: &'a mut dyn Write,
+    options: DumpOptions,
+    depth: usize,
+}
+
+impl<'a> DumpWriter<'a> {
+    pub fn new(out: &'a mut dyn Write, options: DumpOptions) -> Self {
+        Self { out, options, depth: 0 }
+    }
+    pub fn options(&self) -> &DumpOptions {
+        &self.options
+    }
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+    fn indent(&mut self) -> Result<()> {
+        for _ in 0..self.depth {
+            self.out.write_str(INDENT)?;
+        }
+        Ok(())
+    }
+    /// Writes single "name: value" line
+    pub fn field(&mut self, name: &str, value: impl fmt::Display) -> Result<()> {
+        self.indent()?;
+        writeln!(self.out, "{}: {}", name, value)?;
+        Ok(())
+    }
+    /// Writes every line of possibly multi-line value at current indentation
+    pub fn lines(&mut self, value: impl fmt::Display) -> Result<()> {
+        for line in value.to_string().lines() {
+            self.indent()?;
+            writeln!(self.out, "{}", line)?;
+        }
+        Ok(())
+    }
+    /// Writes "name:" and the content produced by `f` one level deeper.
+    /// When max depth is reached the content is folded
+    pub fn section(&mut self, name: impl fmt::Display, f: impl FnOnce(&mut Self) -> Result<()>) -> Result<()> {
+        self.indent()?;
+        if self.depth >= self.options.max_depth {
+            writeln!(self.out, "{}: ...", name)?;
+            return Ok(())
+        }
+        writeln!(self.out, "{}:", name)?;
+        self.depth += 1;
+        let result = f(self);
+        self.depth -= 1;
+        result
+    }
+    /// Writes section with dump of the value
+    pub fn item(&mut self, name: impl fmt::Display, value: &impl Dump) -> Result<()> {
+        self.section(name, |writer| value.dump_to(writer))
+    }
+}
+
+/// Type which can be written by `DumpWriter`
+pub trait Dump {
+    fn dump_to(&self, writer: &mut DumpWriter) -> Result<()>;
+
+    fn dump(&self, out: &mut dyn Write, options: DumpOptions) -> Result<()> {
+        self.dump_to(&mut DumpWriter::new(out, options))
+    }
+    fn dump_to_string(&self, options: DumpOptions) -> Result<String> {
+        let mut out = String::new();
+        self.dump(&mut out, options)?;
+        Ok(out)
+    }
+}
+
+impl Dump for BlockInfo {
+    fn dump_to(&self, writer: &mut DumpWriter) -> Result<()> {
+        writer.field("version", self.version())?;
+        writer.field("shard", self.shard())?;
+        writer.field("seq_no", self.seq_no())?;
+        writer.field("vert_seq_no", self.vert_seq_no())?;
+        writer.field("gen_utime", self.gen_utime())?;
+        writer.field("gen_utime_ms", self.gen_utime_ms())?;
+        writer.field("start_lt", self.start_lt())?;
+        writer.field("end_lt", self.end_lt())?;
+        writer.field("key_block", self.key_block())?;
+        writer.field("after_merge", self.after_merge())?;
+        writer.field("after_split", self.after_split())?;
+        writer.field("before_split", self.before_split())?;
+        writer.field("min_ref_mc_seqno", self.min_ref_mc_seqno())?;
+        writer.field("prev_key_block_seqno", self.prev_key_block_seqno())?;
+        for prev in self.read_prev_ids()? {
+            writer.field("prev", prev)?;
+        }
+        if let Some(master) = self.read_master_ref()? {
+            writer.field("master", format_args!("{} {:x}", master.master.seq_no, master.master.root_hash))?;
+        }
+        Ok(())
+    }
+}
+
+impl Dump for ValueFlow {
+    fn dump_to(&self, writer: &mut DumpWriter) -> Result<()> {
+        writer.field("from_prev_blk", &self.from_prev_blk)?;
+        writer.field("to_next_blk", &self.to_next_blk)?;
+        writer.field("imported", &self.imported)?;
+        writer.field("exported", &self.exported)?;
+        writer.field("fees_collected", &self.fees_collected)?;
+        writer.field("fees_imported", &self.fees_imported)?;
+        writer.field("recovered", &self.recovered)?;
+        writer.field("created", &self.created)?;
+        writer.field("minted", &self.minted)?;
+        self.mesh_exported.iterate_with_keys(|nw_id: u32, value| {
+            writer.field(&format!("mesh_exported {}", nw_id), value)?;
+            Ok(true)
+        })?;
+        Ok(())
+    }
+}
+
+impl Dump for MerkleUpdate {
+    fn dump_to(&self, writer: &mut DumpWriter) -> Result<()> {
+        writer.field("old_hash", format_args!("{:x}", self.old_hash))?;
+        writer.field("new_hash", format_args!("{:x}", self.new_hash))?;
+        writer.field("old_depth", self.old_depth)?;
+        writer.field("new_depth", self.new_depth)
+    }
+}
+
+impl Dump for Transaction {
+    fn dump_to(&self, writer: &mut DumpWriter) -> Result<()> {
+        writer.field("account", format_args!("{:x}", self.account_id()))?;
+        writer.field("lt", self.logical_time())?;
+        writer.field("now", self.now())?;
+        writer.field("prev_trans_lt", self.prev_trans_lt())?;
+        writer.field("prev_trans_hash", format_args!("{:x}", self.prev_trans_hash()))?;
+        writer.field("orig_status", format_args!("{:?}", self.orig_status))?;
+        writer.field("end_status", format_args!("{:?}", self.end_status))?;
+        writer.field("total_fees", self.total_fees())?;
+        writer.field("out_msgs", self.msg_count())?;
+        if writer.options().show_messages {
+            if let Some(msg) = self.read_in_msg()? {
+                writer.field("in_msg", msg)?;
+            }
+            self.iterate_out_msgs(|msg| {
+                writer.field("out_msg", msg)?;
+                Ok(true)
+            })?;
+        }
+        Ok(())
+    }
+}
+
+impl Dump for BlockExtra {
+    fn dump_to(&self, writer: &mut DumpWriter) -> Result<()> {
+        let in_msg_descr = self.read_in_msg_descr()?;
+        let out_msg_descr = self.read_out_msg_descr()?;
+        writer.field("in_msgs", in_msg_descr.len()?)?;
+        writer.field("out_msgs", out_msg_descr.len()?)?;
+        if writer.options().show_messages {
+            writer.section("in_msg_descr", |writer| {
+                in_msg_descr.iterate_with_keys(|hash: UInt256, msg| {
+                    writer.field(&format!("{:x}", hash), format_args!("{:?}", msg))?;
+                    Ok(true)
+                })?;
+                Ok(())
+            })?;
+            writer.section("out_msg_descr", |writer| {
+                out_msg_descr.iterate_with_keys(|hash: UInt256, msg| {
+                    writer.field(&format!("{:x}", hash), format_args!("{:?}", msg))?;
+                    Ok(true)
+                })?;
+                Ok(())
+            })?;
+        }
+        let account_blocks = self.read_account_blocks()?;
+        writer.field("transactions", account_blocks.count_transactions()?)?;
+        writer.section("account_blocks", |writer| {
+            account_blocks.iterate_with_keys(|account: UInt256, block| {
+                writer.section(format_args!("account {:x}", account), |writer| {
+                    block.transaction_iterate(|tr| {
+                        writer.item(format_args!("transaction {}", tr.logical_time()), &tr)?;
+                        Ok(true)
+                    })?;
+                    Ok(())
+                })?;
+                Ok(true)
+            })?;
+            Ok(())
+        })?;
+        if let Some(custom) = self.read_custom()? {
+            writer.item("custom", &custom)?;
+        }
+        Ok(())
+    }
+}
+
+impl Dump for Block {
+    fn dump_to(&self, writer: &mut DumpWriter) -> Result<()> {
+        writer.field("global_id", self.global_id())?;
+        writer.item("info", &self.read_info()?)?;
+        writer.item("value_flow", &self.read_value_flow()?)?;
+        writer.item("state_update", &self.read_state_update()?)?;
+        writer.item("extra", &self.read_extra()?)
+    }
+}
+
+impl Dump for ShardHashes {
+    fn dump_to(&self, writer: &mut DumpWriter) -> Result<()> {
+        self.iterate_with_keys(|workchain_id: i32, InRefValue(bintree)| {
+            writer.section(format_args!("workchain {}", workchain_id), |writer| {
+                bintree.iterate(|prefix, descr| {
+                    let shard = ShardIdent::with_prefix_slice(workchain_id, prefix)?;
+                    writer.section(format_args!("shard {}", shard), |writer| {
+                        writer.lines(format_args!("{:#}", descr))
+                    })?;
+                    Ok(true)
+                })?;
+                Ok(())
+            })?;
+            Ok(true)
+        })?;
+        Ok(())
+    }
+}
+
+impl Dump for ConfigParams {
+    fn dump_to(&self, writer: &mut DumpWriter) -> Result<()> {
+        writer.field("config_addr", format_args!("{:x}", self.config_addr))?;
+        self.config_params.iterate_slices(|mut key, _| {
+            let index = key.get_next_u32()?;
+            if let Some(param) = self.config(index)? {
+                writer.field(&format!("param {}", index), format_args!("{:?}", param))?;
+            }
+            Ok(true)
+        })?;
+        Ok(())
+    }
+}
+
+impl Dump for McBlockExtra {
+    fn dump_to(&self, writer: &mut DumpWriter) -> Result<()> {
+        writer.field("key_block", self.is_key_block())?;
+        writer.field("total_fee", self.total_fee())?;
+        writer.field("prev_blk_signatures", self.prev_blk_signatures().len()?)?;
+        writer.item("shards", self.shards())?;
+        if writer.options().show_messages {
+            if let Some(msg) = self.read_recover_create_msg()? {
+                writer.field("recover_create_msg", msg)?;
+            }
+            if let Some(msg) = self.read_mint_msg()? {
+                writer.field("mint_msg", msg)?;
+            }
+        }
+        if writer.options().show_config {
+            if let Some(config) = self.config() {
+                writer.item("config", config)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Dump for McStateExtra {
+    fn dump_to(&self, writer: &mut DumpWriter) -> Result<()> {
+        writer.field("after_key_block", self.after_key_block)?;
+        if let Some(last_key_block) = &self.last_key_block {
+            writer.field(
+                "last_key_block",
+                format_args!("{} {:x}", last_key_block.seq_no, last_key_block.root_hash)
+            )?;
+        }
+        writer.field("global_balance", &self.global_balance)?;
+        writer.section("validator_info", |writer| {
+            writer.field("validator_list_hash_short", self.validator_info.validator_list_hash_short)?;
+            writer.field("catchain_seqno", self.validator_info.catchain_seqno)?;
+            writer.field("nx_cc_updated", self.validator_info.nx_cc_updated)
+        })?;
+        writer.field("prev_blocks", self.prev_blocks.len()?)?;
+        if let Some(stats) = &self.block_create_stats {
+            writer.field("block_create_stats", stats.counters.len()?)?;
+        }
+        writer.item("shards", &self.shards)?;
+        if writer.options().show_config {
+            writer.item("config", &self.config)?;
+        }
+        Ok(())
+    }
+}
+
+impl Dump for ShardStateUnsplit {
+    fn dump_to(&self, writer: &mut DumpWriter) -> Result<()> {
+        writer.field("global_id", self.global_id())?;
+        writer.field("shard", self.shard())?;
+        writer.field("seq_no", self.seq_no())?;
+        writer.field("vert_seq_no", self.vert_seq_no())?;
+        writer.field("gen_time", self.gen_time())?;
+        writer.field("gen_lt", self.gen_lt())?;
+        writer.field("min_ref_mc_seqno", self.min_ref_mc_seqno())?;
+        writer.field("before_split", self.before_split())?;
+        writer.field("overload_history", self.overload_history())?;
+        writer.field("underload_history", self.underload_history())?;
+        writer.field("total_balance", self.total_balance())?;
+        writer.field("total_validator_fees", self.total_validator_fees())?;
+        if let Some(master) = self.master_ref() {
+            writer.field("master", format_args!("{} {:x}", master.master.seq_no, master.master.root_hash))?;
+        }
+        let accounts = self.read_accounts()?;
+        writer.field("accounts_count", accounts.len()?)?;
+        writer.section("accounts", |writer| {
+            accounts.iterate_with_keys(|id: UInt256, shard_account| {
+                let account = shard_account.read_account()?;
+                writer.section(format_args!("account {:x}", id), |writer| {
+                    writer.field("status", format_args!("{:?}", account.status()))?;
+                    if let Some(balance) = account.balance() {
+                        writer.field("balance", balance)?;
+                    }
+                    writer.field("last_trans_lt", shard_account.last_trans_lt())?;
+                    writer.field("last_trans_hash", format_args!("{:x}", shard_account.last_trans_hash()))
+                })?;
+                Ok(true)
+            })?;
+            Ok(())
+        })?;
+        let queue_info = self.read_out_msg_queue_info()?;
+        writer.field("out_msg_queue_len", queue_info.out_queue().len()?)?;
+        if writer.options().show_messages {
+            writer.section("out_msg_queue", |writer| {
+                queue_info.out_queue().iterate_objects(|enq| {
+                    let msg = enq.read_out_msg()?.read_message()?;
+                    writer.field(&format!("enqueued {}", enq.enqueued_lt()), msg)?;
+                    Ok(true)
+                })?;
+                Ok(())
+            })?;
+        }
+        if let Some(custom) = self.read_custom()? {
+            writer.item("custom", &custom)?;
+        }
+        Ok(())
+    }
+}
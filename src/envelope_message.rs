@@ -17,7 +17,7 @@ use crate::{
     shard::{AccountIdPrefixFull, ShardIdent},
     messages::Message,
     types::{AddSub, ChildCell, Grams},
-    Serializable, Deserializable,
+    Serializable, Deserializable, SemanticEq,
     error, fail, Result, SERDE_OPTS_EMPTY, SERDE_OPTS_COMMON_MESSAGE,
     BuilderData, Cell, IBitstring, SliceData, UInt256,
 };
@@ -560,6 +560,15 @@ fn serialize_msgenvelope(x: &MsgEnvelope, cell: &mut BuilderData, opts: u8) -> R
     Ok(())
 }
 
+impl SemanticEq for MsgEnvelope {
+    fn semantic_eq(&self, other: &Self) -> bool {
+        self.cur_addr == other.cur_addr &&
+        self.next_addr == other.next_addr &&
+        self.fwd_fee_remaining == other.fwd_fee_remaining &&
+        self.msg.semantic_eq(&other.msg)
+    }
+}
+
 impl Serializable for MsgEnvelope {
     fn write_to(&self, cell: &mut BuilderData) -> Result<()> {
         serialize_msgenvelope(self, cell, SERDE_OPTS_EMPTY)
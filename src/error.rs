@@ -11,6 +11,13 @@
 * limitations under the License.
 */
 
+use crate::{Error, Result, SliceData, UInt256};
+use std::fmt;
+
+#[cfg(test)]
+#[path = "tests/test_error.rs"]
+mod tests;
+
 #[derive(Debug, thiserror::Error)]
 pub enum BlockError {
     /// Fatal error.
@@ -61,3 +68,131 @@ pub enum BlockError {
     #[error("Mismatched serde options: {0} exp={1} real={2}")]
     MismatchedSerdeOptions(String, usize, usize),
 }
+
+/// Reason of failed Merkle proof verification, available via `downcast_ref`
+#[derive(Clone, Debug, Eq, PartialEq, thiserror::Error)]
+pub enum MerkleProofError {
+    /// Hash of the proof root doesn't match expected one.
+    #[error("proof root hash {actual:x} is not equal to expected {expected:x}")]
+    RootHashMismatch {
+        expected: UInt256,
+        actual: UInt256,
+    },
+    /// Pruned branch has level too high for its Merkle depth.
+    #[error("pruned branch {hash:x} has level {level} at Merkle depth {merkle_depth}")]
+    WrongPrunedBranchLevel {
+        hash: UInt256,
+        level: u8,
+        merkle_depth: u8,
+    },
+    /// Depth of the cell doesn't match depths of its children or stored one.
+    #[error("cell {hash:x} has depth {depth} but {expected} is expected")]
+    InconsistentDepth {
+        hash: UInt256,
+        depth: u16,
+        expected: u16,
+    },
+}
+
+/// Structure on the path to the failed one: its field being read
+/// and the bit and reference position in its cell where reading stopped
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DeserializeFrame {
+    pub type_name: String,
+    pub field: Option<String>,
+    pub position: Option<(usize, usize)>,
+}
+
+impl fmt::Display for DeserializeFrame {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.type_name)?;
+        if let Some(field) = &self.field {
+            write!(f, ".{}", field)?;
+        }
+        if let Some((bit_pos, ref_pos)) = self.position {
+            write!(f, " (bit {}, ref {})", bit_pos, ref_pos)?;
+        }
+        Ok(())
+    }
+}
+
+/// Context attached to errors of nested deserialization: path of structures from
+/// the outermost one and the message of the original error.
+/// The original error is still available via `downcast_ref`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct DeserializeContext {
+    pub path: Vec<DeserializeFrame>,
+    pub cause: String,
+}
+
+impl DeserializeContext {
+    /// Adds frame of the structure of type `T` being read from `slice` to the context of the error.
+    /// Position is merged into the frame of the field of the same structure if it is the last added
+    pub fn wrap<T: ?Sized>(mut err: Error, slice: &SliceData) -> Error {
+        let type_name = short_type_name(std::any::type_name::<T>());
+        let position = Some((slice.pos(), slice.get_references().start));
+        if let Some(context) = err.downcast_mut::<DeserializeContext>() {
+            if let Some(frame) = context.path.first_mut() {
+                if frame.type_name == type_name && frame.field.is_some() && frame.position.is_none() {
+                    frame.position = position;
+                    return err
+                }
+            }
+        }
+        Self::push_frame(err, DeserializeFrame { type_name, field: None, position })
+    }
+
+    fn push_frame(mut err: Error, frame: DeserializeFrame) -> Error {
+        if let Some(context) = err.downcast_mut::<DeserializeContext>() {
+            context.path.insert(0, frame);
+            return err
+        }
+        let cause = err.to_string();
+        err.context(DeserializeContext { path: vec![frame], cause })
+    }
+}
+
+impl fmt::Display for DeserializeContext {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for frame in &self.path {
+            write!(f, "{} -> ", frame)?;
+        }
+        write!(f, "{}", self.cause)
+    }
+}
+
+/// Adds the field being read to the deserialization path of the error:
+/// `self.shards.read_from(slice).with_ctx("McBlockExtra", "shards")?`
+pub trait WithDeserializeContext<T> {
+    fn with_ctx(self, type_name: &str, field: &str) -> Result<T>;
+}
+
+impl<T> WithDeserializeContext<T> for Result<T> {
+    fn with_ctx(self, type_name: &str, field: &str) -> Result<T> {
+        self.map_err(|err| {
+            let frame = DeserializeFrame {
+                type_name: type_name.to_string(),
+                field: Some(field.to_string()),
+                position: None,
+            };
+            DeserializeContext::push_frame(err, frame)
+        })
+    }
+}
+
+/// Strips module paths: `ever_block::bintree::BinTree<ever_block::master::ShardDescr>` -> `BinTree<ShardDescr>`
+fn short_type_name(name: &str) -> String {
+    let mut result = String::with_capacity(name.len());
+    let mut segment_start = 0;
+    for c in name.chars() {
+        if c == ':' {
+            result.truncate(segment_start);
+            continue
+        }
+        result.push(c);
+        if !(c.is_alphanumeric() || c == '_') {
+            segment_start = result.len();
+        }
+    }
+    result
+}
@@ -25,7 +25,7 @@ use crate::{
     common_message::CommonMessage,
     transactions::Transaction,
     types::{AddSub, ChildCell, CurrencyCollection, Grams},
-    Serializable, Deserializable,
+    Serializable, Deserializable, SemanticEq,
     error, fail, Result, SERDE_OPTS_EMPTY, SERDE_OPTS_COMMON_MESSAGE,
     BuilderData, Cell, IBitstring, SliceData, UInt256, hm_label,
 };
@@ -441,6 +441,22 @@ impl Augmentation<ImportFees> for InMsg {
     }
 }
 
+impl SemanticEq for InMsg {
+    fn semantic_eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (InMsg::None, InMsg::None) => true,
+            (InMsg::External(x), InMsg::External(y)) => x.semantic_eq(y),
+            (InMsg::IHR(x), InMsg::IHR(y)) => x.semantic_eq(y),
+            (InMsg::Immediate(x), InMsg::Immediate(y)) => x.semantic_eq(y),
+            (InMsg::Final(x), InMsg::Final(y)) => x.semantic_eq(y),
+            (InMsg::Transit(x), InMsg::Transit(y)) => x.semantic_eq(y),
+            (InMsg::DiscardedFinal(x), InMsg::DiscardedFinal(y)) => x.semantic_eq(y),
+            (InMsg::DiscardedTransit(x), InMsg::DiscardedTransit(y)) => x.semantic_eq(y),
+            _ => false
+        }
+    }
+}
+
 impl Serializable for InMsg {
     fn write_to(&self, cell: &mut BuilderData) -> Result<()> {
         self.write_with_opts(cell, SERDE_OPTS_EMPTY)
@@ -537,6 +553,13 @@ impl InMsgExternal {
     }
 }
 
+impl SemanticEq for InMsgExternal {
+    fn semantic_eq(&self, other: &Self) -> bool {
+        self.msg.semantic_eq(&other.msg) &&
+        self.transaction.semantic_eq(&other.transaction)
+    }
+}
+
 impl Serializable for InMsgExternal {
     fn write_to(&self, cell: &mut BuilderData) -> Result<()> {
         self.msg.write_to(cell)?;
@@ -615,6 +638,15 @@ impl InMsgIHR {
 }
 
 
+impl SemanticEq for InMsgIHR {
+    fn semantic_eq(&self, other: &Self) -> bool {
+        self.msg.semantic_eq(&other.msg) &&
+        self.transaction.semantic_eq(&other.transaction) &&
+        self.ihr_fee == other.ihr_fee &&
+        self.proof_created == other.proof_created
+    }
+}
+
 impl Serializable for InMsgIHR {
     fn write_to(&self, cell: &mut BuilderData) -> Result<()> {
         self.msg.write_to(cell)?;
@@ -683,6 +715,14 @@ impl InMsgFinal {
     }
 }
 
+impl SemanticEq for InMsgFinal {
+    fn semantic_eq(&self, other: &Self) -> bool {
+        self.in_msg.semantic_eq(&other.in_msg) &&
+        self.transaction.semantic_eq(&other.transaction) &&
+        self.fwd_fee == other.fwd_fee
+    }
+}
+
 impl Serializable for InMsgFinal {
     fn write_to(&self, cell: &mut BuilderData) -> Result<()> {
         self.in_msg.write_to(cell)?;
@@ -749,6 +789,14 @@ impl InMsgTransit {
     }
 }
 
+impl SemanticEq for InMsgTransit {
+    fn semantic_eq(&self, other: &Self) -> bool {
+        self.in_msg.semantic_eq(&other.in_msg) &&
+        self.out_msg.semantic_eq(&other.out_msg) &&
+        self.transit_fee == other.transit_fee
+    }
+}
+
 impl Serializable for InMsgTransit {
     fn write_to(&self, cell: &mut BuilderData) -> Result<()> {
         self.in_msg.write_to(cell)?;
@@ -815,6 +863,14 @@ impl InMsgDiscardedFinal {
     }
 }
 
+impl SemanticEq for InMsgDiscardedFinal {
+    fn semantic_eq(&self, other: &Self) -> bool {
+        self.in_msg.semantic_eq(&other.in_msg) &&
+        self.transaction_id == other.transaction_id &&
+        self.fwd_fee == other.fwd_fee
+    }
+}
+
 impl Serializable for InMsgDiscardedFinal {
     fn write_to(&self, cell: &mut BuilderData) -> Result<()> {
         self.in_msg.write_to(cell)?;
@@ -888,6 +944,15 @@ impl InMsgDiscardedTransit {
     }
 }
 
+impl SemanticEq for InMsgDiscardedTransit {
+    fn semantic_eq(&self, other: &Self) -> bool {
+        self.in_msg.semantic_eq(&other.in_msg) &&
+        self.transaction_id == other.transaction_id &&
+        self.fwd_fee == other.fwd_fee &&
+        self.proof_delivered == other.proof_delivered
+    }
+}
+
 impl Serializable for InMsgDiscardedTransit {
     fn write_to(&self, cell: &mut BuilderData) -> Result<()> {
         self.in_msg.write_to(cell)?;
@@ -0,0 +1,298 @@
+/*
+* Copyright (C) 2019-2023 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+//! JSON representation of the core block structures (enabled by `json` feature).
+//!
+//! Schema conventions:
+//! * hashes and account ids are lowercase hex strings without `0x` prefix;
+//! * `Grams` and extra currency values are decimal strings;
+//! * shards are objects `{ "workchain_id": i32, "shard": "8000000000000000" }`;
+//! * every composite structure carries its full serialized form in the `boc` field
+//!   (base64), which is the only field used during deserialization. All other fields
+//!   are informational and derived from the structure itself.
+
+use crate::{
+    accounts::{Account, AccountStatus},
+    blocks::{Block, BlockInfo},
+    master::{McBlockExtra, McStateExtra, ShardDescr},
+    messages::{CommonMsgInfo, Message},
+    shard::ShardIdent,
+    transactions::Transaction,
+    types::{CurrencyCollection, Grams, VarUInteger32},
+    base64_decode, base64_encode, read_single_root_boc, write_boc,
+    Deserializable, Serializable, UInt256, SERDE_OPTS_EMPTY,
+};
+use serde::{
+    de::Error as DeError, ser::{Error as SerError, SerializeStruct},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use std::{collections::BTreeMap, str::FromStr};
+
+#[cfg(test)]
+#[path = "tests/test_json.rs"]
+mod tests;
+
+fn hash_to_hex(hash: &UInt256) -> String {
+    format!("{:x}", hash)
+}
+
+fn to_boc<T: Serializable, E: SerError>(value: &T, opts: u8) -> std::result::Result<String, E> {
+    let cell = value.serialize_with_opts(opts).map_err(E::custom)?;
+    let bytes = write_boc(&cell).map_err(E::custom)?;
+    Ok(base64_encode(bytes))
+}
+
+fn from_boc<T: Deserializable, E: DeError>(boc: &str) -> std::result::Result<T, E> {
+    let bytes = base64_decode(boc).map_err(E::custom)?;
+    let cell = read_single_root_boc(bytes).map_err(E::custom)?;
+    T::construct_from_cell(cell).map_err(E::custom)
+}
+
+fn status_to_str(status: &AccountStatus) -> &'static str {
+    match status {
+        AccountStatus::AccStateUninit => "uninit",
+        AccountStatus::AccStateFrozen => "frozen",
+        AccountStatus::AccStateActive => "active",
+        AccountStatus::AccStateNonexist => "nonexist",
+    }
+}
+
+/// Only `boc` field is taken into account when deserializing composite structures
+#[derive(Deserialize)]
+struct BocJson {
+    boc: String,
+}
+
+#[derive(Serialize)]
+struct ShardJson {
+    workchain_id: i32,
+    shard: String,
+}
+
+impl From<&ShardIdent> for ShardJson {
+    fn from(shard: &ShardIdent) -> Self {
+        Self {
+            workchain_id: shard.workchain_id(),
+            shard: shard.shard_prefix_as_str_with_tag(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct CurrencyCollectionJson {
+    grams: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    other: BTreeMap<u32, String>,
+}
+
+impl Serialize for CurrencyCollection {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut other = BTreeMap::new();
+        self.other.iterate_with_keys(|key: u32, value| {
+            other.insert(key, value.to_string());
+            Ok(true)
+        }).map_err(S::Error::custom)?;
+        CurrencyCollectionJson { grams: self.grams.to_string(), other }.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for CurrencyCollection {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let json = CurrencyCollectionJson::deserialize(deserializer)?;
+        if json.grams.starts_with("0x") || json.grams.starts_with("0X") {
+            return Err(D::Error::custom(format!("grams must be decimal string, got {}", json.grams)))
+        }
+        let mut result = CurrencyCollection::from_grams(
+            Grams::from_str(&json.grams).map_err(D::Error::custom)?
+        );
+        for (key, value) in json.other {
+            let value = VarUInteger32::from_str(&value).map_err(D::Error::custom)?;
+            result.set_other_ex(key, &value).map_err(D::Error::custom)?;
+        }
+        Ok(result)
+    }
+}
+
+impl Serialize for BlockInfo {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("BlockInfo", 18)?;
+        s.serialize_field("version", &self.version())?;
+        s.serialize_field("shard", &ShardJson::from(self.shard()))?;
+        s.serialize_field("seq_no", &self.seq_no())?;
+        s.serialize_field("vert_seq_no", &self.vert_seq_no())?;
+        s.serialize_field("gen_utime", &self.gen_utime().as_u32())?;
+        s.serialize_field("gen_utime_ms", &self.gen_utime_ms())?;
+        s.serialize_field("start_lt", &self.start_lt())?;
+        s.serialize_field("end_lt", &self.end_lt())?;
+        s.serialize_field("key_block", &self.key_block())?;
+        s.serialize_field("after_merge", &self.after_merge())?;
+        s.serialize_field("before_split", &self.before_split())?;
+        s.serialize_field("after_split", &self.after_split())?;
+        s.serialize_field("want_split", &self.want_split())?;
+        s.serialize_field("want_merge", &self.want_merge())?;
+        s.serialize_field("gen_catchain_seqno", &self.gen_catchain_seqno())?;
+        s.serialize_field("min_ref_mc_seqno", &self.min_ref_mc_seqno())?;
+        s.serialize_field("prev_key_block_seqno", &self.prev_key_block_seqno())?;
+        s.serialize_field("boc", &to_boc::<_, S::Error>(self, SERDE_OPTS_EMPTY)?)?;
+        s.end()
+    }
+}
+
+impl Serialize for Block {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let cell = self.serialize_with_opts(self.serde_opts()).map_err(S::Error::custom)?;
+        let info = self.read_info().map_err(S::Error::custom)?;
+        let mut s = serializer.serialize_struct("Block", 4)?;
+        s.serialize_field("id", &hash_to_hex(&cell.repr_hash()))?;
+        s.serialize_field("global_id", &self.global_id())?;
+        s.serialize_field("info", &info)?;
+        let bytes = write_boc(&cell).map_err(S::Error::custom)?;
+        s.serialize_field("boc", &base64_encode(bytes))?;
+        s.end()
+    }
+}
+
+impl Serialize for ShardDescr {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("ShardDescr", 11)?;
+        s.serialize_field("seq_no", &self.seq_no)?;
+        s.serialize_field("reg_mc_seqno", &self.reg_mc_seqno)?;
+        s.serialize_field("start_lt", &self.start_lt)?;
+        s.serialize_field("end_lt", &self.end_lt)?;
+        s.serialize_field("root_hash", &hash_to_hex(&self.root_hash))?;
+        s.serialize_field("file_hash", &hash_to_hex(&self.file_hash))?;
+        s.serialize_field("gen_utime", &self.gen_utime)?;
+        s.serialize_field("next_catchain_seqno", &self.next_catchain_seqno)?;
+        s.serialize_field("fees_collected", &self.fees_collected)?;
+        s.serialize_field("funds_created", &self.funds_created)?;
+        s.serialize_field("boc", &to_boc::<_, S::Error>(self, SERDE_OPTS_EMPTY)?)?;
+        s.end()
+    }
+}
+
+#[derive(Serialize)]
+struct ShardDescrJson {
+    workchain_id: i32,
+    shard: String,
+    #[serde(flatten)]
+    descr: ShardDescr,
+}
+
+fn shards_json<E: SerError>(extra_shards: &crate::master::ShardHashes) -> std::result::Result<Vec<ShardDescrJson>, E> {
+    let mut shards = Vec::new();
+    extra_shards.iterate_shards(|shard, descr| {
+        shards.push(ShardDescrJson {
+            workchain_id: shard.workchain_id(),
+            shard: shard.shard_prefix_as_str_with_tag(),
+            descr,
+        });
+        Ok(true)
+    }).map_err(E::custom)?;
+    Ok(shards)
+}
+
+impl Serialize for McBlockExtra {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("McBlockExtra", 4)?;
+        s.serialize_field("key_block", &self.is_key_block())?;
+        s.serialize_field("shards", &shards_json::<S::Error>(self.shards())?)?;
+        s.serialize_field("fees", self.total_fee())?;
+        s.serialize_field("boc", &to_boc::<_, S::Error>(self, self.serde_opts())?)?;
+        s.end()
+    }
+}
+
+impl Serialize for McStateExtra {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("McStateExtra", 6)?;
+        s.serialize_field("shards", &shards_json::<S::Error>(&self.shards)?)?;
+        s.serialize_field("after_key_block", &self.after_key_block)?;
+        s.serialize_field("last_key_block_seqno", &self.last_key_block.as_ref().map(|id| id.seq_no))?;
+        s.serialize_field("global_balance", &self.global_balance)?;
+        s.serialize_field("config_address", &hash_to_hex(&self.config.config_addr))?;
+        s.serialize_field("boc", &to_boc::<_, S::Error>(self, SERDE_OPTS_EMPTY)?)?;
+        s.end()
+    }
+}
+
+impl Serialize for Transaction {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let cell = self.serialize_with_opts(self.serde_opts()).map_err(S::Error::custom)?;
+        let mut s = serializer.serialize_struct("Transaction", 11)?;
+        s.serialize_field("id", &hash_to_hex(&cell.repr_hash()))?;
+        s.serialize_field("account_addr", &self.account_id().as_hex_string())?;
+        s.serialize_field("lt", &self.logical_time())?;
+        s.serialize_field("prev_trans_hash", &hash_to_hex(self.prev_trans_hash()))?;
+        s.serialize_field("prev_trans_lt", &self.prev_trans_lt())?;
+        s.serialize_field("now", &self.now())?;
+        s.serialize_field("outmsg_cnt", &self.msg_count())?;
+        s.serialize_field("orig_status", status_to_str(&self.orig_status))?;
+        s.serialize_field("end_status", status_to_str(&self.end_status))?;
+        s.serialize_field("total_fees", self.total_fees())?;
+        let bytes = write_boc(&cell).map_err(S::Error::custom)?;
+        s.serialize_field("boc", &base64_encode(bytes))?;
+        s.end()
+    }
+}
+
+impl Serialize for Message {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let cell = Serializable::serialize(self).map_err(S::Error::custom)?;
+        let msg_type = match self.header() {
+            CommonMsgInfo::IntMsgInfo(_) => "internal",
+            CommonMsgInfo::ExtInMsgInfo(_) => "external_in",
+            CommonMsgInfo::ExtOutMsgInfo(_) => "external_out",
+        };
+        let mut s = serializer.serialize_struct("Message", 8)?;
+        s.serialize_field("id", &hash_to_hex(&cell.repr_hash()))?;
+        s.serialize_field("msg_type", msg_type)?;
+        s.serialize_field("src", &self.src_ref().map(|addr| addr.to_string()))?;
+        s.serialize_field("dst", &self.dst_ref().map(|addr| addr.to_string()))?;
+        s.serialize_field("value", &self.get_value())?;
+        s.serialize_field("created_at", &self.at_and_lt().map(|(at, _)| at))?;
+        s.serialize_field("created_lt", &self.lt())?;
+        let bytes = write_boc(&cell).map_err(S::Error::custom)?;
+        s.serialize_field("boc", &base64_encode(bytes))?;
+        s.end()
+    }
+}
+
+impl Serialize for Account {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("Account", 6)?;
+        s.serialize_field("address", &self.get_addr().map(|addr| addr.to_string()))?;
+        s.serialize_field("status", status_to_str(&self.status()))?;
+        s.serialize_field("balance", &self.balance())?;
+        s.serialize_field("last_trans_lt", &self.last_tr_time())?;
+        s.serialize_field("last_paid", &self.last_paid())?;
+        s.serialize_field("boc", &to_boc::<_, S::Error>(self, SERDE_OPTS_EMPTY)?)?;
+        s.end()
+    }
+}
+
+macro_rules! impl_deserialize_from_boc {
+    ($($ty:ty),*) => {
+        $(
+            impl<'de> Deserialize<'de> for $ty {
+                fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+                    let json = BocJson::deserialize(deserializer)?;
+                    from_boc::<$ty, D::Error>(&json.boc)
+                }
+            }
+        )*
+    };
+}
+
+impl_deserialize_from_boc!(
+    Block, BlockInfo, McBlockExtra, McStateExtra, ShardDescr, Transaction, Message, Account
+);
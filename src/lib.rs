@@ -93,6 +93,53 @@ pub use self::signature::*;
 pub mod config_params;
 pub use self::config_params::*;
 
+pub mod throughput;
+pub use self::throughput::*;
+
+pub mod cell_graph;
+pub use self::cell_graph::*;
+
+pub mod bootstrap;
+pub use self::bootstrap::*;
+
+pub mod proof_index;
+pub use self::proof_index::*;
+
+pub mod block_builder;
+pub use self::block_builder::*;
+
+pub mod zerostate;
+pub use self::zerostate::*;
+
+pub mod state_editor;
+pub use self::state_editor::*;
+
+pub mod message_builder;
+pub use self::message_builder::*;
+
+pub mod dump;
+pub use self::dump::*;
+
+#[cfg(feature = "state_generator")]
+pub mod state_generator;
+#[cfg(feature = "state_generator")]
+pub use self::state_generator::*;
+
+#[cfg(feature = "arbitrary")]
+mod arbitrary_types;
+
+#[cfg(feature = "json")]
+pub mod json;
+
+#[cfg(feature = "derive")]
+extern crate self as ever_block;
+#[cfg(feature = "derive")]
+pub use ever_block_derive::TlbSerializable;
+
+#[cfg(all(test, feature = "derive"))]
+#[path = "tests/test_derive.rs"]
+mod test_derive;
+
 use std::{collections::HashMap, hash::Hash};
 
 include!("../common/src/info.rs");
@@ -225,6 +272,80 @@ impl Deserializable for HashmapE {
 pub const SERDE_OPTS_EMPTY: u8 = 0b0000_0000;
 pub const SERDE_OPTS_COMMON_MESSAGE: u8 = 0b0000_0001;
 
+/// Serialization options carried through nested structures.
+/// Use it to construct children (`ChildCell`s, dictionaries) of a structure
+/// so they share the options of their parent instead of falling back to defaults.
+/// `Block::with_serde_context`, `BlockExtra::with_serde_context` and `BlockBuilder` take it.
+/// `BlockExtra` serializes not filled descriptors with its own options
+/// and fails on filled ones serialized with other options.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct SerdeContext {
+    opts: u8,
+}
+
+impl SerdeContext {
+    pub const fn with_opts(opts: u8) -> Self {
+        Self { opts }
+    }
+    pub const fn empty() -> Self {
+        Self::with_opts(SERDE_OPTS_EMPTY)
+    }
+    pub const fn common_message() -> Self {
+        Self::with_opts(SERDE_OPTS_COMMON_MESSAGE)
+    }
+    pub fn opts(&self) -> u8 {
+        self.opts
+    }
+    pub fn is_empty(&self) -> bool {
+        self.opts == SERDE_OPTS_EMPTY
+    }
+    pub fn has(&self, opts: u8) -> bool {
+        self.opts & opts == opts
+    }
+    /// Creates empty child cell which inherits options of the context
+    pub fn child<T: Serializable + Deserializable>(&self) -> ChildCell<T> {
+        ChildCell::with_serde_opts(self.opts)
+    }
+    /// Serializes struct into child cell with options of the context
+    pub fn child_with_struct<T: Serializable + Deserializable>(&self, s: &T) -> Result<ChildCell<T>> {
+        ChildCell::with_struct_and_opts(s, self.opts)
+    }
+    /// Fails if nested structure options differ from the context ones
+    pub fn check<T>(&self, opts: u8) -> Result<()> {
+        if self.opts != opts {
+            fail!(BlockError::MismatchedSerdeOptions(
+                std::any::type_name::<T>().to_string(),
+                self.opts as usize,
+                opts as usize
+            ))
+        }
+        Ok(())
+    }
+}
+
+impl From<u8> for SerdeContext {
+    fn from(opts: u8) -> Self {
+        Self::with_opts(opts)
+    }
+}
+
+/// Equality of the content regardless of serialization options: structures
+/// constructed with different options but holding the same data are equal.
+/// Derived `PartialEq` compares options and serialized cells, so it can't be used for that.
+pub trait SemanticEq {
+    fn semantic_eq(&self, other: &Self) -> bool;
+}
+
+impl<T: SemanticEq> SemanticEq for Option<T> {
+    fn semantic_eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Some(value), Some(other)) => value.semantic_eq(other),
+            (None, None) => true,
+            _ => false
+        }
+    }
+}
+
 pub trait Serializable {
     fn write_to(&self, cell: &mut BuilderData) -> Result<()>;
 
@@ -280,12 +401,12 @@ pub trait Serializable {
 pub trait Deserializable: Default {
     fn construct_from(slice: &mut SliceData) -> Result<Self> {
         let mut x = Self::default();
-        x.read_from(slice)?;
+        x.read_from(slice).map_err(|err| DeserializeContext::wrap::<Self>(err, slice))?;
         Ok(x)
     }
     fn construct_from_with_opts(slice: &mut SliceData, opts: u8) -> Result<Self> {
         let mut x = Self::default();
-        x.read_from_with_opts(slice, opts)?;
+        x.read_from_with_opts(slice, opts).map_err(|err| DeserializeContext::wrap::<Self>(err, slice))?;
         Ok(x)
     }
     fn construct_maybe_from(slice: &mut SliceData) -> Result<Option<Self>> {
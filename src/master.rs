@@ -13,21 +13,24 @@
 
 use crate::{
     bintree::{BinTree, BinTreeType},
-    blocks::{Block, BlockIdExt, ExtBlkRef, ProofChain},
+    blocks::{Block, BlockIdExt, ExtBlkRef, ProofChain, ValueFlow},
     config_params::ConfigParams,
     define_HashmapAugE, define_HashmapE,
     dictionary::hashmapaug::{Augmentable, HashmapAugType, TraverseNextStep},
-    error::BlockError, HashUpdate,
+    error::{BlockError, WithDeserializeContext}, HashUpdate,
+    envelope_message::MsgEnvelope,
     inbound_messages::InMsg,
-    shard::{AccountIdPrefixFull, ShardIdent, SHARD_FULL},
-    signature::CryptoSignaturePair,
-    types::{ChildCell, CurrencyCollection, InRefValue},
-    validators::ValidatorInfo, VarUInteger32,
-    CopyleftRewards, Deserializable, Serializable, U15, Augmentation,
-    error, fail, hm_label, AccountId, BuilderData, Cell, IBitstring, Result,
+    messages::{CommonMessage, InternalMessageHeader, Message, MsgAddressInt},
+    shard::{AccountIdPrefixFull, ShardIdent, MASTERCHAIN_ID, MAX_SPLIT_DEPTH, SHARD_FULL},
+    signature::{check_signatures_weight, CryptoSignaturePair, KeyProvider},
+    transactions::Transaction,
+    types::{AddSub, ChildCell, CurrencyCollection, Grams, InRefValue},
+    validators::{ValidatorDescr, ValidatorInfo, ValidatorSet}, VarUInteger32,
+    CopyleftRewards, Deserializable, SemanticEq, Serializable, U15, Augmentation,
+    crypto_provider, error, fail, hm_label, AccountId, BuilderData, Cell, IBitstring, Result,
     SERDE_OPTS_COMMON_MESSAGE, SERDE_OPTS_EMPTY, SliceData, UInt256,
 };
-use std::{collections::HashMap, fmt};
+use std::{cmp::Reverse, collections::{BTreeMap, HashMap, HashSet}, fmt, ops::Range};
 
 #[cfg(test)]
 #[path = "tests/test_master.rs"]
@@ -41,6 +44,108 @@ define_HashmapE!{ShardHashes, 32, InRefValue<BinTree<ShardDescr>>}
 define_HashmapE!{CryptoSignatures, 16, CryptoSignaturePair}
 define_HashmapAugE!{ShardFees, 96, ShardIdentFull, ShardFeeCreated, ShardFeeCreated}
 
+impl CryptoSignatures {
+    /// Adds signature of the validator from the set with the first free index.
+    /// Signatures of unknown validators and second signatures of the same validator are rejected.
+    pub fn insert_unique(&mut self, pair: CryptoSignaturePair, vset: &ValidatorSet) -> Result<u16> {
+        if !vset.list().iter().any(|vd| vd.compute_node_id_short() == pair.node_id_short) {
+            fail!(BlockError::InvalidArg(
+                format!("validator {:x} is not in the validator set", pair.node_id_short)
+            ))
+        }
+        let mut used = HashSet::new();
+        self.iterate_with_keys(|index: u16, other| {
+            if other.node_id_short == pair.node_id_short {
+                fail!(BlockError::InvalidArg(
+                    format!("signature of validator {:x} is already present", pair.node_id_short)
+                ))
+            }
+            used.insert(index);
+            Ok(true)
+        })?;
+        let index = (0..=u16::MAX)
+            .take(vset.list().len())
+            .find(|index| !used.contains(index))
+            .ok_or_else(|| error!(BlockError::InvalidOperation(
+                "there is no free index for the signature".to_string()
+            )))?;
+        self.set(&index, &pair)?;
+        Ok(index)
+    }
+
+    /// Sum of weights of validators made the signatures (signatures are not verified).
+    /// Signatures of unknown validators, second signatures of the same validator
+    /// and indexes out of the set are rejected.
+    pub fn total_weight(&self, vset: &ValidatorSet) -> Result<u64> {
+        let validators: HashMap<UInt256, &ValidatorDescr> = vset.list().iter()
+            .map(|vd| (vd.compute_node_id_short(), vd))
+            .collect();
+        let mut signers = HashSet::new();
+        let mut weight = 0;
+        self.iterate_with_keys(|index: u16, pair| {
+            if index as usize >= vset.list().len() {
+                fail!(BlockError::InvalidIndex(index as usize))
+            }
+            let vd = validators.get(&pair.node_id_short).ok_or_else(|| error!(BlockError::InvalidData(
+                format!("signature #{} is made by unknown validator {:x}", index, pair.node_id_short)
+            )))?;
+            if !signers.insert(pair.node_id_short.clone()) {
+                fail!(BlockError::InvalidData(
+                    format!("validator {:x} signed the block more than once", pair.node_id_short)
+                ))
+            }
+            weight += vd.weight;
+            Ok(true)
+        })?;
+        Ok(weight)
+    }
+
+    /// Same as `total_weight` but every signature is verified against `data`
+    pub fn verified_weight(&self, vset: &ValidatorSet, data: &[u8]) -> Result<u64> {
+        self.total_weight(vset)?;
+        check_signatures_weight(&self.0, vset.list(), data)
+    }
+
+    /// Verifies all signatures of `data` at once using batch verification.
+    /// Batch verification may reject signatures accepted one by one, so rejected batch
+    /// is verified signature by signature to get the same result and find the bad signer.
+    /// Fails if there are no signatures, some signer is unknown to `keys` or any signature is invalid
+    pub fn verify_batch(&self, data: &[u8], keys: &dyn KeyProvider) -> Result<()> {
+        let mut signers = Vec::new();
+        self.iterate_with_keys(|index: u16, pair| {
+            let key = keys.public_key(&pair.node_id_short).ok_or_else(|| error!(BlockError::InvalidData(
+                format!("signature #{} is made by unknown validator {:x}", index, pair.node_id_short)
+            )))?;
+            signers.push((index, key, pair));
+            Ok(true)
+        })?;
+        if signers.is_empty() {
+            fail!(BlockError::InvalidData("there are no signatures to verify".to_string()))
+        }
+        let messages = vec![data; signers.len()];
+        let pub_keys: Vec<_> = signers.iter().map(|(_, key, _)| key.as_slice()).collect();
+        let signatures: Vec<_> = signers.iter().map(|(_, _, pair)| pair.sign.as_bytes()).collect();
+        if crypto_provider().ed25519_verify_batch(&messages, &pub_keys, &signatures).is_ok() {
+            return Ok(())
+        }
+        for (index, key, pair) in &signers {
+            if !key.verify_signature(data, &pair.sign) {
+                fail!(BlockError::InvalidData(format!(
+                    "signature #{} of validator {:x} is invalid", index, pair.node_id_short
+                )))
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks if weight of the verified signatures of `data` is greater
+    /// than 2/3 of the total weight of the set
+    pub fn is_quorum(&self, vset: &ValidatorSet, data: &[u8]) -> Result<bool> {
+        let weight = self.verified_weight(vset, data)?;
+        Ok((weight as u128) * 3 > (vset.total_weight() as u128) * 2)
+    }
+}
+
 impl Augmentation<ShardFeeCreated> for ShardFeeCreated {
     fn aug(&self) -> Result<ShardFeeCreated> {
         Ok(self.clone())
@@ -72,8 +177,8 @@ impl Serializable for ShardIdentFull {
 
 impl Deserializable for ShardIdentFull {
     fn read_from(&mut self, cell: &mut SliceData) -> Result<()> {
-        self.workchain_id.read_from(cell)?;
-        self.prefix.read_from(cell)?;
+        self.workchain_id.read_from(cell).with_ctx("ShardIdentFull", "workchain_id")?;
+        self.prefix.read_from(cell).with_ctx("ShardIdentFull", "prefix")?;
         Ok(())
     }
 }
@@ -110,6 +215,35 @@ impl ShardHashes {
             })
         })
     }
+    /// Ids of the latest blocks of all shards sorted by workchain and shard prefix
+    pub fn top_blocks(&self) -> Result<Vec<BlockIdExt>> {
+        let mut shards = Vec::new();
+        self.iterate_shards(|shard, descr| {
+            shards.push(McShardRecord::from_shard_descr(shard, descr).block_id);
+            Ok(true)
+        })?;
+        Self::sort_top_blocks(&mut shards);
+        Ok(shards)
+    }
+    /// Ids of the latest blocks of the shards of given workchains sorted by
+    /// workchain and shard prefix, absent workchains are skipped
+    pub fn top_blocks_for_workchains(&self, workchains: &[i32]) -> Result<Vec<BlockIdExt>> {
+        let mut shards = Vec::new();
+        let mut workchains = workchains.to_vec();
+        workchains.sort_unstable();
+        workchains.dedup();
+        for workchain_id in workchains {
+            self.iterate_shards_for_workchain(workchain_id, |shard, descr| {
+                shards.push(McShardRecord::from_shard_descr(shard, descr).block_id);
+                Ok(true)
+            })?;
+        }
+        Self::sort_top_blocks(&mut shards);
+        Ok(shards)
+    }
+    fn sort_top_blocks(shards: &mut [BlockIdExt]) {
+        shards.sort_by_key(|id| (id.shard().workchain_id(), id.shard().shard_prefix_with_tag()));
+    }
     pub fn iterate_shards_with_siblings<F>(&self, mut func: F) -> Result<bool>
     where F: FnMut(ShardIdent, ShardDescr, Option<ShardDescr>) -> Result<bool> {
         self.iterate_with_keys(|wc_id: i32, InRefValue(shards)| {
@@ -202,20 +336,31 @@ impl ShardHashes {
         let shard1 = self.find_shard(&shard.left_ancestor_mask()?)?
             .ok_or_else(|| error!("get_shard_cc_seqno: can't find shard1"))?;
 
+        // shard is the same or split from registered one by one or several levels
         if shard1.shard().is_ancestor_for(shard) {
             return Ok(shard1.descr.next_catchain_seqno)
-        } else if !shard.is_parent_for(shard1.shard()) {
+        } else if !shard.is_ancestor_for(shard1.shard()) {
             fail!("get_shard_cc_seqno: invalid shard1 {} for {}", shard1.shard(), shard)
         }
 
         let shard2 = self.find_shard(&shard.right_ancestor_mask()?)?
             .ok_or_else(|| error!("get_shard_cc_seqno: can't find shard2"))?;
 
-        if !shard.is_parent_for(shard2.shard()) {
+        if !shard.is_ancestor_for(shard2.shard()) {
             fail!("get_shard_cc_seqno: invalid shard2 {} for {}", shard2.shard(), shard)
         }
 
-        Ok(std::cmp::max(shard1.descr.next_catchain_seqno, shard2.descr.next_catchain_seqno) + 1)
+        // shard is merged from all registered shards it covers, they can be on different levels
+        let mut cc_seqno = std::cmp::max(shard1.descr.next_catchain_seqno, shard2.descr.next_catchain_seqno);
+        if !shard.is_parent_for(shard1.shard()) || !shard.is_parent_for(shard2.shard()) {
+            self.iterate_shards_for_workchain(shard.workchain_id(), |ident, descr| {
+                if shard.is_ancestor_for(&ident) {
+                    cc_seqno = std::cmp::max(cc_seqno, descr.next_catchain_seqno);
+                }
+                Ok(true)
+            })?;
+        }
+        Ok(cc_seqno + 1)
     }
     pub fn split_shard(
         &mut self,
@@ -243,6 +388,61 @@ impl ShardHashes {
             self.set(&new_shard.workchain_id(), &tree)
         }
     }
+    /// Splits shard checking split depth and shard count limits of its workchain from the config
+    pub fn split_shard_with_config(
+        &mut self,
+        splitted_shard: &ShardIdent,
+        config: &ConfigParams,
+        splitter: impl FnOnce(ShardDescr) -> Result<(ShardDescr, ShardDescr)>
+    ) -> Result<()> {
+        let limits = ShardSplitLimits::with_config(config, splitted_shard.workchain_id())?;
+        self.split_shard_with_limits(splitted_shard, &limits, splitter)
+    }
+    pub fn split_shard_with_limits(
+        &mut self,
+        splitted_shard: &ShardIdent,
+        limits: &ShardSplitLimits,
+        splitter: impl FnOnce(ShardDescr) -> Result<(ShardDescr, ShardDescr)>
+    ) -> Result<()> {
+        if splitted_shard.prefix_len() >= limits.max_split {
+            fail!(BlockError::InvalidArg(format!(
+                "shard {} can't be split: max split depth {} is reached", splitted_shard, limits.max_split
+            )))
+        }
+        let mut tree = self.get(&splitted_shard.workchain_id())?
+            .ok_or_else(|| error!("Can't find workchain {}", splitted_shard.workchain_id()))?;
+        let mut count = 0u64;
+        tree.0.iterate(|_, _| {
+            count += 1;
+            Ok(true)
+        })?;
+        if count >= limits.max_shards {
+            fail!(BlockError::InvalidArg(format!(
+                "shard {} can't be split: workchain already has {} shards of {} allowed",
+                splitted_shard, count, limits.max_shards
+            )))
+        }
+        if !tree.0.split_with_max_depth(splitted_shard.shard_key(false), limits.max_split as usize, splitter)? {
+            fail!("Splitted shard {} is not found", splitted_shard)
+        } else {
+            self.set(&splitted_shard.workchain_id(), &tree)
+        }
+    }
+    /// Merges shards checking min split depth of their workchain from the config
+    pub fn merge_shards_with_config(
+        &mut self,
+        new_shard: &ShardIdent,
+        config: &ConfigParams,
+        merger: impl FnOnce(ShardDescr, ShardDescr) -> Result<ShardDescr>
+    ) -> Result<()> {
+        let limits = ShardSplitLimits::with_config(config, new_shard.workchain_id())?;
+        if new_shard.prefix_len() < limits.min_split {
+            fail!(BlockError::InvalidArg(format!(
+                "shards can't be merged into {}: min split depth is {}", new_shard, limits.min_split
+            )))
+        }
+        self.merge_shards(new_shard, merger)
+    }
     pub fn update_shard(
         &mut self,
         shard: &ShardIdent,
@@ -283,7 +483,32 @@ impl ShardHashes {
     }
 }
 
+/// Protocol limits for the shards of the workchain
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ShardSplitLimits {
+    pub min_split: u8,
+    pub max_split: u8,
+    pub max_shards: u64,
+}
+
+impl ShardSplitLimits {
+    /// Split depth limits are taken from the workchain description (ConfigParam12),
+    /// shard count is limited by max split depth
+    pub fn with_config(config: &ConfigParams, workchain_id: i32) -> Result<Self> {
+        let descr = config.workchains()?.get(&workchain_id)?.ok_or_else(|| error!(BlockError::NotFound(
+            format!("workchain {} description", workchain_id)
+        )))?;
+        let max_split = std::cmp::min(descr.max_split(), MAX_SPLIT_DEPTH);
+        Ok(Self {
+            min_split: descr.min_split(),
+            max_split,
+            max_shards: 1 << max_split,
+        })
+    }
+}
+
 impl ShardHashes {
+    #[deprecated(note = "use Dump::dump_to_string")]
     pub fn dump(&self, heading: &str) -> usize {
         let mut count = 0;
         println!("dumping shard records for: {}", heading);
@@ -305,6 +530,158 @@ impl ShardHashes {
     }
 }
 
+/// In-memory index of the shard tops built once from `ShardHashes`.
+/// Answers shard lookups by binary search over the table sorted by workchain and prefix.
+/// The index is bound to the root of `ShardHashes` it was built from
+/// and must be refreshed after any mutation of it.
+#[derive(Clone, Debug, Default)]
+pub struct ShardHashesIndex {
+    root_hash: Option<UInt256>,
+    shards: Vec<McShardRecord>,
+}
+
+impl ShardHashesIndex {
+    pub fn with_shard_hashes(shard_hashes: &ShardHashes) -> Result<Self> {
+        let mut shards = Vec::new();
+        shard_hashes.iterate_shards(|shard, descr| {
+            shards.push(McShardRecord::from_shard_descr(shard, descr));
+            Ok(true)
+        })?;
+        shards.sort_by_key(|record| Self::sort_key(record.shard()));
+        Ok(Self {
+            root_hash: Self::root_hash_of(shard_hashes),
+            shards,
+        })
+    }
+
+    /// Checks if the index was built from the same state of `ShardHashes`
+    pub fn is_actual(&self, shard_hashes: &ShardHashes) -> bool {
+        self.root_hash == Self::root_hash_of(shard_hashes)
+    }
+
+    /// Rebuilds the index if `ShardHashes` were changed, returns true if rebuilt
+    pub fn refresh(&mut self, shard_hashes: &ShardHashes) -> Result<bool> {
+        if self.is_actual(shard_hashes) {
+            return Ok(false)
+        }
+        *self = Self::with_shard_hashes(shard_hashes)?;
+        Ok(true)
+    }
+
+    pub fn len(&self) -> usize { self.shards.len() }
+
+    pub fn is_empty(&self) -> bool { self.shards.is_empty() }
+
+    /// All shard tops sorted by workchain and prefix
+    pub fn shards(&self) -> &[McShardRecord] { &self.shards }
+
+    /// Same as `ShardHashes::find_shard`
+    pub fn find_shard(&self, shard: &ShardIdent) -> Option<&McShardRecord> {
+        self.find_by_prefix(shard.workchain_id(), shard.shard_prefix_without_tag())
+            .filter(|record| record.shard().is_ancestor_for(shard))
+    }
+
+    /// Same as `ShardHashes::find_shard_by_prefix`
+    pub fn find_shard_by_prefix(&self, prefix: &AccountIdPrefixFull) -> Option<&McShardRecord> {
+        self.find_by_prefix(prefix.workchain_id(), prefix.prefix)
+    }
+
+    /// Same as `ShardHashes::get_shard`
+    pub fn get_shard(&self, shard: &ShardIdent) -> Option<&McShardRecord> {
+        self.find_by_prefix(shard.workchain_id(), shard.shard_prefix_without_tag())
+            .filter(|record| record.shard() == shard)
+    }
+
+    fn find_by_prefix(&self, workchain_id: i32, prefix: u64) -> Option<&McShardRecord> {
+        let pos = self.shards.partition_point(|record| {
+            Self::sort_key(record.shard()) <= (workchain_id, prefix)
+        });
+        let record = self.shards.get(pos.checked_sub(1)?)?;
+        if record.shard().contains_prefix(workchain_id, prefix) {
+            Some(record)
+        } else {
+            None
+        }
+    }
+
+    /// Position of the shard top equal to `shard` in the sorted table
+    fn position(&self, shard: &ShardIdent) -> Option<usize> {
+        let pos = self.shards
+            .binary_search_by_key(&Self::sort_key(shard), |record| Self::sort_key(record.shard()))
+            .ok()?;
+        (self.shards[pos].shard() == shard).then_some(pos)
+    }
+
+    fn sort_key(shard: &ShardIdent) -> (i32, u64) {
+        (shard.workchain_id(), shard.shard_prefix_without_tag())
+    }
+
+    fn root_hash_of(shard_hashes: &ShardHashes) -> Option<UInt256> {
+        shard_hashes.root().map(|root| root.repr_hash())
+    }
+}
+
+/// Neighbors of every shard top precomputed once from `ShardHashes` for message routing.
+/// Like `ShardHashesIndex` it is bound to the root of `ShardHashes` it was built from.
+#[derive(Clone, Debug, Default)]
+pub struct NeighborIndex {
+    shards: ShardHashesIndex,
+    neighbors: Vec<Vec<usize>>, // sorted positions of the neighbors for every shard top
+}
+
+impl NeighborIndex {
+    pub fn with_shard_hashes(shard_hashes: &ShardHashes) -> Result<Self> {
+        Ok(Self::with_index(ShardHashesIndex::with_shard_hashes(shard_hashes)?))
+    }
+
+    pub fn with_index(shards: ShardHashesIndex) -> Self {
+        let neighbors = shards.shards().iter().map(|record| {
+            shards.shards().iter().enumerate()
+                .filter(|(_, other)| record.shard().is_neighbor_for(other.shard()))
+                .map(|(pos, _)| pos)
+                .collect()
+        }).collect();
+        Self { shards, neighbors }
+    }
+
+    /// Checks if the index was built from the same state of `ShardHashes`
+    pub fn is_actual(&self, shard_hashes: &ShardHashes) -> bool {
+        self.shards.is_actual(shard_hashes)
+    }
+
+    /// Rebuilds the index if `ShardHashes` were changed, returns true if rebuilt
+    pub fn refresh(&mut self, shard_hashes: &ShardHashes) -> Result<bool> {
+        if self.is_actual(shard_hashes) {
+            return Ok(false)
+        }
+        *self = Self::with_shard_hashes(shard_hashes)?;
+        Ok(true)
+    }
+
+    pub fn shards(&self) -> &ShardHashesIndex { &self.shards }
+
+    /// Same as `ShardHashes::get_neighbours` but sorted by workchain and prefix.
+    /// Shards which are not tops of the index are looked up by full scan
+    pub fn neighbors_of(&self, shard: &ShardIdent) -> Vec<&McShardRecord> {
+        let records = self.shards.shards();
+        if shard.is_masterchain() {
+            return records.iter().collect()
+        }
+        match self.shards.position(shard) {
+            Some(pos) => self.neighbors[pos].iter().map(|pos| &records[*pos]).collect(),
+            None => records.iter().filter(|record| shard.is_neighbor_for(record.shard())).collect()
+        }
+    }
+
+    /// Same as `ShardIdent::is_neighbor_for` answered from the index for the shard tops
+    pub fn is_neighbor(&self, a: &ShardIdent, b: &ShardIdent) -> bool {
+        match (self.shards.position(a), self.shards.position(b)) {
+            (Some(pos_a), Some(pos_b)) => self.neighbors[pos_a].binary_search(&pos_b).is_ok(),
+            _ => a.is_neighbor_for(b)
+        }
+    }
+}
+
 #[derive(Clone, Default, Debug, Eq, PartialEq)]
 pub struct McShardRecord {
     pub descr: ShardDescr,
@@ -371,6 +748,13 @@ impl McShardRecord {
 
     pub fn block_id(&self) -> &BlockIdExt { &self.block_id }
 
+    /// Priority of the shard top competing for inclusion into masterchain block,
+    /// greater value goes first: blocks waiting longer since gen_utime,
+    /// then blocks with lower collation_order_key
+    pub fn priority(&self, now: u32) -> (u32, Reverse<(i32, u64, u32, UInt256, UInt256)>) {
+        (now.saturating_sub(self.descr.gen_utime), Reverse(self.block_id.collation_order_key()))
+    }
+
     pub fn basic_info_equal(&self, other: &Self, compare_fees: bool, compare_reg_seqno: bool) -> bool {
         self.block_id == other.block_id
             && self.descr.start_lt == other.descr.start_lt
@@ -388,6 +772,18 @@ impl McShardRecord {
     }
 }
 
+/// `{}` prints compact line, `{:#}` prints all fields of the descr line by line
+impl fmt::Display for McShardRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            writeln!(f, "block_id: {}", self.block_id)?;
+            write!(f, "{:#}", self.descr)
+        } else {
+            write!(f, "{} {}", self.block_id.shard(), self.descr)
+        }
+    }
+}
+
 impl ShardFees {
     pub fn store_shard_fees(
         &mut self,
@@ -403,6 +799,51 @@ impl ShardFees {
         self.set(&id, &fee, &fee)?;
         Ok(())
     }
+
+    /// Sums of fees and created amounts of all shards grouped by workchain
+    pub fn totals_by_workchain(&self) -> Result<BTreeMap<i32, ShardFeeCreated>> {
+        let mut totals = BTreeMap::<i32, ShardFeeCreated>::new();
+        self.iterate_with_keys(|id: ShardIdentFull, fee| {
+            totals.entry(id.workchain_id).or_default().calc(&fee)?;
+            Ok(true)
+        })?;
+        Ok(totals)
+    }
+
+    /// Checks that fees_imported of the masterchain block value flow is equal to the shard
+    /// fees total and created is the masterchain block create fee plus the shard created total
+    pub fn reconcile_with_value_flow(
+        &self,
+        mc_value_flow: &ValueFlow,
+        mc_block_create_fee: &CurrencyCollection,
+    ) -> Result<()> {
+        let total = self.root_extra();
+        let mut errors = Vec::new();
+        if total.fees != mc_value_flow.fees_imported {
+            errors.push(format!(
+                "fees_imported is {} but shard fees total is {}", mc_value_flow.fees_imported, total.fees
+            ));
+        }
+        let mut created = total.create.clone();
+        created.add(mc_block_create_fee)?;
+        if created != mc_value_flow.created {
+            errors.push(format!(
+                "created is {} but shard created total is {} and block create fee is {}",
+                mc_value_flow.created, total.create, mc_block_create_fee
+            ));
+        }
+        if !errors.is_empty() {
+            let mut by_workchain = String::new();
+            for (workchain_id, fee) in self.totals_by_workchain()? {
+                by_workchain += &format!(" workchain {}: fees {}, created {};", workchain_id, fee.fees, fee.create);
+            }
+            fail!(BlockError::InvalidData(format!(
+                "value flow doesn't match shard fees: {}; totals by workchain:{}",
+                errors.join(", "), by_workchain
+            )))
+        }
+        Ok(())
+    }
 }
 
 define_HashmapE!{CopyleftMessages, 15, InRefValue<InMsg>}
@@ -470,9 +911,138 @@ impl McBlockExtra {
     pub fn fees(&self) -> &ShardFees { &self.fees }
     pub fn fees_mut(&mut self) -> &mut ShardFees { &mut self.fees }
 
+    /// Registers new top block of the shard in the masterchain block `mc_seq_no`:
+    /// replaces its description in the shard hashes splitting the parent or merging the children
+    /// if the shard has changed, and stores fees of the block into the shard fees.
+    /// After split the sibling keeps the description of the parent until its own block is registered
+    pub fn register_shard_block(&mut self, record: &McShardRecord, mc_seq_no: u32) -> Result<()> {
+        let shard = record.shard();
+        if shard.is_masterchain() {
+            fail!(BlockError::InvalidArg(format!("block {} is not a shard block", record.block_id)))
+        }
+        let mut descr = record.descr.clone();
+        descr.reg_mc_seqno = mc_seq_no;
+        descr.validate()?;
+        let seq_no = descr.seq_no;
+        let check_seq_no = |prev: &McShardRecord| -> Result<()> {
+            if seq_no <= prev.descr.seq_no {
+                fail!(BlockError::InvalidArg(format!(
+                    "block {} is not newer than registered top {}", record.block_id, prev.block_id
+                )))
+            }
+            Ok(())
+        };
+        match self.shards.find_shard(shard)? {
+            Some(top) if top.shard() == shard => {
+                check_seq_no(&top)?;
+                self.shards.update_shard(shard, |_| Ok(descr))?;
+            }
+            Some(top) if top.shard().is_parent_for(shard) => {
+                if !top.descr.before_split {
+                    fail!(BlockError::InvalidData(format!("shard {} is not before split", top.shard())))
+                }
+                check_seq_no(&top)?;
+                let is_left = shard.is_left_child();
+                self.shards.split_shard(top.shard(), |mut sibling| {
+                    sibling.before_split = false;
+                    Ok(if is_left { (descr, sibling) } else { (sibling, descr) })
+                })?;
+            }
+            Some(top) => {
+                fail!(BlockError::InvalidArg(format!(
+                    "shard {} can't be registered over top {}", shard, top.block_id
+                )))
+            }
+            None => {
+                let (left, right) = shard.split()?;
+                for child in [left, right] {
+                    let child = self.shards.get_shard(&child)?.ok_or_else(|| error!(BlockError::NotFound(
+                        format!("shard {} or its children in shard hashes", shard)
+                    )))?;
+                    if !child.descr.before_merge {
+                        fail!(BlockError::InvalidData(format!("shard {} is not before merge", child.shard())))
+                    }
+                    check_seq_no(&child)?;
+                }
+                self.shards.merge_shards(shard, |_, _| Ok(descr))?;
+            }
+        }
+        self.fees.store_shard_fees(shard, record.descr.fees_collected.clone(), record.descr.funds_created.clone())
+    }
+
     pub fn prev_blk_signatures(&self) -> &CryptoSignatures { &self.prev_blk_signatures }
     pub fn prev_blk_signatures_mut(&mut self) -> &mut CryptoSignatures { &mut self.prev_blk_signatures }
 
+    /// Checks signatures of the previous masterchain block by validators from the set:
+    /// all indexes must be in range, every validator may sign only once, every signature
+    /// must be valid. Returns total weight of the signers.
+    pub fn check_prev_signatures(
+        &self,
+        vset: &ValidatorSet,
+        prev_block_root: &UInt256,
+        prev_block_file_hash: &UInt256,
+    ) -> Result<u64> {
+        let data = Block::build_data_for_sign(prev_block_root, prev_block_file_hash);
+        self.prev_blk_signatures.verified_weight(vset, &data)
+    }
+
+    /// Checks that every shard fees entry belongs to a shard present in shard hashes
+    /// and that the totals are equal to the root augmentation of the shard fees.
+    /// If `compare_with_descr` is set, the fees and created amounts must also be equal to
+    /// `fees_collected` and `funds_created` of the corresponding shard descr
+    pub fn check_shard_fees(&self, compare_with_descr: bool) -> Result<()> {
+        let mut total = ShardFeeCreated::default();
+        self.fees.iterate_with_keys(|id: ShardIdentFull, fee| {
+            let shard = ShardIdent::with_tagged_prefix(id.workchain_id, id.prefix)?;
+            let descr = match self.shards.get_shard(&shard)? {
+                Some(record) => record.descr,
+                None => fail!(BlockError::InvalidData(format!(
+                    "shard fees contain {} which is absent in shard hashes", shard
+                )))
+            };
+            if compare_with_descr {
+                if fee.fees != descr.fees_collected {
+                    fail!(BlockError::InvalidData(format!(
+                        "shard {} fees {} are not equal to fees_collected {} of its descr",
+                        shard, fee.fees, descr.fees_collected
+                    )))
+                }
+                if fee.create != descr.funds_created {
+                    fail!(BlockError::InvalidData(format!(
+                        "shard {} created {} are not equal to funds_created {} of its descr",
+                        shard, fee.create, descr.funds_created
+                    )))
+                }
+            }
+            total.calc(&fee)?;
+            Ok(true)
+        })?;
+        let root_extra = self.fees.root_extra();
+        if total != *root_extra {
+            fail!(BlockError::InvalidData(format!(
+                "shard fees total fees {} and created {} are not equal to the root augmentation fees {} and created {}",
+                total.fees, total.create, root_extra.fees, root_extra.create
+            )))
+        }
+        Ok(())
+    }
+
+    /// Cross-checks shard fees with the masterchain block value flow: entries must match
+    /// their shard descrs (see `check_shard_fees`), totals must match fees_imported and
+    /// created (see `ShardFees::reconcile_with_value_flow`) and fees_imported can't exceed
+    /// fees_collected
+    pub fn verify_total_fees(&self, value_flow: &ValueFlow, mc_block_create_fee: &CurrencyCollection) -> Result<()> {
+        self.check_shard_fees(true)?;
+        self.fees.reconcile_with_value_flow(value_flow, mc_block_create_fee)?;
+        let mut own_fees = value_flow.fees_collected.clone();
+        if !own_fees.sub(&value_flow.fees_imported)? {
+            fail!(BlockError::InvalidData(format!(
+                "fees_collected {} is less than fees_imported {}", value_flow.fees_collected, value_flow.fees_imported
+            )))
+        }
+        Ok(())
+    }
+
     pub fn config(&self) -> Option<&ConfigParams> { self.config.as_ref() }
     pub fn config_mut(&mut self) -> &mut Option<ConfigParams> { &mut self.config }
     pub fn set_config(&mut self, config: ConfigParams) { self.config = Some(config) }
@@ -501,6 +1071,87 @@ impl McBlockExtra {
         self.mint_msg.as_ref().map(|mr| mr.cell())
     }
 
+    /// Special message minting `value` to the minter account (ConfigParam 2)
+    pub fn create_mint_msg(
+        to_minter: &AccountId,
+        value: CurrencyCollection,
+        created_lt: u64,
+        created_at: u32,
+    ) -> Result<Message> {
+        Self::create_special_msg(to_minter, value, created_lt, created_at)
+    }
+
+    /// Special message returning recovered `fees` to the elector account (ConfigParam 1)
+    pub fn create_recover_msg(
+        fees: CurrencyCollection,
+        elector_addr: &AccountId,
+        created_lt: u64,
+        created_at: u32,
+    ) -> Result<Message> {
+        Self::create_special_msg(elector_addr, fees, created_lt, created_at)
+    }
+
+    /// Installs mint message processed by the `transaction` of the minter account
+    pub fn set_mint_msg(&mut self, transaction: &Transaction) -> Result<InMsg> {
+        let in_msg = self.special_in_msg(transaction)?;
+        self.write_mint_msg(Some(&in_msg))?;
+        Ok(in_msg)
+    }
+
+    /// Installs recover message processed by the `transaction` of the elector account
+    pub fn set_recover_create_msg(&mut self, transaction: &Transaction) -> Result<InMsg> {
+        let in_msg = self.special_in_msg(transaction)?;
+        self.write_recover_create_msg(Some(&in_msg))?;
+        Ok(in_msg)
+    }
+
+    fn special_src_addr() -> Result<MsgAddressInt> {
+        MsgAddressInt::with_standart(None, MASTERCHAIN_ID as i8, AccountId::from(UInt256::ZERO))
+    }
+
+    fn create_special_msg(
+        dst: &AccountId,
+        value: CurrencyCollection,
+        created_lt: u64,
+        created_at: u32,
+    ) -> Result<Message> {
+        let dst = MsgAddressInt::with_standart(None, MASTERCHAIN_ID as i8, dst.clone())?;
+        let header = InternalMessageHeader::with_addresses_and_bounce(
+            Self::special_src_addr()?, dst, value, true
+        );
+        let mut msg = Message::with_int_header(header);
+        msg.set_at_and_lt(created_at, created_lt);
+        Ok(msg)
+    }
+
+    fn special_in_msg(&self, transaction: &Transaction) -> Result<InMsg> {
+        let msg = transaction.read_in_msg()?.ok_or_else(|| error!(BlockError::InvalidArg(
+            format!("transaction of account {:x} has no inbound message", transaction.account_id())
+        )))?;
+        let header = msg.get_std()?.int_header().ok_or_else(|| error!(BlockError::InvalidArg(
+            "special message must be internal".to_string()
+        )))?;
+        let dst = MsgAddressInt::with_standart(None, MASTERCHAIN_ID as i8, transaction.account_id().clone())?;
+        if header.src_ref() != Some(&Self::special_src_addr()?) || header.dst != dst {
+            fail!(BlockError::InvalidArg(format!(
+                "message {} -> {} is not special for account {:x}", header.src, header.dst, transaction.account_id()
+            )))
+        }
+        if !header.ihr_fee.is_zero() || !header.fwd_fee.is_zero() {
+            fail!(BlockError::InvalidArg("special message must have no forwarding fees".to_string()))
+        }
+        let env = if self.serde_opts & SERDE_OPTS_COMMON_MESSAGE != 0 {
+            MsgEnvelope::with_common_msg_support(&msg, Grams::zero())?
+        } else {
+            MsgEnvelope::with_message_and_fee(msg.get_std()?, Grams::zero())?
+        };
+        Ok(InMsg::immediate(
+            ChildCell::with_struct_and_opts(&env, self.serde_opts)?,
+            ChildCell::with_struct_and_opts(transaction, self.serde_opts)?,
+            Grams::zero(),
+        ))
+    }
+
     pub fn read_copyleft_msgs(&self) -> Result<Vec<InMsg>> {
         let mut result = Vec::<InMsg>::default();
         for i in 0..self.copyleft_msgs.len()? {
@@ -524,6 +1175,63 @@ impl McBlockExtra {
     pub fn serde_opts(&self) -> u8 {
         self.serde_opts
     }
+
+    /// Serialized reference with signatures, recover/mint messages
+    /// and copyleft messages or mesh
+    pub fn extension_cell(&self) -> Result<Cell> {
+        let tag = self.tag(self.serde_opts)?;
+        self.write_extension(tag)?.into_cell()
+    }
+
+    /// Reads tag and extension reference from the serialized McBlockExtra
+    /// without parsing of the extension, works for unknown tags too
+    pub fn read_extension_cell(mc_extra: Cell) -> Result<(u16, Cell)> {
+        let slice = &mut SliceData::load_cell(mc_extra)?;
+        let tag = slice.get_next_u16()?;
+        slice.get_next_bit()?; // key_block
+        ShardHashes::construct_from(slice)?;
+        ShardFees::construct_from(slice)?;
+        Ok((tag, slice.checked_drain_reference()?))
+    }
+
+    /// Parses extension of the serialized McBlockExtra even if the rest of it
+    /// can't be parsed because of unknown (newer) tag
+    pub fn read_extension(mc_extra: Cell) -> Result<McBlockExtraExtension> {
+        let (tag, cell) = Self::read_extension_cell(mc_extra)?;
+        McBlockExtraExtension::construct_from_cell_with_tag(cell, tag)
+    }
+
+    fn tag(&self, opts: u8) -> Result<u16> {
+        let copyleft = !self.copyleft_msgs.is_empty();
+        let common_message = opts & SERDE_OPTS_COMMON_MESSAGE != 0;
+        if copyleft && common_message {
+            fail!("copyleft and common messages is not supported together");
+        }
+        if !self.mesh.is_empty() && !common_message {
+            fail!("mesh is not empty but common messages option is not set");
+        }
+        let tag = if copyleft {
+            MC_BLOCK_EXTRA_TAG_2
+        } else if common_message {
+            MC_BLOCK_EXTRA_TAG_3
+        } else {
+            MC_BLOCK_EXTRA_TAG
+        };
+        Ok(tag)
+    }
+
+    fn write_extension(&self, tag: u16) -> Result<BuilderData> {
+        let mut cell1 = self.prev_blk_signatures.write_to_new_cell()?;
+        self.recover_create_msg.write_to(&mut cell1)?;
+        self.mint_msg.write_to(&mut cell1)?;
+
+        if tag == MC_BLOCK_EXTRA_TAG_2 {
+            self.copyleft_msgs.write_to(&mut cell1)?;
+        } else if tag == MC_BLOCK_EXTRA_TAG_3 {
+            self.mesh.write_to(&mut cell1)?;
+        }
+        Ok(cell1)
+    }
 }
 
 const MC_BLOCK_EXTRA_TAG : u16 = 0xCCA5;   // Original struct.
@@ -531,6 +1239,40 @@ const MC_BLOCK_EXTRA_TAG_2 : u16 = 0xdc75; // With copyleft, but without common
 const MC_BLOCK_EXTRA_TAG_3 : u16 = 0xdc76; // With common messages and mesh (might be empty),
                                            // but without copyleft!
 
+impl SemanticEq for McBlockExtra {
+    fn semantic_eq(&self, other: &Self) -> bool {
+        self.key_block == other.key_block &&
+        self.shards == other.shards &&
+        self.fees == other.fees &&
+        self.prev_blk_signatures == other.prev_blk_signatures &&
+        self.recover_create_msg.semantic_eq(&other.recover_create_msg) &&
+        self.copyleft_msgs.semantic_eq(&other.copyleft_msgs) &&
+        self.mint_msg.semantic_eq(&other.mint_msg) &&
+        self.mesh == other.mesh &&
+        self.config == other.config
+    }
+}
+
+impl SemanticEq for CopyleftMessages {
+    fn semantic_eq(&self, other: &Self) -> bool {
+        let collect = |msgs: &Self| -> Result<Vec<(i16, InMsg)>> {
+            let mut result = Vec::new();
+            msgs.iterate_with_keys(|index: U15, InRefValue(msg)| {
+                result.push((index.0, msg));
+                Ok(true)
+            })?;
+            Ok(result)
+        };
+        match (collect(self), collect(other)) {
+            (Ok(msgs), Ok(other)) => {
+                msgs.len() == other.len() && msgs.iter().zip(other.iter())
+                    .all(|((i, x), (j, y))| i == j && x.semantic_eq(y))
+            }
+            _ => false
+        }
+    }
+}
+
 impl Deserializable for McBlockExtra {
     fn read_from(&mut self, cell: &mut SliceData) -> Result<()> {
         let tag = cell.get_next_u16()?;
@@ -547,23 +1289,19 @@ impl Deserializable for McBlockExtra {
             _ => 0,
         };
         let key_block = cell.get_next_bit()?;
-        self.shards.read_from(cell)?;
-        self.fees.read_from(cell)?;
+        self.shards.read_from(cell).with_ctx("McBlockExtra", "shards")?;
+        self.fees.read_from(cell).with_ctx("McBlockExtra", "fees")?;
 
-        let cell1 = &mut SliceData::load_cell(cell.checked_drain_reference()?)?;
-        self.prev_blk_signatures.read_from(cell1)?;
-        self.recover_create_msg.read_from_with_opts(cell1, self.serde_opts)?;
-        self.mint_msg.read_from_with_opts(cell1, self.serde_opts)?;
-
-        if tag == MC_BLOCK_EXTRA_TAG_2 {
-            self.copyleft_msgs.read_from(cell1)?;
-        } else if tag == MC_BLOCK_EXTRA_TAG_3 {
-            self.mesh.read_from(cell1)?;
-            self.copyleft_msgs = CopyleftMessages::with_serde_opts(self.serde_opts);
-        }
+        let extension = McBlockExtraExtension::construct_from_cell_with_tag(cell.checked_drain_reference()?, tag)
+            .with_ctx("McBlockExtra", "extension")?;
+        self.prev_blk_signatures = extension.prev_blk_signatures;
+        self.recover_create_msg = extension.recover_create_msg;
+        self.mint_msg = extension.mint_msg;
+        self.copyleft_msgs = extension.copyleft_msgs;
+        self.mesh = extension.mesh;
 
         self.config = if key_block {
-            Some(ConfigParams::construct_from(cell)?)
+            Some(ConfigParams::construct_from(cell).with_ctx("McBlockExtra", "config")?)
         } else {
             None
         };
@@ -572,41 +1310,67 @@ impl Deserializable for McBlockExtra {
     }
 }
 
+/// Parsed reference of McBlockExtra: signatures of the previous block,
+/// recover/mint messages and copyleft messages or mesh depending on the tag
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct McBlockExtraExtension {
+    pub tag: u16,
+    pub prev_blk_signatures: CryptoSignatures,
+    pub recover_create_msg: Option<ChildCell<InMsg>>,
+    pub mint_msg: Option<ChildCell<InMsg>>,
+    pub copyleft_msgs: CopyleftMessages,
+    pub mesh: MeshHashesExt,
+    /// Rest of the data for unknown tag
+    pub unparsed: Option<SliceData>,
+}
+
+impl McBlockExtraExtension {
+    /// Unknown (newer) tags are supposed to keep the common part
+    /// with messages in common message format
+    pub fn construct_from_cell_with_tag(cell: Cell, tag: u16) -> Result<Self> {
+        let serde_opts = match tag {
+            MC_BLOCK_EXTRA_TAG | MC_BLOCK_EXTRA_TAG_2 => SERDE_OPTS_EMPTY,
+            _ => SERDE_OPTS_COMMON_MESSAGE,
+        };
+        let mut extension = Self {
+            tag,
+            copyleft_msgs: CopyleftMessages::with_serde_opts(serde_opts),
+            ..Default::default()
+        };
+        let slice = &mut SliceData::load_cell(cell)?;
+        extension.prev_blk_signatures.read_from(slice)?;
+        extension.recover_create_msg.read_from_with_opts(slice, serde_opts)?;
+        extension.mint_msg.read_from_with_opts(slice, serde_opts)?;
+        match tag {
+            MC_BLOCK_EXTRA_TAG => (),
+            MC_BLOCK_EXTRA_TAG_2 => extension.copyleft_msgs.read_from(slice)?,
+            MC_BLOCK_EXTRA_TAG_3 => extension.mesh.read_from(slice)?,
+            _ => extension.unparsed = Some(slice.clone()),
+        }
+        Ok(extension)
+    }
+
+    pub fn read_recover_create_msg(&self) -> Result<Option<InMsg>> {
+        self.recover_create_msg.as_ref().map(|mr| mr.read_struct()).transpose()
+    }
+
+    pub fn read_mint_msg(&self) -> Result<Option<InMsg>> {
+        self.mint_msg.as_ref().map(ChildCell::read_struct).transpose()
+    }
+}
+
 impl Serializable for McBlockExtra {
     fn write_to(&self, cell: &mut BuilderData) -> Result<()> {
         self.write_with_opts(cell, SERDE_OPTS_EMPTY)
     }
     fn write_with_opts(&self, cell: &mut BuilderData, opts: u8) -> Result<()> {
-        let copyleft = !self.copyleft_msgs.is_empty();
-        let common_message = opts & SERDE_OPTS_COMMON_MESSAGE != 0;
-        if copyleft && common_message {
-            fail!("copyleft and common messages is not supported together");
-        }
-        if !self.mesh.is_empty() && !common_message {
-            fail!("mesh is not empty but common messages option is not set");
-        }
-        let tag = if copyleft {
-            MC_BLOCK_EXTRA_TAG_2
-        } else if common_message {
-            MC_BLOCK_EXTRA_TAG_3
-        } else {
-            MC_BLOCK_EXTRA_TAG
-        };
+        let tag = self.tag(opts)?;
         cell.append_u16(tag)?;
         self.config.is_some().write_to(cell)?;
         self.shards.write_to(cell)?;
         self.fees.write_to(cell)?;
 
-        let mut cell1 = self.prev_blk_signatures.write_to_new_cell()?;
-        self.recover_create_msg.write_to(&mut cell1)?;
-        self.mint_msg.write_to(&mut cell1)?;
-
-        if copyleft {
-            self.copyleft_msgs.write_to(&mut cell1)?;
-        } else if common_message {
-            self.mesh.write_to(&mut cell1)?;
-        }
-
+        let cell1 = self.write_extension(tag)?;
         cell.checked_append_reference(cell1.into_cell()?)?;
 
         if let Some(config) = &self.config {
@@ -626,8 +1390,8 @@ pub struct KeyMaxLt {
 
 impl Deserializable for KeyMaxLt {
     fn read_from(&mut self, slice: &mut SliceData) -> Result<()> {
-        self.key.read_from(slice)?;
-        self.max_end_lt.read_from(slice)?;
+        self.key.read_from(slice).with_ctx("KeyMaxLt", "key")?;
+        self.max_end_lt.read_from(slice).with_ctx("KeyMaxLt", "max_end_lt")?;
         Ok(())
     }
 }
@@ -673,8 +1437,8 @@ impl KeyExtBlkRef {
 
 impl Deserializable for KeyExtBlkRef {
     fn read_from(&mut self, slice: &mut SliceData) -> Result<()> {
-        self.key.read_from(slice)?;
-        self.blk_ref.read_from(slice)?;
+        self.key.read_from(slice).with_ctx("KeyExtBlkRef", "key")?;
+        self.blk_ref.read_from(slice).with_ctx("KeyExtBlkRef", "blk_ref")?;
         Ok(())
     }
 }
@@ -790,6 +1554,62 @@ impl OldMcBlocksInfo {
         }
     }
 
+    /// Returns key blocks with seqno in range ordered by seqno.
+    /// Subtrees without key blocks or out of range are skipped by augmentation
+    pub fn key_blocks_in_range(&self, seqno_range: Range<u32>) -> Result<Vec<ExtBlkRef>> {
+        let mut key_blocks = Vec::new();
+        if seqno_range.is_empty() {
+            return Ok(key_blocks)
+        }
+        let (start, end) = (seqno_range.start as u64, seqno_range.end as u64);
+        self.traverse(|key_prefix, key_prefix_len, aug, value_opt| {
+            if !aug.key {
+                return Ok(TraverseNextStep::<()>::Stop)
+            }
+            let x = Self::build_key_part(key_prefix, key_prefix_len)? as u64;
+            let d = 32 - key_prefix_len;
+            // subtree contains seqnos from x << d to ((x + 1) << d) - 1
+            if ((x + 1) << d) <= start || (x << d) >= end {
+                return Ok(TraverseNextStep::Stop)
+            }
+            if d == 0 {
+                let value = value_opt.ok_or_else(|| error!(BlockError::InvalidData(
+                    "OldMcBlocksInfo's node with max key length doesn't have value".to_string()
+                )))?;
+                key_blocks.push(value.blk_ref);
+                return Ok(TraverseNextStep::Stop)
+            }
+            Ok(TraverseNextStep::VisitZeroOne)
+        })?;
+        Ok(key_blocks)
+    }
+
+    /// Returns block with the largest end_lt not greater than `lt`.
+    /// Relies on end_lt growing with seqno as it does in masterchain:
+    /// subtrees with max_end_lt <= lt are passed by their rightmost leaf
+    /// and the search ends on the first block with greater end_lt
+    pub fn find_block_by_lt(&self, lt: u64) -> Result<Option<ExtBlkRef>> {
+        let mut found = None;
+        self.traverse(|_key_prefix, key_prefix_len, aug, value_opt| {
+            if key_prefix_len == 32 {
+                let value = value_opt.ok_or_else(|| error!(BlockError::InvalidData(
+                    "OldMcBlocksInfo's node with max key length doesn't have value".to_string()
+                )))?;
+                if value.blk_ref.end_lt > lt {
+                    return Ok(TraverseNextStep::End(()))
+                }
+                found = Some(value.blk_ref);
+                return Ok(TraverseNextStep::Stop)
+            }
+            if aug.max_end_lt <= lt {
+                Ok(TraverseNextStep::VisitOne)
+            } else {
+                Ok(TraverseNextStep::VisitZeroOne)
+            }
+        })?;
+        Ok(found)
+    }
+
     pub fn check_block(&self, id: &BlockIdExt) -> Result<()> {
         self.check_key_block(id, None)
     }
@@ -828,7 +1648,7 @@ impl OldMcBlocksInfo {
         let mut key_buf = [0_u8; 4];
         key_buf[..key_prefix.len()].copy_from_slice(key_prefix);
         Ok(
-            u32::from_be_bytes(key_buf) >> (32 - key_prefix_len)
+            u32::from_be_bytes(key_buf).checked_shr(32 - key_prefix_len as u32).unwrap_or_default()
         )
     }
 }
@@ -859,8 +1679,8 @@ impl Augmentable for ShardFeeCreated {
 
 impl Deserializable for ShardFeeCreated {
     fn read_from(&mut self, cell: &mut SliceData) -> Result<()> {
-        self.fees.read_from(cell)?;
-        self.create.read_from(cell)?;
+        self.fees.read_from(cell).with_ctx("ShardFeeCreated", "fees")?;
+        self.create.read_from(cell).with_ctx("ShardFeeCreated", "create")?;
         Ok(())
     }
 }
@@ -873,18 +1693,205 @@ impl Serializable for ShardFeeCreated {
     }
 }
 
-pub fn umulnexps32(x : u64, k : u32, _trunc : bool) -> u64 {
-    (
-        (x as f64 * (k as f64 / -65536f64).exp()) // x * exp(-k / 2^16)
-        + 0.5f64 // Need to round up the number to the nearest integer
-    ) as u64
+/// exp(-i) as normalized Q63 mantissa and extra shift: exp(-i) = m / 2^(63 + shift)
+const EXP_NEG_INT: [(u64, u32); 48] = [
+    (0x8000000000000000, 0), (0x5e2d58d8b3bcdf1b, 1),
+    (0x454aaa8efe072e7f, 2), (0x65f6c3333ac2bb25, 4),
+    (0x4b0556e084f3d1e0, 5), (0x6e64ff80354dd871, 7),
+    (0x513947c44f535760, 8), (0x7785ae7092a63aae, 10),
+    (0x57f08410409eb2f0, 11), (0x40b3c89499516645, 12),
+    (0x5f35e6d591f26a71, 14), (0x460d508e3d9d900f, 15),
+    (0x671530ed0ef22bc6, 17), (0x4bd814ee607660d1, 18),
+    (0x6f9b1bf6c0584843, 20), (0x521d72889fb0e030, 21),
+    (0x78d56eebba172b6a, 23), (0x58e78c5d69a272f6, 24),
+    (0x41698a31a67e8322, 25), (0x60415bfcb15dc69e, 27),
+    (0x46d21957cdc99b98, 28), (0x6836c34376cfb082, 30),
+    (0x4cad22fc45f6894f, 31), (0x70d49f90cfeac460, 33),
+    (0x53041e3fa48a2828, 34), (0x7a28de917f0cb741, 36),
+    (0x59e14a9c01f9061e, 37), (0x42214a61d9fa688e, 38),
+    (0x614fc075b54ee7f6, 40), (0x47990aebae128143, 41),
+    (0x695b830ab1fdd354, 43), (0x4d84878934eee626, 44),
+    (0x721193dd8591a079, 46), (0x53ed51f1dd769e1c, 47),
+    (0x7b8007bb58aa7f52, 49), (0x5addc66967c380aa, 50),
+    (0x42db0ebf77818e02, 51), (0x62611c7f298153fa, 53),
+    (0x48622b5ab8cc0c37, 54), (0x6a83792fcd6cdfa8, 56),
+    (0x4e5e49266c6890a5, 57), (0x73520286dd207f76, 59),
+    (0x54d914bb8bdd6fa0, 60), (0x7cdaf4dfd0bcf85f, 62),
+    (0x5bdd07785d23afb0, 63), (0x4396dcf47fd4f3ea, 64),
+    (0x6375786ec275f9b2, 66), (0x492d80c6d26f69c1, 67),
+];
+/// exp(-i / 2^8) as Q63
+const EXP_NEG_FRAC8: [u64; 256] = [
+    0x8000000000000000, 0x7f803feaaffeef1c, 0x7f00ff55aa8893e6, 0x7e823dc1aefd4e2f,
+    0x7e03faaffbbe924a, 0x7d8635a24db02761, 0x7d08ee1adfb9e44f, 0x7c8c239c6a49ea7d,
+    0x7c0fd5aa22d75e45, 0x7b9403c7bb659c60, 0x7b18ad796207ebdf, 0x7a9dd243c065ac32,
+    0x7a2371abfb3efec7, 0x79a98b37b1f1ebc1, 0x79301e6cfe000147, 0x78b72ad272946d02,
+    0x783eafef1c0a8f39, 0x77c6ad4a7f750724, 0x774f226c9a2537f5, 0x76d80edde133461e,
+    0x7661722741068c62, 0x75eb4bd21cde882e, 0x75759b684e5c3cd3, 0x75006074250c0d1c,
+    0x748b9a8065f00acd, 0x741749184b0abba2, 0x73a36bc782ea5340, 0x7330021a303461c1,
+    0x72bd0b9ce931f64a, 0x724a87dcb75c354f, 0x71d8766716e961fe, 0x7166d6c9f65a5a70,
+    0x70f5a893b608861e, 0x7084eb5327b43631, 0x70149e978e13773a, 0x6fa4c1f09c6153de,
+    0x6f3554ee75ed8806, 0x6ec65721adaca429, 0x6e57c81b45c8a035, 0x6de9a76caf31ddb0,
+    0x6d7bf4a7c930989f, 0x6d0eaf5ee0f6c6c4, 0x6ca1d724b13264cc, 0x6c356b8c61a030eb,
+    0x6bc96c29869ed299, 0x6b5dd89020c26ee0, 0x6af2b0549c68a8ec, 0x6a87f30bd14d0e5f,
+    0x6a1da04b021def01, 0x69b3b7a7dc119f69, 0x694a38b8767c2625, 0x68e123135265530d,
+    0x6878764f5a1f403c, 0x68103203e0dd3c5c, 0x67a855c8a24b1dcd, 0x6740e135c224fe4f,
+    0x66d9d3e3cbcf5eae, 0x66732d6bb1efb224, 0x660ced66ce0550f0, 0x65a7136ee002d1d3,
+    0x65419f1e0de7c9f5, 0x64dc900ee35af2de, 0x6477e5dc5144b616, 0x6413a021ad6a1e02,
+    0x63afbe7ab2082ba2, 0x634c40837d6f90c5, 0x62e925d891a0ce55, 0x62866e16d3e8b649,
+    0x622418db8c7d50ed, 0x61c225c4661b2511, 0x6160946f6da2e2bc, 0x60ff647b11b77002,
+    0x609e9586225c57a5, 0x603e272fd0949909, 0x5fde1917ae01d935, 0x5f7e6addac83f469,
+    0x5f1f1c221dd8eff8, 0x5ec02c85b33d4bfb, 0x5e619ba97d0cb48a, 0x5e03692eea63120c,
+    0x5da594b7c8bdf84d, 0x5d481de6439e73f4, 0x5ceb045ce42b35f9, 0x5c8e47be90d31cc8,
+    0x5c31e7ae8cf01aa7, 0x5bd5e3d0786a7906, 0x5b7a3bc84f5c7863, 0x5b1eef3a69b64c58,
+    0x5ac3fdcb7ae27389, 0x5a696720916a6b04, 0x5a0f2adf169bbcc1, 0x59b548acce2d68ed,
+    0x595bc02fd5e5a994, 0x5902910ea5401064, 0x58a9baf00d13fe20, 0x58513d7b373b736d,
+    0x57f91857a63a3aaa, 0x57a14b2d34e56a67, 0x5749d5a4160b4035, 0x56f2b764d41b5371,
+    0x569bf01850cf1fa3, 0x56457f67c4d2e639, 0x55ef64fcbf6ee728, 0x5599a0812630f02d,
+    0x5544319f34964256, 0x54ef18017bb5cd74, 0x549a5352e1eac12f, 0x5445e33ea27f7358,
+    0x53f1c7704d589b2c, 0x539dff93c6a0e133, 0x534a8b554674c363, 0x52f76a61588ecd35,
+    0x52a49c64dbf42359, 0x5252210d02a162b3, 0x51fff8075137d256, 0x51ae21019eaae816,
+    0x515c9baa13ee1f7b, 0x510b67af2ba322ac, 0x50ba84bfb1c84507, 0x5069f28ac3674f20,
+    0x5019b0bfce449bb9, 0x4fc9bf0e908e858b, 0x4f7a1d27188d2566, 0x4f2acab9c4526073,
+    0x4edbc777416a4646, 0x4e8d13108c8bbe5b, 0x4e3ead36f14984c9, 0x4df0959c09c375d3,
+    0x4da2cbf1be5827fa, 0x4d554fea4556d45a, 0x4d08213822b18cf0, 0x4cbb3f8e27afc089,
+    0x4c6eaa9f72a10bff, 0x4c22621f6e905884, 0x4bd665c1d2f746a7, 0x4b8ab53aa371e5c8,
+    0x4b3f503e2f72b7ab, 0x4af4368111f6ffe5, 0x4aa967b8313b5ed7, 0x4a5ee398be70b7de,
+    0x4a14a9d835716282, 0x49caba2c5c76a649, 0x4981144b43ce80e9, 0x4937b7eb4591b692,
+    0x48eea4c3055a2bff, 0x48a5da896ff98a0b, 0x485d58f5bb302a7a, 0x48151fbf65644db6,
+    0x47cd2e9e3559992f, 0x4785854a39e8de17, 0x473e237bc9b82837, 0x46f708eb82f3148c,
+    0x46b035524b036f6f, 0x4669a8694e4a19f9, 0x462361e9ffd8365e, 0x45dd618e19289af5,
+    0x4597a70f99d98bb3, 0x45523228c766b9bd, 0x450d02942ce388e3, 0x44c8180c9ab59aa9,
+    0x4483724d264f9ead, 0x443f111129ec680c, 0x43faf414444a479f, 0x43b71b125866aaaf,
+    0x437385c78d39fdec, 0x433033f04d73d464, 0x42ed254947375229, 0x42aa598f6bd7da70,
+    0x4267d07fef9600df, 0x422589d8495cbdc9, 0x41e38556327ee511, 0x41a1c2b7a674df79,
+    0x416041bae29aa613, 0x411f021e65edff9c, 0x40de03a0f0ccff6e, 0x409d460184b4c5de,
+    0x405cc8ff640081b1, 0x401c8c5a11a8b271, 0x3fdc8fd15102ab66, 0x3f9cd325258056db,
+    0x3f5d5615d2703997, 0x3f1e1863dabdb61c, 0x3edf19d000b18f94, 0x3ea05a1b45b2ac12,
+    0x3e61d906ea0715f0, 0x3e2396546c953c18, 0x3de591c58aa570de, 0x3da7cb1c3fa3a746,
+    0x3d6a421ac4e16e6a, 0x3d2cf68391582ac8, 0x3cefe819596b8d35, 0x3cb3169f0eac473b,
+    0x3c7681d7df9afca6, 0x3c3a2987376b71fe, 0x3bfe0d70bdc7f7b7, 0x3bc22d58569511d7,
+    0x3b86890221b55bd4, 0x3b4b20327acda870, 0x3b0ff2adf9095d5e, 0x3ad500396edf0a63,
+    0x3a9a4899e9d53bca, 0x3a5fcb94b24787e9, 0x3a2588ef4b2bd76f, 0x39eb806f71d7e85e,
+    0x39b1b1db1dc70b58, 0x39781cf880601b14, 0x393ec18e04bbadc3, 0x39059f624f6a8023,
+    0x38ccb63c3e3c1a08, 0x389405e2e805ac2c, 0x385b8e1d9c6926fc, 0x38234eb3e39c8a35,
+    0x37eb476d7e316d16, 0x37b3781264dcbeef, 0x377be06ac83ebfce, 0x3744803f10ab311c,
+    0x370d5757ddf1bded, 0x36d6657e07269ac8, 0x369faa7a9a6b5cbc, 0x36692616dcb80776,
+    0x3632d81c49a4523c, 0x35fcc0549331237a, 0x35c6de89a19242c1, 0x3591328592f840f7,
+    0x355bbc12bb5a9680, 0x35267afba441f737, 0x34f16f0b0c92dbea, 0x34bc980be8584141,
+    0x3487f5c9608e9bc2, 0x3453880ed2ef00c8, 0x341f4ea7d1ba8438, 0x33eb49602385cac1,
+    0x33b77803c304d067, 0x3383da5eded6e336, 0x3350703dd952d1df, 0x331d396d48534e04,
+    0x32ea35b9f5038215, 0x32b764f0dbabda72, 0x3284c6df2b7f01b3, 0x32525b5246670fd4,
+    0x32202217c0d2ec1c, 0x31ee1afd6183e188, 0x31bc45d1215b6585, 0x318aa2612b2910d2,
+    0x3159307bdb78ca4a, 0x3127efefc0612367, 0x30f6e08b9951e65c, 0x30c6021e56e2d57d,
+    0x309554771aa29bd0, 0x3064d76536e5ee9d, 0x30348ab82e96dfbb, 0x30046e3fb5046077,
+    0x2fd481cbadb1f4de, 0x2fa4c52c2c279740, 0x2f75383173c1cbaf, 0x2f45daabf781e35a,
+];
+/// exp(-i / 2^16) as Q63
+const EXP_NEG_FRAC16: [u64; 256] = [
+    0x8000000000000000, 0x7fff80003fffeaab, 0x7fff0000ffff5556, 0x7ffe80023ffdc002,
+    0x7ffe0003fffaaab0, 0x7ffd80063ff59562, 0x7ffd0008ffee001b, 0x7ffc800c3fe36add,
+    0x7ffc000fffd555ab, 0x7ffb80143fc34089, 0x7ffb0018ffacab7b, 0x7ffa801e3f911686,
+    0x7ffa0023ff7001b0, 0x7ff9802a3f48ecfe, 0x7ff90030ff1b5876, 0x7ff880383ee6c41f,
+    0x7ff8003ffeaab000, 0x7ff780483e669c21, 0x7ff70050fe1a088b, 0x7ff6805a3dc47546,
+    0x7ff60063fd65625a, 0x7ff5806e3cfc4fd3, 0x7ff50078fc88bdbb, 0x7ff480843c0a2c1b,
+    0x7ff4008ffb801aff, 0x7ff3809c3aea0a74, 0x7ff300a8fa477a85, 0x7ff280b63997eb3f,
+    0x7ff200c3f8dadcaf, 0x7ff180d2380fcee3, 0x7ff100e0f73641e9, 0x7ff080f0364db5d1,
+    0x7ff000fff555aaa9, 0x7fef8110344da080, 0x7fef0120f3351768, 0x7fee8132320b8f71,
+    0x7fee0143f0d088ac, 0x7fed81562f83832b, 0x7fed0168ee23ff01, 0x7fec817c2cb17c3f,
+    0x7fec018feb2b7af9, 0x7feb81a429917b44, 0x7feb01b8e7e2fd33, 0x7fea81ce261f80da,
+    0x7fea01e3e4468650, 0x7fe981fa22578daa, 0x7fe90210e05216fe, 0x7fe882281e35a263,
+    0x7fe8023fdc01aff0, 0x7fe7825819b5bfbd, 0x7fe70270d75151e2, 0x7fe6828a14d3e678,
+    0x7fe602a3d23cfd98, 0x7fe582be0f8c175c, 0x7fe502d8ccc0b3de, 0x7fe482f409da5339,
+    0x7fe4030fc6d87588, 0x7fe3832c03ba9ae6, 0x7fe30348c0804371, 0x7fe28365fd28ef45,
+    0x7fe20383b9b41e7f, 0x7fe183a1f621513c, 0x7fe103c0b270079b, 0x7fe083dfee9fc1bc,
+    0x7fe003ffaaafffbc, 0x7fdf841fe6a041bc, 0x7fdf0440a27007db, 0x7fde8461de1ed23c,
+    0x7fde048399ac20fe, 0x7fdd84a5d5177444, 0x7fdd04c890604c30, 0x7fdc84ebcb8628e5,
+    0x7fdc050f86888a85, 0x7fdb8533c166f135, 0x7fdb05587c20dd19, 0x7fda857db6b5ce55,
+    0x7fda05a37125450f, 0x7fd985c9ab6ec16c, 0x7fd905f06591c393, 0x7fd886179f8dcbab,
+    0x7fd8063f596259da, 0x7fd78667930eee49, 0x7fd706904c93091f, 0x7fd686b985ee2a86,
+    0x7fd606e33f1fd2a6, 0x7fd5870d782781aa, 0x7fd507383104b7bb, 0x7fd4876369b6f504,
+    0x7fd4078f223db9b1, 0x7fd387bb5a9885ec, 0x7fd307e812c6d9e4, 0x7fd288154ac835c3,
+    0x7fd20843029c19b8, 0x7fd188713a4205ef, 0x7fd1089ff1b97a99, 0x7fd088cf2901f7e2,
+    0x7fd008fee01afdfa, 0x7fcf892f17040d11, 0x7fcf095fcdbca557, 0x7fce8991044446fd,
+    0x7fce09c2ba9a7234, 0x7fcd89f4f0bea72e, 0x7fcd0a27a6b0661d, 0x7fcc8a5adc6f2f34,
+    0x7fcc0a8e91fa82a5, 0x7fcb8ac2c751e0a5, 0x7fcb0af77c74c968, 0x7fca8b2cb162bd23,
+    0x7fca0b62661b3c0a, 0x7fc98b989a9dc654, 0x7fc90bcf4ee9dc36, 0x7fc88c0682fefde8,
+    0x7fc80c3e36dcaba0, 0x7fc78c766a826596, 0x7fc70caf1defac03, 0x7fc68ce85123ff1f,
+    0x7fc60d22041edf24, 0x7fc58d5c36dfcc4a, 0x7fc50d96e96646cd, 0x7fc48dd21bb1cee7,
+    0x7fc40e0dcdc1e4d2, 0x7fc38e49ff9608cc, 0x7fc30e86b12dbb10, 0x7fc28ec3e2887bda,
+    0x7fc20f0193a5cb69, 0x7fc18f3fc48529f9, 0x7fc10f7e752617c9, 0x7fc08fbda5881517,
+    0x7fc00ffd55aaa223, 0x7fbf903d858d3f2c, 0x7fbf107e352f6c73, 0x7fbe90bf6490aa38,
+    0x7fbe110113b078bd, 0x7fbd9143428e5843, 0x7fbd1185f129c90c, 0x7fbc91c91f824b5b,
+    0x7fbc120ccd975f73, 0x7fbb9250fb688598, 0x7fbb1295a8f53e0e, 0x7fba92dad63d091a,
+    0x7fba1320833f6700, 0x7fb99366affbd808, 0x7fb913ad5c71dc76, 0x7fb893f488a0f491,
+    0x7fb8143c3488a0a1, 0x7fb79484602860ed, 0x7fb714cd0b7fb5be, 0x7fb69516368e1f5b,
+    0x7fb6155fe1531e0f, 0x7fb595aa0bce3223, 0x7fb515f4b5fedbe1, 0x7fb4963fdfe49b94,
+    0x7fb4168b897ef186, 0x7fb396d7b2cd5e04, 0x7fb317245bcf615a, 0x7fb2977184847bd4,
+    0x7fb217bf2cec2dc0, 0x7fb1980d5505f76b, 0x7fb1185bfcd15924, 0x7fb098ab244dd338,
+    0x7fb018facb7ae5f8, 0x7faf994af25811b3, 0x7faf199b98e4d6b8, 0x7fae99ecbf20b559,
+    0x7fae1a3e650b2de7, 0x7fad9a908aa3c0b3, 0x7fad1ae32fe9ee10, 0x7fac9b3654dd3651,
+    0x7fac1b89f97d19c7, 0x7fab9bde1dc918c8, 0x7fab1c32c1c0b3a6, 0x7faa9c87e5636ab8,
+    0x7faa1cdd88b0be51, 0x7fa99d33aba82ec8, 0x7fa91d8a4e493c73, 0x7fa89de1709367a8,
+    0x7fa81e39128630bf, 0x7fa79e913421180e, 0x7fa71ee9d5639def, 0x7fa69f42f64d42ba,
+    0x7fa61f9c96dd86c8, 0x7fa59ff6b713ea72, 0x7fa5205156efee14, 0x7fa4a0ac76711206,
+    0x7fa421081596d6a5, 0x7fa3a1643460bc4c, 0x7fa321c0d2ce4358, 0x7fa2a21df0deec24,
+    0x7fa2227b8e92370e, 0x7fa1a2d9abe7a474, 0x7fa1233848deb4b4, 0x7fa0a3976576e82c,
+    0x7fa023f701afbf3b, 0x7f9fa4571d88ba42, 0x7f9f24b7b901599f, 0x7f9ea518d4191db5,
+    0x7f9e257a6ecf86e3, 0x7f9da5dc8924158c, 0x7f9d263f23164a11, 0x7f9ca6a23ca5a4d5,
+    0x7f9c2705d5d1a63c, 0x7f9ba769ee99cea9, 0x7f9b27ce86fd9e7f, 0x7f9aa8339efc9624,
+    0x7f9a2899369635fd, 0x7f99a8ff4dc9fe6f, 0x7f992965e4976fe1, 0x7f98a9ccfafe0ab8,
+    0x7f982a3490fd4f5c, 0x7f97aa9ca694be35, 0x7f972b053bc3d7ab, 0x7f96ab6e508a1c26,
+    0x7f962bd7e4e70c0f, 0x7f95ac41f8da27d0, 0x7f952cac8c62efd4, 0x7f94ad179f80e483,
+    0x7f942d833233864b, 0x7f93adef447a5596, 0x7f932e5bd654d2d0, 0x7f92aec8e7c27e66,
+    0x7f922f3678c2d8c4, 0x7f91afa48955625a, 0x7f91301319799b94, 0x7f90b082292f04e1,
+    0x7f9030f1b8751eb0, 0x7f8fb161c74b6971, 0x7f8f31d255b16594, 0x7f8eb24363a69389,
+    0x7f8e32b4f12a73c2, 0x7f8db326fe3c86b0, 0x7f8d33998adc4cc4, 0x7f8cb40c97094673,
+    0x7f8c348022c2f42e, 0x7f8bb4f42e08d669, 0x7f8b3568b8da6d98, 0x7f8ab5ddc3373a30,
+    0x7f8a36534d1ebca6, 0x7f89b6c95690756f, 0x7f89373fdf8be502, 0x7f88b7b6e8108bd4,
+    0x7f88382e701dea5e, 0x7f87b8a677b38116, 0x7f87391efed0d074, 0x7f86b998057558f2,
+    0x7f863a118ba09b07, 0x7f85ba8b9152172f, 0x7f853b0616894de1, 0x7f84bb811b45bf9a,
+    0x7f843bfc9f86ecd4, 0x7f83bc78a34c560b, 0x7f833cf526957bba, 0x7f82bd722961de5f,
+    0x7f823defabb0fe76, 0x7f81be6dad825c7c, 0x7f813eec2ed578f0, 0x7f80bf6b2fa9d451,
+];
+
+/// Computes x * exp(-k / 2^16) rounded to the nearest integer (or truncated if `trunc` is set).
+/// Uses fixed-point arithmetic only, so the result does not depend on the platform's floats.
+/// Precision of the tables is about 2^-60, so the result is exact for x < 2^56
+pub fn umulnexps32(x : u64, k : u32, trunc : bool) -> u64 {
+    if x == 0 || k >= (EXP_NEG_INT.len() as u32) << 16 {
+        return 0
+    }
+    if k == 0 {
+        return x
+    }
+    // multiplication of two Q63 numbers with rounding
+    let mul = |a: u64, b: u64| ((a as u128 * b as u128 + (1 << 62)) >> 63) as u64;
+    let (int_part, shift) = EXP_NEG_INT[(k >> 16) as usize];
+    let factor = mul(
+        mul(int_part, EXP_NEG_FRAC8[((k >> 8) & 0xff) as usize]),
+        EXP_NEG_FRAC16[(k & 0xff) as usize]
+    );
+    let shift = 63 + shift;
+    if shift >= 128 {
+        return 0
+    }
+    let product = x as u128 * factor as u128;
+    if trunc {
+        (product >> shift) as u64
+    } else {
+        ((product + (1 << (shift - 1))) >> shift) as u64
+    }
 }
 
 /// counters#_ last_updated:uint32 total:uint64 cnt2048:uint64 cnt65536:uint64 = Counters;
+/// counters_ext#_ last_updated:uint32 total:uint64 cnt256:uint64 cnt2048:uint64 cnt65536:uint64 = CountersExt;
+/// The extended format with the short window is used only inside `CreatorStats` tagged as `creator_info_ext`
 #[derive(Clone, Debug, Default, Eq)]
 pub struct Counters {
     last_updated: u32,
     total: u64,
+    cnt256: Option<u64>,
     cnt2048: u64,
     cnt65536: u64,
 }
@@ -893,6 +1900,7 @@ impl PartialEq for Counters {
     fn eq(&self, other: &Self) -> bool {
         self.last_updated == other.last_updated
         && self.total == other.total
+        && self.cnt256 == other.cnt256
         && self.cnt2048 == other.cnt2048
         && self.cnt65536 == other.cnt65536
     }
@@ -901,7 +1909,7 @@ impl PartialEq for Counters {
 impl Counters {
     pub fn is_valid(&self) -> bool {
         if self.total == 0 {
-            if (self.cnt2048 | self.cnt65536) != 0 {
+            if (self.cnt256.unwrap_or_default() | self.cnt2048 | self.cnt65536) != 0 {
                 return false;
             }
         } else if self.last_updated == 0 {
@@ -913,11 +1921,17 @@ impl Counters {
         self.total == 0
     }
     pub fn almost_zero(&self) -> bool {
-        (self.cnt2048 | self.cnt65536) <= 1
+        (self.cnt256.unwrap_or_default() | self.cnt2048 | self.cnt65536) <= 1
     }
     pub fn almost_equals(&self, other: &Self) -> bool {
         self.last_updated == other.last_updated
             && self.total == other.total
+            && match (self.cnt256, other.cnt256) {
+                (Some(cnt256), Some(other_cnt256)) =>
+                    cnt256 <= other_cnt256 + 1 && other_cnt256 <= cnt256 + 1,
+                (None, None) => true,
+                _ => false
+            }
             && self.cnt2048 <= other.cnt2048 + 1
             && other.cnt2048 <= self.cnt2048 + 1
             && self.cnt65536 <= other.cnt65536 + 1
@@ -934,15 +1948,37 @@ impl Counters {
         if self.total == 0 {
             self.last_updated = now;
             self.total = count;
+            if self.cnt256.is_some() {
+                self.cnt256 = Some(scaled);
+            }
             self.cnt2048 = scaled;
             self.cnt65536 = scaled;
             return true
         }
-        if count > !self.total || self.cnt2048 > !scaled || self.cnt65536 > !scaled {
+        if count > !self.total || self.cnt2048 > !scaled || self.cnt65536 > !scaled
+            || self.cnt256.unwrap_or_default() > !scaled {
             return false;
         }
+        self.decay_to(now);
+        self.total += count;
+        if let Some(cnt256) = self.cnt256.as_mut() {
+            *cnt256 += scaled;
+        }
+        self.cnt2048 += scaled;
+        self.cnt65536 += scaled;
+        self.last_updated = now;
+        true
+    }
+    /// Decays all windows to the time `now` without counting new events
+    pub fn decay_to(&mut self, now: u32) {
         let dt = now.checked_sub(self.last_updated).unwrap_or_default();
         if dt != 0 {
+            // cnt256 = llround(cnt256 * exp(-dt / 256.));
+            if let Some(cnt256) = self.cnt256.as_mut() {
+                *cnt256 = if dt >= 48 * 256 {0} else {
+                    umulnexps32(*cnt256, dt << 8, false)
+                };
+            }
             // more precise version of cnt2048 = llround(cnt2048 * exp(-dt / 2048.));
             // (rounding error has absolute value < 1)
             self.cnt2048 = if dt >= 48 * 2048 {0} else {
@@ -951,12 +1987,87 @@ impl Counters {
             // more precise version of cnt65536 = llround(cnt65536 * exp(-dt / 65536.));
             // (rounding error has absolute value < 1)
             self.cnt65536 = umulnexps32(self.cnt65536, dt, false);
+            self.last_updated = now;
+        }
+    }
+    /// Sums counters decayed to the common time `now`.
+    /// The short window is kept only if all counters have it.
+    /// Returns None on overflow or if some counters are invalid
+    pub fn aggregate<'a>(counters: impl IntoIterator<Item = &'a Counters>, now: u32) -> Option<Self> {
+        let mut result = Self {
+            cnt256: Some(0),
+            ..Self::default()
+        };
+        for counters in counters {
+            if !counters.is_valid() {
+                return None
+            }
+            if counters.is_zero() {
+                if counters.cnt256.is_none() {
+                    result.cnt256 = None;
+                }
+                continue
+            }
+            let mut counters = counters.clone();
+            counters.decay_to(now);
+            result.total = result.total.checked_add(counters.total)?;
+            result.cnt256 = match (result.cnt256, counters.cnt256) {
+                (Some(sum), Some(cnt256)) => Some(sum.checked_add(cnt256)?),
+                _ => None
+            };
+            result.cnt2048 = result.cnt2048.checked_add(counters.cnt2048)?;
+            result.cnt65536 = result.cnt65536.checked_add(counters.cnt65536)?;
+            result.last_updated = result.last_updated.max(counters.last_updated);
+        }
+        if result.total == 0 {
+            result.last_updated = 0;
+        }
+        Some(result)
+    }
+    /// Combines counters of two merged shards decaying both to the common time `now`.
+    /// Returns false and keeps self unchanged on overflow or if some counters are invalid
+    pub fn merge(&mut self, other: &Counters, now: u32) -> bool {
+        match Self::aggregate([&*self, other], now) {
+            Some(merged) => {
+                *self = merged;
+                true
+            }
+            None => false
         }
-        self.total += count;
-        self.cnt2048 += scaled;
-        self.cnt65536 += scaled;
-        self.last_updated = now;
-        true
+    }
+    /// Converts legacy counters to the extended format.
+    /// The short window is estimated from cnt2048 as for the steady rate of events
+    pub fn with_cnt256(mut self) -> Self {
+        if self.cnt256.is_none() {
+            self.cnt256 = Some(self.cnt2048 >> 3);
+        }
+        self
+    }
+    /// Drops the short window converting counters to the legacy format
+    pub fn to_legacy(&self) -> Self {
+        Self {
+            cnt256: None,
+            ..self.clone()
+        }
+    }
+    pub fn is_ext(&self) -> bool {
+        self.cnt256.is_some()
+    }
+    pub fn read_ext_from(&mut self, slice: &mut SliceData) -> Result<()> {
+        self.last_updated.read_from(slice).with_ctx("Counters", "last_updated")?;
+        self.total.read_from(slice).with_ctx("Counters", "total")?;
+        self.cnt256 = Some(slice.get_next_u64().with_ctx("Counters", "cnt256")?);
+        self.cnt2048.read_from(slice).with_ctx("Counters", "cnt2048")?;
+        self.cnt65536.read_from(slice).with_ctx("Counters", "cnt65536")?;
+        Ok(())
+    }
+    pub fn write_ext_to(&self, cell: &mut BuilderData) -> Result<()> {
+        self.last_updated.write_to(cell)?;
+        self.total.write_to(cell)?;
+        self.cnt256.unwrap_or_default().write_to(cell)?;
+        self.cnt2048.write_to(cell)?;
+        self.cnt65536.write_to(cell)?;
+        Ok(())
     }
     pub fn total(&self) -> u64 {
         self.total
@@ -964,6 +2075,9 @@ impl Counters {
     pub fn last_updated(&self) -> u32 {
         self.last_updated
     }
+    pub fn cnt256(&self) -> Option<u64> {
+        self.cnt256
+    }
     pub fn cnt2048(&self) -> u64 {
         self.cnt2048
     }
@@ -974,10 +2088,11 @@ impl Counters {
 
 impl Deserializable for Counters {
     fn read_from(&mut self, slice: &mut SliceData) -> Result<()> {
-        self.last_updated.read_from(slice)?;
-        self.total.read_from(slice)?;
-        self.cnt2048.read_from(slice)?;
-        self.cnt65536.read_from(slice)?;
+        self.last_updated.read_from(slice).with_ctx("Counters", "last_updated")?;
+        self.total.read_from(slice).with_ctx("Counters", "total")?;
+        self.cnt256 = None;
+        self.cnt2048.read_from(slice).with_ctx("Counters", "cnt2048")?;
+        self.cnt65536.read_from(slice).with_ctx("Counters", "cnt65536")?;
         Ok(())
     }
 }
@@ -993,6 +2108,7 @@ impl Serializable for Counters {
 }
 
 /// creator_info#4 mc_blocks:Counters shard_blocks:Counters = CreatorStats;
+/// creator_info_ext#5 mc_blocks:CountersExt shard_blocks:CountersExt = CreatorStats;
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
 pub struct CreatorStats {
     pub mc_blocks: Counters,
@@ -1004,10 +2120,27 @@ impl CreatorStats {
         0x4
     }
 
+    pub fn ext_tag() -> u32 {
+        0x5
+    }
+
     pub fn tag_len_bits() -> usize {
         4
     }
 
+    /// Both counters are stored in the extended format
+    pub fn is_ext(&self) -> bool {
+        self.mc_blocks.is_ext() && self.shard_blocks.is_ext()
+    }
+
+    /// Converts both counters to the extended format
+    pub fn with_cnt256(self) -> Self {
+        Self {
+            mc_blocks: self.mc_blocks.with_cnt256(),
+            shard_blocks: self.shard_blocks.with_cnt256(),
+        }
+    }
+
     pub fn mc_blocks(&self) -> &Counters {
         &self.mc_blocks
     }
@@ -1015,12 +2148,31 @@ impl CreatorStats {
     pub fn shard_blocks(&self) -> &Counters {
         &self.shard_blocks
     }
+
+    /// Combines statistics of two merged shards decaying all counters to the common time `now`.
+    /// Returns false and keeps self unchanged on overflow or if some counters are invalid
+    pub fn merge(&mut self, other: &CreatorStats, now: u32) -> bool {
+        let mut mc_blocks = self.mc_blocks.clone();
+        let mut shard_blocks = self.shard_blocks.clone();
+        if !mc_blocks.merge(&other.mc_blocks, now) || !shard_blocks.merge(&other.shard_blocks, now) {
+            return false
+        }
+        self.mc_blocks = mc_blocks;
+        self.shard_blocks = shard_blocks;
+        true
+    }
 }
 
 impl Deserializable for CreatorStats {
     fn read_from(&mut self, slice: &mut SliceData) -> Result<()> {
         let tag = slice.get_next_int(Self::tag_len_bits())? as u32;
-        if tag != Self::tag() {
+        if tag == Self::tag() {
+            self.mc_blocks.read_from(slice).with_ctx("CreatorStats", "mc_blocks")?;
+            self.shard_blocks.read_from(slice).with_ctx("CreatorStats", "shard_blocks")?;
+        } else if tag == Self::ext_tag() {
+            self.mc_blocks.read_ext_from(slice).with_ctx("CreatorStats", "mc_blocks")?;
+            self.shard_blocks.read_ext_from(slice).with_ctx("CreatorStats", "shard_blocks")?;
+        } else {
             fail!(
                 BlockError::InvalidConstructorTag {
                     t: tag,
@@ -1028,19 +2180,27 @@ impl Deserializable for CreatorStats {
                 }
             )
         }
-
-        self.mc_blocks.read_from(slice)?;
-        self.shard_blocks.read_from(slice)?;
         Ok(())
     }
 }
 
 impl Serializable for CreatorStats {
     fn write_to(&self, cell: &mut BuilderData) -> Result<()> {
-        cell.append_bits(Self::tag() as usize, Self::tag_len_bits())?;
-
-        self.mc_blocks.write_to(cell)?;
-        self.shard_blocks.write_to(cell)?;
+        match (self.mc_blocks.is_ext(), self.shard_blocks.is_ext()) {
+            (false, false) => {
+                cell.append_bits(Self::tag() as usize, Self::tag_len_bits())?;
+                self.mc_blocks.write_to(cell)?;
+                self.shard_blocks.write_to(cell)?;
+            }
+            (true, true) => {
+                cell.append_bits(Self::ext_tag() as usize, Self::tag_len_bits())?;
+                self.mc_blocks.write_ext_to(cell)?;
+                self.shard_blocks.write_ext_to(cell)?;
+            }
+            _ => fail!(BlockError::InvalidData(
+                "CreatorStats can't mix legacy and extended counters".to_string()
+            ))
+        }
         Ok(())
     }
 }
@@ -1061,6 +2221,71 @@ impl BlockCreateStats {
     pub fn tag_len_bits() -> usize {
         8
     }
+
+    /// Returns up to `n` creators updated since `since_utime` ordered by the number
+    /// of created blocks (masterchain and shardchain together) in descending order.
+    /// The zero key holding the summary statistics of all creators is skipped
+    pub fn top_creators(&self, n: usize, since_utime: u32) -> Result<Vec<(UInt256, CreatorStats)>> {
+        let mut creators = Vec::new();
+        self.counters.iterate_with_keys(|key: UInt256, stats| {
+            if !key.is_zero() && Self::is_active(&stats, since_utime) {
+                creators.push((key, stats));
+            }
+            Ok(true)
+        })?;
+        creators.sort_by(|(key1, stats1), (key2, stats2)| {
+            Self::blocks_count(stats2).cmp(&Self::blocks_count(stats1)).then_with(|| key1.cmp(key2))
+        });
+        creators.truncate(n);
+        Ok(creators)
+    }
+
+    /// Returns total numbers of masterchain and shardchain blocks created by
+    /// creators updated since `since_utime`, the zero summary key is skipped
+    pub fn total_blocks(&self, since_utime: u32) -> Result<(u64, u64)> {
+        let mut mc_blocks = 0u64;
+        let mut shard_blocks = 0u64;
+        self.counters.iterate_with_keys(|key: UInt256, stats| {
+            if !key.is_zero() && Self::is_active(&stats, since_utime) {
+                mc_blocks = mc_blocks.checked_add(stats.mc_blocks.total())
+                    .ok_or_else(|| error!(BlockError::InvalidData("masterchain blocks count overflow".to_string())))?;
+                shard_blocks = shard_blocks.checked_add(stats.shard_blocks.total())
+                    .ok_or_else(|| error!(BlockError::InvalidData("shardchain blocks count overflow".to_string())))?;
+            }
+            Ok(true)
+        })?;
+        Ok((mc_blocks, shard_blocks))
+    }
+
+    /// Removes creators not updated since `threshold_utime` whose counters decayed
+    /// to almost zero by that time. The zero summary key is never removed.
+    /// Returns the number of removed entries
+    pub fn prune_stale(&mut self, threshold_utime: u32) -> Result<usize> {
+        let mut removed = 0;
+        self.counters.retain(|key: &UInt256, stats| {
+            if key.is_zero() || Self::is_active(stats, threshold_utime) {
+                return Ok(true)
+            }
+            let mut mc_blocks = stats.mc_blocks.clone();
+            mc_blocks.decay_to(threshold_utime);
+            let mut shard_blocks = stats.shard_blocks.clone();
+            shard_blocks.decay_to(threshold_utime);
+            if mc_blocks.almost_zero() && shard_blocks.almost_zero() {
+                removed += 1;
+                return Ok(false)
+            }
+            Ok(true)
+        })?;
+        Ok(removed)
+    }
+
+    fn is_active(stats: &CreatorStats, since_utime: u32) -> bool {
+        stats.mc_blocks.modified_since(since_utime) || stats.shard_blocks.modified_since(since_utime)
+    }
+
+    fn blocks_count(stats: &CreatorStats) -> u128 {
+        stats.mc_blocks.total() as u128 + stats.shard_blocks.total() as u128
+    }
 }
 
 impl Deserializable for BlockCreateStats {
@@ -1075,7 +2300,7 @@ impl Deserializable for BlockCreateStats {
             )
         }
 
-        self.counters.read_from(slice)?;
+        self.counters.read_from(slice).with_ctx("BlockCreateStats", "counters")?;
         Ok(())
     }
 }
@@ -1091,6 +2316,50 @@ impl Serializable for BlockCreateStats {
 
 define_HashmapE!{MeshHashes, 32, ConnectedNwDescr}
 
+impl MeshHashes {
+    /// Adds description of the new connected network, fails if the id is already registered
+    pub fn register_network(&mut self, nw_id: u32, descr: ConnectedNwDescr) -> Result<()> {
+        if self.contains_key(&nw_id)? {
+            fail!(BlockError::InvalidArg(format!("network {} is already registered", nw_id)))
+        }
+        self.set(&nw_id, &descr)
+    }
+
+    /// Modifies description of the registered network, gen_utime must not decrease
+    pub fn update_network<F>(&mut self, nw_id: u32, f: F) -> Result<()>
+    where F: FnOnce(&mut ConnectedNwDescr) -> Result<()> {
+        let old = self.get(&nw_id)?.ok_or_else(|| error!(BlockError::NotFound(
+            format!("network {}", nw_id)
+        )))?;
+        let mut new = old.clone();
+        f(&mut new)?;
+        check_mesh_update(nw_id, Some(&old), Some(&new))?;
+        self.set(&nw_id, &new)
+    }
+
+    /// Returns false if the network was not registered
+    pub fn remove_network(&mut self, nw_id: u32) -> Result<bool> {
+        self.remove(&nw_id)
+    }
+
+    pub fn iterate_networks<F>(&self, mut p: F) -> Result<bool>
+    where F: FnMut(u32, ConnectedNwDescr) -> Result<bool> {
+        self.iterate_with_keys(|nw_id: u32, descr| p(nw_id, descr))
+    }
+}
+
+fn check_mesh_update(nw_id: u32, old: Option<&ConnectedNwDescr>, new: Option<&ConnectedNwDescr>) -> Result<()> {
+    match (old, new) {
+        (Some(old), Some(new)) if new.gen_utime < old.gen_utime => fail!(BlockError::InvalidArg(format!(
+            "gen_utime of network {} can't decrease from {} to {}", nw_id, old.gen_utime, new.gen_utime
+        ))),
+        (Some(_), None) => fail!(BlockError::InvalidArg(format!(
+            "description of network {} can't be removed", nw_id
+        ))),
+        _ => Ok(())
+    }
+}
+
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
 pub struct ConnectedNwDescr {
     pub seq_no: u32,
@@ -1114,11 +2383,11 @@ impl Deserializable for ConnectedNwDescr {
                 }
             )
         }
-        self.seq_no.read_from(slice)?;
-        self.root_hash.read_from(slice)?;
-        self.file_hash.read_from(slice)?;
-        self.imported.read_from(slice)?;
-        self.gen_utime.read_from(slice)?;
+        self.seq_no.read_from(slice).with_ctx("ConnectedNwDescr", "seq_no")?;
+        self.root_hash.read_from(slice).with_ctx("ConnectedNwDescr", "root_hash")?;
+        self.file_hash.read_from(slice).with_ctx("ConnectedNwDescr", "file_hash")?;
+        self.imported.read_from(slice).with_ctx("ConnectedNwDescr", "imported")?;
+        self.gen_utime.read_from(slice).with_ctx("ConnectedNwDescr", "gen_utime")?;
         Ok(())
     }
 }
@@ -1228,8 +2497,8 @@ impl Deserializable for McStateExtra {
                 }
             )
         }
-        self.shards.read_from(cell)?;
-        self.config.read_from(cell)?;
+        self.shards.read_from(cell).with_ctx("McStateExtra", "shards")?;
+        self.config.read_from(cell).with_ctx("McStateExtra", "config")?;
 
         let cell1 = &mut SliceData::load_cell(cell.checked_drain_reference()?)?;
         let mut flags = 0u16;
@@ -1241,22 +2510,22 @@ impl Deserializable for McStateExtra {
                 )
             )
         }
-        self.validator_info.read_from(cell1)?; // 65 + 0
-        self.prev_blocks.read_from(cell1)?; // 1 + 1
-        self.after_key_block.read_from(cell1)?; // 1 + 0
-        self.last_key_block.read_from(cell1)?; // 609 + 0
+        self.validator_info.read_from(cell1).with_ctx("McStateExtra", "validator_info")?; // 65 + 0
+        self.prev_blocks.read_from(cell1).with_ctx("McStateExtra", "prev_blocks")?; // 1 + 1
+        self.after_key_block.read_from(cell1).with_ctx("McStateExtra", "after_key_block")?; // 1 + 0
+        self.last_key_block.read_from(cell1).with_ctx("McStateExtra", "last_key_block")?; // 609 + 0
         self.block_create_stats = if flags & MC_STATE_CREATE_STATS_FLAG == 0 {
             None
         } else {
-            Some(BlockCreateStats::construct_from(cell1)?) // 1 + 1
+            Some(BlockCreateStats::construct_from(cell1).with_ctx("McStateExtra", "block_create_stats")?) // 1 + 1
         };
         if flags & MC_STATE_COPYLEFT_FLAG != 0 {
-            self.state_copyleft_rewards.read_from(cell1)?; // 1 + 1
+            self.state_copyleft_rewards.read_from(cell1).with_ctx("McStateExtra", "state_copyleft_rewards")?; // 1 + 1
         }
         if flags & MC_STATE_MESH_FLAG != 0 {
-            self.mesh.read_from(cell1)?;
+            self.mesh.read_from(cell1).with_ctx("McStateExtra", "mesh")?;
         }
-        self.global_balance.read_from(cell)?;
+        self.global_balance.read_from(cell).with_ctx("McStateExtra", "global_balance")?;
         Ok(())
     }
 }
@@ -1375,6 +2644,29 @@ pub struct CollatorRange {
     pub finish: u32,
 }
 
+impl CollatorRange {
+    pub fn is_valid(&self) -> bool {
+        self.start <= self.finish
+    }
+
+    pub fn contains(&self, seq_no: u32) -> bool {
+        self.start <= seq_no && seq_no <= self.finish
+    }
+
+    fn check(&self, name: &str, vset: &ValidatorSet) -> Result<()> {
+        if !self.is_valid() {
+            fail!(BlockError::InvalidData(format!("{} collator range {} is invalid", name, self)))
+        }
+        if self.collator as usize >= vset.list().len() {
+            fail!(BlockError::InvalidData(format!(
+                "{} collator index {} is out of validator set bounds {}",
+                name, self.collator, vset.list().len()
+            )))
+        }
+        Ok(())
+    }
+}
+
 impl fmt::Display for CollatorRange {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{} ({}..{})", self.collator, self.start, self.finish)
@@ -1393,9 +2685,9 @@ impl Serializable for CollatorRange {
 impl Deserializable for CollatorRange {
     fn construct_from(slice: &mut SliceData) -> Result<Self> {
         Ok(Self {
-            collator: u16::construct_from(slice)?,
-            start: slice.get_next_u32()?,
-            finish: slice.get_next_u32()?,
+            collator: u16::construct_from(slice).with_ctx("CollatorRange", "collator")?,
+            start: slice.get_next_u32().with_ctx("CollatorRange", "start")?,
+            finish: slice.get_next_u32().with_ctx("CollatorRange", "finish")?,
         })
     }
 }
@@ -1410,6 +2702,102 @@ pub struct ShardCollators {
     pub updated_at: u32,
 }
 
+impl ShardCollators {
+    /// Checks collator indexes against the validator set of the shard
+    /// and that prev (and prev2) ranges end before the current one and next (and next2) start after it
+    pub fn validate(&self, vset: &ValidatorSet) -> Result<()> {
+        self.prev.check("prev", vset)?;
+        if let Some(prev2) = &self.prev2 {
+            prev2.check("prev2", vset)?;
+        }
+        self.current.check("current", vset)?;
+        self.next.check("next", vset)?;
+        if let Some(next2) = &self.next2 {
+            next2.check("next2", vset)?;
+        }
+        for (name, prev) in [("prev", Some(&self.prev)), ("prev2", self.prev2.as_ref())] {
+            if let Some(prev) = prev {
+                if prev.finish >= self.current.start {
+                    fail!(BlockError::InvalidData(format!(
+                        "{} collator range {} overlaps current {}", name, prev, self.current
+                    )))
+                }
+            }
+        }
+        for (name, next) in [("next", Some(&self.next)), ("next2", self.next2.as_ref())] {
+            if let Some(next) = next {
+                if self.current.finish >= next.start {
+                    fail!(BlockError::InvalidData(format!(
+                        "current collator range {} overlaps {} {}", self.current, name, next
+                    )))
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that the current range contains `current_seqno` and prev, current
+    /// and next ranges are contiguous (prev2 and next2 are checked like prev and next)
+    pub fn validate_schedule(&self, current_seqno: u32) -> Result<()> {
+        for (name, range) in [
+            ("prev", Some(&self.prev)), ("prev2", self.prev2.as_ref()), ("current", Some(&self.current)),
+            ("next", Some(&self.next)), ("next2", self.next2.as_ref())
+        ] {
+            if let Some(range) = range {
+                if !range.is_valid() {
+                    fail!(BlockError::InvalidData(format!("{} collator range {} is invalid", name, range)))
+                }
+            }
+        }
+        if !self.current.contains(current_seqno) {
+            fail!(BlockError::InvalidData(format!(
+                "current collator range {} doesn't contain seqno {}", self.current, current_seqno
+            )))
+        }
+        for (name, prev) in [("prev", Some(&self.prev)), ("prev2", self.prev2.as_ref())] {
+            if let Some(prev) = prev {
+                if prev.finish.checked_add(1) != Some(self.current.start) {
+                    fail!(BlockError::InvalidData(format!(
+                        "{} collator range {} is not followed by current {}", name, prev, self.current
+                    )))
+                }
+            }
+        }
+        for (name, next) in [("next", Some(&self.next)), ("next2", self.next2.as_ref())] {
+            if let Some(next) = next {
+                if self.current.finish.checked_add(1) != Some(next.start) {
+                    fail!(BlockError::InvalidData(format!(
+                        "current collator range {} is not followed by {} {}", self.current, name, next
+                    )))
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Shifts current range to prev and next to current, `new_next` must follow the next range.
+    /// Not allowed before the split when next2 is set, prev2 is dropped
+    pub fn rotate(&mut self, new_next: CollatorRange, updated_at: u32) -> Result<()> {
+        if self.next2.is_some() {
+            fail!(BlockError::InvalidOperation(
+                "collator ranges can't be rotated while next2 is set".to_string()
+            ))
+        }
+        if !new_next.is_valid() {
+            fail!(BlockError::InvalidArg(format!("new next collator range {} is invalid", new_next)))
+        }
+        if self.next.finish.checked_add(1) != Some(new_next.start) {
+            fail!(BlockError::InvalidArg(format!(
+                "new next collator range {} doesn't follow next {}", new_next, self.next
+            )))
+        }
+        self.prev = std::mem::replace(&mut self.current, std::mem::replace(&mut self.next, new_next));
+        self.prev2 = None;
+        self.updated_at = updated_at;
+        Ok(())
+    }
+}
+
 impl fmt::Display for ShardCollators {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         writeln!(f, "prev: {}", self.prev)?;
@@ -1457,12 +2845,12 @@ impl Deserializable for ShardCollators {
             )
         }
         Ok(Self {
-            prev: Deserializable::construct_from(slice)?,
-            prev2: Deserializable::construct_maybe_from(slice)?,
-            current: Deserializable::construct_from(slice)?,
-            next: Deserializable::construct_from(slice)?,
-            next2: Deserializable::construct_maybe_from(slice)?,
-            updated_at: Deserializable::construct_from(slice)?,
+            prev: Deserializable::construct_from(slice).with_ctx("ShardCollators", "prev")?,
+            prev2: Deserializable::construct_maybe_from(slice).with_ctx("ShardCollators", "prev2")?,
+            current: Deserializable::construct_from(slice).with_ctx("ShardCollators", "current")?,
+            next: Deserializable::construct_from(slice).with_ctx("ShardCollators", "next")?,
+            next2: Deserializable::construct_maybe_from(slice).with_ctx("ShardCollators", "next2")?,
+            updated_at: Deserializable::construct_from(slice).with_ctx("ShardCollators", "updated_at")?,
         })
     }
 }
@@ -1478,10 +2866,10 @@ pub struct ShardBlockRef {
 impl Deserializable for ShardBlockRef {
     fn construct_from(slice: &mut SliceData) -> Result<Self> {
         Ok(Self {
-            seq_no: slice.get_next_u32()?,
-            root_hash: UInt256::construct_from(slice)?,
-            file_hash: UInt256::construct_from(slice)?,
-            end_lt: slice.get_next_u64()?,
+            seq_no: slice.get_next_u32().with_ctx("ShardBlockRef", "seq_no")?,
+            root_hash: UInt256::construct_from(slice).with_ctx("ShardBlockRef", "root_hash")?,
+            file_hash: UInt256::construct_from(slice).with_ctx("ShardBlockRef", "file_hash")?,
+            end_lt: slice.get_next_u64().with_ctx("ShardBlockRef", "end_lt")?,
         })
     }
 }
@@ -1521,52 +2909,17 @@ define_HashmapE!{RefShardBlocks, 32, BinTree<ShardBlockRef>}
 
 impl RefShardBlocks {
     pub fn with_ids<'a>(ids: impl IntoIterator<Item = &'a (BlockIdExt, u64)>) -> Result<Self> {
-        // Naive implementation. 
-        //TODO optimise me!
-
         let mut ref_shard_blocks = HashMap::new(); // wc -> shard -> id
         for (id, end_lt) in ids {
-            let shards = loop {
-                if let Some(wc) = ref_shard_blocks.get_mut(&id.shard().workchain_id()) {
-                    break wc
-                }
-                ref_shard_blocks.insert(id.shard().workchain_id(), HashMap::new());
-            };
-            shards.insert(id.shard(), ShardBlockRef::with_params(id, *end_lt));
+            ref_shard_blocks
+                .entry(id.shard().workchain_id())
+                .or_insert_with(HashMap::new)
+                .insert(id.shard().clone(), ShardBlockRef::with_params(id, *end_lt));
         }
 
         let mut result = Self::default();
-        for (wc, mut shards) in ref_shard_blocks {
-            let key = ShardIdent::full(wc);
-            let mut bintree;
-            if let Some(val) = shards.get(&key) {
-                bintree = BinTree::with_item(val)?;
-            } else {
-                bintree = BinTree::with_item(&ShardBlockRef::default())?;
-                let mut unfinished_keys = vec!(key);
-                while let Some(key) = unfinished_keys.pop() {
-                    bintree.split(key.shard_key(false), |_| {
-                        let (left, right) = key.split()?;
-                        let left_val = if let Some(val) = shards.remove(&left) {
-                            val
-                        } else {
-                            unfinished_keys.push(left);
-                            ShardBlockRef::default()
-                        };
-                        let right_val = if let Some(val) = shards.remove(&right) {
-                            val
-                        } else {
-                            unfinished_keys.push(right);
-                            ShardBlockRef::default()
-                        };
-                        Ok((left_val, right_val))
-                    })?;
-                }
-                if !shards.is_empty() {
-                    fail!("wrong ids (shards is not empty after bintree filling)")
-                }
-            }
-            result.set(&wc, &bintree)?;
+        for (wc, shards) in ref_shard_blocks {
+            result.set(&wc, &BinTree::from_map(shards)?)?;
         }
 
         Ok(result)
@@ -1598,6 +2951,50 @@ impl RefShardBlocks {
 
 define_HashmapE!(MeshHashesExt, 32, ConnectedNwDescrExt);
 
+impl MeshHashesExt {
+    /// Adds the new connected network, fails if the id is already registered
+    pub fn register_network(&mut self, nw_id: u32, descr: ConnectedNwDescrExt) -> Result<()> {
+        if self.contains_key(&nw_id)? {
+            fail!(BlockError::InvalidArg(format!("network {} is already registered", nw_id)))
+        }
+        self.set(&nw_id, &descr)
+    }
+
+    /// Modifies the registered network, gen_utime of its description must not decrease
+    pub fn update_network<F>(&mut self, nw_id: u32, f: F) -> Result<()>
+    where F: FnOnce(&mut ConnectedNwDescrExt) -> Result<()> {
+        let old = self.get(&nw_id)?.ok_or_else(|| error!(BlockError::NotFound(
+            format!("network {}", nw_id)
+        )))?;
+        let mut new = old.clone();
+        f(&mut new)?;
+        check_mesh_update(nw_id, old.descr.as_ref(), new.descr.as_ref())?;
+        self.set(&nw_id, &new)
+    }
+
+    /// Returns false if the network was not registered
+    pub fn remove_network(&mut self, nw_id: u32) -> Result<bool> {
+        self.remove(&nw_id)
+    }
+
+    pub fn iterate_networks<F>(&self, mut p: F) -> Result<bool>
+    where F: FnMut(u32, ConnectedNwDescrExt) -> Result<bool> {
+        self.iterate_with_keys(|nw_id: u32, descr| p(nw_id, descr))
+    }
+
+    /// Amount exported to the network since the previous masterchain state,
+    /// the whole counter if the network was not registered there
+    pub fn exported_since(&self, prev: &MeshHashesExt, nw_id: u32) -> Result<VarUInteger32> {
+        let current = self.get(&nw_id)?.ok_or_else(|| error!(BlockError::NotFound(
+            format!("network {}", nw_id)
+        )))?;
+        match prev.get(&nw_id)? {
+            Some(prev) => current.queue_descr.exported_since(&prev.queue_descr),
+            None => Ok(current.queue_descr.exported)
+        }
+    }
+}
+
 const CONNECTED_NW_DESCR_EXT_TAG: u8 = 1; // 4 bits
 
 #[derive(Clone, Debug, Eq, PartialEq, Default)]
@@ -1618,8 +3015,8 @@ impl Deserializable for ConnectedNwDescrExt {
                 }
             )
         }
-        self.queue_descr.read_from(slice)?;
-        self.descr.read_from(slice)?;
+        self.queue_descr.read_from(slice).with_ctx("ConnectedNwDescrExt", "queue_descr")?;
+        self.descr.read_from(slice).with_ctx("ConnectedNwDescrExt", "descr")?;
         Ok(())
     }
 }
@@ -1643,6 +3040,43 @@ pub struct ConnectedNwOutDescr {
     pub exported: VarUInteger32,
 }
 
+impl ConnectedNwOutDescr {
+    /// Applies the queue update to the known hash of the queue root, returns the new root hash
+    pub fn apply_queue_update(&self, known_root_hash: &UInt256) -> Result<UInt256> {
+        if *known_root_hash != self.out_queue_update.old_hash {
+            fail!(BlockError::InvalidData(format!(
+                "out queue update starts from {:x} but the known queue root is {:x}",
+                self.out_queue_update.old_hash, known_root_hash
+            )))
+        }
+        Ok(self.out_queue_update.new_hash.clone())
+    }
+
+    /// Checks that the queue update turns the old queue root into the new one
+    pub fn verify_queue_update(&self, old_root: &Cell, new_root: &Cell) -> Result<()> {
+        let new_hash = self.apply_queue_update(&old_root.repr_hash())?;
+        if new_hash != new_root.repr_hash() {
+            fail!(BlockError::InvalidData(format!(
+                "out queue update ends with {:x} but the new queue root is {:x}",
+                new_hash, new_root.repr_hash()
+            )))
+        }
+        Ok(())
+    }
+
+    /// Amount exported since the previous state of the queue, the counter must not decrease
+    pub fn exported_since(&self, prev: &ConnectedNwOutDescr) -> Result<VarUInteger32> {
+        if self.exported < prev.exported {
+            fail!(BlockError::InvalidData(format!(
+                "exported counter decreased from {} to {}", prev.exported, self.exported
+            )))
+        }
+        let mut delta = self.exported.clone();
+        delta.sub(&prev.exported)?;
+        Ok(delta)
+    }
+}
+
 impl Deserializable for ConnectedNwOutDescr {
     fn read_from(&mut self, slice: &mut SliceData) -> Result<()> {
         let tag = slice.get_next_int(4)? as u8;
@@ -1654,8 +3088,8 @@ impl Deserializable for ConnectedNwOutDescr {
                 }
             )
         }
-        self.exported.read_from(slice)?;
-        self.out_queue_update.read_from_cell(slice.checked_drain_reference()?)?;
+        self.exported.read_from(slice).with_ctx("ConnectedNwOutDescr", "exported")?;
+        self.out_queue_update.read_from_cell(slice.checked_drain_reference()?).with_ctx("ConnectedNwOutDescr", "out_queue_update")?;
         Ok(())
     }
 }
@@ -1761,6 +3195,181 @@ impl ShardDescr {
             _ => 0
         }
     }
+    /// Checks invariants of the description, is called on serialization
+    pub fn validate(&self) -> Result<()> {
+        if self.flags & 7 != 0 {
+            fail!(BlockError::InvalidData(format!("flags & 7 in ShardDescr must be zero, but {}", self.flags)))
+        }
+        if self.before_split && self.before_merge {
+            fail!(BlockError::InvalidData("ShardDescr can't be both before_split and before_merge".to_string()))
+        }
+        if !self.copyleft_rewards.is_empty() && (self.collators.is_some() || !self.mesh_msg_queues.is_empty()) {
+            fail!(BlockError::InvalidData(
+                "copyleft_rewards is not supported with 'collators' or 'mesh_msg_queues'".to_string()
+            ))
+        }
+        Ok(())
+    }
+}
+
+/// Builds `ShardDescr` checking its invariants on `build`
+#[derive(Clone, Debug, Default)]
+pub struct ShardDescrBuilder {
+    descr: ShardDescr,
+}
+
+impl ShardDescrBuilder {
+    pub fn new(seq_no: u32, start_lt: u64, end_lt: u64, root_hash: UInt256, file_hash: UInt256) -> Self {
+        let mut descr = ShardDescr::with_params(seq_no, start_lt, end_lt, root_hash, FutureSplitMerge::None);
+        descr.file_hash = file_hash;
+        Self { descr }
+    }
+    /// Continues building from existing description, e.g. of the previous block of the shard
+    pub fn with_descr(descr: ShardDescr) -> Self {
+        Self { descr }
+    }
+    pub fn with_reg_mc_seqno(mut self, reg_mc_seqno: u32) -> Self {
+        self.descr.reg_mc_seqno = reg_mc_seqno;
+        self
+    }
+    pub fn with_min_ref_mc_seqno(mut self, min_ref_mc_seqno: u32) -> Self {
+        self.descr.min_ref_mc_seqno = min_ref_mc_seqno;
+        self
+    }
+    pub fn with_gen_utime(mut self, gen_utime: u32) -> Self {
+        self.descr.gen_utime = gen_utime;
+        self
+    }
+    pub fn with_before_split(mut self, before_split: bool) -> Self {
+        self.descr.before_split = before_split;
+        self
+    }
+    pub fn with_before_merge(mut self, before_merge: bool) -> Self {
+        self.descr.before_merge = before_merge;
+        self
+    }
+    pub fn with_want_split(mut self, want_split: bool) -> Self {
+        self.descr.want_split = want_split;
+        self
+    }
+    pub fn with_want_merge(mut self, want_merge: bool) -> Self {
+        self.descr.want_merge = want_merge;
+        self
+    }
+    pub fn with_nx_cc_updated(mut self, nx_cc_updated: bool) -> Self {
+        self.descr.nx_cc_updated = nx_cc_updated;
+        self
+    }
+    pub fn with_flags(mut self, flags: u8) -> Self {
+        self.descr.flags = flags;
+        self
+    }
+    pub fn with_next_catchain_seqno(mut self, next_catchain_seqno: u32) -> Self {
+        self.descr.next_catchain_seqno = next_catchain_seqno;
+        self
+    }
+    pub fn with_next_validator_shard(mut self, next_validator_shard: u64) -> Self {
+        self.descr.next_validator_shard = next_validator_shard;
+        self
+    }
+    pub fn with_split_merge_at(mut self, split_merge_at: FutureSplitMerge) -> Self {
+        self.descr.split_merge_at = split_merge_at;
+        self
+    }
+    pub fn with_fees_collected(mut self, fees_collected: CurrencyCollection) -> Self {
+        self.descr.fees_collected = fees_collected;
+        self
+    }
+    pub fn with_funds_created(mut self, funds_created: CurrencyCollection) -> Self {
+        self.descr.funds_created = funds_created;
+        self
+    }
+    pub fn with_copyleft_rewards(mut self, copyleft_rewards: CopyleftRewards) -> Self {
+        self.descr.copyleft_rewards = copyleft_rewards;
+        self
+    }
+    pub fn with_proof_chain(mut self, proof_chain: ProofChain) -> Self {
+        self.descr.proof_chain = Some(proof_chain);
+        self
+    }
+    pub fn with_collators(mut self, collators: ShardCollators) -> Self {
+        self.descr.collators = Some(collators);
+        self
+    }
+    pub fn with_mesh_msg_queues(mut self, mesh_msg_queues: MeshOutDescr) -> Self {
+        self.descr.mesh_msg_queues = mesh_msg_queues;
+        self
+    }
+    pub fn build(self) -> Result<ShardDescr> {
+        self.descr.validate()?;
+        Ok(self.descr)
+    }
+}
+
+impl fmt::Display for FutureSplitMerge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FutureSplitMerge::None => write!(f, "none"),
+            FutureSplitMerge::Split{split_utime, interval} => write!(f, "split at {} in {}", split_utime, interval),
+            FutureSplitMerge::Merge{merge_utime, interval} => write!(f, "merge at {} in {}", merge_utime, interval),
+        }
+    }
+}
+
+/// `{}` prints compact line, `{:#}` prints all fields line by line
+impl fmt::Display for ShardDescr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let flags = [
+            (self.before_split, "before_split"),
+            (self.before_merge, "before_merge"),
+            (self.want_split, "want_split"),
+            (self.want_merge, "want_merge"),
+            (self.nx_cc_updated, "nx_cc_updated"),
+        ];
+        let flags = flags.iter().filter(|(set, _)| *set).map(|(_, name)| *name).collect::<Vec<_>>();
+        let flags = if flags.is_empty() { "none".to_string() } else { flags.join(" ") };
+        if !f.alternate() {
+            return write!(
+                f, "seq_no: {}, gen_utime: {}, lt: {}..{}, flags: {}",
+                self.seq_no, self.gen_utime, self.start_lt, self.end_lt, flags
+            )
+        }
+        writeln!(f, "seq_no: {}", self.seq_no)?;
+        writeln!(f, "reg_mc_seqno: {}", self.reg_mc_seqno)?;
+        writeln!(f, "start_lt: {}", self.start_lt)?;
+        writeln!(f, "end_lt: {}", self.end_lt)?;
+        writeln!(f, "root_hash: {:x}", self.root_hash)?;
+        writeln!(f, "file_hash: {:x}", self.file_hash)?;
+        writeln!(f, "flags: {} ({:#x})", flags, self.flags)?;
+        writeln!(f, "next_catchain_seqno: {}", self.next_catchain_seqno)?;
+        writeln!(f, "next_validator_shard: {:016x}", self.next_validator_shard)?;
+        writeln!(f, "min_ref_mc_seqno: {}", self.min_ref_mc_seqno)?;
+        writeln!(f, "gen_utime: {}", self.gen_utime)?;
+        writeln!(f, "split_merge_at: {}", self.split_merge_at)?;
+        writeln!(f, "fees_collected: {}", self.fees_collected)?;
+        writeln!(f, "funds_created: {}", self.funds_created)?;
+        if let Some(proof_chain) = &self.proof_chain {
+            writeln!(f, "proof_chain: {} cells", proof_chain.len())?;
+        }
+        if let Some(collators) = &self.collators {
+            writeln!(f, "collators:")?;
+            writeln!(f, "  prev: {}", collators.prev)?;
+            if let Some(prev2) = &collators.prev2 {
+                writeln!(f, "  prev2: {}", prev2)?;
+            }
+            writeln!(f, "  current: {}", collators.current)?;
+            writeln!(f, "  next: {}", collators.next)?;
+            if let Some(next2) = &collators.next2 {
+                writeln!(f, "  next2: {}", next2)?;
+            }
+            writeln!(f, "  updated_at: {}", collators.updated_at)?;
+        }
+        if !self.mesh_msg_queues.is_empty() {
+            let count = self.mesh_msg_queues.len().map_err(|_| fmt::Error)?;
+            writeln!(f, "mesh_msg_queues: {} networks", count)?;
+        }
+        Ok(())
+    }
 }
 
 const SHARD_IDENT_TAG_A: u8 = 0xa; // 4 bit
@@ -1784,12 +3393,12 @@ impl Deserializable for ShardDescr {
             )
         }
 
-        self.seq_no.read_from(slice)?;
-        self.reg_mc_seqno.read_from(slice)?;
-        self.start_lt.read_from(slice)?;
-        self.end_lt.read_from(slice)?;
-        self.root_hash.read_from(slice)?;
-        self.file_hash.read_from(slice)?;
+        self.seq_no.read_from(slice).with_ctx("ShardDescr", "seq_no")?;
+        self.reg_mc_seqno.read_from(slice).with_ctx("ShardDescr", "reg_mc_seqno")?;
+        self.start_lt.read_from(slice).with_ctx("ShardDescr", "start_lt")?;
+        self.end_lt.read_from(slice).with_ctx("ShardDescr", "end_lt")?;
+        self.root_hash.read_from(slice).with_ctx("ShardDescr", "root_hash")?;
+        self.file_hash.read_from(slice).with_ctx("ShardDescr", "file_hash")?;
         let mut flags: u8 = 0;
         flags.read_from(slice)?;
         self.before_split = (flags >> 7) & 1 == 1;
@@ -1802,48 +3411,48 @@ impl Deserializable for ShardDescr {
             fail!("flags & 7 in ShardDescr must be zero, but {}", flags)
         }
 
-        self.next_catchain_seqno.read_from(slice)?;
-        self.next_validator_shard.read_from(slice)?;
-        self.min_ref_mc_seqno.read_from(slice)?;
-        self.gen_utime.read_from(slice)?;
-        self.split_merge_at.read_from(slice)?;
+        self.next_catchain_seqno.read_from(slice).with_ctx("ShardDescr", "next_catchain_seqno")?;
+        self.next_validator_shard.read_from(slice).with_ctx("ShardDescr", "next_validator_shard")?;
+        self.min_ref_mc_seqno.read_from(slice).with_ctx("ShardDescr", "min_ref_mc_seqno")?;
+        self.gen_utime.read_from(slice).with_ctx("ShardDescr", "gen_utime")?;
+        self.split_merge_at.read_from(slice).with_ctx("ShardDescr", "split_merge_at")?;
         match tag {
             SHARD_IDENT_TAG_B => {
-                self.fees_collected.read_from(slice)?;
-                self.funds_created.read_from(slice)?;
+                self.fees_collected.read_from(slice).with_ctx("ShardDescr", "fees_collected")?;
+                self.funds_created.read_from(slice).with_ctx("ShardDescr", "funds_created")?;
             }
             SHARD_IDENT_TAG_A => {
                 let mut slice1 = SliceData::load_cell(slice.checked_drain_reference()?)?;
-                self.fees_collected.read_from(&mut slice1)?;
-                self.funds_created.read_from(&mut slice1)?;
+                self.fees_collected.read_from(&mut slice1).with_ctx("ShardDescr", "fees_collected")?;
+                self.funds_created.read_from(&mut slice1).with_ctx("ShardDescr", "funds_created")?;
             }
             SHARD_IDENT_TAG_C => {
                 let mut slice1 = SliceData::load_cell(slice.checked_drain_reference()?)?;
-                self.fees_collected.read_from(&mut slice1)?;
-                self.funds_created.read_from(&mut slice1)?;
-                self.copyleft_rewards.read_from(&mut slice1)?;
+                self.fees_collected.read_from(&mut slice1).with_ctx("ShardDescr", "fees_collected")?;
+                self.funds_created.read_from(&mut slice1).with_ctx("ShardDescr", "funds_created")?;
+                self.copyleft_rewards.read_from(&mut slice1).with_ctx("ShardDescr", "copyleft_rewards")?;
             }
             SHARD_IDENT_TAG_D => {
                 let mut slice1 = SliceData::load_cell(slice.checked_drain_reference()?)?;
-                self.fees_collected.read_from(&mut slice1)?;
-                self.funds_created.read_from(&mut slice1)?;
+                self.fees_collected.read_from(&mut slice1).with_ctx("ShardDescr", "fees_collected")?;
+                self.funds_created.read_from(&mut slice1).with_ctx("ShardDescr", "funds_created")?;
                 if slice1.get_next_bit()? {
-                    self.copyleft_rewards.read_from(&mut slice1)?;
+                    self.copyleft_rewards.read_from(&mut slice1).with_ctx("ShardDescr", "copyleft_rewards")?;
                 }
-                let proof_chain = ProofChain::construct_from(&mut slice1)?;
+                let proof_chain = ProofChain::construct_from(&mut slice1).with_ctx("ShardDescr", "proof_chain")?;
                 self.proof_chain = Some(proof_chain);
             }
             SHARD_IDENT_TAG_E | SHARD_IDENT_TAG_F => {
                 let mut slice1 = SliceData::load_cell(slice.checked_drain_reference()?)?;
-                self.fees_collected.read_from(&mut slice1)?;
-                self.funds_created.read_from(&mut slice1)?;
-                self.proof_chain.read_from(&mut slice1)?;
-                self.collators.read_from(&mut slice1)?;
+                self.fees_collected.read_from(&mut slice1).with_ctx("ShardDescr", "fees_collected")?;
+                self.funds_created.read_from(&mut slice1).with_ctx("ShardDescr", "funds_created")?;
+                self.proof_chain.read_from(&mut slice1).with_ctx("ShardDescr", "proof_chain")?;
+                self.collators.read_from(&mut slice1).with_ctx("ShardDescr", "collators")?;
             }
             _ => ()
         }
         if tag == SHARD_IDENT_TAG_F {
-            self.mesh_msg_queues.read_from(slice)?;
+            self.mesh_msg_queues.read_from(slice).with_ctx("ShardDescr", "mesh_msg_queues")?;
         }
 
         Ok(())
@@ -1852,6 +3461,7 @@ impl Deserializable for ShardDescr {
 
 impl Serializable for ShardDescr {
     fn write_to(&self, cell: &mut BuilderData) -> Result<()> {
+        self.validate()?;
         let mut tag = SHARD_IDENT_TAG_A; // TAG_B is not used at all.
         
         if !self.mesh_msg_queues.is_empty() {
@@ -1889,10 +3499,6 @@ impl Serializable for ShardDescr {
         if self.nx_cc_updated {
             flags |= 1 << 3;
         }
-        if (self.flags & 7) != 0 {
-            fail!("flags & 7 must be zero, but it {}", self.flags)
-        }
-
         flags.write_to(cell)?;
 
         self.next_catchain_seqno.write_to(cell)?;
@@ -1906,9 +3512,6 @@ impl Serializable for ShardDescr {
         self.funds_created.write_to(&mut child)?;
         match tag {
             SHARD_IDENT_TAG_E | SHARD_IDENT_TAG_F => {
-                if !self.copyleft_rewards.is_empty() {
-                    fail!("copyleft_rewards is not supported with 'collators' or 'mesh_msg_queues'")
-                }
                 self.proof_chain.write_to(&mut child)?;
                 self.collators.write_to(&mut child)?;
             }
@@ -1947,7 +3550,7 @@ pub struct BlkMasterInfo {
 
 impl Deserializable for BlkMasterInfo {
      fn read_from(&mut self, cell: &mut SliceData) -> Result<()> {
-        self.master.read_from(cell)
+        self.master.read_from(cell).with_ctx("BlkMasterInfo", "master")
     }
 }
 
@@ -1992,6 +3595,39 @@ impl LibDescr {
     pub fn lib(&self) -> &Cell {
         &self.lib
     }
+    /// Returns false if the account is already a publisher
+    pub fn add_publisher(&mut self, publisher: &AccountId) -> Result<bool> {
+        if self.has_publisher(publisher)? {
+            return Ok(false)
+        }
+        self.publishers.set(publisher, &())?;
+        Ok(true)
+    }
+    /// Returns false if the account is not a publisher.
+    /// Descriptor without publishers can't be serialized and must be removed from libraries
+    pub fn remove_publisher(&mut self, publisher: &AccountId) -> Result<bool> {
+        self.publishers.remove(publisher)
+    }
+    pub fn has_publisher(&self, publisher: &AccountId) -> Result<bool> {
+        Ok(self.publishers.get_as_slice(publisher)?.is_some())
+    }
+    pub fn publishers_count(&self) -> Result<usize> {
+        self.publishers.len()
+    }
+    /// Unites publishers of the same library
+    pub fn merge(&mut self, other: &LibDescr) -> Result<()> {
+        if self.lib.repr_hash() != other.lib.repr_hash() {
+            fail!(BlockError::InvalidArg(format!(
+                "can't merge descriptors of different libraries {:x} and {:x}",
+                self.lib.repr_hash(), other.lib.repr_hash()
+            )))
+        }
+        other.publishers.iterate_keys(|publisher: UInt256| {
+            self.publishers.set(&publisher, &())?;
+            Ok(true)
+        })?;
+        Ok(())
+    }
 }
 
 impl Deserializable for LibDescr {
@@ -2005,8 +3641,8 @@ impl Deserializable for LibDescr {
                 }
             )
         }
-        self.lib.read_from(slice)?;
-        self.publishers.read_hashmap_root(slice)?;
+        self.lib.read_from(slice).with_ctx("LibDescr", "lib")?;
+        self.publishers.read_hashmap_root(slice).with_ctx("LibDescr", "publishers")?;
         Ok(())
     }
 }
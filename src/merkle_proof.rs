@@ -17,7 +17,7 @@ use crate::{
     Serializable, Deserializable, GetRepresentationHash,
     accounts::Account,
     shard::ShardStateUnsplit,
-    error::BlockError,
+    error::{BlockError, MerkleProofError},
     blocks::{Block, BlockInfo, BlockSeqNoAndShard},
     transactions::Transaction,
     messages::Message,
@@ -192,6 +192,104 @@ impl MerkleProof {
         let virt_root = self.proof.clone().virtualize(1);
         T::construct_from_cell(virt_root)
     }
+
+    /// Checks the proof is built for the `root_hash` and its cells are consistent.
+    /// Failure reason is returned as `MerkleProofError` with the hash of the offending cell
+    pub fn verify_against(&self, root_hash: &UInt256) -> Result<()> {
+        let actual = self.proof.hash(0);
+        if actual != self.hash {
+            fail!(MerkleProofError::RootHashMismatch { expected: self.hash.clone(), actual })
+        }
+        if &self.hash != root_hash {
+            fail!(MerkleProofError::RootHashMismatch { expected: root_hash.clone(), actual: self.hash.clone() })
+        }
+        let expected = self.proof.depth(0);
+        if self.depth != expected {
+            fail!(MerkleProofError::InconsistentDepth { hash: self.hash.clone(), depth: self.depth, expected })
+        }
+        Self::verify_cell(&self.proof, 0, &mut HashSet::new())
+    }
+
+    fn verify_cell(cell: &Cell, merkle_depth: u8, visited: &mut HashSet<UInt256>) -> Result<()> {
+        if !visited.insert(cell.repr_hash()) {
+            return Ok(())
+        }
+        if cell.is_pruned() {
+            // pruned branch made at this depth has bit `merkle_depth` in the mask, higher bits are not allowed
+            if cell.level_mask().mask() >> (merkle_depth + 1) != 0 {
+                fail!(MerkleProofError::WrongPrunedBranchLevel {
+                    hash: cell.repr_hash(),
+                    level: cell.level(),
+                    merkle_depth,
+                })
+            }
+            return Ok(())
+        }
+        let child_index = cell.is_merkle() as usize;
+        let child_merkle_depth = merkle_depth + cell.is_merkle() as u8;
+        let mut expected = 0;
+        for i in 0..cell.references_count() {
+            let child = cell.reference(i)?;
+            expected = max(expected, child.depth(child_index) + 1);
+            Self::verify_cell(&child, child_merkle_depth, visited)?;
+        }
+        let depth = cell.depth(0);
+        if depth != expected {
+            fail!(MerkleProofError::InconsistentDepth { hash: cell.repr_hash(), depth, expected })
+        }
+        Ok(())
+    }
+
+    /// Combines two proofs of the same root into one proof covering cells revealed by any of them.
+    pub fn merge(a: &MerkleProof, b: &MerkleProof) -> Result<MerkleProof> {
+        if a.hash != b.hash || a.depth != b.depth {
+            fail!(
+                BlockError::WrongMerkleProof(
+                    format!("proofs are built for different roots {:x} and {:x}", a.hash, b.hash)
+                )
+            )
+        }
+        let mut done_cells = HashMap::new();
+        let proof = Self::merge_cells(&a.proof, &b.proof, &mut done_cells)?;
+        Ok(MerkleProof {
+            hash: a.hash.clone(),
+            depth: a.depth,
+            proof
+        })
+    }
+
+    fn merge_cells(
+        a: &Cell,
+        b: &Cell,
+        done_cells: &mut HashMap<(UInt256, UInt256), Cell>,
+    ) -> Result<Cell> {
+        // zero level hash is the same for original cell and any of its pruned versions
+        if a.hash(0) != b.hash(0) {
+            fail!(
+                BlockError::WrongMerkleProof(
+                    format!("cells {:x} and {:x} are from different trees", a.hash(0), b.hash(0))
+                )
+            )
+        }
+        if a.repr_hash() == b.repr_hash() || b.is_pruned() {
+            return Ok(a.clone())
+        }
+        if a.is_pruned() {
+            return Ok(b.clone())
+        }
+        let key = (a.repr_hash(), b.repr_hash());
+        if let Some(cell) = done_cells.get(&key) {
+            return Ok(cell.clone())
+        }
+        let mut merged = BuilderData::from_cell(a)?;
+        for i in 0..a.references_count() {
+            let child = Self::merge_cells(&a.reference(i)?, &b.reference(i)?, done_cells)?;
+            merged.replace_reference_cell(i, child);
+        }
+        let merged = merged.into_cell()?;
+        done_cells.insert(key, merged.clone());
+        Ok(merged)
+    }
 }
 
 // checks if proof contains correct block info
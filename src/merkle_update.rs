@@ -317,6 +317,40 @@ impl MerkleUpdate {
         }
     }
 
+    /// Applies update to the tree where subtrees untouched by the update may be
+    /// pruned branches (for example the tree taken from Merkle proof).
+    /// Returns new tree with the same subtrees left pruned, so its hash(0)
+    /// is equal to `new_hash`
+    pub fn apply_for_pruned(&self, old_root: &Cell) -> Result<Cell> {
+        if self.old_hash != old_root.hash(0) {
+            fail!(BlockError::WrongMerkleUpdate("old bag's hash mismatch".to_string()))
+        }
+        if self.new_hash == self.old_hash {
+            return Ok(old_root.clone())
+        }
+
+        let mut known_cells = HashSet::new();
+        Self::traverse_old_on_check(&self.old, &mut known_cells, &mut HashSet::new(), 0);
+        Self::traverse_new_on_check(&self.new, &known_cells, &mut HashSet::new(), 0)?;
+
+        // pruned branches of the old tree are collated as is and will be connected to new one
+        let mut old_cells = HashMap::new();
+        Self::collate_old_cells(old_root, &known_cells, &mut old_cells, &mut HashSet::new(), 0);
+
+        let new_root = self.traverse_on_apply(
+            &self.new, &old_cells, &mut HashMap::new(), 0,
+            &(Arc::new(DefaultCellsFactory) as Arc<dyn CellsFactory>)
+        ).map_err(|err| error!(BlockError::WrongMerkleUpdate(
+            format!("update touches pruned part of the old tree: {}", err)
+        )))?;
+
+        if new_root.hash(0) != self.new_hash {
+            fail!(BlockError::WrongMerkleUpdate("new bag's hash mismatch".to_string()))
+        }
+
+        Ok(new_root)
+    }
+
     pub fn apply_for_with_metrics(&self, old_root: &Cell) -> Result<(Cell, MerkleUdateApplyMetrics)> {
         self.apply_for_with_cells_factory(old_root, 
             &(Arc::new(DefaultCellsFactory) as Arc<dyn CellsFactory>))
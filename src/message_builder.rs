@@ -0,0 +1,126 @@
+/*
+* Copyright (C) 2019-2023 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use crate::{
+    accounts::StorageUsedShort,
+    config_params::MsgForwardPrices,
+    error::BlockError,
+    messages::{
+        ExternalInboundMessageHeader, InternalMessageHeader, Message, MsgAddressExt, MsgAddressInt, StateInit
+    },
+    types::{CurrencyCollection, Grams},
+    write_boc, Result, Serializable, SliceData, fail,
+};
+
+#[cfg(test)]
+#[path = "tests/test_message_builder.rs"]
+mod tests;
+
+/// Default size limits of external inbound message
+pub const MAX_EXT_MSG_SIZE: usize = 65535; // bytes of bag of cells
+pub const MAX_EXT_MSG_DEPTH: u16 = 512;
+
+/// Assembles messages checking protocol limits and filling fee fields
+#[derive(Clone, Debug)]
+pub struct MessageBuilder {
+    msg: Message,
+}
+
+impl MessageBuilder {
+    pub fn external_inbound(dst: MsgAddressInt, body: Option<SliceData>, state_init: Option<StateInit>) -> Self {
+        let header = ExternalInboundMessageHeader::new(MsgAddressExt::AddrNone, dst);
+        let mut builder = Self { msg: Message::with_ext_in_header(header) };
+        if let Some(body) = body {
+            builder = builder.with_body(body);
+        }
+        if let Some(state_init) = state_init {
+            builder = builder.with_state_init(state_init);
+        }
+        builder
+    }
+
+    /// Internal message with bounce flag set and IHR disabled
+    pub fn internal(src: MsgAddressInt, dst: MsgAddressInt, value: CurrencyCollection) -> Self {
+        let header = InternalMessageHeader::with_addresses_and_bounce(src, dst, value, true);
+        Self { msg: Message::with_int_header(header) }
+    }
+
+    /// Has no effect for external message
+    pub fn with_bounce(mut self, bounce: bool) -> Self {
+        if let Some(header) = self.msg.int_header_mut() {
+            header.bounce = bounce;
+        }
+        self
+    }
+
+    /// IHR fee is paid only if IHR is enabled, has no effect for external message
+    pub fn with_ihr_disabled(mut self, ihr_disabled: bool) -> Self {
+        if let Some(header) = self.msg.int_header_mut() {
+            header.ihr_disabled = ihr_disabled;
+        }
+        self
+    }
+
+    pub fn with_body(mut self, body: SliceData) -> Self {
+        self.msg.set_body(body);
+        self
+    }
+
+    pub fn with_state_init(mut self, state_init: StateInit) -> Self {
+        self.msg.set_state_init(state_init);
+        self
+    }
+
+    pub fn message(&self) -> &Message {
+        &self.msg
+    }
+
+    /// Import fee the validators charge for the external inbound message:
+    /// forwarding fee of the message without its root cell
+    pub fn import_fee(&self, prices: &MsgForwardPrices) -> Result<Grams> {
+        if !self.msg.is_inbound_external() {
+            fail!(BlockError::InvalidOperation("import fee is paid only for external inbound message".to_string()))
+        }
+        let storage = StorageUsedShort::calculate_for_message_cell(&self.msg.serialize()?)?;
+        prices.fwd_fee_for_storage(&storage)
+    }
+
+    /// Checks the message against protocol limits and returns it.
+    /// Internal message gets forwarding fee without the part collected by
+    /// the sender's validators (first_frac) and IHR fee if IHR is enabled
+    pub fn finalize(mut self, prices: &MsgForwardPrices) -> Result<Message> {
+        let cell = self.msg.serialize()?;
+        if let Some(header) = self.msg.int_header_mut() {
+            let fwd_fee = prices.fwd_fee_for_storage(&StorageUsedShort::calculate_for_message_cell(&cell)?)?;
+            header.fwd_fee = prices.first_frac_split(&fwd_fee)?.1;
+            header.ihr_fee = match header.ihr_disabled {
+                true => Grams::zero(),
+                false => prices.ihr_fee(&fwd_fee)?
+            };
+        }
+        if self.msg.is_inbound_external() {
+            if cell.repr_depth() > MAX_EXT_MSG_DEPTH {
+                fail!(BlockError::InvalidData(format!(
+                    "external message depth {} is bigger than {}", cell.repr_depth(), MAX_EXT_MSG_DEPTH
+                )))
+            }
+            let size = write_boc(&cell)?.len();
+            if size > MAX_EXT_MSG_SIZE {
+                fail!(BlockError::InvalidData(format!(
+                    "external message size {} is bigger than {}", size, MAX_EXT_MSG_SIZE
+                )))
+            }
+        }
+        Ok(self.msg)
+    }
+}
@@ -18,13 +18,13 @@ use crate::{
     error::BlockError,
     dictionary::hashmapaug::HashmapAugType,
     merkle_proof::MerkleProof,
-    shard::MASTERCHAIN_ID,
+    shard::{AccountIdPrefixFull, MASTERCHAIN_ID},
     types::{AddSub, CurrencyCollection, Grams, Number5, Number9, UnixTime32},
     Deserializable, Serializable,
     error, fail, AccountId, BuilderData, Cell, IBitstring, Result,
-    SliceData, UInt256, UsageTree, MAX_DATA_BITS, MAX_REFERENCES_COUNT,
+    CellType, SliceData, UInt256, UsageTree, MAX_DATA_BITS, MAX_REFERENCES_COUNT,
 };
-use std::{fmt, str::FromStr};
+use std::{collections::HashSet, fmt, str::FromStr};
 
 #[cfg(test)]
 #[path = "tests/test_messages.rs"]
@@ -50,6 +50,9 @@ _ MsgAddressInt = MsgAddress;
 _ MsgAddressExt = MsgAddress;
  */
 
+/// anycast_info depth:(#<= 30) { depth >= 1 }
+pub const MAX_ANYCAST_DEPTH: usize = 30;
+
 impl AnycastInfo {
     pub fn with_rewrite_pfx(pfx: SliceData) -> Result<Self> {
         Ok(Self {
@@ -62,6 +65,31 @@ impl AnycastInfo {
         self.rewrite_pfx = pfx;
         Ok(())
     }
+    /// Checks depth is in 1..=MAX_ANYCAST_DEPTH, matches rewrite_pfx length
+    /// and fits into the address of `addr_len` bits
+    pub fn check(&self, addr_len: usize) -> Result<()> {
+        let depth = self.depth.as_usize();
+        if depth == 0 || depth > MAX_ANYCAST_DEPTH || depth > addr_len {
+            fail!(BlockError::InvalidData(format!(
+                "anycast depth {} is out of bounds 1..={} for address of {} bits",
+                depth, MAX_ANYCAST_DEPTH.min(addr_len), addr_len
+            )))
+        }
+        if self.rewrite_pfx.remaining_bits() != depth {
+            fail!(BlockError::InvalidData(format!(
+                "anycast rewrite_pfx has {} bits instead of {}", self.rewrite_pfx.remaining_bits(), depth
+            )))
+        }
+        Ok(())
+    }
+    /// Rewrite prefix placed in the highest bits of u64
+    pub fn prefix_u64(&self) -> Result<u64> {
+        let depth = self.rewrite_pfx.remaining_bits();
+        if depth == 0 {
+            return Ok(0)
+        }
+        Ok(self.rewrite_pfx.clone().get_next_int(depth)? << (64 - depth))
+    }
 }
 
 impl Serializable for AnycastInfo {
@@ -422,6 +450,66 @@ impl MsgAddressInt {
     pub fn is_masterchain(&self) -> bool {
         self.get_workchain_id() == MASTERCHAIN_ID
     }
+
+    pub fn anycast(&self) -> Option<&AnycastInfo> {
+        match self {
+            MsgAddressInt::AddrStd(addr_std) => addr_std.anycast.as_ref(),
+            MsgAddressInt::AddrVar(addr_var) => addr_var.anycast.as_ref()
+        }
+    }
+
+    /// Checks depth bounds of the anycast info if any
+    pub fn check_anycast(&self) -> Result<()> {
+        match self.anycast() {
+            Some(anycast) => anycast.check(self.address().remaining_bits()),
+            None => Ok(())
+        }
+    }
+
+    /// Replaces anycast info keeping the address bits, None removes it
+    pub fn set_anycast(&mut self, anycast: Option<AnycastInfo>) -> Result<()> {
+        if let Some(anycast) = &anycast {
+            anycast.check(self.address().remaining_bits())?;
+        }
+        match self {
+            MsgAddressInt::AddrStd(addr_std) => addr_std.anycast = anycast,
+            MsgAddressInt::AddrVar(addr_var) => addr_var.anycast = anycast
+        }
+        Ok(())
+    }
+
+    /// Copy of the address without anycast info, the address bits are not rewritten
+    pub fn strip_anycast(&self) -> MsgAddressInt {
+        let mut addr = self.clone();
+        match &mut addr {
+            MsgAddressInt::AddrStd(addr_std) => addr_std.anycast = None,
+            MsgAddressInt::AddrVar(addr_var) => addr_var.anycast = None
+        }
+        addr
+    }
+
+    /// Address the message is delivered to: the first depth bits are replaced
+    /// by rewrite_pfx and anycast info is removed
+    pub fn apply_anycast(&self) -> Result<MsgAddressInt> {
+        self.check_anycast()?;
+        let (workchain_id, address) = self.extract_std_address(true)?;
+        match self {
+            MsgAddressInt::AddrStd(addr_std) => MsgAddressInt::with_standart(None, addr_std.workchain_id, address),
+            MsgAddressInt::AddrVar(_) => MsgAddressInt::with_variant(None, workchain_id, address)
+        }
+    }
+
+    /// Prefix used to route the message to the shard of the destination
+    pub fn delivery_prefix(&self) -> Result<AccountIdPrefixFull> {
+        let prefix = AccountIdPrefixFull::prefix(&self.strip_anycast())?;
+        match self.anycast() {
+            Some(anycast) => {
+                anycast.check(self.address().remaining_bits())?;
+                AccountIdPrefixFull::from_anycast(prefix.workchain_id, prefix.prefix, anycast)
+            }
+            None => Ok(prefix)
+        }
+    }
 }
 
 impl Serializable for MsgAddressInt {
@@ -1730,6 +1818,59 @@ impl StateInit {
         self.library.set(&code.repr_hash(), &SimpleLib::new(code, public))?;
         Ok(())
     }
+
+    /// Address of the account deployed with the state init: its representation hash.
+    /// Split depth is kept as anycast with the first split_depth bits of the hash,
+    /// special (tick-tock) accounts can be only in masterchain.
+    /// Library cells are hashed as they are, without resolving.
+    pub fn compute_address(&self, workchain_id: i32) -> Result<MsgAddressInt> {
+        if self.special.is_some() && workchain_id != MASTERCHAIN_ID {
+            fail!(BlockError::InvalidArg(format!(
+                "special account can't be deployed in workchain {}", workchain_id
+            )))
+        }
+        let hash = self.serialize()?.repr_hash();
+        let anycast = match self.split_depth.as_ref().map(|depth| depth.as_usize()) {
+            Some(depth) if depth != 0 => Some(AnycastInfo::with_rewrite_pfx(
+                SliceData::from_raw(hash.as_slice().to_vec(), depth)
+            )?),
+            _ => None
+        };
+        match i8::try_from(workchain_id) {
+            Ok(workchain_id) => MsgAddressInt::with_standart(anycast, workchain_id, hash.into()),
+            Err(_) => MsgAddressInt::with_variant(anycast, workchain_id, hash.into())
+        }
+    }
+
+    /// Representation hash checking that every library cell of code and data is
+    /// resolved by own libraries or by `resolver` (e.g. public libraries of masterchain).
+    /// Library cells are not replaced, so the hash is the same as for `compute_address`.
+    pub fn hash_with_default_libs(
+        &self,
+        mut resolver: impl FnMut(&UInt256) -> Result<Option<Cell>>,
+    ) -> Result<UInt256> {
+        let mut visited = HashSet::new();
+        let mut cells: Vec<Cell> = self.code.iter().chain(self.data.iter()).cloned().collect();
+        while let Some(cell) = cells.pop() {
+            if !visited.insert(cell.repr_hash()) {
+                continue
+            }
+            if cell.cell_type() == CellType::LibraryReference {
+                let hash = UInt256::from_slice(&cell.data()[1..33]);
+                let resolved = match self.library.get(&hash)? {
+                    Some(lib) => Some(lib.root),
+                    None => resolver(&hash)?
+                };
+                if resolved.map(|root| root.repr_hash()) != Some(hash.clone()) {
+                    fail!(BlockError::InvalidData(format!("library {:x} is not resolved", hash)))
+                }
+            }
+            for i in 0..cell.references_count() {
+                cells.push(cell.reference(i)?);
+            }
+        }
+        Ok(self.serialize()?.repr_hash())
+    }
 }
 
 impl Serializable for StateInit {
@@ -13,6 +13,8 @@
 
 use crate::{
     define_HashmapE,
+    outbound_messages::EnqueuedMsg,
+    shard::{AccountIdPrefixFull, ShardIdent},
     Serializable, Deserializable,
     Result, BuilderData, Cell, SliceData, UInt256,
     HashmapSubtree, fail,
@@ -28,6 +30,68 @@ _ (HashmapE 96 ProcessedUpto) = ProcessedInfo;
 */
 define_HashmapE!(ProcessedInfo, 96, ProcessedUpto);
 
+impl ProcessedInfo {
+    /// Message from the outbound queue is processed by some record, the same rules
+    /// as `MsgProcessedUptoCollection::already_processed` of the node use.
+    /// `shard_end_lt(mc_seqno, prefix)` must return end lt of the shard block containing
+    /// `prefix` registered in the masterchain block `mc_seqno`
+    pub fn already_processed<F>(&self, enq: &EnqueuedMsg, mut shard_end_lt: F) -> Result<bool>
+    where F: FnMut(u32, &AccountIdPrefixFull) -> Result<u64> {
+        let env = enq.read_out_msg()?;
+        let (cur_prefix, next_prefix) = env.calc_cur_next_prefix()?;
+        let created_lt = enq.created_lt()?;
+        let hash = env.message_hash();
+        let not_found = self.iterate_with_keys(|key: ProcessedInfoKey, upto| {
+            if created_lt > upto.last_msg_lt
+                || !ShardIdent::contains(key.shard, next_prefix.prefix)
+                || (created_lt == upto.last_msg_lt && upto.last_msg_hash < hash) {
+                return Ok(true)
+            }
+            // messages generated in the same shard could be processed without a reference from the masterchain
+            if cur_prefix.workchain_id == next_prefix.workchain_id && ShardIdent::contains(key.shard, cur_prefix.prefix) {
+                return Ok(false)
+            }
+            Ok(enq.enqueued_lt() >= shard_end_lt(key.mc_seqno, &cur_prefix)?)
+        })?;
+        Ok(!not_found)
+    }
+
+    /// Removes records dominated by others: for the same or ancestor shard,
+    /// not older masterchain block and not earlier last processed message.
+    /// Returns count of removed records
+    pub fn compactify(&mut self) -> Result<usize> {
+        let mut records = Vec::new();
+        self.iterate_with_keys(|key: ProcessedInfoKey, upto| {
+            records.push((key, upto));
+            Ok(true)
+        })?;
+        let mut dominated = vec![false; records.len()];
+        for (i, record) in records.iter().enumerate() {
+            let is_dominated = records.iter().enumerate().any(|(j, other)| {
+                j != i && !dominated[j] && Self::contains(other, record)
+            });
+            dominated[i] = is_dominated;
+        }
+        let mut removed = 0;
+        for ((key, _), dominated) in records.iter().zip(dominated) {
+            if dominated {
+                self.remove(key)?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+
+    fn contains(
+        (key, upto): &(ProcessedInfoKey, ProcessedUpto),
+        (other_key, other_upto): &(ProcessedInfoKey, ProcessedUpto),
+    ) -> bool {
+        ShardIdent::is_ancestor(key.shard, other_key.shard)
+            && key.mc_seqno >= other_key.mc_seqno
+            && (upto.last_msg_lt, &upto.last_msg_hash) >= (other_upto.last_msg_lt, &other_upto.last_msg_hash)
+    }
+}
+
 /// Struct ProcessedInfoKey describe key for ProcessedInfo
 #[derive(Clone, Debug, Eq, PartialEq, Default)]
 pub struct ProcessedInfoKey {
@@ -12,8 +12,9 @@
 */
 
 use crate::{
-    error::BlockError, messages::Message, types::CurrencyCollection, Deserializable, Serializable,
-    fail, AccountId, BuilderData, Cell, IBitstring, Result, SliceData, UInt256,
+    accounts::StorageUsedShort, error::BlockError, messages::Message, types::CurrencyCollection,
+    Deserializable, Serializable, fail, AccountId, BuilderData, Cell, IBitstring, Result,
+    SliceData, UInt256,
 };
 use std::collections::LinkedList;
 
@@ -23,6 +24,11 @@ pub const ACTION_RESERVE:    u32 = 0x36e6b809;
 pub const ACTION_CHANGE_LIB: u32 = 0x26fa1dd4;
 pub const ACTION_COPYLEFT:   u32 = 0x24486f7a;
 
+/// Limits of the action list checked by `parse_out_actions` and `validate_out_actions`
+pub const MAX_OUT_ACTIONS: usize = 255;
+pub const MAX_OUT_MSG_CELLS: u64 = 1 << 13;
+pub const MAX_OUT_MSG_BITS: u64 = 1 << 21;
+
 #[cfg(test)]
 #[path = "tests/test_out_actions.rs"]
 mod tests;
@@ -85,6 +91,77 @@ impl Deserializable for OutActions {
 }
 
 
+///
+/// Parses action list of c5 register failing if it has more than MAX_OUT_ACTIONS actions
+/// before reading the rest of the list, then validates the actions
+///
+pub fn parse_out_actions(c5: Cell) -> Result<OutActions> {
+    let mut actions = OutActions::new();
+    let mut cell = SliceData::load_cell(c5)?;
+    while cell.remaining_references() != 0 {
+        if actions.len() == MAX_OUT_ACTIONS {
+            fail!(BlockError::InvalidData(format!("action list has more than {} actions", MAX_OUT_ACTIONS)))
+        }
+        let prev_cell = cell.checked_drain_reference()?;
+        actions.push_front(OutAction::construct_from(&mut cell)?);
+        if !cell.is_empty() || cell.remaining_references() != 0 {
+            fail!(BlockError::InvalidData(format!("action {} has extra data", actions.len())))
+        }
+        cell = SliceData::load_cell(prev_cell)?;
+    }
+    if !cell.is_empty() {
+        fail!(BlockError::InvalidData("end of action list is not empty".to_string()))
+    }
+    validate_out_actions(&actions)?;
+    Ok(actions)
+}
+
+///
+/// Checks count of actions, their modes and sizes of sent messages
+///
+pub fn validate_out_actions(actions: &OutActions) -> Result<()> {
+    if actions.len() > MAX_OUT_ACTIONS {
+        fail!(BlockError::InvalidData(format!(
+            "action list has {} actions, but maximum is {}", actions.len(), MAX_OUT_ACTIONS
+        )))
+    }
+    for (index, action) in actions.iter().enumerate() {
+        match action {
+            OutAction::SendMsg { mode, out_msg } => {
+                if mode & !SENDMSG_VALID_FLAGS != 0 ||
+                    mode & SENDMSG_REMAINING_MSG_BALANCE != 0 && mode & SENDMSG_ALL_BALANCE != 0 {
+                    fail!(BlockError::InvalidData(format!("action {} has invalid send mode {}", index, mode)))
+                }
+                let size = StorageUsedShort::calculate_for_struct(out_msg)?;
+                if size.cells() > MAX_OUT_MSG_CELLS || size.bits() > MAX_OUT_MSG_BITS {
+                    fail!(BlockError::InvalidData(format!(
+                        "message of action {} is too big: {} cells, {} bits", index, size.cells(), size.bits()
+                    )))
+                }
+            }
+            OutAction::ReserveCurrency { mode, .. } => {
+                if mode & !RESERVE_VALID_MODES != 0 {
+                    fail!(BlockError::InvalidData(format!("action {} has invalid reserve mode {}", index, mode)))
+                }
+            }
+            OutAction::ChangeLibrary { mode, code, hash } => {
+                let valid = match *mode {
+                    CHANGE_LIB_REMOVE => code.is_none() && hash.is_some(),
+                    SET_LIB_CODE_REMOVE | SET_LIB_CODE_ADD_PRIVATE | SET_LIB_CODE_ADD_PUBLIC => {
+                        code.is_some() && hash.is_none()
+                    }
+                    _ => false
+                };
+                if !valid {
+                    fail!(BlockError::InvalidData(format!("action {} has invalid library mode {}", index, mode)))
+                }
+            }
+            OutAction::None => fail!(BlockError::InvalidData(format!("action {} is empty", index))),
+            OutAction::SetCode { .. } | OutAction::CopyLeft { .. } => ()
+        }
+    }
+    Ok(())
+}
 
 ///
 /// Enum OutAction
@@ -19,15 +19,15 @@ use crate::{
     inbound_messages::InMsg,
     messages::{CommonMsgInfo, Message},
     common_message::CommonMessage,
-    miscellaneous::{IhrPendingInfo, ProcessedInfo},
-    shard::{AccountIdPrefixFull, ShardState},
+    miscellaneous::{IhrPendingInfo, ProcessedInfo, ProcessedInfoKey},
+    shard::{AccountIdPrefixFull, ShardIdent, ShardState},
     types::{AddSub, ChildCell, CurrencyCollection},
     transactions::Transaction,
     Serializable, Deserializable, ShardStateUnsplit, MerkleProof, MerkleUpdate, OutQueueUpdate,
     error, fail, Result, SERDE_OPTS_EMPTY, SERDE_OPTS_COMMON_MESSAGE,
     AccountId, UInt256, InRefValue,
     BuilderData, Cell, SliceData, IBitstring,
-    HashmapType, HashmapSubtree, hm_label, UsageTree,
+    HashmapType, HashmapSubtree, HashmapRemover, hm_label, UsageTree,
 };
 use std::{fmt, collections::HashSet};
 
@@ -217,6 +217,106 @@ impl OutMsgQueue {
     }
 }
 
+/*
+_ (HashmapE 352 True) = OutMsgQueueKeys;
+out_msg_queue_diff#01 added:OutMsgQueue removed:OutMsgQueueKeys = OutMsgQueueDiff;
+*/
+define_HashmapE!(OutMsgQueueKeys, 352, ());
+
+const OUT_MSG_QUEUE_DIFF_TAG: u8 = 0x01;
+
+///
+/// Difference between two states of OutMsgQueue: messages enqueued into the new
+/// queue and keys of messages removed from the old one. Replaced message is both
+/// removed and added.
+///
+#[derive(Clone, Debug, Eq, PartialEq, Default)]
+pub struct OutMsgQueueDiff {
+    added: OutMsgQueue,
+    removed: OutMsgQueueKeys,
+}
+
+impl OutMsgQueueDiff {
+    pub fn with_queues(old_queue: &OutMsgQueue, new_queue: &OutMsgQueue) -> Result<Self> {
+        if old_queue.serde_opts() != new_queue.serde_opts() {
+            fail!(BlockError::MismatchedSerdeOptions(
+                std::any::type_name::<OutMsgQueue>().to_string(),
+                old_queue.serde_opts() as usize,
+                new_queue.serde_opts() as usize
+            ))
+        }
+        let mut diff = Self {
+            added: OutMsgQueue::with_serde_opts(new_queue.serde_opts()),
+            removed: OutMsgQueueKeys::default(),
+        };
+        old_queue.scan_diff_with_aug(new_queue, |key, old, new| {
+            if old.is_some() {
+                diff.removed.set(&key, &())?;
+            }
+            if let Some((enq, lt)) = new {
+                diff.added.set(&key, &enq, &lt)?;
+            }
+            Ok(true)
+        })?;
+        Ok(diff)
+    }
+
+    pub fn added(&self) -> &OutMsgQueue {
+        &self.added
+    }
+
+    pub fn removed(&self) -> &OutMsgQueueKeys {
+        &self.removed
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+
+    /// Turns the old queue into the new one
+    pub fn apply_to(&self, queue: &mut OutMsgQueue) -> Result<()> {
+        self.removed.iterate_keys(|key: OutMsgQueueKey| {
+            if queue.remove(SliceData::load_builder(key.write_to_new_cell()?)?)?.is_none() {
+                fail!(BlockError::InvalidData(format!("removed message {:x} is not in the queue", key)))
+            }
+            Ok(true)
+        })?;
+        self.added.iterate_with_keys_and_aug(|key, enq, lt| {
+            queue.set(&key, &enq, &lt)?;
+            Ok(true)
+        })?;
+        Ok(())
+    }
+}
+
+impl Serializable for OutMsgQueueDiff {
+    fn write_to(&self, builder: &mut BuilderData) -> Result<()> {
+        builder.append_u8(OUT_MSG_QUEUE_DIFF_TAG)?;
+        self.added.write_with_opts(builder, self.added.serde_opts())?;
+        self.removed.write_to(builder)?;
+        Ok(())
+    }
+}
+
+impl Deserializable for OutMsgQueueDiff {
+    fn read_from(&mut self, slice: &mut SliceData) -> Result<()> {
+        self.read_from_with_opts(slice, SERDE_OPTS_EMPTY)
+    }
+
+    fn read_from_with_opts(&mut self, slice: &mut SliceData, opts: u8) -> Result<()> {
+        let tag = slice.get_next_byte()?;
+        if tag != OUT_MSG_QUEUE_DIFF_TAG {
+            fail!(BlockError::InvalidConstructorTag {
+                t: tag as u32,
+                s: std::any::type_name::<Self>().to_string()
+            })
+        }
+        self.added = OutMsgQueue::construct_from_with_opts(slice, opts)?;
+        self.removed.read_from(slice)?;
+        Ok(())
+    }
+}
+
 ///
 /// The key used for an outbound message m is the concatenation of its 32-bit
 /// next-hop workchain_id, the first 64 bits of the next-hop address inside that
@@ -357,6 +457,14 @@ pub struct OutMsgQueueInfo {
     ihr_pending: IhrPendingInfo,
 }
 
+/// Statistics of [OutMsgQueueInfo::clean_processed]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct OutQueueCleanStats {
+    pub checked: usize,
+    pub deleted: usize,
+    pub finished: bool,
+}
+
 #[derive(Default)]
 pub struct ProofForWc {
     pub proof: MerkleProof,
@@ -419,6 +527,49 @@ impl OutMsgQueueInfo {
         Ok(result)
     }
 
+    /// Removes messages already delivered to the neighbors according to `processed`
+    /// (usually the proc_info of neighbors). Keys of processed info contain only shard
+    /// prefixes, so `workchain_id` of the neighbors must be given. At most `max_entries`
+    /// messages are removed at once, `finished` is false if the queue must be cleaned again.
+    pub fn clean_processed(
+        &mut self,
+        workchain_id: i32,
+        processed: &ProcessedInfo,
+        max_entries: usize,
+    ) -> Result<OutQueueCleanStats> {
+        let mut upto = Vec::new();
+        processed.iterate_with_keys(|key: ProcessedInfoKey, value| {
+            upto.push((ShardIdent::with_tagged_prefix(workchain_id, key.shard)?, value));
+            Ok(true)
+        })?;
+        let mut stats = OutQueueCleanStats { finished: true, ..Default::default() };
+        let mut delivered = Vec::new();
+        self.out_queue.iterate_with_keys(|key: OutMsgQueueKey, enq| {
+            let created_lt = enq.created_lt()?;
+            let is_delivered = upto.iter().any(|(shard, upto)| {
+                shard.contains_prefix(key.workchain_id, key.prefix)
+                    && (created_lt, &key.hash) <= (upto.last_msg_lt, &upto.last_msg_hash)
+            });
+            if !is_delivered {
+                stats.checked += 1;
+                return Ok(true)
+            }
+            if delivered.len() >= max_entries {
+                // there is one more delivered message left in the queue
+                stats.finished = false;
+                return Ok(false)
+            }
+            stats.checked += 1;
+            delivered.push(key);
+            Ok(true)
+        })?;
+        for key in delivered {
+            self.out_queue.remove(SliceData::load_builder(key.write_to_new_cell()?)?)?;
+            stats.deleted += 1;
+        }
+        Ok(stats)
+    }
+
     // Create proofs in state for
     // - part of out queue related with given WC
     // - proceseed info
@@ -0,0 +1,285 @@
+/*
+* Copyright (C) 2019-2023 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use crate::{
+    blocks::{Block, BlockIdExt, BlockInfo},
+    define_HashmapE,
+    error::BlockError,
+    merkle_proof::MerkleProof,
+    signature::BlockProof,
+    BuilderData, Deserializable, IBitstring, Result, Serializable, SliceData, error, fail,
+};
+use std::{collections::BTreeMap, ops::Bound};
+
+#[cfg(test)]
+#[path = "tests/test_proof_index.rs"]
+mod tests;
+
+/// Header data of the masterchain block kept in the index
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct McIndexEntry {
+    pub id: BlockIdExt,
+    pub prev_key_block_seqno: u32,
+    pub gen_utime: u32,
+    pub end_lt: u64,
+}
+
+impl McIndexEntry {
+    pub fn with_info(id: BlockIdExt, info: &BlockInfo) -> Result<Self> {
+        if !id.shard().is_masterchain() || !info.shard().is_masterchain() {
+            fail!(BlockError::InvalidArg(format!("{} is not a masterchain block", id)))
+        }
+        if info.seq_no() != id.seq_no() {
+            fail!(BlockError::InvalidArg(
+                format!("block info has seqno {} but id is {}", info.seq_no(), id)
+            ))
+        }
+        if id.seq_no() != 0 && info.prev_key_block_seqno() >= id.seq_no() {
+            fail!(BlockError::InvalidData(format!(
+                "block {} refers to newer key block {}", id, info.prev_key_block_seqno()
+            )))
+        }
+        Ok(Self {
+            id,
+            prev_key_block_seqno: info.prev_key_block_seqno(),
+            gen_utime: info.gen_utime().as_u32(),
+            end_lt: info.end_lt(),
+        })
+    }
+
+    pub fn seq_no(&self) -> u32 {
+        self.id.seq_no()
+    }
+}
+
+impl Serializable for McIndexEntry {
+    fn write_to(&self, cell: &mut BuilderData) -> Result<()> {
+        self.id.write_to(cell)?;
+        self.prev_key_block_seqno.write_to(cell)?;
+        self.gen_utime.write_to(cell)?;
+        self.end_lt.write_to(cell)?;
+        Ok(())
+    }
+}
+
+impl Deserializable for McIndexEntry {
+    fn read_from(&mut self, slice: &mut SliceData) -> Result<()> {
+        self.id.read_from(slice)?;
+        self.prev_key_block_seqno.read_from(slice)?;
+        self.gen_utime.read_from(slice)?;
+        self.end_lt.read_from(slice)?;
+        Ok(())
+    }
+}
+
+define_HashmapE!{McIndexKeyBlocks, 32, McIndexEntry}
+
+/// Answer for the block proof request: the block is proved with the state
+/// of the `prover` block (it contains the block in `prev_blocks`) or it is
+/// the `prover` itself; `key_blocks` are the chain of key blocks to check
+/// the signatures of the `prover`
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct McProofPath {
+    pub key_blocks: Vec<BlockIdExt>,
+    pub prover: BlockIdExt,
+}
+
+/// Index of masterchain key blocks and the last known block, enough to
+/// build the proof path for any masterchain block up to the last one
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct McProofIndex {
+    key_blocks: BTreeMap<u32, McIndexEntry>,
+    last_block: Option<McIndexEntry>,
+}
+
+impl McProofIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Checks merkle proof of the key block (signatures are not checked)
+    /// and adds it to the index
+    pub fn add_key_block_proof(&mut self, proof: &BlockProof) -> Result<()> {
+        let merkle_proof = MerkleProof::construct_from_cell(proof.root.clone())?;
+        if merkle_proof.hash != proof.proof_for.root_hash {
+            fail!(BlockError::WrongMerkleProof(format!("proof is not for the block {}", proof.proof_for)))
+        }
+        let info = merkle_proof.virtualize::<Block>()?.read_info()?;
+        if !info.key_block() {
+            fail!(BlockError::InvalidArg(format!("block {} is not a key block", proof.proof_for)))
+        }
+        self.add_block_header(proof.proof_for.clone(), &info)
+    }
+
+    /// Adds key block or updates the last known block
+    pub fn add_block_header(&mut self, id: BlockIdExt, info: &BlockInfo) -> Result<()> {
+        let entry = McIndexEntry::with_info(id, info)?;
+        let seq_no = entry.seq_no();
+        if let Some(known) = self.key_blocks.get(&seq_no) {
+            if known.id != entry.id {
+                fail!(BlockError::InvalidData(format!("{} conflicts with known key block {}", entry.id, known.id)))
+            }
+            return Ok(())
+        }
+        if let Some((_, prev)) = self.key_blocks.range(..seq_no).next_back() {
+            if prev.seq_no() > entry.prev_key_block_seqno {
+                fail!(BlockError::InvalidData(format!(
+                    "block {} refers to key block {} but key block {} is known",
+                    entry.id, entry.prev_key_block_seqno, prev.id
+                )))
+            }
+        }
+        if info.key_block() {
+            if let Some((_, next)) = self.key_blocks.range((Bound::Excluded(seq_no), Bound::Unbounded)).next() {
+                if next.prev_key_block_seqno != seq_no {
+                    fail!(BlockError::InvalidData(format!(
+                        "key block {} refers to key block {} but not to {}",
+                        next.id, next.prev_key_block_seqno, entry.id
+                    )))
+                }
+            }
+        }
+        if let Some(last) = &self.last_block {
+            if last.seq_no() == seq_no && last.id != entry.id {
+                fail!(BlockError::InvalidData(format!("{} conflicts with known block {}", entry.id, last.id)))
+            }
+        }
+        if self.last_block.as_ref().map_or(true, |last| last.seq_no() < seq_no) {
+            self.last_block = Some(entry.clone());
+        }
+        if info.key_block() {
+            self.key_blocks.insert(seq_no, entry);
+        }
+        Ok(())
+    }
+
+    pub fn key_blocks_count(&self) -> usize {
+        self.key_blocks.len()
+    }
+
+    pub fn last_block(&self) -> Option<&McIndexEntry> {
+        self.last_block.as_ref()
+    }
+
+    pub fn key_block(&self, seq_no: u32) -> Option<&McIndexEntry> {
+        self.key_blocks.get(&seq_no)
+    }
+
+    /// Last key block with seqno not greater than given one
+    pub fn prev_key_block(&self, seq_no: u32) -> Option<&McIndexEntry> {
+        self.key_blocks.range(..=seq_no).next_back().map(|(_, entry)| entry)
+    }
+
+    /// Builds the proof path of the block with `seq_no` for the client
+    /// trusting the key block `trusted_key_block`
+    pub fn proof_path(&self, trusted_key_block: u32, seq_no: u32) -> Result<McProofPath> {
+        let last = self.last_block.as_ref()
+            .ok_or_else(|| error!(BlockError::InvalidOperation("proof index is empty".to_string())))?;
+        if seq_no > last.seq_no() {
+            fail!(BlockError::InvalidArg(
+                format!("block {} is newer than the last known block {}", seq_no, last.id)
+            ))
+        }
+        // the first key block after requested one, its state has the block in prev_blocks,
+        // for old blocks the state of the trusted key block is enough
+        let prover = if seq_no <= trusted_key_block {
+            self.key_blocks.get(&trusted_key_block).ok_or_else(|| error!(BlockError::NotFound(
+                format!("trusted key block {} is not in the index", trusted_key_block)
+            )))?
+        } else if let Some(entry) = self.key_blocks.get(&seq_no) {
+            entry
+        } else if let Some((_, entry)) = self.key_blocks.range((Bound::Excluded(seq_no), Bound::Unbounded)).next() {
+            entry
+        } else {
+            last
+        };
+        if prover.prev_key_block_seqno < trusted_key_block && prover.seq_no() != trusted_key_block {
+            fail!(BlockError::InvalidData(format!(
+                "block {} refers to key block {} older than trusted one {}",
+                prover.id, prover.prev_key_block_seqno, trusted_key_block
+            )))
+        }
+        let mut key_blocks = Vec::new();
+        if prover.seq_no() != trusted_key_block {
+            let mut expected_prev = trusted_key_block;
+            let range = (Bound::Excluded(trusted_key_block), Bound::Excluded(prover.seq_no()));
+            for (key_seq_no, entry) in self.key_blocks.range(range) {
+                if entry.prev_key_block_seqno != expected_prev {
+                    fail!(BlockError::InvalidData(format!(
+                        "key block {} is missing in the index", entry.prev_key_block_seqno
+                    )))
+                }
+                key_blocks.push(entry.id.clone());
+                expected_prev = *key_seq_no;
+            }
+            if prover.prev_key_block_seqno != expected_prev {
+                fail!(BlockError::InvalidData(format!(
+                    "key block {} is missing in the index", prover.prev_key_block_seqno
+                )))
+            }
+        }
+        Ok(McProofPath {
+            key_blocks,
+            prover: prover.id.clone(),
+        })
+    }
+}
+
+const MC_PROOF_INDEX_TAG: u8 = 0x6e;
+
+impl Serializable for McProofIndex {
+    fn write_to(&self, cell: &mut BuilderData) -> Result<()> {
+        let mut key_blocks = McIndexKeyBlocks::default();
+        for (seq_no, entry) in &self.key_blocks {
+            key_blocks.set(seq_no, entry)?;
+        }
+        cell.append_u8(MC_PROOF_INDEX_TAG)?;
+        key_blocks.write_to(cell)?;
+        match &self.last_block {
+            Some(last) => {
+                cell.append_bit_one()?;
+                cell.checked_append_reference(last.serialize()?)?;
+            }
+            None => {
+                cell.append_bit_zero()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Deserializable for McProofIndex {
+    fn read_from(&mut self, slice: &mut SliceData) -> Result<()> {
+        let tag = slice.get_next_byte()?;
+        if tag != MC_PROOF_INDEX_TAG {
+            fail!(
+                BlockError::InvalidConstructorTag {
+                    t: tag.into(),
+                    s: std::any::type_name::<Self>().to_string()
+                }
+            )
+        }
+        let key_blocks = McIndexKeyBlocks::construct_from(slice)?;
+        self.key_blocks.clear();
+        key_blocks.iterate_with_keys(|seq_no: u32, entry| {
+            self.key_blocks.insert(seq_no, entry);
+            Ok(true)
+        })?;
+        self.last_block = if slice.get_next_bit()? {
+            Some(McIndexEntry::construct_from_cell(slice.checked_drain_reference()?)?)
+        } else {
+            None
+        };
+        Ok(())
+    }
+}
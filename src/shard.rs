@@ -19,15 +19,16 @@ use crate::{
     error::BlockError,
     dictionary::hashmapaug::{Augmentation, HashmapAugType},
     master::{BlkMasterInfo, LibDescr, McStateExtra},
-    messages::MsgAddressInt,
+    merkle_proof::MerkleProof,
+    messages::{AnycastInfo, MsgAddressInt},
     outbound_messages::{OutMsgQueueInfo, OutMsgQueuesInfo, MeshMsgQueuesInfo},
     shard_accounts::ShardAccounts,
-    types::{ChildCell, CurrencyCollection},
+    types::{AddSub, ChildCell, CurrencyCollection},
     validators::ValidatorSet,
-    CopyleftRewards, Deserializable, IntermediateAddress,
+    CopyleftRewards, Deserializable, HashmapRemover, IntermediateAddress,
     Serializable, Account,
     error, fail, AccountId, BuilderData, Cell, IBitstring, Result,
-    SERDE_OPTS_COMMON_MESSAGE, SERDE_OPTS_EMPTY, SliceData, UInt256,
+    SERDE_OPTS_COMMON_MESSAGE, SERDE_OPTS_EMPTY, SliceData, UInt256, UsageTree,
 };
 use crate::RefShardBlocks;
 use std::fmt::{self, Display, Formatter};
@@ -113,6 +114,13 @@ impl AccountIdPrefixFull {
         Self{ workchain_id, prefix}
     }
 
+    /// Replaces the highest anycast depth bits of the prefix with rewrite_pfx
+    pub fn from_anycast(workchain_id: i32, prefix: u64, anycast: &AnycastInfo) -> Result<Self> {
+        anycast.check(64)?;
+        let mask = u64::MAX >> anycast.depth.as_usize();
+        Ok(Self { workchain_id, prefix: anycast.prefix_u64()? | (prefix & mask) })
+    }
+
     /// Constructs AccountIdPrefixFull prefix for specified address and stores it in the "to" argument.
     /// Returns true if there are sufficient bits in the address (64 or more) and address is valid
     /// (workchain_id != 0x80000000); false otherwise.
@@ -584,6 +592,49 @@ impl ShardIdent {
         }
     }
 
+    /// Iterates over all ancestors from the parent up to the full shard
+    pub fn ancestors(&self) -> impl Iterator<Item = ShardIdent> {
+        std::iter::successors(self.merge().ok(), |shard| shard.merge().ok())
+    }
+
+    /// Iterates from left to right over all descendants `depth` levels below the shard
+    pub fn descendants(&self, depth: u8) -> Result<impl Iterator<Item = ShardIdent>> {
+        let len = self.prefix_len() as u32 + depth as u32;
+        if len > MAX_SPLIT_DEPTH as u32 {
+            fail!(
+                BlockError::InvalidArg(
+                    format!("Can't get descendants of shard {} at depth {}, because of max split depth is {}",
+                        self.shard_prefix_as_str_with_tag(), depth, MAX_SPLIT_DEPTH)
+                )
+            )
+        }
+        let workchain_id = self.workchain_id;
+        let base = self.shard_prefix_without_tag();
+        let tag = 1 << (63 - len);
+        Ok((0..1u64 << depth).map(move |i| {
+            ShardIdent {
+                workchain_id,
+                prefix: base | i.checked_shl(64 - len).unwrap_or_default() | tag,
+            }
+        }))
+    }
+
+    /// Iterates over the path from `root` down to the shard itself inclusive
+    pub fn path_from(&self, root: &ShardIdent) -> Result<impl Iterator<Item = ShardIdent>> {
+        if !root.is_ancestor_for(self) {
+            fail!(
+                BlockError::InvalidArg(
+                    format!("Shard {} is not an ancestor of {}", root, self)
+                )
+            )
+        }
+        let depth = (self.prefix_len() - root.prefix_len()) as usize;
+        let mut path = Vec::with_capacity(depth + 1);
+        path.push(self.clone());
+        path.extend(self.ancestors().take(depth));
+        Ok(path.into_iter().rev())
+    }
+
     // TODO: need to check max split first
     pub fn left_ancestor_mask(&self) -> Result<Self> {
         Self::with_tagged_prefix(self.workchain_id, (self.prefix - 1) & (!0 << (64 - MAX_SPLIT_DEPTH)))
@@ -783,6 +834,65 @@ impl Serializable for ShardStateSplit {
 
 define_HashmapE!(Libraries, 256, LibDescr);
 
+impl Libraries {
+    /// Adds library published by the account, returns false if it was already published by it
+    pub fn add_library(&mut self, lib: Cell, publisher: &AccountId) -> Result<bool> {
+        let lib_hash = lib.repr_hash();
+        let descr = match self.get(&lib_hash)? {
+            Some(mut descr) => {
+                Self::check_lib_hash(&lib_hash, &descr)?;
+                if !descr.add_publisher(publisher)? {
+                    return Ok(false)
+                }
+                descr
+            }
+            None => LibDescr::from_lib_data_by_publisher(lib, publisher.clone())
+        };
+        self.set(&lib_hash, &descr)?;
+        Ok(true)
+    }
+
+    /// Removes the publisher of the library, the library itself is removed with the last publisher.
+    /// Returns false if the library was not published by the account
+    pub fn remove_publisher(&mut self, lib_hash: &UInt256, publisher: &AccountId) -> Result<bool> {
+        let mut descr = match self.get(lib_hash)? {
+            Some(descr) => descr,
+            None => return Ok(false)
+        };
+        Self::check_lib_hash(lib_hash, &descr)?;
+        if !descr.remove_publisher(publisher)? {
+            return Ok(false)
+        }
+        if descr.publishers().is_empty() {
+            self.remove(lib_hash)?;
+        } else {
+            self.set(lib_hash, &descr)?;
+        }
+        Ok(true)
+    }
+
+    /// Checks that every library is stored by its hash and has publishers
+    pub fn validate(&self) -> Result<()> {
+        self.iterate_with_keys(|lib_hash: UInt256, descr| {
+            Self::check_lib_hash(&lib_hash, &descr)?;
+            if descr.publishers().is_empty() {
+                fail!(BlockError::InvalidData(format!("library {:x} has no publishers", lib_hash)))
+            }
+            Ok(true)
+        })?;
+        Ok(())
+    }
+
+    fn check_lib_hash(lib_hash: &UInt256, descr: &LibDescr) -> Result<()> {
+        if descr.lib().repr_hash() != *lib_hash {
+            fail!(BlockError::InvalidData(format!(
+                "library {:x} is stored with key {:x}", descr.lib().repr_hash(), lib_hash
+            )))
+        }
+        Ok(())
+    }
+}
+
 ///
 /// Struct ShardStateUnsplit
 ///
@@ -853,6 +963,11 @@ impl ShardStateUnsplit {
         format!("shard: {}, seq_no: {}", self.shard(), self.seq_no)
     }
 
+    /// Serialization options the state was constructed or read with
+    pub fn serde_opts(&self) -> u8 {
+        self.out_msg_queues_info.serde_opts()
+    }
+
     pub fn global_id(&self) -> i32 {
         self.global_id
     }
@@ -980,6 +1095,90 @@ impl ShardStateUnsplit {
         self.write_accounts(&accounts)
     }
 
+    /// Inserts or replaces the account adjusting `total_balance` by the difference
+    /// of balances. Returns previous value of the account.
+    pub fn update_account(&mut self, account_id: &UInt256, acc: &ShardAccount) -> Result<Option<ShardAccount>> {
+        let mut accounts = self.read_accounts()?;
+        let prev = accounts.get_serialized_with_aug(SliceData::from(account_id))?;
+        let aug = acc.aug()?;
+        let mut total_balance = self.total_balance.clone();
+        total_balance.add(aug.balance())?;
+        if let Some((_, prev_aug)) = &prev {
+            Self::sub_total(&mut total_balance, prev_aug.balance(), "total_balance")?;
+        }
+        accounts.set(account_id, acc, &aug)?;
+        self.write_accounts(&accounts)?;
+        self.total_balance = total_balance;
+        Ok(prev.map(|(acc, _)| acc))
+    }
+
+    /// Removes the account subtracting its balance from `total_balance`.
+    /// Returns removed account if it was present.
+    pub fn remove_account(&mut self, account_id: &UInt256) -> Result<Option<ShardAccount>> {
+        let mut accounts = self.read_accounts()?;
+        let key = SliceData::from(account_id);
+        let (prev, prev_aug) = match accounts.get_serialized_with_aug(key.clone())? {
+            Some(prev) => prev,
+            None => return Ok(None)
+        };
+        let mut total_balance = self.total_balance.clone();
+        Self::sub_total(&mut total_balance, prev_aug.balance(), "total_balance")?;
+        accounts.remove(key)?;
+        self.write_accounts(&accounts)?;
+        self.total_balance = total_balance;
+        Ok(Some(prev))
+    }
+
+    pub fn add_validator_fees(&mut self, fees: &CurrencyCollection) -> Result<()> {
+        self.total_validator_fees.add(fees)?;
+        Ok(())
+    }
+
+    pub fn withdraw_validator_fees(&mut self, fees: &CurrencyCollection) -> Result<()> {
+        let mut total_validator_fees = self.total_validator_fees.clone();
+        Self::sub_total(&mut total_validator_fees, fees, "total_validator_fees")?;
+        self.total_validator_fees = total_validator_fees;
+        Ok(())
+    }
+
+    fn sub_total(total: &mut CurrencyCollection, value: &CurrencyCollection, name: &str) -> Result<()> {
+        if !total.sub(value)? {
+            fail!(BlockError::InvalidOperation(format!("{} {} is less than {}", name, total, value)))
+        }
+        Ok(())
+    }
+
+    /// Checks that `total_balance` is equal to the sum of all accounts' balances
+    pub fn verify_totals(&self) -> Result<()> {
+        let accounts = self.read_accounts()?;
+        if accounts.full_balance() != &self.total_balance {
+            fail!(BlockError::InvalidData(format!(
+                "total_balance {} is not equal to the sum of accounts' balances {}",
+                self.total_balance, accounts.full_balance()
+            )))
+        }
+        Ok(())
+    }
+
+    /// Builds Merkle proof which contains path from the state root
+    /// through ShardAccounts to the given account
+    pub fn build_account_proof(&self, account_id: &AccountId) -> Result<MerkleProof> {
+        let opts = self.serde_opts();
+        let state_root = self.serialize_with_opts(opts)?;
+        let usage_tree = UsageTree::with_root(state_root.clone());
+        let state = Self::construct_from_cell_with_opts(usage_tree.root_cell(), opts)?;
+        state
+            .read_accounts()?
+            .get_serialized(account_id.clone())?
+            .ok_or_else(||
+                error!(
+                    BlockError::NotFound(format!("account {:x}", account_id))
+                )
+            )?
+            .read_account()?;
+        MerkleProof::create_by_usage_tree(&state_root, usage_tree)
+    }
+
     pub fn overload_history(&self) -> u64 {
         self.overload_history
     }
@@ -1028,6 +1227,27 @@ impl ShardStateUnsplit {
         &mut self.libraries
     }
 
+    /// Adds public library, only masterchain state can contain them
+    pub fn add_library(&mut self, lib: Cell, publisher: &AccountId) -> Result<bool> {
+        self.check_libraries_allowed()?;
+        self.libraries.add_library(lib, publisher)
+    }
+
+    /// Removes publisher of the public library and the library without publishers
+    pub fn remove_publisher(&mut self, lib_hash: &UInt256, publisher: &AccountId) -> Result<bool> {
+        self.check_libraries_allowed()?;
+        self.libraries.remove_publisher(lib_hash, publisher)
+    }
+
+    fn check_libraries_allowed(&self) -> Result<()> {
+        if !self.shard_id.is_masterchain() {
+            fail!(BlockError::InvalidOperation(format!(
+                "public libraries are allowed only in masterchain, not in {}", self.shard_id
+            )))
+        }
+        Ok(())
+    }
+
     pub fn ref_shard_blocks(&self) -> Option<&RefShardBlocks> {
         self.ref_shard_blocks.as_ref()
     }
@@ -13,14 +13,15 @@
 
 use crate::{
     define_HashmapAugE,
-    accounts::{Account, ShardAccount},
+    accounts::{Account, ShardAccount, StorageUsed},
     dictionary::hashmapaug::{Augmentable, HashmapAugType},
+    error::BlockError,
     types::{CurrencyCollection, Number5},
-    Serializable, Deserializable, Augmentation,
+    Serializable, Deserializable, Augmentation, HashmapType,
     error, fail, Result,
     AccountId, UInt256,
     BuilderData, Cell, IBitstring,
-    SliceData, hm_label, HashmapSubtree,
+    SliceData, hm_label, HashmapSubtree, ShardStateUnsplit,
 };
 use std::fmt;
 
@@ -63,10 +64,171 @@ impl ShardAccounts {
         &self.root_extra().balance
     }
 
+    /// Returns up to `limit` accounts with addresses not less than `from` in key order.
+    /// The next page should be requested from the `next` address of the returned page
+    pub fn iterate_range(&self, from: AccountId, limit: usize) -> Result<ShardAccountsPage> {
+        let mut key = UInt256::construct_from(&mut from.clone())?;
+        let mut accounts = Vec::new();
+        let mut eq = true;
+        while let Some((account_id, shard_account, _)) = self.find_leaf(&key, true, eq, false)? {
+            if accounts.len() == limit {
+                return Ok(ShardAccountsPage { accounts, next: Some(AccountId::from(account_id)) })
+            }
+            accounts.push((AccountId::from(&account_id), shard_account));
+            key = account_id;
+            eq = false;
+        }
+        Ok(ShardAccountsPage { accounts, next: None })
+    }
+
     pub fn split_for(&mut self, split_key: &SliceData) -> Result<&DepthBalanceInfo> {
         *self = self.subtree_with_prefix(split_key, &mut 0)?;
         self.update_root_extra()
     }
+
+    /// Storage stats of the changed accounts sorted by growth (the biggest first),
+    /// accounts with unchanged storage stats are skipped
+    pub fn compare_sizes(&self, new: &ShardAccounts) -> Result<Vec<AccountSizeDelta>> {
+        fn storage_used(shard_account: Option<(ShardAccount, DepthBalanceInfo)>) -> Result<StorageUsed> {
+            Ok(match shard_account {
+                Some((shard_account, _)) => shard_account.read_account()?
+                    .storage_info()
+                    .map(|info| info.used().clone())
+                    .unwrap_or_default(),
+                None => StorageUsed::default()
+            })
+        }
+        let mut deltas = Vec::new();
+        self.scan_diff_with_aug(new, |account_id, old, new| {
+            let old_used = storage_used(old)?;
+            let new_used = storage_used(new)?;
+            if old_used != new_used {
+                deltas.push(AccountSizeDelta { account_id, old_used, new_used });
+            }
+            Ok(true)
+        })?;
+        deltas.sort_by(|a, b| {
+            b.bits_delta().cmp(&a.bits_delta())
+                .then_with(|| b.cells_delta().cmp(&a.cells_delta()))
+                .then_with(|| a.account_id.as_slice().cmp(b.account_id.as_slice()))
+        });
+        Ok(deltas)
+    }
+
+    /// Recomputes DepthBalanceInfo of every account and fork from the leaves and
+    /// compares it with the stored one. Returns the first mismatch in key order,
+    /// subtrees are checked before their forks so the deepest broken node is reported
+    pub fn check_augmentation(&self) -> Result<Option<AugmentationMismatch>> {
+        let root = match self.data() {
+            Some(root) => root,
+            None => {
+                if self.root_extra() != &DepthBalanceInfo::default() {
+                    return Ok(Some(AugmentationMismatch {
+                        key: SliceData::default(),
+                        stored: self.root_extra().clone(),
+                        computed: DepthBalanceInfo::default(),
+                    }))
+                }
+                return Ok(None)
+            }
+        };
+        let computed = match Self::check_node_augmentation(
+            &mut SliceData::load_cell_ref(root)?, BuilderData::default(), self.bit_len(), self.serde_opts()
+        )? {
+            Ok(computed) => computed,
+            Err(mismatch) => return Ok(Some(mismatch))
+        };
+        if self.root_extra() != &computed {
+            return Ok(Some(AugmentationMismatch {
+                key: SliceData::default(),
+                stored: self.root_extra().clone(),
+                computed,
+            }))
+        }
+        Ok(None)
+    }
+
+    fn check_node_augmentation(
+        cursor: &mut SliceData,
+        mut key: BuilderData,
+        bit_len: usize,
+        opts: u8,
+    ) -> Result<std::result::Result<DepthBalanceInfo, AugmentationMismatch>> {
+        let label = cursor.get_label(bit_len)?;
+        key.checked_append_references_and_data(&label)?;
+        let label_length = label.remaining_bits();
+        let computed = if label_length < bit_len {
+            let mut children = Vec::with_capacity(2);
+            for i in 0..2 {
+                let mut child_key = key.clone();
+                child_key.append_bit_bool(i != 0)?;
+                let child = &mut SliceData::load_cell(cursor.checked_drain_reference()?)?;
+                match Self::check_node_augmentation(child, child_key, bit_len - label_length - 1, opts)? {
+                    Ok(aug) => children.push(aug),
+                    Err(mismatch) => return Ok(Err(mismatch))
+                }
+            }
+            let right = children.pop();
+            let mut computed = children.pop().unwrap_or_default();
+            computed.calc(&right.unwrap_or_default())?;
+            computed
+        } else if label_length == bit_len {
+            let mut leaf = cursor.clone();
+            DepthBalanceInfo::construct_from_with_opts(&mut leaf, opts)?;
+            ShardAccount::construct_from_with_opts(&mut leaf, opts)?.aug()?
+        } else {
+            fail!(BlockError::InvalidData("label_length > bit_len".to_string()))
+        };
+        let stored = DepthBalanceInfo::construct_from_with_opts(cursor, opts)?;
+        if stored != computed {
+            return Ok(Err(AugmentationMismatch {
+                key: SliceData::load_builder(key)?,
+                stored,
+                computed,
+            }))
+        }
+        Ok(Ok(computed))
+    }
+}
+
+/// Node of ShardAccounts with the stored augmentation differing from the recomputed one
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AugmentationMismatch {
+    /// Account id for the leaf, key prefix for the fork, empty for the root
+    pub key: SliceData,
+    pub stored: DepthBalanceInfo,
+    pub computed: DepthBalanceInfo,
+}
+
+/// Page of accounts returned by `ShardAccounts::iterate_range`
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ShardAccountsPage {
+    pub accounts: Vec<(AccountId, ShardAccount)>,
+    /// Address to continue from, None if there are no more accounts
+    pub next: Option<AccountId>,
+}
+
+/// Change of the account storage stats between two states,
+/// absent account has empty stats
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct AccountSizeDelta {
+    pub account_id: UInt256,
+    pub old_used: StorageUsed,
+    pub new_used: StorageUsed,
+}
+
+impl AccountSizeDelta {
+    pub fn bits_delta(&self) -> i64 {
+        self.new_used.bits() as i64 - self.old_used.bits() as i64
+    }
+    pub fn cells_delta(&self) -> i64 {
+        self.new_used.cells() as i64 - self.old_used.cells() as i64
+    }
+}
+
+/// Reports growth of the accounts storage between two states of the shard
+pub fn compare_account_sizes(old_state: &ShardStateUnsplit, new_state: &ShardStateUnsplit) -> Result<Vec<AccountSizeDelta>> {
+    old_state.read_accounts()?.compare_sizes(&new_state.read_accounts()?)
 }
 
 impl Augmentation<DepthBalanceInfo> for ShardAccount {
@@ -12,13 +12,14 @@
 */
 
 use crate::{
-    blocks::BlockIdExt, define_HashmapE, error::BlockError, validators::ValidatorBaseInfo,
-    validators::ValidatorDescr, Deserializable, Serializable,
-    fail, BuilderData, Cell, Ed25519KeyOption, HashmapE, HashmapType, IBitstring, KeyOption,
-    Result, SliceData, UInt256,
+    blocks::{Block, BlockIdExt}, config_params::CatchainConfig, define_HashmapE, error::BlockError,
+    validators::ValidatorBaseInfo,
+    validators::{ValidatorDescr, ValidatorSet}, Deserializable, Serializable,
+    crypto_provider, error, fail, BuilderData, Cell, Ed25519KeyOption, HashmapE, HashmapType,
+    IBitstring, KeyOption, Result, SliceData, UInt256,
     ED25519_PUBLIC_KEY_LENGTH, ED25519_SIGNATURE_LENGTH
 };
-use std::{collections::HashMap, str::FromStr, sync::Arc, convert::TryInto};
+use std::{collections::{HashMap, HashSet}, str::FromStr, sync::Arc, convert::TryInto};
 
 /*
 ed25519_signature#5 R:bits256 s:bits256 = CryptoSignature;
@@ -146,6 +147,33 @@ impl CryptoSignaturePair {
     }
 }
 
+/// Source of validator public keys by their short node ids
+pub trait KeyProvider {
+    fn public_key(&self, node_id_short: &UInt256) -> Option<&SigPubKey>;
+}
+
+impl KeyProvider for HashMap<UInt256, SigPubKey> {
+    fn public_key(&self, node_id_short: &UInt256) -> Option<&SigPubKey> {
+        self.get(node_id_short)
+    }
+}
+
+/// Public keys of the validator set by short node ids computed once
+#[derive(Clone, Debug, Default)]
+pub struct ValidatorSetKeys(HashMap<UInt256, SigPubKey>);
+
+impl ValidatorSetKeys {
+    pub fn new(vset: &ValidatorSet) -> Self {
+        Self(vset.list().iter().map(|vd| (vd.compute_node_id_short(), vd.public_key.clone())).collect())
+    }
+}
+
+impl KeyProvider for ValidatorSetKeys {
+    fn public_key(&self, node_id_short: &UInt256) -> Option<&SigPubKey> {
+        self.0.get(node_id_short)
+    }
+}
+
 impl Serializable for CryptoSignaturePair {
     fn write_to(&self, cell: &mut BuilderData) -> Result<()> {
         self.node_id_short.write_to(cell)?;
@@ -196,9 +224,9 @@ impl SigPubKey {
         *self.pub_key().id().data()
     }
 
-    // be careful here - we recreate public key object everytime
+    /// Verifies the signature with the installed crypto provider
     pub fn verify_signature(&self, data: &[u8], signature: &CryptoSignature) -> bool {
-        self.pub_key().verify(data, signature.as_bytes()).is_ok()
+        crypto_provider().ed25519_verify(&self.0, data, signature.as_bytes()).is_ok()
     }
 
     pub fn as_slice(&self) -> &[u8; 32] {
@@ -306,27 +334,37 @@ impl BlockSignaturesPure {
     }
 
     pub fn check_signatures(&self, validators_list: &[ValidatorDescr], data: &[u8]) -> Result<u64> {
-        // Calc validators short ids
-        let mut validators_map = HashMap::new();
-        for vd in validators_list {
-            validators_map.insert(vd.compute_node_id_short(), vd);
-        }
+        check_signatures_weight(&self.signatures.0, validators_list, data)
+    }
+}
 
-        // Check signatures
-        let mut weight = 0;
-        self.signatures()
-            .iterate_slices(|ref mut _key, ref mut slice| {
-                let sign = CryptoSignaturePair::construct_from(slice)?;
-                if let Some(vd) = validators_map.get(&sign.node_id_short) {
-                    if !vd.verify_signature(data, &sign.sign) {
-                        fail!(BlockError::BadSignature)
-                    }
-                    weight += vd.weight;
+/// Verifies signatures of `data` made by validators from the list and returns their weight,
+/// signatures of validators absent in the list are skipped
+pub(crate) fn check_signatures_weight(
+    signatures: &HashmapE,
+    validators_list: &[ValidatorDescr],
+    data: &[u8],
+) -> Result<u64> {
+    // Calc validators short ids
+    let mut validators_map = HashMap::new();
+    for vd in validators_list {
+        validators_map.insert(vd.compute_node_id_short(), vd);
+    }
+
+    // Check signatures
+    let mut weight = 0;
+    signatures
+        .iterate_slices(|ref mut _key, ref mut slice| {
+            let sign = CryptoSignaturePair::construct_from(slice)?;
+            if let Some(vd) = validators_map.get(&sign.node_id_short) {
+                if !vd.verify_signature(data, &sign.sign) {
+                    fail!(BlockError::BadSignature)
                 }
-                Ok(true)
-            })?;
-        Ok(weight)
-    }
+                weight += vd.weight;
+            }
+            Ok(true)
+        })?;
+    Ok(weight)
 }
 
 impl Serializable for BlockSignaturesPure {
@@ -436,6 +474,64 @@ impl BlockProof {
             signatures,
         }
     }
+
+    /// Checks signatures of the block by the validators subset of the block's shard
+    /// and catchain seqno (see `ValidatorSet::calc_subset`), returns signed weight
+    pub fn check_signatures(
+        &self,
+        validator_set: &ValidatorSet,
+        cc_config: &CatchainConfig,
+        min_weight_percent: u8,
+    ) -> Result<u64> {
+        let signatures = self.signatures.as_ref().ok_or_else(|| error!(BlockError::InvalidData(
+            format!("proof of {} has no signatures", self.proof_for)
+        )))?;
+        let (subset, _hash_short) = validator_set.calc_subset_for_shard(
+            self.proof_for.shard(), signatures.validator_info.catchain_seqno, cc_config
+        )?;
+        self.check_signatures_with_list(&subset, min_weight_percent)
+    }
+
+    /// Every signer must be in the list and sign only once, the list must match
+    /// `validator_list_hash_short` of the signatures. Signed weight must be greater
+    /// than 2/3 and not less than `min_weight_percent` of the total weight.
+    pub fn check_signatures_with_list(&self, validators: &[ValidatorDescr], min_weight_percent: u8) -> Result<u64> {
+        let signatures = self.signatures.as_ref().ok_or_else(|| error!(BlockError::InvalidData(
+            format!("proof of {} has no signatures", self.proof_for)
+        )))?;
+        let hash_short = ValidatorSet::calc_subset_hash_short(validators, signatures.validator_info.catchain_seqno)?;
+        if hash_short != signatures.validator_info.validator_list_hash_short {
+            fail!(BlockError::InvalidData(format!(
+                "block {} is signed by other validators list: hash short {:08x} but expected {:08x}",
+                self.proof_for, signatures.validator_info.validator_list_hash_short, hash_short
+            )))
+        }
+        let known: HashSet<_> = validators.iter().map(|vd| vd.compute_node_id_short()).collect();
+        let mut signers = HashSet::new();
+        signatures.pure_signatures.signatures.iterate(|pair| {
+            if !known.contains(&pair.node_id_short) {
+                fail!(BlockError::InvalidData(format!(
+                    "block {} is signed by unknown validator {:x}", self.proof_for, pair.node_id_short
+                )))
+            }
+            if !signers.insert(pair.node_id_short.clone()) {
+                fail!(BlockError::InvalidData(format!(
+                    "validator {:x} signed the block {} more than once", pair.node_id_short, self.proof_for
+                )))
+            }
+            Ok(true)
+        })?;
+        let data = Block::build_data_for_sign(&self.proof_for.root_hash, &self.proof_for.file_hash);
+        let weight = signatures.pure_signatures.check_signatures(validators, &data)?;
+        let total_weight: u64 = validators.iter().map(|vd| vd.weight).sum();
+        if (weight as u128) * 3 <= (total_weight as u128) * 2
+            || (weight as u128) * 100 < (total_weight as u128) * (min_weight_percent as u128) {
+            fail!(BlockError::InvalidData(format!(
+                "block {} has not enough signatures weight {} of {}", self.proof_for, weight, total_weight
+            )))
+        }
+        Ok(weight)
+    }
 }
 
 const BLOCK_PROOF_TAG: u8 = 0xC3;
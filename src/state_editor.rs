@@ -0,0 +1,165 @@
+/*
+* Copyright (C) 2019-2023 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use crate::{
+    accounts::ShardAccount,
+    config_params::ConfigParams,
+    dictionary::hashmapaug::{Augmentation, HashmapAugType},
+    error::BlockError,
+    master::McStateExtra,
+    merkle_update::MerkleUpdate,
+    outbound_messages::OutMsgQueueInfo,
+    shard::ShardStateUnsplit,
+    shard_accounts::ShardAccounts,
+    types::{AddSub, CurrencyCollection},
+    Cell, Deserializable, HashmapRemover, Result, Serializable, SliceData, UInt256, error, fail,
+};
+use std::collections::BTreeMap;
+
+#[cfg(test)]
+#[path = "tests/test_state_editor.rs"]
+mod tests;
+
+/// Editing session of the shard state: changes of accounts, outbound queue
+/// and masterchain extra are kept in memory and written into the state
+/// only once on commit instead of serializing the state after every change
+#[derive(Clone, Debug)]
+pub struct StateEditor {
+    old_root: Cell,
+    state: ShardStateUnsplit,
+    accounts: ShardAccounts,
+    changed_accounts: BTreeMap<UInt256, Option<ShardAccount>>,
+    out_queue_info: Option<OutMsgQueueInfo>,
+    mc_extra: Option<McStateExtra>,
+}
+
+impl StateEditor {
+    pub fn with_root(root: Cell) -> Result<Self> {
+        let state = ShardStateUnsplit::construct_from_cell(root.clone())?;
+        Self::with_state_and_root(state, root)
+    }
+
+    /// State is serialized to get its root for the Merkle update
+    pub fn new(state: ShardStateUnsplit) -> Result<Self> {
+        let root = state.serialize()?;
+        Self::with_state_and_root(state, root)
+    }
+
+    fn with_state_and_root(state: ShardStateUnsplit, old_root: Cell) -> Result<Self> {
+        Ok(Self {
+            accounts: state.read_accounts()?,
+            old_root,
+            state,
+            changed_accounts: BTreeMap::new(),
+            out_queue_info: None,
+            mc_extra: None,
+        })
+    }
+
+    /// State before changes of accounts, queue and masterchain extra
+    pub fn state(&self) -> &ShardStateUnsplit { &self.state }
+    /// Gives access to the fields of the state written directly (seq_no, time etc.)
+    pub fn state_mut(&mut self) -> &mut ShardStateUnsplit { &mut self.state }
+    pub fn old_root(&self) -> &Cell { &self.old_root }
+
+    pub fn is_modified(&self) -> bool {
+        !self.changed_accounts.is_empty() || self.out_queue_info.is_some() || self.mc_extra.is_some()
+    }
+
+    pub fn account(&self, account_id: &UInt256) -> Result<Option<ShardAccount>> {
+        match self.changed_accounts.get(account_id) {
+            Some(account) => Ok(account.clone()),
+            None => self.accounts.get(account_id)
+        }
+    }
+
+    pub fn set_account(&mut self, account_id: UInt256, account: ShardAccount) {
+        self.changed_accounts.insert(account_id, Some(account));
+    }
+
+    pub fn remove_account(&mut self, account_id: UInt256) {
+        self.changed_accounts.insert(account_id, None);
+    }
+
+    pub fn out_msg_queue_info_mut(&mut self) -> Result<&mut OutMsgQueueInfo> {
+        let out_queue_info = match self.out_queue_info.take() {
+            Some(out_queue_info) => out_queue_info,
+            None => self.state.read_out_msg_queue_info()?
+        };
+        Ok(self.out_queue_info.insert(out_queue_info))
+    }
+
+    /// Fails for shardchain states
+    pub fn mc_extra_mut(&mut self) -> Result<&mut McStateExtra> {
+        let mc_extra = match self.mc_extra.take() {
+            Some(mc_extra) => mc_extra,
+            None => self.state.read_custom()?.ok_or_else(|| error!(BlockError::InvalidOperation(
+                format!("state of {} has no McStateExtra", self.state.shard())
+            )))?
+        };
+        Ok(self.mc_extra.insert(mc_extra))
+    }
+
+    pub fn config_mut(&mut self) -> Result<&mut ConfigParams> {
+        Ok(&mut self.mc_extra_mut()?.config)
+    }
+
+    /// Writes all changes into the state adjusting its total balance
+    pub fn commit(mut self) -> Result<(ShardStateUnsplit, Cell)> {
+        if !self.changed_accounts.is_empty() {
+            let mut added = CurrencyCollection::default();
+            let mut removed = CurrencyCollection::default();
+            for (account_id, account) in std::mem::take(&mut self.changed_accounts) {
+                let key = SliceData::from(&account_id);
+                if let Some((_, prev_aug)) = self.accounts.get_serialized_with_aug(key.clone())? {
+                    removed.add(prev_aug.balance())?;
+                }
+                match account {
+                    Some(account) => {
+                        let aug = account.aug()?;
+                        added.add(aug.balance())?;
+                        self.accounts.set(&account_id, &account, &aug)?;
+                    }
+                    None => {
+                        self.accounts.remove(key)?;
+                    }
+                }
+            }
+            let mut total_balance = self.state.total_balance().clone();
+            total_balance.add(&added)?;
+            if !total_balance.sub(&removed)? {
+                fail!(BlockError::InvalidOperation(format!(
+                    "total_balance {} is less than balance {} of changed accounts", total_balance, removed
+                )))
+            }
+            self.state.write_accounts(&self.accounts)?;
+            self.state.set_total_balance(total_balance);
+        }
+        if let Some(out_queue_info) = self.out_queue_info.take() {
+            self.state.write_out_msg_queue_info(&out_queue_info)?;
+        }
+        if let Some(mc_extra) = self.mc_extra.take() {
+            self.state.write_custom(Some(&mc_extra))?;
+        }
+        let root = self.state.serialize()?;
+        Ok((self.state, root))
+    }
+
+    /// Commits changes and builds update from the original state root
+    pub fn commit_with_update(self) -> Result<(ShardStateUnsplit, Cell, MerkleUpdate)> {
+        let old_root = self.old_root.clone();
+        let (state, root) = self.commit()?;
+        let update = MerkleUpdate::create(&old_root, &root)?;
+        Ok((state, root, update))
+    }
+}
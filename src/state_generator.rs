@@ -0,0 +1,215 @@
+/*
+* Copyright (C) 2019-2023 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use crate::{
+    accounts::Account,
+    envelope_message::MsgEnvelope,
+    error::BlockError,
+    messages::{InternalMessageHeader, Message, MsgAddressInt, StateInit},
+    outbound_messages::{OutMsgQueue, OutMsgQueueInfo},
+    shard::{ShardIdent, ShardStateUnsplit},
+    shard_accounts::ShardAccounts,
+    types::{CurrencyCollection, Grams},
+    AccountId, BuilderData, Cell, Result, SliceData, UInt256, fail,
+};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+#[cfg(test)]
+#[path = "tests/test_state_generator.rs"]
+mod tests;
+
+const CELL_DATA_BYTES: usize = 127;
+const MAX_BALANCE: u64 = 1_000_000_000_000_000;
+
+/// Distribution of sizes in bytes of generated cell trees
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SizeDistribution {
+    Fixed(usize),
+    Uniform { min: usize, max: usize },
+}
+
+impl Default for SizeDistribution {
+    fn default() -> Self {
+        SizeDistribution::Fixed(0)
+    }
+}
+
+impl SizeDistribution {
+    fn sample(&self, rng: &mut ChaCha8Rng) -> usize {
+        match *self {
+            SizeDistribution::Fixed(size) => size,
+            SizeDistribution::Uniform { min, max } => rng.gen_range(min..=max),
+        }
+    }
+    fn check(&self) -> Result<()> {
+        if let SizeDistribution::Uniform { min, max } = *self {
+            if min > max {
+                fail!(BlockError::InvalidArg(format!("Wrong size range {}..={}", min, max)))
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Produces synthetic shard states for load testing.
+/// The same parameters and seed always give the same state: ChaCha8 output
+/// doesn't depend on the platform or version of `rand` unlike `StdRng`.
+#[derive(Clone, Debug)]
+pub struct StateGenerator {
+    shard: ShardIdent,
+    seed: u64,
+    accounts: usize,
+    messages: usize,
+    code_size: SizeDistribution,
+    data_size: SizeDistribution,
+    body_size: SizeDistribution,
+    seq_no: u32,
+    gen_utime: u32,
+    gen_lt: u64,
+}
+
+impl StateGenerator {
+    pub fn new(shard: ShardIdent, seed: u64) -> Self {
+        Self {
+            shard,
+            seed,
+            accounts: 0,
+            messages: 0,
+            code_size: SizeDistribution::Fixed(CELL_DATA_BYTES),
+            data_size: SizeDistribution::Fixed(CELL_DATA_BYTES),
+            body_size: SizeDistribution::default(),
+            seq_no: 1,
+            gen_utime: 1_600_000_000,
+            gen_lt: 1_000_000,
+        }
+    }
+    pub fn with_accounts(mut self, accounts: usize) -> Self {
+        self.accounts = accounts;
+        self
+    }
+    /// Messages are sent by generated accounts, so at least one account is needed for them
+    pub fn with_messages(mut self, messages: usize) -> Self {
+        self.messages = messages;
+        self
+    }
+    pub fn with_code_size(mut self, code_size: SizeDistribution) -> Self {
+        self.code_size = code_size;
+        self
+    }
+    pub fn with_data_size(mut self, data_size: SizeDistribution) -> Self {
+        self.data_size = data_size;
+        self
+    }
+    pub fn with_body_size(mut self, body_size: SizeDistribution) -> Self {
+        self.body_size = body_size;
+        self
+    }
+    pub fn with_seq_no(mut self, seq_no: u32) -> Self {
+        self.seq_no = seq_no;
+        self
+    }
+    pub fn with_gen_time(mut self, gen_utime: u32, gen_lt: u64) -> Self {
+        self.gen_utime = gen_utime;
+        self.gen_lt = gen_lt;
+        self
+    }
+
+    pub fn generate(&self) -> Result<ShardStateUnsplit> {
+        self.code_size.check()?;
+        self.data_size.check()?;
+        self.body_size.check()?;
+        if self.messages != 0 && self.accounts == 0 {
+            fail!(BlockError::InvalidArg("Messages can't be generated without accounts".to_string()))
+        }
+        let workchain_id = self.shard.workchain_id();
+        let wc = i8::try_from(workchain_id).map_err(|_| {
+            BlockError::InvalidArg(format!("Workchain {} is not supported", workchain_id))
+        })?;
+        let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
+
+        let mut lt = self.gen_lt.saturating_sub(self.accounts as u64 + self.messages as u64);
+        let mut addresses = Vec::with_capacity(self.accounts);
+        let mut accounts = ShardAccounts::default();
+        for _ in 0..self.accounts {
+            let account_id = self.random_account_id(&mut rng);
+            let mut state_init = StateInit::default();
+            state_init.set_code(random_tree(&mut rng, self.code_size.sample(&mut rng))?);
+            state_init.set_data(random_tree(&mut rng, self.data_size.sample(&mut rng))?);
+            let address = MsgAddressInt::with_standart(None, wc, account_id)?;
+            let balance = CurrencyCollection::with_grams(rng.gen_range(1..MAX_BALANCE));
+            let account = Account::active_by_init_code_hash(
+                address.clone(), balance, self.gen_utime, state_init, false
+            )?;
+            lt += 1;
+            accounts.insert(0, &account, UInt256::from(rng.gen::<[u8; 32]>()), lt)?;
+            addresses.push(address);
+        }
+
+        let mut queue = OutMsgQueue::default();
+        for _ in 0..self.messages {
+            let src = addresses[rng.gen_range(0..addresses.len())].clone();
+            let dst_id = rng.gen::<[u8; 32]>();
+            let dst_prefix = prefix_of(&dst_id);
+            let dst = MsgAddressInt::with_standart(None, wc, AccountId::from(dst_id))?;
+            let value = CurrencyCollection::with_grams(rng.gen_range(1..1_000_000_000));
+            let mut msg = Message::with_int_header(InternalMessageHeader::with_addresses(src, dst, value));
+            lt += 1;
+            msg.set_at_and_lt(self.gen_utime, lt);
+            let body_size = self.body_size.sample(&mut rng);
+            if body_size != 0 {
+                msg.set_body(SliceData::load_cell(random_tree(&mut rng, body_size)?)?);
+            }
+            let env = MsgEnvelope::with_message_and_fee(&msg, Grams::from(rng.gen_range(1..10_000_000u64)))?;
+            queue.insert(workchain_id, dst_prefix, &env, lt)?;
+        }
+
+        let mut state = ShardStateUnsplit::with_ident(self.shard.clone());
+        state.set_seq_no(self.seq_no);
+        state.set_gen_time(self.gen_utime);
+        state.set_gen_lt(self.gen_lt.max(lt));
+        state.set_total_balance(accounts.full_balance().clone());
+        state.write_accounts(&accounts)?;
+        state.write_out_msg_queue_info(&OutMsgQueueInfo::with_params(queue, Default::default(), Default::default()))?;
+        Ok(state)
+    }
+
+    fn random_account_id(&self, rng: &mut ChaCha8Rng) -> AccountId {
+        let mut id = rng.gen::<[u8; 32]>();
+        let len = self.shard.prefix_len() as u32;
+        let mask = !(u64::MAX.checked_shr(len).unwrap_or_default());
+        let head = (prefix_of(&id) & !mask) | (self.shard.shard_prefix_without_tag() & mask);
+        id[..8].copy_from_slice(&head.to_be_bytes());
+        AccountId::from(id)
+    }
+}
+
+fn prefix_of(id: &[u8; 32]) -> u64 {
+    u64::from_be_bytes([id[0], id[1], id[2], id[3], id[4], id[5], id[6], id[7]])
+}
+
+/// Builds tree of cells with `size` bytes of random data
+fn random_tree(rng: &mut ChaCha8Rng, size: usize) -> Result<Cell> {
+    let len = size.min(CELL_DATA_BYTES);
+    let mut data = vec![0u8; len];
+    rng.fill(&mut data[..]);
+    let mut builder = BuilderData::with_raw(data, len * 8)?;
+    let mut rest = size - len;
+    let refs = ((rest + CELL_DATA_BYTES - 1) / CELL_DATA_BYTES).min(4);
+    for i in 0..refs {
+        let part = rest / (refs - i);
+        rest -= part;
+        builder.checked_append_reference(random_tree(rng, part)?)?;
+    }
+    builder.into_cell()
+}
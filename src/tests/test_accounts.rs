@@ -787,3 +787,34 @@ fn test_account_formats() {
     assert!(account1.init_code_hash().is_none());
     assert!(account2.init_code_hash().is_none());
 }
+
+#[test]
+fn test_storage_used_compute_for_account() {
+    // the same subcell is referenced twice and must be counted once
+    let leaf = SliceData::new(vec![0x12, 0x80]).into_cell();
+    let mut code = BuilderData::with_raw(vec![0xFF, 0x80], 9).unwrap();
+    code.checked_append_reference(leaf.clone()).unwrap();
+    code.checked_append_reference(leaf).unwrap();
+    let mut stinit = StateInit::default();
+    stinit.set_code(code.into_cell().unwrap());
+    stinit.library = prepare_library(true);
+
+    let used = StorageUsed::compute_for_state_init(&stinit).unwrap();
+    assert_eq!(used.public_cells(), 1);
+    let expected = StorageUsed::calculate_for_struct(&stinit).unwrap();
+    assert_eq!((used.cells(), used.bits()), (expected.cells(), expected.bits()));
+    assert!(used.cells() < stinit.serialize().unwrap().tree_cell_count());
+
+    let acc_st = AccountStorage::active_by_init_code_hash(0, CurrencyCollection::with_grams(1), stinit, false);
+    let addr = MsgAddressInt::with_standart(None, 0, AccountId::from([1; 32])).unwrap();
+    let mut acc = Account::with_storage(&addr, &StorageInfo::default(), &acc_st);
+    acc.check_storage_used().expect_err("storage stat is not set");
+    acc.update_storage_stat().unwrap();
+    acc.check_storage_used().unwrap();
+    let used = StorageUsed::compute_for_account(&acc).unwrap();
+    assert_eq!(used.public_cells(), 1);
+    assert_eq!(used.cells(), acc.storage_info().unwrap().used().cells());
+
+    assert_eq!(StorageUsed::compute_for_account(&Account::default()).unwrap(), StorageUsed::default());
+    Account::default().check_storage_used().unwrap();
+}
@@ -0,0 +1,54 @@
+/*
+* Copyright (C) 2019-2023 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use super::*;
+use crate::{write_read_and_assert, Deserializable, Serializable};
+use rand::{rngs::StdRng, RngCore, SeedableRng};
+use std::fmt::Debug;
+
+fn check_round_trip<T>(seed: u64)
+where T: for<'a> Arbitrary<'a> + Serializable + Deserializable + Debug + PartialEq {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut data = vec![0u8; 16 * 1024];
+    for _ in 0..50 {
+        rng.fill_bytes(&mut data);
+        let mut u = Unstructured::new(&data);
+        let value = T::arbitrary(&mut u).unwrap();
+        write_read_and_assert(value);
+    }
+}
+
+#[test]
+fn test_arbitrary_round_trip() {
+    check_round_trip::<ShardIdent>(1);
+    check_round_trip::<BlockIdExt>(2);
+    check_round_trip::<CurrencyCollection>(3);
+    check_round_trip::<ShardDescr>(4);
+    check_round_trip::<Message>(5);
+    check_round_trip::<Transaction>(6);
+    check_round_trip::<McBlockExtra>(7);
+}
+
+#[test]
+fn test_arbitrary_shard_ident_is_valid() {
+    let data = [0xA5u8; 1024];
+    let mut u = Unstructured::new(&data);
+    for _ in 0..100 {
+        let shard = ShardIdent::arbitrary(&mut u).unwrap();
+        assert!(shard.prefix_len() <= MAX_SPLIT_DEPTH);
+        assert!(!shard.is_masterchain() || shard.is_full());
+    }
+    // exhausted data gives default-like values instead of failures
+    let mut u = Unstructured::new(&[]);
+    ShardDescr::arbitrary(&mut u).unwrap();
+}
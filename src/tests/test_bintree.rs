@@ -113,4 +113,51 @@ mod test_bintree {
         assert_eq!(tree.find(prepare_key(0b001111, 6)).unwrap(), Some((prepare_key(1, 3), 5)));
         assert_eq!(tree.find(prepare_key(0b000111, 6)).unwrap(), Some((prepare_key(0, 3), 1)));
     }
+
+    #[test]
+    fn test_bintree_split_with_max_depth() {
+        let mut tree = BinTree::with_item(&0u8).unwrap();
+        tree.split_with_max_depth(SliceData::default(), 0, |v| Ok((v, 1))).expect_err("depth 0");
+        assert!(tree.split_with_max_depth(SliceData::default(), 2, |v| Ok((v, 1))).unwrap());
+        assert!(tree.split_with_max_depth(prepare_key(1, 1), 2, |v| Ok((v, 2))).unwrap());
+        tree.split_with_max_depth(prepare_key(1, 2), 2, |v| Ok((v, 3))).expect_err("depth 3");
+        assert!(!tree.split_with_max_depth(prepare_key(1, 1), 2, |v| Ok((v, 4))).unwrap());
+        assert_eq!(tree.get(prepare_key(0b11, 2)).unwrap(), Some(2));
+    }
+
+    #[test]
+    fn test_bintree_from_map() {
+        let shard = |prefix: u64| crate::ShardIdent::with_tagged_prefix(0, prefix).unwrap();
+        let mut items = HashMap::new();
+        items.insert(shard(0x4000_0000_0000_0000), 1u8);
+        items.insert(shard(0xa000_0000_0000_0000), 2u8);
+        items.insert(shard(0xe000_0000_0000_0000), 3u8);
+        let tree = BinTree::from_map(items.clone()).unwrap();
+        assert_eq!(tree.get(prepare_key(0, 1)).unwrap(), Some(1));
+        assert_eq!(tree.get(prepare_key(0b10, 2)).unwrap(), Some(2));
+        assert_eq!(tree.get(prepare_key(0b11, 2)).unwrap(), Some(3));
+
+        let mut expected = BinTree::with_item(&1u8).unwrap();
+        assert!(expected.split(SliceData::default(), |v| Ok((v, 0))).unwrap());
+        assert!(expected.split(prepare_key(1, 1), |_| Ok((2, 3))).unwrap());
+        assert_eq!(tree, expected);
+
+        let mut single = HashMap::new();
+        single.insert(crate::ShardIdent::full(-1), 5u8);
+        assert_eq!(BinTree::from_map(single).unwrap().get(SliceData::default()).unwrap(), Some(5));
+
+        // 11 is missing
+        let mut gap = items.clone();
+        gap.remove(&shard(0xe000_0000_0000_0000));
+        BinTree::from_map(gap).expect_err("not covered");
+        // 1 overlaps with 10 and 11
+        let mut overlap = items.clone();
+        overlap.insert(shard(0xc000_0000_0000_0000), 4);
+        BinTree::from_map(overlap).expect_err("overlapping");
+        // other workchain
+        let mut mixed = items;
+        mixed.insert(crate::ShardIdent::with_tagged_prefix(1, 0x4000_0000_0000_0000).unwrap(), 4);
+        BinTree::from_map(mixed).expect_err("mixed workchains");
+        BinTree::<u8>::from_map(HashMap::new()).expect_err("empty");
+    }
 }
@@ -0,0 +1,124 @@
+/*
+* Copyright (C) 2019-2023 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use super::*;
+use crate::{
+    AccountStatus, ChildCell, CommonMessage, ConfigParamEnum, Deserializable, HashmapType,
+    Message, MerkleProof, ParamLimits, ShardIdent, TrComputePhase, TrComputePhaseVm,
+    TransactionDescr, TransactionDescrOrdinary, VarUInteger7,
+};
+
+fn transaction(account: u8, lt: u64, gas_used: u64) -> Transaction {
+    let mut tr = Transaction::with_address_and_status(AccountId::from([account; 32]), AccountStatus::AccStateActive);
+    tr.set_logical_time(lt);
+    let descr = TransactionDescr::Ordinary(TransactionDescrOrdinary {
+        compute_ph: TrComputePhase::Vm(TrComputePhaseVm {
+            gas_used: VarUInteger7::new(gas_used).unwrap(),
+            ..Default::default()
+        }),
+        ..Default::default()
+    });
+    tr.write_description(&descr).unwrap();
+    tr
+}
+
+fn limits() -> BlockLimits {
+    BlockLimits::with_limits(
+        ParamLimits::with_limits(0, 100_000, 200_000).unwrap(),
+        ParamLimits::with_limits(0, 1000, 2000).unwrap(),
+        ParamLimits::with_limits(0, 100, 200).unwrap(),
+    )
+}
+
+fn block_info() -> BlockInfo {
+    let mut info = BlockInfo::new();
+    info.set_shard(ShardIdent::full(0));
+    info.set_seq_no(1).unwrap();
+    info.set_start_lt(1000);
+    info
+}
+
+#[test]
+fn test_block_builder() {
+    let mut config = ConfigParams::new();
+    config.set_config(ConfigParamEnum::ConfigParam23(limits())).unwrap();
+    let mut builder = BlockBuilder::with_config(42, block_info(), &config).unwrap();
+    assert_eq!(builder.limits(), &limits());
+
+    let tr1 = transaction(1, 1001, 500);
+    builder.add_transaction(&tr1).unwrap();
+    assert_eq!(builder.limits_level(), ParamLimitIndex::Normal);
+    builder.add_transaction(&transaction(1, 1001, 0)).expect_err("lt is not increased");
+    builder.add_transaction(&transaction(2, 999, 0)).expect_err("lt is before block start");
+    builder.add_transaction(&transaction(2, 1002, 1000)).unwrap();
+    assert!(builder.limits_level() >= ParamLimitIndex::Soft);
+    builder.add_transaction(&transaction(3, 1003, 600)).expect_err("gas hard limit");
+    builder.add_transaction(&transaction(3, 1300, 0)).expect_err("lt delta hard limit");
+    builder.add_transaction(&transaction(1, 1005, 0)).unwrap();
+    assert_eq!(builder.usage().gas, 1500);
+    assert_eq!(builder.end_lt(), 1006);
+
+    let msg = Message::with_ext_in_header(Default::default());
+    let in_msg = InMsg::external(
+        ChildCell::with_struct(&CommonMessage::Std(msg)).unwrap(),
+        ChildCell::with_struct(&tr1).unwrap(),
+    );
+    builder.add_in_msg(&in_msg).unwrap();
+
+    builder.clone().build().expect_err("no state update");
+    builder.set_state_update(MerkleUpdate::default());
+    let built = builder.build().unwrap();
+
+    assert_eq!(built.id.root_hash, built.root.repr_hash());
+    assert_eq!(built.id.file_hash, UInt256::calc_file_hash(&built.boc));
+    let block = Block::construct_from_cell(built.root.clone()).unwrap();
+    assert_eq!(block, built.block);
+    assert_eq!(block.global_id(), 42);
+    assert_eq!(block.read_info().unwrap().end_lt(), 1006);
+    let extra = block.read_extra().unwrap();
+    assert_eq!(extra.read_account_blocks().unwrap().len().unwrap(), 2);
+    assert_eq!(extra.read_in_msg_descr().unwrap().len().unwrap(), 1);
+
+    assert!(built.proof.signatures.is_none());
+    let proof = MerkleProof::construct_from_cell(built.proof.root.clone()).unwrap();
+    assert_eq!(proof.hash, built.id.root_hash);
+    let header: Block = proof.virtualize().unwrap();
+    assert_eq!(header.read_info().unwrap().end_lt(), 1006);
+    assert!(header.read_extra().is_err());
+}
+
+#[test]
+fn test_block_builder_mc_extra() {
+    let mut builder = BlockBuilder::with_limits(0, block_info(), limits());
+    builder.set_mc_extra(McBlockExtra::default());
+    builder.set_state_update(MerkleUpdate::default());
+    builder.build().expect_err("shard block with McBlockExtra");
+
+    let mut info = block_info();
+    info.set_shard(ShardIdent::masterchain());
+    info.set_key_block(true);
+    let mut mc_extra = McBlockExtra::default();
+    let mut config = ConfigParams::new();
+    config.set_config(ConfigParamEnum::ConfigParam22(limits())).unwrap();
+    mc_extra.set_config(config.clone());
+    let mut builder = BlockBuilder::with_config(0, info, &config).unwrap();
+    builder.set_mc_extra(mc_extra);
+    builder.set_state_update(MerkleUpdate::default());
+    let built = builder.build().unwrap();
+    assert_eq!(built.block.read_info().unwrap().end_lt(), 1001);
+
+    let proof = MerkleProof::construct_from_cell(built.proof.root).unwrap();
+    let header: Block = proof.virtualize().unwrap();
+    let proved_config = header.read_extra().unwrap().read_custom().unwrap().unwrap().config().cloned().unwrap();
+    assert_eq!(proved_config.block_limits(true).unwrap(), limits());
+}
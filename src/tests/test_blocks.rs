@@ -17,7 +17,7 @@ use std::str::FromStr;
 
 use crate::{
     HashmapAugType, HashmapE,
-    AccountBlock, Message, TickTock, write_read_and_assert,
+    AccountBlock, Message, TickTock, write_read_and_assert, write_read_and_assert_with_opts,
     bintree::BinTreeType, CommonMessage, Transaction,
     types::{AddSub, Grams}, OutMsg, UsageTree,
     AccountId, Cell, read_boc,
@@ -288,6 +288,150 @@ fn test_value_flow() {
     write_read_and_assert(value_flow_without_copyleft);
 }
 
+#[test]
+fn test_value_flow_validate() {
+    let mut value_flow = ValueFlow {
+        from_prev_blk: CurrencyCollection::with_grams(100),
+        imported: CurrencyCollection::with_grams(10),
+        created: CurrencyCollection::with_grams(5),
+        to_next_blk: CurrencyCollection::with_grams(90),
+        exported: CurrencyCollection::with_grams(20),
+        fees_collected: CurrencyCollection::with_grams(5),
+        ..Default::default()
+    };
+    value_flow.from_prev_blk.set_other(7, 50).unwrap();
+    value_flow.to_next_blk.set_other(7, 50).unwrap();
+    value_flow.validate(false).unwrap();
+    value_flow.validate(true).unwrap();
+
+    let mut minted = value_flow.clone();
+    minted.minted = CurrencyCollection::with_grams(3);
+    minted.to_next_blk.grams = 93u64.into();
+    minted.validate(true).unwrap();
+    let err = minted.validate(false).unwrap_err().to_string();
+    assert!(err.contains("minted is 3"), "{}", err);
+    assert!(!err.contains("inflow"), "{}", err);
+
+    let mut unbalanced = value_flow.clone();
+    unbalanced.to_next_blk.set_other(7, 40).unwrap();
+    unbalanced.exported.grams = 21u64.into();
+    let err = unbalanced.validate(true).unwrap_err().to_string();
+    assert!(err.contains(
+        "grams inflow 115 (from_prev_blk 100 + imported 10 + created 5) != \
+        outflow 116 (to_next_blk 90 + exported 21 + fees_collected 5)"
+    ), "{}", err);
+    assert!(err.contains("extra currency 7 inflow 50 (from_prev_blk 50) != outflow 40 (to_next_blk 40)"), "{}", err);
+
+    let mut rewards = value_flow.clone();
+    rewards.copyleft_rewards.set(&AccountId::from([1; 32]), &10u64.into()).unwrap();
+    let err = rewards.validate(true).unwrap_err().to_string();
+    assert!(err.contains("copyleft rewards total 10 exceeds fees_collected 5"), "{}", err);
+
+    let block = Block::construct_from_file("src/tests/data/key_block.boc").unwrap();
+    block.read_value_flow().unwrap().validate(true).unwrap();
+}
+
+fn block_with_descriptors(in_msg_descr: &InMsgDescr, out_msg_descr: &OutMsgDescr, value_flow: ValueFlow) -> Block {
+    let mut extra = BlockExtra::new();
+    extra.write_in_msg_descr(in_msg_descr).unwrap();
+    extra.write_out_msg_descr(out_msg_descr).unwrap();
+    Block::with_params(0, BlockInfo::default(), value_flow, MerkleUpdate::default(), extra).unwrap()
+}
+
+#[test]
+fn test_check_msg_descriptors() {
+    let src = crate::MsgAddressInt::with_standart(None, 0, AccountId::from([1; 32])).unwrap();
+    let dst = crate::MsgAddressInt::with_standart(None, 0, AccountId::from([2; 32])).unwrap();
+    let mut header = crate::InternalMessageHeader::with_addresses(src, dst, CurrencyCollection::with_grams(100));
+    header.fwd_fee = 10.into();
+    let msg = Message::with_int_header(header);
+    let env = MsgEnvelope::with_message_and_fee(&msg, 10.into()).unwrap();
+    let env_cell = ChildCell::with_struct(&env).unwrap();
+    let tr_cell = ChildCell::with_struct(&Transaction::with_address_and_status(
+        AccountId::from([2; 32]), Default::default()
+    )).unwrap();
+
+    let immediate = |fwd_fee: u64| {
+        let in_msg = InMsg::immediate(env_cell.clone(), tr_cell.clone(), fwd_fee.into());
+        let out_msg = OutMsg::immediate(env_cell.clone(), tr_cell.clone(), ChildCell::with_struct(&in_msg).unwrap());
+        let mut in_msg_descr = InMsgDescr::default();
+        in_msg_descr.insert(&in_msg).unwrap();
+        let mut out_msg_descr = OutMsgDescr::default();
+        out_msg_descr.insert(&out_msg).unwrap();
+        (in_msg_descr, out_msg_descr)
+    };
+
+    let (in_msg_descr, out_msg_descr) = immediate(10);
+    block_with_descriptors(&in_msg_descr, &out_msg_descr, ValueFlow::default()).check_msg_descriptors().unwrap();
+
+    let value_flow = ValueFlow { exported: CurrencyCollection::with_grams(5), ..Default::default() };
+    let err = block_with_descriptors(&in_msg_descr, &out_msg_descr, value_flow)
+        .check_msg_descriptors().unwrap_err().to_string();
+    assert!(err.contains("exported value 0 of OutMsgDescr != exported 5 of value flow"), "{}", err);
+
+    let err = block_with_descriptors(&InMsgDescr::default(), &out_msg_descr, ValueFlow::default())
+        .check_msg_descriptors().unwrap_err().to_string();
+    assert!(err.contains("is not imported"), "{}", err);
+
+    let err = block_with_descriptors(&in_msg_descr, &OutMsgDescr::default(), ValueFlow::default())
+        .check_msg_descriptors().unwrap_err().to_string();
+    assert!(err.contains("is not exported"), "{}", err);
+
+    let (in_msg_descr, out_msg_descr) = immediate(7);
+    let err = block_with_descriptors(&in_msg_descr, &out_msg_descr, ValueFlow::default())
+        .check_msg_descriptors().unwrap_err().to_string();
+    assert!(err.contains("fwd fee 7 of reimported message"), "{}", err);
+}
+
+#[test]
+fn test_iterate_transactions_for_account() {
+    let block = Block::construct_from_file("src/tests/data/key_block.boc").unwrap();
+    let account_blocks = block.read_extra().unwrap().read_account_blocks().unwrap();
+    account_blocks.iterate_objects(|account_block| {
+        let mut count = 0;
+        assert!(block.iterate_transactions_for_account(account_block.account_id(), |tr| {
+            assert_eq!(tr.account_id(), account_block.account_id());
+            count += 1;
+            Ok(true)
+        }).unwrap());
+        assert_eq!(count, account_block.transaction_count().unwrap());
+        Ok(true)
+    }).unwrap();
+
+    let missing = AccountId::from([0x5a; 32]);
+    assert!(!account_blocks.contains_key(&UInt256::from([0x5a; 32])).unwrap());
+    assert!(block.iterate_transactions_for_account(&missing, |_| panic!("no transactions")).unwrap());
+}
+
+#[test]
+fn test_extract_key_block_data() {
+    let mut block = Block::construct_from_file("src/tests/data/key_block.boc").unwrap();
+    let data = block.extract_key_block_data().unwrap();
+    let (vset, cc_config) = block.read_cur_validator_set_and_cc_conf().unwrap();
+    let info = block.read_info().unwrap();
+    assert_eq!(data.seq_no, info.seq_no());
+    assert_eq!(data.cur_validators, vset);
+    assert_eq!(data.catchain_config, cc_config);
+    assert_eq!(data.validator_info.catchain_seqno, info.gen_catchain_seqno());
+    assert_eq!(data.next_validators.is_some(), data.config.next_validator_set_present().unwrap());
+
+    let mut extra = block.read_extra().unwrap();
+    let mut mc_extra = extra.read_custom().unwrap().unwrap();
+    let mut config = data.config.clone();
+    config.set_next_validator_set(vset.clone()).unwrap();
+    mc_extra.set_config(config);
+    extra.write_custom(Some(&mc_extra)).unwrap();
+    let mut wrong = block.clone();
+    wrong.write_extra(&extra).unwrap();
+    let err = wrong.extract_key_block_data().unwrap_err().to_string();
+    assert!(err.contains("next validator set of key block"), "{}", err);
+
+    let mut info = info;
+    info.set_key_block(false);
+    block.write_info(&info).unwrap();
+    block.extract_key_block_data().expect_err("not a key block");
+}
+
 
 fn read_file_de_and_serialise(filename: &Path) -> Cell {
     let orig_bytes = read(Path::new(filename)).unwrap_or_else(|_| panic!("Error reading file {:?}", filename));
@@ -508,6 +652,51 @@ fn test_block_id_ext_from_str() {
     assert_eq!(id3, id3.to_string().parse().unwrap());
 }
 
+#[test]
+fn test_block_id_ext_canonical_str() {
+    let id = BlockIdExt::with_params(
+        ShardIdent::with_tagged_prefix(-1, 0x8000000000000000).unwrap(),
+        994703,
+        UInt256::from_str("04da9f61d063d49a5bb4e0c253ed81e1e2a27513e77d630a9aca1e29971fbf4e").unwrap(),
+        UInt256::from_str("ba1059b7a17104b4b44742326076e8394f21c1a1dd21fc1b3737d3ca8d779756").unwrap()
+    );
+    let canonical = "(-1,8000000000000000,994703):\
+        04DA9F61D063D49A5BB4E0C253ED81E1E2A27513E77D630A9ACA1E29971FBF4E:\
+        BA1059B7A17104B4B44742326076E8394F21C1A1DD21FC1B3737D3CA8D779756";
+    assert_eq!(id.to_canonical_string(), canonical);
+    assert_eq!(id.to_short_string(), "(-1,8000000000000000,994703)");
+    // Display keeps the legacy form
+    assert_eq!(id.to_string(), format!("(-1:8000000000000000, 994703, rh {:x}, fh {:x})", id.root_hash, id.file_hash));
+    assert_eq!(canonical.parse::<BlockIdExt>().unwrap(), id);
+    assert_eq!(canonical.to_lowercase().parse::<BlockIdExt>().unwrap(), id);
+    assert_eq!(format!(" ( -1, 8000000000000000 , 994703 ){} ", &canonical[28..]).parse::<BlockIdExt>().unwrap(), id);
+
+    let shard = ShardIdent::with_tagged_prefix(0, 0x1800000000000000).unwrap();
+    assert_eq!(BlockIdExt::parse_short("(0,1800000000000000,1203696)").unwrap(), (shard.clone(), 1203696));
+    let id = BlockIdExt::with_params(shard, 1203696, UInt256::rand(), UInt256::rand());
+    assert_eq!(BlockIdExt::parse_short(&id.to_short_string()).unwrap(), (id.shard().clone(), id.seq_no()));
+    assert_eq!(id.to_canonical_string().parse::<BlockIdExt>().unwrap(), id);
+    assert_eq!(id.to_string().parse::<BlockIdExt>().unwrap(), id);
+
+    let check_error = |s: &str, reason: &str| {
+        let err = s.parse::<BlockIdExt>().expect_err(s).to_string();
+        assert!(err.contains(reason), "{} doesn't contain {}", err, reason);
+    };
+    check_error("", "expected (workchain,shard,seqno)");
+    check_error("-1,8000000000000000,1:00:00", "expected (workchain,shard,seqno)");
+    check_error("(-1,8000000000000000):00:00", "expected 3 comma separated parts");
+    check_error("(x,8000000000000000,1):00:00", "invalid workchain id \"x\"");
+    check_error("(-1,80000000000000000,1):00:00", "invalid shard \"80000000000000000\"");
+    check_error("(-1,0,1):00:00", "invalid shard \"0\"");
+    check_error("(-1,8000000000000000,-1):00:00", "invalid seqno \"-1\"");
+    check_error("(-1,8000000000000000,1)", "expected :root_hash:file_hash");
+    check_error("(-1,8000000000000000,1):00", "expected root_hash:file_hash");
+    check_error(&format!("(-1,8000000000000000,1):{}:00", "0".repeat(64)), "invalid file hash \"00\"");
+    check_error(&format!("(-1,8000000000000000,1):xx:{}", "0".repeat(64)), "invalid root hash \"xx\"");
+    check_error("(0:1800000000000000, 1203696, rh 00)", "expected 4 comma separated parts");
+    BlockIdExt::parse_short("(0,8000000000000000,1):").expect_err("tail after short id");
+}
+
 
 #[test]
 fn calc_value_flow() {
@@ -614,6 +803,48 @@ fn test_copyleft_rewards() {
     assert_eq!(copyleft_rewards.get(&AccountId::from([5; 32])).unwrap().unwrap(), 1200);
 }
 
+#[test]
+fn test_copyleft_rewards_payout() {
+    let mut copyleft_rewards = CopyleftRewards::default();
+    for (index, reward) in [(1u8, 300u64), (2, 100), (3, 300), (4, 50)] {
+        copyleft_rewards.add_reward(&AccountId::from([index; 32]), &reward.into()).unwrap();
+    }
+    copyleft_rewards.add_reward(&AccountId::from([4; 32]), &400.into()).unwrap();
+    assert_eq!(copyleft_rewards.reward_of(&AccountId::from([4; 32])).unwrap(), 450);
+    assert_eq!(copyleft_rewards.reward_of(&AccountId::from([9; 32])).unwrap(), 0);
+
+    let mut sorted = Vec::new();
+    copyleft_rewards.iterate_sorted_by_amount(|address, value| {
+        sorted.push((address, value));
+        Ok(true)
+    }).unwrap();
+    assert_eq!(sorted, vec![
+        (AccountId::from([4; 32]), Grams::from(450)),
+        (AccountId::from([1; 32]), Grams::from(300)),
+        (AccountId::from([3; 32]), Grams::from(300)),
+        (AccountId::from([2; 32]), Grams::from(100)),
+    ]);
+
+    let paid = copyleft_rewards.split_above_threshold(&300.into()).unwrap();
+    assert_eq!(paid, vec![
+        (AccountId::from([1; 32]), Grams::from(300)),
+        (AccountId::from([3; 32]), Grams::from(300)),
+        (AccountId::from([4; 32]), Grams::from(450)),
+    ]);
+    assert_eq!(copyleft_rewards.len().unwrap(), 1);
+    assert_eq!(copyleft_rewards.reward_of(&AccountId::from([2; 32])).unwrap(), 100);
+
+    // same result as paying out rewards while merging
+    let mut merged = CopyleftRewards::default();
+    let mut other = CopyleftRewards::default();
+    other.add_reward(&AccountId::from([4; 32]), &450.into()).unwrap();
+    other.add_reward(&AccountId::from([2; 32]), &100.into()).unwrap();
+    assert_eq!(merged.merge_rewards_with_threshold(&other, &300.into()).unwrap(), vec![
+        (AccountId::from([4; 32]), Grams::from(450))
+    ]);
+    assert_eq!(merged, copyleft_rewards);
+}
+
 #[test]
 fn test_copyleft_rewards_merge() {
     let mut copyleft_rewards = CopyleftRewards::default();
@@ -827,6 +1058,102 @@ fn test_block_with_common_message() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_serde_context_survives_block_roundtrip() -> Result<()> {
+    for opts in [SERDE_OPTS_EMPTY, SERDE_OPTS_COMMON_MESSAGE] {
+        let block = create_test_block(opts);
+        assert_eq!(block.serde_opts(), opts);
+        let cell = block.serialize_with_opts(opts)?;
+        let block2 = Block::construct_from_cell(cell.clone())?;
+        assert_eq!(block2.serde_opts(), opts);
+        let extra = block2.read_extra()?;
+        assert_eq!(extra.serde_opts(), opts);
+        assert_eq!(extra.read_in_msg_descr()?.serde_opts(), opts);
+        assert_eq!(extra.read_out_msg_descr()?.serde_opts(), opts);
+        let account_blocks = extra.read_account_blocks()?;
+        account_blocks.iterate_objects(|account_block| {
+            account_block.transaction_iterate(|tr| {
+                assert_eq!(tr.serde_opts(), opts);
+                Ok(true)
+            })?;
+            Ok(true)
+        })?;
+        // re-serialization gives the same cell
+        assert_eq!(block2.serialize_with_opts(block2.serde_opts())?, cell);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_serde_context_nested_descriptors() -> Result<()> {
+    let opts = SERDE_OPTS_COMMON_MESSAGE;
+    let ctx = SerdeContext::with_opts(opts);
+    let msg = CommonMessage::Std(Message::default());
+    let env = ctx.child_with_struct(&MsgEnvelope::with_common_msg_support(&msg, 10.into())?)?;
+    let tr = ctx.child_with_struct(&Transaction::with_common_msg_support(AccountId::from([1; 32])))?;
+    let mut in_msg_descr = InMsgDescr::with_serde_opts(opts);
+    in_msg_descr.insert(&InMsg::final_msg(env.clone(), tr.clone(), 10.into()))?;
+    let mut out_msg_descr = OutMsgDescr::with_serde_opts(opts);
+    out_msg_descr.insert(&OutMsg::new(env, tr))?;
+
+    let mut extra = BlockExtra::with_serde_context(ctx);
+    extra.write_in_msg_descr(&in_msg_descr)?;
+    extra.write_out_msg_descr(&out_msg_descr)?;
+    let block = Block::with_serde_context(
+        ctx, 1, &BlockInfo::default(), &ValueFlow::default(), &MerkleUpdate::default(), None, &extra
+    )?;
+    let block = Block::construct_from_cell(block.serialize_with_opts(opts)?)?;
+    let extra = block.read_extra()?;
+    let in_msg_descr = extra.read_in_msg_descr()?;
+    assert_eq!(in_msg_descr.serde_opts(), opts);
+    assert_eq!(in_msg_descr.len()?, 1);
+    in_msg_descr.iterate_objects(|in_msg| {
+        assert_eq!(in_msg.read_transaction()?.unwrap().serde_opts(), opts);
+        assert_eq!(in_msg.read_in_msg_envelope()?.unwrap().read_common_message()?, msg);
+        Ok(true)
+    })?;
+    let out_msg_descr = extra.read_out_msg_descr()?;
+    assert_eq!(out_msg_descr.serde_opts(), opts);
+    out_msg_descr.iterate_objects(|out_msg| {
+        assert_eq!(out_msg.read_out_message()?.unwrap().read_common_message()?, msg);
+        Ok(true)
+    })?;
+    // not filled descriptor has options of the extra too
+    assert_eq!(extra.read_account_blocks()?.serde_opts(), opts);
+
+    // filled descriptor without options can't be put into common message block
+    let mut extra = BlockExtra::new();
+    extra.write_in_msg_descr(&InMsgDescr::default())?;
+    let err = Block::with_common_msg_support(
+        1, &BlockInfo::default(), &ValueFlow::default(), &MerkleUpdate::default(), None, &extra
+    ).unwrap_err();
+    assert!(matches!(err.downcast_ref().unwrap(), &BlockError::MismatchedSerdeOptions(_, _, _)));
+    Ok(())
+}
+
+#[test]
+fn test_serde_context_mismatch() {
+    let ctx = SerdeContext::common_message();
+    let err = Block::with_serde_context(
+        ctx, 1, &BlockInfo::default(), &ValueFlow::default(), &MerkleUpdate::default(), None, &BlockExtra::new()
+    ).unwrap_err();
+    assert!(matches!(err.downcast_ref().unwrap(), &BlockError::MismatchedSerdeOptions(_, _, _)));
+
+    // not filled descriptors of default extra get options of the block
+    let block = Block::with_common_msg_support(
+        1, &BlockInfo::default(), &ValueFlow::default(), &MerkleUpdate::default(), None, &BlockExtra::new()
+    ).unwrap();
+    assert_eq!(block.read_extra().unwrap().read_in_msg_descr().unwrap().serde_opts(), ctx.opts());
+    let extra = BlockExtra::with_serde_context(ctx);
+    let err = Block::with_params(
+        1, BlockInfo::default(), ValueFlow::default(), MerkleUpdate::default(), extra.clone()
+    ).unwrap_err();
+    assert!(matches!(err.downcast_ref().unwrap(), &BlockError::MismatchedSerdeOptions(_, _, _)));
+    let extra = write_read_and_assert_with_opts(extra, ctx.opts()).unwrap();
+    assert_eq!(extra.serde_opts(), ctx.opts());
+    assert_eq!(ctx.child::<Transaction>().serde_context(), ctx);
+}
+
 #[test]
 fn test_block_queue_updates_serde() {
     let mut block = create_test_block(SERDE_OPTS_COMMON_MESSAGE);
@@ -836,6 +1163,31 @@ fn test_block_queue_updates_serde() {
     assert_eq!(block2.out_msg_queue_updates, None);
 }
 
+#[test]
+fn test_out_queue_update_for_wc() {
+    let old_root = read_single_root_boc(std::fs::read("src/tests/data/shard_state.boc").unwrap()).unwrap();
+    let mut state = crate::ShardStateUnsplit::construct_from_cell(old_root.clone()).unwrap();
+    state.set_seq_no(state.seq_no() + 1);
+    let new_root = state.serialize().unwrap();
+
+    // all cells of the old state are visited
+    let usage_tree = UsageTree::with_root(old_root.clone());
+    let _ = format!("{:#.100}", usage_tree.root_cell());
+    let update = OutQueueUpdate::with_states(&old_root, &usage_tree, &new_root, 0).unwrap();
+    assert!(update.is_empty);
+    let old_proof = OutMsgQueueInfo::prepare_proof_for_wc(&old_root, 0).unwrap().serialize().unwrap();
+    let new_proof = OutMsgQueueInfo::prepare_proof_for_wc(&new_root, 0).unwrap().serialize().unwrap();
+    assert_eq!(update.update.apply_for(&old_proof).unwrap().repr_hash(), new_proof.repr_hash());
+
+    let mut block = create_test_block(SERDE_OPTS_EMPTY);
+    block.out_msg_queue_updates = None;
+    assert_eq!(block.read_out_queue_update_for(0).unwrap(), None);
+    block.write_out_queue_update_for(0, &update).unwrap();
+    let block = Block::construct_from_cell(block.serialize().unwrap()).unwrap();
+    assert_eq!(block.read_out_queue_update_for(0).unwrap(), Some(update));
+    assert_eq!(block.read_out_queue_update_for(1).unwrap(), None);
+}
+
 
 fn create_block_proof() -> MerkleProof {
     let block_root = read_single_root_boc(std::fs::read("src/tests/data/key_block.boc").unwrap()).unwrap();
@@ -873,4 +1225,61 @@ fn test_mesh_update_serde() {
     let cell = mesh_update.serialize().unwrap();
     let mesh_update2 = MeshUpdate::construct_from_cell(cell).unwrap();
     assert_eq!(mesh_update, mesh_update2);
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_build_transaction_proof() {
+    let root = read_single_root_boc(
+        read("src/tests/data/3F7B3F53F9F0684E29D67B813E2197689FE725A77491BD50A5438EA66D4341E0.boc").unwrap()
+    ).unwrap();
+    let block = Block::construct_from_cell(root.clone()).unwrap();
+
+    let mut transactions = vec!();
+    block.read_extra().unwrap().read_account_blocks().unwrap().iterate_objects(|account_block| {
+        account_block.transaction_iterate(|tr| {
+            transactions.push(tr);
+            Ok(true)
+        })?;
+        Ok(true)
+    }).unwrap();
+    assert!(!transactions.is_empty());
+
+    for tr in transactions {
+        let proof = block.build_transaction_proof(tr.account_id(), tr.logical_time()).unwrap();
+        assert_eq!(proof.hash, root.repr_hash());
+        crate::check_transaction_proof(&proof, &tr, &root.repr_hash()).unwrap();
+    }
+
+    let account = AccountId::from([0xAA; 32]);
+    assert!(block.build_transaction_proof(&account, 1).is_err());
+}
+
+#[test]
+fn test_lazy_block() {
+    for opts in [SERDE_OPTS_EMPTY, SERDE_OPTS_COMMON_MESSAGE] {
+        let cell = create_test_block(opts).serialize_with_opts(opts).unwrap();
+        let block = Block::construct_from_cell(cell.clone()).unwrap();
+        let lazy = LazyBlock::with_cell(cell.clone()).unwrap();
+        assert_eq!(lazy.hash(), cell.repr_hash());
+        assert_eq!(lazy.global_id(), block.global_id());
+        assert_eq!(lazy.serde_opts(), opts);
+        assert_eq!(lazy.read_info().unwrap(), &block.read_info().unwrap());
+        // memoized
+        assert!(std::ptr::eq(lazy.read_info().unwrap(), lazy.read_info().unwrap()));
+        assert_eq!(lazy.read_value_flow().unwrap(), &block.read_value_flow().unwrap());
+        assert_eq!(lazy.read_state_update().unwrap(), &block.read_state_update().unwrap());
+        assert_eq!(lazy.read_out_msg_queue_updates().unwrap(), block.out_msg_queue_updates.as_ref());
+        assert_eq!(lazy.read_extra().unwrap(), &block.read_extra().unwrap());
+        assert_eq!(lazy.to_block().unwrap(), block);
+    }
+
+    let root = read_single_root_boc(
+        read("src/tests/data/3F7B3F53F9F0684E29D67B813E2197689FE725A77491BD50A5438EA66D4341E0.boc").unwrap()
+    ).unwrap();
+    let block = Block::construct_from_cell(root.clone()).unwrap();
+    let lazy = LazyBlock::with_cell(root).unwrap();
+    assert_eq!(lazy.read_info().unwrap().seq_no(), block.read_info().unwrap().seq_no());
+    assert_eq!(lazy.read_out_msg_queue_updates().unwrap(), None);
+
+    assert!(LazyBlock::with_cell(BlockInfo::default().serialize().unwrap()).is_err());
+}
@@ -695,6 +695,10 @@ fn test_bad_boc(boc: Vec<u8>) {
         Ok(_) => panic!("BocReader::new().read must panic"),
         Err(e) => println!("{:?}", e),
     }
+    match BocReader::new().read_stream(&mut ChunkedReader::new(&boc, 3)) {
+        Ok(_) => panic!("BocReader::new().read_stream must panic"),
+        Err(e) => println!("{:?}", e),
+    }
     match BocReader::new().read_inmem(Arc::new(boc)) {
         Ok(_) => panic!("BocReader::new().read_inmem must panic"),
         Err(e) => println!("{:?}", e),
@@ -804,4 +808,147 @@ fn test_bad_boc_12() {
     let elapsed = d1.elapsed().as_nanos();
     println!("Parse: {}nanos,", elapsed);
     assert!(elapsed < 1_000_000);
-}
\ No newline at end of file
+}
+
+/// Non-seekable reader which returns data by small chunks
+struct ChunkedReader<'a> {
+    data: &'a [u8],
+    chunk: usize,
+}
+
+impl<'a> ChunkedReader<'a> {
+    fn new(data: &'a [u8], chunk: usize) -> Self {
+        Self { data, chunk }
+    }
+}
+
+impl<'a> Read for ChunkedReader<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let len = self.chunk.min(buf.len()).min(self.data.len());
+        buf[..len].copy_from_slice(&self.data[..len]);
+        self.data = &self.data[len..];
+        Ok(len)
+    }
+}
+
+#[test]
+fn test_read_stream() -> Result<()> {
+    let data = read(Path::new("src/tests/data/7992DD77CEB677577A7D5A8B6F388CDA76B4D0DDE16FF5004C87215E6ADF84DD.boc"))?;
+    let root = read_single_root_boc(&data)?;
+
+    let stream_root = BocReader::new().read_stream(&mut ChunkedReader::new(&data, 7))?.withdraw_single_root()?;
+    assert_eq!(root, stream_root);
+
+    // with crc and index
+    let mut bytes = Vec::new();
+    let file_hash = BocWriter::with_root(&root)?.write_ex_with_file_hash(&mut bytes, true, true, None, None)?;
+    assert_eq!(file_hash, UInt256::calc_file_hash(&bytes));
+    let stream_root = BocReader::new()
+        .set_expected_file_hash(file_hash.clone())
+        .read_stream(&mut ChunkedReader::new(&bytes, 1000))?
+        .withdraw_single_root()?;
+    assert_eq!(root, stream_root);
+    BocReader::new().set_expected_file_hash(file_hash.clone()).read(&mut Cursor::new(&bytes))?;
+    BocReader::new().set_expected_file_hash(file_hash).read_inmem(Arc::new(bytes.clone()))?;
+
+    // wrong file hash
+    let wrong_hash = UInt256::from([1; 32]);
+    assert!(BocReader::new()
+        .set_expected_file_hash(wrong_hash.clone())
+        .read_stream(&mut ChunkedReader::new(&bytes, 1000))
+        .is_err());
+    assert!(BocReader::new().set_expected_file_hash(wrong_hash).read_inmem(Arc::new(bytes.clone())).is_err());
+
+    // broken crc
+    let len = bytes.len();
+    bytes[len - 1] ^= 1;
+    assert!(BocReader::new().read_stream(&mut ChunkedReader::new(&bytes, 1000)).is_err());
+
+    // truncated data
+    assert!(BocReader::new().read_stream(&mut ChunkedReader::new(&bytes[..len / 2], 1000)).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_boc_index() -> Result<()> {
+    let data = read(Path::new("src/tests/data/7992DD77CEB677577A7D5A8B6F388CDA76B4D0DDE16FF5004C87215E6ADF84DD.boc"))?;
+    let root = read_single_root_boc(&data)?;
+
+    let mut indexed = Vec::new();
+    BocWriter::with_root(&root)?.write_ex(&mut indexed, true, true, None, None)?;
+
+    for data in [data, indexed] {
+        let index = BocIndex::with_data(Arc::new(data))?;
+        assert_eq!(index.cells_count(), index.header().cells_count);
+        let first_ref = index.load_cell_by_index(0)?.reference(0)?;
+        assert_eq!(first_ref, root.reference(0)?);
+        assert_eq!(index.root_hashes()?, vec![root.repr_hash()]);
+
+        let mut cells = vec![root.clone()];
+        let mut checked = 0;
+        while let Some(cell) = cells.pop() {
+            assert!(index.contains(&cell.repr_hash())?);
+            let loaded = index.load_cell(&cell.repr_hash())?;
+            assert_eq!(loaded, cell);
+            assert_eq!(loaded.references_count(), cell.references_count());
+            checked += 1;
+            if checked < 20 {
+                cells.extend(cell.clone_references());
+            }
+        }
+        assert_eq!(index.load_roots()?, vec![root.clone()]);
+
+        assert!(!index.contains(&UInt256::default())?);
+        assert!(index.load_cell_by_index(index.cells_count() as u32).is_err());
+        assert!(index.load_cell(&UInt256::default()).is_err());
+    }
+    Ok(())
+}
+
+#[cfg(feature = "compression")]
+#[test]
+fn test_compressed_boc() -> Result<()> {
+    let data = read(Path::new("src/tests/data/7992DD77CEB677577A7D5A8B6F388CDA76B4D0DDE16FF5004C87215E6ADF84DD.boc"))?;
+    let root = read_single_root_boc(&data)?;
+
+    let compressed = write_boc_compressed(&root, 3)?;
+    assert!(is_compressed_boc(&compressed));
+    assert!(!is_compressed_boc(&data));
+    assert!(compressed.len() < data.len());
+    assert_eq!(read_single_root_boc(&compressed)?, root);
+
+    // file hash of the uncompressed BOC is checked
+    let mut bad = compressed.clone();
+    bad[4] ^= 1;
+    assert!(read_boc(&bad).is_err());
+
+    // truncated frame
+    assert!(read_boc(&compressed[..compressed.len() / 2]).is_err());
+    Ok(())
+}
+
+#[cfg(feature = "mmap")]
+#[test]
+fn test_mapped_boc() -> Result<()> {
+    let path = Path::new("src/tests/data/7992DD77CEB677577A7D5A8B6F388CDA76B4D0DDE16FF5004C87215E6ADF84DD.boc");
+    let data = read(path)?;
+    let expected = read_single_root_boc(&data)?;
+
+    // the file is a test fixture and is not modified while mapped
+    let mmap = unsafe { map_boc_file(path)? };
+    let root = read_mapped_boc(mmap.clone())?.withdraw_single_root()?;
+    assert_eq!(root, expected);
+    assert_eq!(root.tree_cell_count(), expected.tree_cell_count());
+
+    // cells keep the map alive
+    drop(mmap);
+    assert_eq!(root.reference(0)?, expected.reference(0)?);
+    assert_eq!(write_boc(&root)?, write_boc(&expected)?);
+
+    let mmap = unsafe { map_boc_file(path)? };
+    let result = BocReader::new()
+        .set_expected_file_hash(UInt256::default())
+        .read_mapped(mmap);
+    assert!(result.is_err());
+    Ok(())
+}
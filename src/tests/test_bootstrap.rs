@@ -0,0 +1,163 @@
+/*
+* Copyright (C) 2019-2023 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use super::*;
+use crate::{
+    write_boc, BlockExtra, BlockInfo, BlockSignatures, BlockSignaturesPure, CatchainConfig, Cell,
+    ConfigParam34, ConfigParamEnum, CryptoSignature, CryptoSignaturePair, Ed25519KeyOption,
+    KeyOption, McBlockExtra, McStateExtra, MerkleUpdate, Serializable, ShardIdent, SigPubKey,
+    ValidatorBaseInfo, ValidatorDescr, ValidatorSet, ValueFlow,
+};
+use std::sync::Arc;
+
+struct Validators {
+    keys: Vec<Arc<dyn KeyOption>>,
+    config: ConfigParams,
+}
+
+impl Validators {
+    fn generate(count: usize) -> Self {
+        let keys: Vec<_> = (0..count).map(|_| Ed25519KeyOption::generate().unwrap()).collect();
+        let list = keys.iter().map(|key| {
+            let key = SigPubKey::from_bytes(key.pub_key().unwrap()).unwrap();
+            ValidatorDescr::with_params(key, 10, None, None)
+        }).collect();
+        let cur_validators = ValidatorSet::new(0, u32::MAX, count as u16, list).unwrap();
+        let mut config = ConfigParams::new();
+        config.set_config(ConfigParamEnum::ConfigParam28(CatchainConfig::default())).unwrap();
+        config.set_config(ConfigParamEnum::ConfigParam34(ConfigParam34 { cur_validators })).unwrap();
+        Self { keys, config }
+    }
+
+    fn sign(&self, id: &BlockIdExt, cc_seqno: u32, count: usize) -> BlockSignatures {
+        let vset = self.config.validator_set().unwrap();
+        let (_, hash_short) = vset.calc_subset(
+            &CatchainConfig::default(), SHARD_FULL, MASTERCHAIN_ID, cc_seqno, 0.into()
+        ).unwrap();
+        let data = Block::build_data_for_sign(&id.root_hash, &id.file_hash);
+        let mut pure = BlockSignaturesPure::with_weight(10 * count as u64);
+        for (key, vd) in self.keys.iter().zip(vset.list()).take(count) {
+            pure.add_sigpair(CryptoSignaturePair::with_params(
+                vd.compute_node_id_short(),
+                CryptoSignature::from_bytes(&key.sign(&data).unwrap()).unwrap(),
+            ));
+        }
+        BlockSignatures::with_params(ValidatorBaseInfo::with_params(hash_short, cc_seqno), pure)
+    }
+}
+
+fn mc_state(seq_no: u32, config: &ConfigParams) -> Cell {
+    let mut state = ShardStateUnsplit::with_ident(ShardIdent::masterchain());
+    state.set_seq_no(seq_no);
+    let extra = McStateExtra { config: config.clone(), ..Default::default() };
+    state.write_custom(Some(&extra)).unwrap();
+    state.serialize().unwrap()
+}
+
+/// Makes block with proof signed by `signers`, key block contains `new_config`
+fn mc_block(
+    seq_no: u32,
+    prev_key_block_seqno: u32,
+    signers: &Validators,
+    new_config: Option<&ConfigParams>,
+    state_update: MerkleUpdate,
+) -> BlockProof {
+    let cc_seqno = seq_no;
+    let (_, hash_short) = signers.config.validator_set().unwrap().calc_subset(
+        &CatchainConfig::default(), SHARD_FULL, MASTERCHAIN_ID, cc_seqno, 0.into()
+    ).unwrap();
+    let mut info = BlockInfo::new();
+    info.set_shard(ShardIdent::masterchain());
+    info.set_seq_no(seq_no).unwrap();
+    info.set_key_block(new_config.is_some());
+    info.set_prev_key_block_seqno(prev_key_block_seqno);
+    info.set_gen_catchain_seqno(cc_seqno);
+    info.set_gen_validator_list_hash_short(hash_short);
+    let mut mc_extra = McBlockExtra::default();
+    if let Some(config) = new_config {
+        mc_extra.set_config(config.clone());
+    }
+    let mut extra = BlockExtra::new();
+    extra.write_custom(Some(&mc_extra)).unwrap();
+    let block = Block::with_params(0, info, ValueFlow::default(), state_update, extra).unwrap();
+    let root = block.serialize().unwrap();
+    let id = BlockIdExt::with_params(
+        ShardIdent::masterchain(),
+        seq_no,
+        root.repr_hash(),
+        UInt256::calc_file_hash(&write_boc(&root).unwrap()),
+    );
+    let proof = MerkleProof::create(&root, |_| true).unwrap();
+    let signatures = signers.sign(&id, cc_seqno, signers.keys.len());
+    BlockProof::with_params(id, proof.serialize().unwrap(), Some(signatures))
+}
+
+#[test]
+fn test_trusted_state_bootstrap() {
+    let validators0 = Validators::generate(3);
+    let validators1 = Validators::generate(4);
+
+    let zerostate = mc_state(0, &validators0.config);
+    let zerostate_boc = write_boc(&zerostate).unwrap();
+    let zerostate_id = BlockIdExt::with_params(
+        ShardIdent::masterchain(),
+        0,
+        zerostate.repr_hash(),
+        UInt256::calc_file_hash(&zerostate_boc),
+    );
+
+    let mut wrong_id = zerostate_id.clone();
+    wrong_id.file_hash = UInt256::rand();
+    assert!(TrustedStateBootstrap::with_zerostate(wrong_id, &zerostate_boc).is_err());
+
+    let state = mc_state(2, &validators1.config);
+    let state_update = MerkleUpdate::create(&zerostate, &state).unwrap();
+    let key_block = mc_block(1, 0, &validators0, Some(&validators1.config), MerkleUpdate::default());
+    let block = mc_block(2, 1, &validators1, None, state_update);
+    let state_proof = MerkleProof::create(&state, |_| true).unwrap();
+
+    // key block must be signed by validators from zerostate
+    let mut bootstrap = TrustedStateBootstrap::with_zerostate(zerostate_id.clone(), &zerostate_boc).unwrap();
+    let forged_key_block = mc_block(1, 0, &validators1, Some(&validators1.config), MerkleUpdate::default());
+    assert!(bootstrap.add_key_block_proof(&forged_key_block).is_err());
+
+    // not enough signatures
+    let mut weak_key_block = key_block.clone();
+    weak_key_block.signatures = Some(validators0.sign(&key_block.proof_for, 1, 2));
+    assert!(bootstrap.add_key_block_proof(&weak_key_block).is_err());
+
+    // proof of the other block
+    let mut wrong_key_block = key_block.clone();
+    wrong_key_block.proof_for.root_hash = UInt256::rand();
+    assert!(bootstrap.add_key_block_proof(&wrong_key_block).is_err());
+
+    // not a key block
+    assert!(bootstrap.add_key_block_proof(&block).is_err());
+
+    bootstrap.add_key_block_proof(&key_block).unwrap();
+    assert_eq!(bootstrap.last_key_block_id(), &key_block.proof_for);
+    assert_eq!(bootstrap.config(), &validators1.config);
+    assert!(bootstrap.add_key_block_proof(&key_block).is_err());
+
+    let wrong_state_proof = MerkleProof::create(&zerostate, |_| true).unwrap();
+    let result = TrustedStateBootstrap::with_zerostate(zerostate_id.clone(), &zerostate_boc)
+        .and_then(|mut bootstrap| {
+            bootstrap.add_key_block_proof(&key_block)?;
+            bootstrap.finish(&block, &wrong_state_proof)
+        });
+    assert!(result.is_err());
+
+    let trusted = bootstrap.finish(&block, &state_proof).unwrap();
+    assert_eq!(trusted.block_id, block.proof_for);
+    assert_eq!(trusted.config, validators1.config);
+}
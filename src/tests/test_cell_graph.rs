@@ -0,0 +1,120 @@
+/*
+* Copyright (C) 2019-2023 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use super::*;
+use crate::{BuilderData, IBitstring, MerkleProof, Serializable};
+
+fn build_tree() -> Cell {
+    let mut leaf = BuilderData::new();
+    leaf.append_u32(1).unwrap();
+    let leaf = leaf.into_cell().unwrap();
+    let mut middle = BuilderData::new();
+    middle.append_u8(2).unwrap();
+    middle.checked_append_reference(leaf.clone()).unwrap();
+    let middle = middle.into_cell().unwrap();
+    let mut root = BuilderData::new();
+    root.checked_append_reference(middle).unwrap();
+    root.checked_append_reference(leaf).unwrap();
+    root.into_cell().unwrap()
+}
+
+fn export(root: &Cell, format: GraphFormat, options: &CellGraphOptions) -> (String, CellGraphStat) {
+    let mut output = Vec::new();
+    let stat = export_cell_graph(root, format, options, &mut output).unwrap();
+    (String::from_utf8(output).unwrap(), stat)
+}
+
+#[test]
+fn test_export_cell_graph() {
+    let root = build_tree();
+    let leaf_hash = root.reference(1).unwrap().repr_hash();
+
+    // leaf is shared and exported once
+    let (dot, stat) = export(&root, GraphFormat::Dot, &CellGraphOptions::default());
+    assert_eq!(stat, CellGraphStat { nodes: 3, edges: 3, truncated: 0 });
+    assert!(dot.starts_with("digraph cells {"));
+    assert_eq!(dot.matches(&format!("-> \"{:x}\"", leaf_hash)).count(), 2);
+    assert!(dot.contains("32 bits, 0 refs"));
+
+    let (graphml, _) = export(&root, GraphFormat::GraphML, &CellGraphOptions::default());
+    assert_eq!(graphml.matches("<node ").count(), 3);
+    assert_eq!(graphml.matches("<edge ").count(), 3);
+
+    let (jsonl, _) = export(&root, GraphFormat::JsonL, &CellGraphOptions::default());
+    let lines: Vec<serde_json::Value> = jsonl.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+    assert_eq!(lines.len(), 6);
+    assert_eq!(lines[0]["hash"], root.repr_hash().to_hex_string());
+    assert_eq!(lines[0]["refs"], 2);
+    assert!(lines.iter().any(|line| line["type"] == "node" && line["hash"] == leaf_hash.to_hex_string()
+        && line["bits"] == 32 && line["cell_type"] == "ordinary"));
+
+    // limits
+    let options = CellGraphOptions { max_depth: 0, ..Default::default() };
+    let (_, stat) = export(&root, GraphFormat::JsonL, &options);
+    assert_eq!(stat, CellGraphStat { nodes: 1, edges: 0, truncated: 1 });
+
+    let options = CellGraphOptions { max_cells: 2, ..Default::default() };
+    let (_, stat) = export(&root, GraphFormat::JsonL, &options);
+    assert_eq!(stat, CellGraphStat { nodes: 2, edges: 1, truncated: 2 });
+}
+
+#[test]
+fn test_export_cell_graph_edge_indexes() {
+    // the first reference of the middle cell is not exported, the second one is
+    let leaf = build_tree().reference(1).unwrap();
+    let mut other = BuilderData::new();
+    other.append_u8(3).unwrap();
+    let mut middle = BuilderData::new();
+    middle.checked_append_reference(other.into_cell().unwrap()).unwrap();
+    middle.checked_append_reference(leaf.clone()).unwrap();
+    let middle = middle.into_cell().unwrap();
+    let mut root = BuilderData::new();
+    root.checked_append_reference(middle.clone()).unwrap();
+    root.checked_append_reference(leaf.clone()).unwrap();
+    let root = root.into_cell().unwrap();
+
+    let options = CellGraphOptions { max_cells: 3, ..Default::default() };
+    let (jsonl, stat) = export(&root, GraphFormat::JsonL, &options);
+    assert_eq!(stat, CellGraphStat { nodes: 3, edges: 3, truncated: 1 });
+    let edge = jsonl.lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+        .find(|line| line["type"] == "edge" && line["from"] == middle.repr_hash().to_hex_string())
+        .unwrap();
+    assert_eq!(edge["to"], leaf.repr_hash().to_hex_string());
+    assert_eq!(edge["index"], 1);
+
+    let (dot, _) = export(&root, GraphFormat::Dot, &options);
+    assert!(dot.contains(&format!("\"{:x}\" -> \"{:x}\" [label=\"1\"]", middle.repr_hash(), leaf.repr_hash())));
+    let (graphml, _) = export(&root, GraphFormat::GraphML, &options);
+    assert!(graphml.contains(&format!(
+        "<edge source=\"{:x}\" target=\"{:x}\"><data key=\"index\">1</data>", middle.repr_hash(), leaf.repr_hash()
+    )));
+}
+
+#[test]
+fn test_export_cell_graph_special_cells() {
+    let root = build_tree();
+    let leaf_hash = root.reference(1).unwrap().repr_hash();
+    let proof = MerkleProof::create(&root, |hash| hash != &leaf_hash).unwrap();
+    let proof_root = proof.serialize().unwrap();
+
+    let (jsonl, stat) = export(&proof_root, GraphFormat::JsonL, &CellGraphOptions::default());
+    assert_eq!(stat.truncated, 0);
+    assert!(jsonl.contains("\"cell_type\":\"merkle_proof\""));
+    assert!(jsonl.contains("\"cell_type\":\"pruned_branch\""));
+
+    let (dot, _) = export(&proof_root, GraphFormat::Dot, &CellGraphOptions::default());
+    assert!(dot.contains("shape=octagon"));
+    assert!(dot.contains("\\nmerkle_proof\""));
+    assert!(dot.contains("\\npruned_branch\""));
+}
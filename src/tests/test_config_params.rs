@@ -140,6 +140,75 @@ fn test_config_gas_limit_price() {
     }
 }
 
+#[test]
+fn test_gas_limit_prices_fees() {
+    let mut prices = GasLimitsPrices {
+        gas_price: 1000 << 16,
+        gas_limit: 1_000_000,
+        special_gas_limit: 70_000_000,
+        flat_gas_limit: 100,
+        flat_gas_price: 100_000,
+        ..Default::default()
+    };
+    prices.max_gas_threshold = prices.calc_max_gas_threshold();
+    assert_eq!(prices.flat_fee(), Grams::from(100_000));
+    assert_eq!(prices.compute_fee(0).unwrap(), Grams::from(100_000));
+    assert_eq!(prices.compute_fee(100).unwrap(), Grams::from(100_000));
+    assert_eq!(prices.compute_fee(1_100).unwrap(), Grams::from(1_100_000));
+
+    assert_eq!(prices.gas_for_grams(&Grams::from(99_999)), 0);
+    assert_eq!(prices.gas_for_grams(&Grams::from(100_000)), 100);
+    assert_eq!(prices.gas_for_grams(&Grams::from(1_100_999)), 1_100);
+    assert_eq!(prices.gas_for_grams(&Grams::from(u64::MAX)), 1_000_000);
+    for gas in [100, 5_000, 1_000_000] {
+        assert_eq!(prices.gas_for_grams(&prices.compute_fee(gas).unwrap()), gas);
+    }
+    assert_eq!(prices.max_gas(&Grams::from(1_100_000), false), 1_100);
+    assert_eq!(prices.max_gas(&Grams::zero(), true), 70_000_000);
+
+    let mut config = ConfigParams::default();
+    config.set_gas_prices(true, GasLimitsPrices { gas_price: 10_000 << 16, ..prices.clone() }).unwrap();
+    config.set_gas_prices(false, prices).unwrap();
+    assert_eq!(config.compute_gas_fee(true, 1_100).unwrap(), Grams::from(10_100_000));
+    assert_eq!(config.compute_gas_fee(false, 1_100).unwrap(), Grams::from(1_100_000));
+}
+
+#[test]
+fn test_storage_fee_across_epochs() {
+    let mut param = ConfigParam18::default();
+    param.insert(&StoragePrices {
+        utime_since: 100,
+        bit_price_ps: 1 << 16,
+        cell_price_ps: 2 << 16,
+        mc_bit_price_ps: 10 << 16,
+        mc_cell_price_ps: 20 << 16,
+    }).unwrap();
+    param.insert(&StoragePrices {
+        utime_since: 200,
+        bit_price_ps: 2 << 16,
+        cell_price_ps: 4 << 16,
+        mc_bit_price_ps: 20 << 16,
+        mc_cell_price_ps: 40 << 16,
+    }).unwrap();
+    let stats = StorageUsed::with_values_checked(3, 100, 0).unwrap();
+
+    assert_eq!(param.storage_fee(&stats, 120, 180, false).unwrap(), Grams::from(60 * 106));
+    assert_eq!(param.storage_fee(&stats, 150, 250, false).unwrap(), Grams::from(50 * 106 + 50 * 212));
+    assert_eq!(param.storage_fee(&stats, 150, 250, true).unwrap(), Grams::from(50 * 1060 + 50 * 2120));
+    assert_eq!(param.storage_fee(&stats, 50, 250, false).unwrap(), Grams::from(100 * 106 + 50 * 212));
+    assert_eq!(param.storage_fee(&stats, 220, 250, false).unwrap(), Grams::from(30 * 212));
+
+    assert_eq!(param.storage_fee(&stats, 250, 250, false).unwrap(), Grams::zero());
+    assert_eq!(param.storage_fee(&stats, 0, 250, false).unwrap(), Grams::zero());
+    assert_eq!(param.storage_fee(&stats, 50, 100, false).unwrap(), Grams::zero());
+    assert_eq!(ConfigParam18::default().storage_fee(&stats, 150, 250, false).unwrap(), Grams::zero());
+
+    let mut param = ConfigParam18::default();
+    param.insert(&StoragePrices { utime_since: 1, bit_price_ps: 1, ..Default::default() }).unwrap();
+    let stats = StorageUsed::with_values_checked(1, 100, 0).unwrap();
+    assert_eq!(param.storage_fee(&stats, 10, 110, false).unwrap(), Grams::from(1));
+}
+
 
 fn get_msg_forward_prices() -> MsgForwardPrices {
         
@@ -161,6 +230,34 @@ fn test_config_msg_forward_prices() {
     }
 }
 
+#[test]
+fn test_msg_forward_prices_fees() {
+    let prices = MsgForwardPrices {
+        lump_price: 1_000_000,
+        bit_price: 65_536_000,
+        cell_price: 6_553_600_000,
+        ihr_price_factor: 98_304,
+        first_frac: 21_845,
+        next_frac: 21_845,
+    };
+    assert_eq!(prices.fwd_fee(0, 0).unwrap(), Grams::from(1_000_000));
+    assert_eq!(prices.fwd_fee(2, 1_000).unwrap(), Grams::from(1_000_000 + 1_000_000 + 200_000));
+    // fractional part of nanograms is rounded up
+    let prices_with_fraction = MsgForwardPrices { bit_price: 1, ..prices.clone() };
+    assert_eq!(prices_with_fraction.fwd_fee(0, 1).unwrap(), Grams::from(1_000_001));
+    assert_eq!(
+        prices.fwd_fee_for_storage(&crate::StorageUsedShort::with_values_checked(2, 1_000).unwrap()).unwrap(),
+        prices.fwd_fee(2, 1_000).unwrap()
+    );
+
+    let fwd_fee = Grams::from(3_000_000);
+    assert_eq!(prices.ihr_fee(&fwd_fee).unwrap(), Grams::from(4_500_000));
+    let (mine, remaining) = prices.first_frac_split(&fwd_fee).unwrap();
+    assert_eq!(mine, Grams::from(999_984));
+    assert_eq!(remaining, Grams::from(2_000_016));
+    assert_eq!(prices.first_frac_split(&Grams::zero()).unwrap(), (Grams::zero(), Grams::zero()));
+}
+
 fn get_cat_chain_config() -> CatchainConfig {
     let mut rng = rand::thread_rng();
     CatchainConfig {
@@ -877,3 +974,147 @@ fn test_real_ton_config_params() {
     config2.config_params.remove(key).unwrap();
     assert!(!config2.valid_config_data(true, None).unwrap());
 }
+
+#[test]
+fn test_typed_accessors() {
+    let mut cp = ConfigParams::default();
+    assert!(cp.gas_prices(true).is_err());
+    assert!(cp.block_limits(false).is_err());
+    assert!(cp.slashing_config().is_err());
+    assert_eq!(cp.validator_signed_temp_keys().unwrap(), None);
+
+    cp.set_dns_root_address(UInt256::from([4; 32])).unwrap();
+    assert_eq!(cp.dns_root_address().unwrap(), UInt256::from([4; 32]));
+    cp.set_owner_address(UInt256::from([5; 32])).unwrap();
+    assert_eq!(cp.owner_address().unwrap(), UInt256::from([5; 32]));
+    assert_eq!(cp.config(5).unwrap(), Some(ConfigParamEnum::ConfigParam5(ConfigParam5 { owner_addr: UInt256::from([5; 32]) })));
+
+    let critical_params = get_config_param10().critical_params;
+    cp.set_critical_params(critical_params.clone()).unwrap();
+    assert_eq!(cp.critical_params().unwrap(), critical_params);
+    cp.set_config_voting_setup(get_config_param11()).unwrap();
+    assert_eq!(cp.config_voting_setup().unwrap(), get_config_param11());
+    let fees = get_config_param14().block_create_fees;
+    cp.set_block_create_fees(fees.clone()).unwrap();
+    assert_eq!(cp.block_create_fees_config().unwrap(), fees);
+    assert_eq!(cp.block_create_fees(true).unwrap(), fees.masterchain_block_fee);
+
+    let mc_prices = get_gas_limit_prices();
+    let wc_prices = get_gas_limit_prices();
+    cp.set_gas_prices(true, mc_prices.clone()).unwrap();
+    cp.set_gas_prices(false, wc_prices.clone()).unwrap();
+    assert_eq!(cp.gas_prices(true).unwrap(), mc_prices);
+    assert_eq!(cp.gas_prices(false).unwrap(), wc_prices);
+    assert_eq!(cp.config(21).unwrap(), Some(ConfigParamEnum::ConfigParam21(wc_prices)));
+
+    cp.set_block_limits(true, get_block_limits(100)).unwrap();
+    cp.set_block_limits(false, get_block_limits(200)).unwrap();
+    assert_eq!(cp.block_limits(true).unwrap(), get_block_limits(100));
+    assert_eq!(cp.block_limits(false).unwrap(), get_block_limits(200));
+
+    let fwd_prices = get_msg_forward_prices();
+    cp.set_fwd_prices(false, fwd_prices.clone()).unwrap();
+    assert_eq!(cp.fwd_prices(false).unwrap(), fwd_prices);
+    assert!(cp.fwd_prices(true).is_err());
+
+    let catchain_config = get_cat_chain_config();
+    cp.set_catchain_config(catchain_config.clone()).unwrap();
+    assert_eq!(cp.catchain_config().unwrap(), catchain_config);
+    let consensus_config = get_config_param29().consensus_config;
+    cp.set_consensus_config(consensus_config.clone()).unwrap();
+    assert_eq!(cp.consensus_config().unwrap(), consensus_config);
+
+    let vset = get_validator_set();
+    cp.set_validator_set(vset.clone()).unwrap();
+    assert_eq!(cp.validator_set().unwrap(), vset);
+    cp.set_next_validator_set(vset.clone()).unwrap();
+    assert_eq!(cp.next_validator_set().unwrap(), vset);
+    assert!(cp.next_validator_set_present().unwrap());
+
+    let keys = get_config_param_39().validator_keys;
+    cp.set_validator_signed_temp_keys(keys.clone()).unwrap();
+    assert_eq!(cp.validator_signed_temp_keys().unwrap(), Some(keys));
+    let slashing_config = get_config_param40().slashing_config;
+    cp.set_slashing_config(slashing_config.clone()).unwrap();
+    assert_eq!(cp.slashing_config().unwrap(), slashing_config);
+
+    cp.set_suspended_addresses(get_suspended_addresses()).unwrap();
+    assert!(cp.suspended_addresses().unwrap().is_some());
+    let mesh_config = get_mesh_config();
+    cp.set_mesh_config(mesh_config.clone()).unwrap();
+    assert_eq!(cp.mesh_config().unwrap(), Some(mesh_config));
+
+    write_read_and_assert(cp);
+}
+
+#[test]
+fn test_config_params_diff() {
+    let mut old = ConfigParams::default();
+    old.set_config_address(UInt256::from([1; 32])).unwrap();
+    old.set_elector_address(UInt256::from([2; 32])).unwrap();
+    old.set_catchain_config(get_cat_chain_config()).unwrap();
+    let mut slice = SliceData::new(vec![0xAB, 0x80]);
+    old.set_config(ConfigParamEnum::ConfigParamAny(100, slice.clone())).unwrap();
+    assert!(old.diff(&old.clone()).unwrap().is_empty());
+
+    let mut new = old.clone();
+    new.set_elector_address(UInt256::from([3; 32])).unwrap();
+    new.set_minter_address(UInt256::from([4; 32])).unwrap();
+    new.config_params.remove(SliceData::load_bitstring(28u32.write_to_new_cell().unwrap()).unwrap()).unwrap();
+    slice = SliceData::new(vec![0xCD, 0x80]);
+    new.set_config(ConfigParamEnum::ConfigParamAny(100, slice)).unwrap();
+
+    let changes = old.diff(&new).unwrap();
+    let indexes: Vec<_> = changes.iter().map(|change| (change.index, change.kind())).collect();
+    assert_eq!(indexes, vec![
+        (1, ConfigParamChangeKind::Modified),
+        (2, ConfigParamChangeKind::Added),
+        (28, ConfigParamChangeKind::Removed),
+        (100, ConfigParamChangeKind::Modified),
+    ]);
+    assert_eq!(changes[0].old_value, Some(ConfigParamEnum::ConfigParam1(ConfigParam1 { elector_addr: UInt256::from([2; 32]) })));
+    assert_eq!(changes[0].new_value, Some(ConfigParamEnum::ConfigParam1(ConfigParam1 { elector_addr: UInt256::from([3; 32]) })));
+    assert!(changes[1].old_cell.is_none());
+    assert!(changes[2].new_value.is_none());
+    assert!(matches!(changes[2].old_value, Some(ConfigParamEnum::ConfigParam28(_))));
+    assert!(changes[3].old_value.is_none() && changes[3].old_cell.is_some());
+
+    let reverse = new.diff(&old).unwrap();
+    assert_eq!(reverse[1].kind(), ConfigParamChangeKind::Removed);
+    assert_eq!(reverse[2].kind(), ConfigParamChangeKind::Added);
+}
+
+#[test]
+fn test_global_capabilities() {
+    let unknown = 0x8000_0000_0000_0000;
+    let caps = GlobalCapabilities::CapCopyleft | GlobalCapabilities::CapSmft
+        | GlobalCapabilities::from_bits(unknown);
+    assert!(caps.contains(GlobalCapabilities::CapCopyleft));
+    assert!(!caps.contains(GlobalCapabilities::CapCopyleft | GlobalCapabilities::CapCommonMessage));
+    assert!(caps.intersects(GlobalCapabilities::CapCopyleft | GlobalCapabilities::CapCommonMessage));
+    assert_eq!(caps.unknown_bits(), unknown);
+    assert_eq!(caps.to_string(), "CapCopyleft | CapSmft | 0x8000000000000000");
+    assert_eq!(GlobalCapabilities::empty().to_string(), "CapNone");
+    assert_eq!(u64::from(GlobalCapabilities::from(0x3)), 0x3);
+
+    let mut version = GlobalVersion { version: 50, capabilities: caps.bits() };
+    assert!(version.has_capability(GlobalCapabilities::CapSmft));
+    assert!(!version.has_capability(GlobalCapabilities::CapNone));
+    version.set_capability(GlobalCapabilities::CapNoSplitOutQueue | GlobalCapabilities::CapIhrEnabled, true);
+    version.set_capability(GlobalCapabilities::CapSmft, false);
+    assert!(version.supports(GlobalCapabilities::CapNoSplitOutQueue | GlobalCapabilities::CapIhrEnabled));
+    assert!(!version.supports(GlobalCapabilities::CapSmft));
+    assert!(version.supports(GlobalCapabilities::empty()));
+    // unknown bits are kept through serialization
+    assert_eq!(version.global_capabilities().unknown_bits(), unknown);
+    write_read_and_assert(version.clone());
+
+    let mut config = ConfigParams::default();
+    assert!(!config.supports(GlobalCapabilities::CapCopyleft));
+    assert!(config.supports(GlobalCapabilities::empty()));
+    config.set_config(ConfigParamEnum::ConfigParam8(ConfigParam8 { global_version: version.clone() })).unwrap();
+    assert!(config.supports(GlobalCapabilities::CapCopyleft | GlobalCapabilities::CapIhrEnabled));
+    assert!(config.has_capability(GlobalCapabilities::CapNoSplitOutQueue));
+    assert!(!config.has_capability(GlobalCapabilities::CapSmft));
+    assert_eq!(config.global_capabilities().bits(), version.capabilities);
+}
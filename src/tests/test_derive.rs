@@ -0,0 +1,58 @@
+/*
+* Copyright (C) 2019-2023 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use super::*;
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, TlbSerializable)]
+struct DerivedKeyMaxLt {
+    key: bool,
+    max_end_lt: u64,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, TlbSerializable)]
+#[tlb(tag = 0xcc26, bits = 16)]
+struct Tagged {
+    shard: ShardIdent,
+    seq_no: u32,
+    grams: Grams,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, TlbSerializable)]
+struct Wrapper<T: Clone + Default>(T, u8);
+
+#[test]
+fn test_derived_same_as_manual() {
+    let manual = KeyMaxLt { key: true, max_end_lt: 12345 };
+    let derived = DerivedKeyMaxLt { key: true, max_end_lt: 12345 };
+    assert_eq!(manual.serialize().unwrap(), derived.serialize().unwrap());
+    write_read_and_assert(derived);
+}
+
+#[test]
+fn test_derived_with_tag() {
+    let tagged = Tagged {
+        shard: ShardIdent::with_tagged_prefix(0, 0x4000_0000_0000_0000).unwrap(),
+        seq_no: 17,
+        grams: Grams::from(1_000_000_000u64),
+    };
+    let cell = tagged.serialize().unwrap();
+    assert_eq!(SliceData::load_cell(cell).unwrap().get_next_u16().unwrap(), 0xcc26);
+    write_read_and_assert(tagged);
+
+    let mut builder = BuilderData::new();
+    builder.append_u16(0xcc27).unwrap();
+    let err = Tagged::construct_from_cell(builder.into_cell().unwrap()).unwrap_err();
+    assert!(err.to_string().contains("Invalid TL-B constructor tag"), "{}", err);
+
+    write_read_and_assert(Wrapper(KeyMaxLt { key: false, max_end_lt: 1 }, 7));
+}
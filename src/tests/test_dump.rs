@@ -0,0 +1,99 @@
+/*
+* Copyright (C) 2019-2023 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use super::*;
+use crate::{
+    AccountId, AccountStatus, CommonMessage, CurrencyCollection, Deserializable,
+    InternalMessageHeader, Message, MsgAddressInt,
+};
+
+struct Pair(u32, u32);
+
+impl Dump for Pair {
+    fn dump_to(&self, writer: &mut DumpWriter) -> Result<()> {
+        writer.field("first", self.0)?;
+        writer.section("nested", |writer| {
+            writer.field("second", self.1)?;
+            writer.lines("line 1\nline 2")
+        })
+    }
+}
+
+#[test]
+fn test_dump_writer() {
+    let text = Pair(1, 2).dump_to_string(DumpOptions::default()).unwrap();
+    assert_eq!(text, "first: 1\nnested:\n  second: 2\n  line 1\n  line 2\n");
+
+    let options = DumpOptions { max_depth: 0, ..Default::default() };
+    let text = Pair(1, 2).dump_to_string(options).unwrap();
+    assert_eq!(text, "first: 1\nnested: ...\n");
+}
+
+#[test]
+fn test_dump_key_block() {
+    let block = Block::construct_from_file("src/tests/data/key_block.boc").unwrap();
+    let options = DumpOptions { max_depth: usize::MAX, show_messages: true, show_config: false };
+    let text = block.dump_to_string(options).unwrap();
+    println!("{}", text);
+    assert_eq!(text, block.dump_to_string(options).unwrap());
+    assert!(text.starts_with(&format!("global_id: {}\ninfo:\n", block.global_id())));
+    assert!(text.contains(&format!("  seq_no: {}\n", block.read_info().unwrap().seq_no())));
+    assert!(text.contains("\n  custom:\n    key_block: true\n"));
+    assert!(!text.contains("config_addr"));
+
+    let options = DumpOptions { show_config: true, ..options };
+    let text = block.dump_to_string(options).unwrap();
+    assert!(text.contains("config_addr: "));
+    assert!(text.contains("param 34: "));
+
+    let options = DumpOptions { max_depth: 1, ..options };
+    let text = block.dump_to_string(options).unwrap();
+    assert!(text.contains("\ninfo:\n  version: "));
+    assert!(text.contains("\n  custom: ...\n"));
+    assert!(!text.contains("config_addr"));
+}
+
+#[test]
+fn test_dump_shard_state() {
+    let state = ShardStateUnsplit::construct_from_file("src/tests/data/shard_state.boc").unwrap();
+    let text = state.dump_to_string(DumpOptions::default()).unwrap();
+    println!("{}", text);
+    let accounts = state.read_accounts().unwrap().len().unwrap();
+    assert!(text.contains(&format!("\naccounts_count: {}\naccounts:\n", accounts)));
+    assert_eq!(text.matches("\n  account ").count(), accounts);
+    let queue_len = state.read_out_msg_queue_info().unwrap().out_queue().len().unwrap();
+    assert!(text.contains(&format!("\nout_msg_queue_len: {}\n", queue_len)));
+    assert_eq!(text, state.dump_to_string(DumpOptions::default()).unwrap());
+}
+
+#[test]
+fn test_dump_transaction() {
+    let account_id = AccountId::from([0x11; 32]);
+    let src = MsgAddressInt::with_standart(None, 0, AccountId::from([0x22; 32])).unwrap();
+    let dst = MsgAddressInt::with_standart(None, 0, account_id.clone()).unwrap();
+    let msg = Message::with_int_header(InternalMessageHeader::with_addresses(
+        src, dst, CurrencyCollection::with_grams(100)
+    ));
+    let mut tr = Transaction::with_address_and_status(account_id, AccountStatus::AccStateActive);
+    tr.write_in_msg(Some(&CommonMessage::Std(msg))).unwrap();
+    tr.set_logical_time(10);
+
+    let text = tr.dump_to_string(DumpOptions::default()).unwrap();
+    assert!(text.starts_with(&format!("account: {}\nlt: 10\n", "11".repeat(32))));
+    assert!(text.contains("orig_status: AccStateActive\n"));
+    assert!(!text.contains("in_msg"));
+
+    let options = DumpOptions { show_messages: true, ..Default::default() };
+    let text = tr.dump_to_string(options).unwrap();
+    assert!(text.contains("\nin_msg: "));
+}
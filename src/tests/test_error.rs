@@ -0,0 +1,88 @@
+/*
+* Copyright (C) 2019-2023 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use super::*;
+use crate::{
+    BuilderData, CreatorStats, Deserializable, FutureSplitMerge, IBitstring, Result, Serializable,
+    ShardDescr, SliceData, UInt256,
+};
+
+#[derive(Default)]
+struct Outer {
+    stats: CreatorStats,
+}
+
+impl Deserializable for Outer {
+    fn read_from(&mut self, slice: &mut SliceData) -> Result<()> {
+        slice.get_next_u32()?;
+        self.stats = CreatorStats::construct_from_reference(slice)?;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_short_type_name() {
+    assert_eq!(short_type_name("ever_block::master::McBlockExtra"), "McBlockExtra");
+    assert_eq!(
+        short_type_name("ever_block::bintree::BinTree<ever_block::master::ShardDescr>"),
+        "BinTree<ShardDescr>"
+    );
+    assert_eq!(short_type_name("(u32, alloc::vec::Vec<u8>)"), "(u32, Vec<u8>)");
+}
+
+#[test]
+fn test_deserialize_context() {
+    let mut stats = BuilderData::new();
+    stats.append_bits(0x7, 4).unwrap();
+    let mut outer = BuilderData::new();
+    outer.append_u32(0x12345678).unwrap();
+    outer.checked_append_reference(stats.into_cell().unwrap()).unwrap();
+    let cell = outer.into_cell().unwrap();
+
+    let err = Outer::construct_from_cell(cell).unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<BlockError>(),
+        Some(BlockError::InvalidConstructorTag { t: 7, .. })
+    ));
+    let context = err.downcast_ref::<DeserializeContext>().unwrap();
+    let path = context.path.iter().map(|frame| frame.to_string()).collect::<Vec<_>>();
+    assert_eq!(path, ["Outer (bit 32, ref 1)", "CreatorStats (bit 4, ref 0)"]);
+    assert!(context.cause.contains("Invalid TL-B constructor tag"), "{}", context.cause);
+    let message = err.to_string();
+    assert!(message.starts_with("Outer (bit 32, ref 1) -> CreatorStats (bit 4, ref 0) -> "), "{}", message);
+
+    // successful reading is not affected
+    let stats = CreatorStats::default();
+    let cell = stats.serialize().unwrap();
+    assert_eq!(CreatorStats::construct_from_cell(cell).unwrap(), stats);
+}
+
+#[test]
+fn test_deserialize_context_with_fields() {
+    let descr = ShardDescr::with_params(42, 17, 25, UInt256::from([70; 32]), FutureSplitMerge::None);
+    let mut slice = SliceData::load_cell(descr.serialize().unwrap()).unwrap();
+    // cut right before split_merge_at
+    slice.shrink_data(..876);
+    let err = ShardDescr::construct_from(&mut slice).unwrap_err();
+    let context = err.downcast_ref::<DeserializeContext>().unwrap();
+    assert_eq!(context.path.len(), 1);
+    assert_eq!(context.path[0].type_name, "ShardDescr");
+    assert_eq!(context.path[0].field.as_deref(), Some("split_merge_at"));
+    assert_eq!(context.path[0].position, Some((876, 0)));
+    assert!(err.to_string().starts_with("ShardDescr.split_merge_at (bit 876, ref 0) -> "), "{}", err);
+
+    let err: Result<()> = Err(BlockError::InvalidData("broken".to_string()).into());
+    let err = err.with_ctx("Inner", "value").with_ctx("Outer", "inner").unwrap_err();
+    assert_eq!(err.to_string(), "Outer.inner -> Inner.value -> Invalid data: broken");
+    assert!(matches!(err.downcast_ref::<BlockError>(), Some(BlockError::InvalidData(_))));
+}
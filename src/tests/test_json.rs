@@ -0,0 +1,86 @@
+/*
+* Copyright (C) 2019-2023 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use super::*;
+use crate::read_single_root_boc;
+
+#[test]
+fn test_currency_collection_json() {
+    let mut cc = CurrencyCollection::with_grams(1_000_000_000_000);
+    cc.set_other(1, 100500).unwrap();
+    cc.set_other(17, 42).unwrap();
+
+    let json = serde_json::to_value(&cc).unwrap();
+    assert_eq!(json["grams"], "1000000000000");
+    assert_eq!(json["other"]["1"], "100500");
+    assert_eq!(json["other"]["17"], "42");
+
+    let cc2: CurrencyCollection = serde_json::from_value(json).unwrap();
+    assert_eq!(cc, cc2);
+
+    let json = serde_json::to_string(&CurrencyCollection::with_grams(5)).unwrap();
+    assert_eq!(json, r#"{"grams":"5"}"#);
+
+    assert!(serde_json::from_str::<CurrencyCollection>(r#"{"grams":"0x10"}"#).is_err());
+    assert!(serde_json::from_str::<CurrencyCollection>(r#"{"grams":"0X10"}"#).is_err());
+    assert!(serde_json::from_str::<CurrencyCollection>(r#"{"grams":"abc"}"#).is_err());
+}
+
+#[test]
+fn test_block_json() {
+    let bytes = std::fs::read(
+        "src/tests/data/8A8270ED58F5F982EFC3A16DA19D3EF99D12D7A9E0039B911213D0F2940A1B29.boc"
+    ).unwrap();
+    let root = read_single_root_boc(bytes).unwrap();
+    let block = Block::construct_from_cell(root.clone()).unwrap();
+
+    let json = serde_json::to_value(&block).unwrap();
+    assert_eq!(json["id"], format!("{:x}", root.repr_hash()));
+    let info = block.read_info().unwrap();
+    assert_eq!(json["info"]["seq_no"], info.seq_no());
+    assert_eq!(json["info"]["shard"]["workchain_id"], info.shard().workchain_id());
+
+    let block2: Block = serde_json::from_value(json).unwrap();
+    assert_eq!(block, block2);
+
+    let mut tr = None;
+    block.read_extra().unwrap().read_account_blocks().unwrap().iterate_objects(|account_block| {
+        account_block.transaction_iterate(|transaction| {
+            tr = Some(transaction);
+            Ok(false)
+        })?;
+        Ok(false)
+    }).unwrap();
+    let tr = tr.unwrap();
+    let json = serde_json::to_value(&tr).unwrap();
+    assert_eq!(json["lt"], tr.logical_time());
+    assert_eq!(json["account_addr"], tr.account_id().as_hex_string());
+    let tr2: Transaction = serde_json::from_value(json).unwrap();
+    assert_eq!(tr, tr2);
+
+    if let Some(msg) = tr.read_in_msg().unwrap() {
+        let msg = msg.get_std().unwrap().clone();
+        let json = serde_json::to_value(&msg).unwrap();
+        let msg2: Message = serde_json::from_value(json).unwrap();
+        assert_eq!(msg, msg2);
+    }
+}
+
+#[test]
+fn test_account_json() {
+    let account = Account::default();
+    let json = serde_json::to_value(&account).unwrap();
+    assert_eq!(json["status"], "nonexist");
+    let account2: Account = serde_json::from_value(json).unwrap();
+    assert_eq!(account, account2);
+}
@@ -17,7 +17,9 @@ use crate::{
     Deserializable, ExtBlkRef, HashmapAugType, MsgAddressInt, ShardStateUnsplit, 
     BASE_WORKCHAIN_ID, SERDE_OPTS_EMPTY, CommonMessage, Transaction, BlockInfo, ValueFlow,
     MerkleUpdate, transactions::tests::generate_test_shard_account_block,
-    HashmapType, HashmapE, InMsgFinal,
+    HashmapType, HashmapE, InMsgFinal, Ed25519KeyOption, KeyOption, SigPubKey, ValidatorDescr,
+    CryptoSignature, ValidatorSetKeys, WorkchainDescr, Workchains, Message, AccountStatus, SemanticEq,
+    Dump, DumpOptions,
 };
 use std::collections::{HashMap, HashSet};
 use rand::Rng;
@@ -55,6 +57,38 @@ fn test_libraries() {
     assert_eq!(data, restored_data);
 }
 
+#[test]
+fn test_lib_descr_publishers() {
+    let alice = AccountId::from([1; 32]);
+    let bob = AccountId::from([2; 32]);
+    let carol = AccountId::from([3; 32]);
+    let lib_code = SliceData::new(vec![0x11, 0x80]).into_cell();
+
+    let mut descr = LibDescr::from_lib_data_by_publisher(lib_code.clone(), alice.clone());
+    assert!(descr.has_publisher(&alice).unwrap());
+    assert!(!descr.has_publisher(&bob).unwrap());
+    assert!(descr.add_publisher(&bob).unwrap());
+    assert!(!descr.add_publisher(&bob).unwrap());
+    assert_eq!(descr.publishers_count().unwrap(), 2);
+    write_read_and_assert(descr.clone());
+
+    let mut other = LibDescr::from_lib_data_by_publisher(lib_code.clone(), bob.clone());
+    other.add_publisher(&carol).unwrap();
+    descr.merge(&other).unwrap();
+    assert_eq!(descr.publishers_count().unwrap(), 3);
+    assert!(descr.has_publisher(&carol).unwrap());
+
+    let different = LibDescr::from_lib_data_by_publisher(SliceData::new(vec![0x75, 0x80]).into_cell(), alice.clone());
+    descr.merge(&different).expect_err("different libraries can't be merged");
+
+    assert!(descr.remove_publisher(&alice).unwrap());
+    assert!(!descr.remove_publisher(&alice).unwrap());
+    assert!(descr.remove_publisher(&bob).unwrap());
+    assert!(descr.remove_publisher(&carol).unwrap());
+    assert_eq!(descr.publishers_count().unwrap(), 0);
+    descr.serialize().expect_err("descriptor without publishers can't be serialized");
+}
+
 #[test]
 fn test_shard_descr() {
     let descr_none = ShardDescr::with_params(42, 17, 25, UInt256::from([70; 32]), FutureSplitMerge::None);
@@ -86,6 +120,43 @@ fn test_shard_descr_with_copyleft() {
     write_read_and_assert(descr_merge);
 }
 
+#[test]
+fn test_shard_descr_builder() {
+    let descr = ShardDescrBuilder::new(42, 17, 25, UInt256::from([70; 32]), UInt256::from([71; 32]))
+        .with_gen_utime(0x12345678)
+        .with_want_split(true)
+        .with_before_split(true)
+        .with_split_merge_at(FutureSplitMerge::Split{split_utime: 0x12345678, interval: 100})
+        .with_fees_collected(CurrencyCollection::with_grams(10))
+        .build().unwrap();
+    assert_eq!(descr.file_hash, UInt256::from([71; 32]));
+    assert!(descr.before_split && descr.is_fsm_split());
+    write_read_and_assert(descr.clone());
+
+    ShardDescrBuilder::with_descr(descr.clone()).with_flags(1).build().expect_err("flags & 7 must be zero");
+    ShardDescrBuilder::with_descr(descr.clone()).with_before_merge(true).build()
+        .expect_err("before_split and before_merge together");
+    // serialization checks the same invariants
+    let mut invalid = descr.clone();
+    invalid.before_merge = true;
+    invalid.serialize().expect_err("before_split and before_merge together");
+
+    let mut copyleft_rewards = CopyleftRewards::default();
+    copyleft_rewards.set(&AccountId::from([1; 32]), &100.into()).unwrap();
+    let with_copyleft = ShardDescrBuilder::with_descr(descr).with_copyleft_rewards(copyleft_rewards);
+    with_copyleft.clone().build().unwrap();
+    let collators = ShardCollators {
+        prev: gen_collator(),
+        prev2: None,
+        current: gen_collator(),
+        next: gen_collator(),
+        next2: None,
+        updated_at: 0x12345678,
+    };
+    let err = with_copyleft.with_collators(collators).build().unwrap_err();
+    assert!(matches!(err.downcast_ref().unwrap(), &BlockError::InvalidData(_)));
+}
+
 #[test]
 fn test_shard_descr_fast_finality() {
     let mut descr_none = ShardDescr::with_params(42, 17, 25, UInt256::from([70; 32]), FutureSplitMerge::None);
@@ -144,6 +215,92 @@ fn build_mesh_descr() -> ConnectedNwDescrExt {
     descr
 }
 
+#[test]
+fn test_mesh_hashes_networks() {
+    let descr = build_mesh_descr().descr.unwrap();
+    let mut mesh = MeshHashes::default();
+    mesh.register_network(7, descr.clone()).unwrap();
+    mesh.register_network(3, ConnectedNwDescr::default()).unwrap();
+    mesh.register_network(7, descr.clone()).expect_err("network is already registered");
+
+    mesh.update_network(7, |descr| {
+        descr.seq_no += 1;
+        descr.gen_utime += 10;
+        Ok(())
+    }).unwrap();
+    assert_eq!(mesh.get(&7).unwrap().unwrap().seq_no, descr.seq_no + 1);
+    mesh.update_network(7, |descr| {
+        descr.gen_utime -= 1;
+        Ok(())
+    }).expect_err("gen_utime decreased");
+    assert_eq!(mesh.get(&7).unwrap().unwrap().gen_utime, descr.gen_utime + 10);
+    mesh.update_network(5, |_| Ok(())).expect_err("network is not registered");
+
+    let mut ids = Vec::new();
+    mesh.iterate_networks(|nw_id, _| {
+        ids.push(nw_id);
+        Ok(true)
+    }).unwrap();
+    assert_eq!(ids, vec![3, 7]);
+
+    assert!(mesh.remove_network(3).unwrap());
+    assert!(!mesh.remove_network(3).unwrap());
+    assert_eq!(mesh.len().unwrap(), 1);
+
+    let mut mesh = MeshHashesExt::default();
+    mesh.register_network(1, ConnectedNwDescrExt::default()).unwrap();
+    mesh.update_network(1, |ext| {
+        ext.descr = Some(descr.clone());
+        Ok(())
+    }).unwrap();
+    mesh.update_network(1, |ext| {
+        ext.descr = None;
+        Ok(())
+    }).expect_err("description can't be removed");
+    mesh.update_network(1, |ext| {
+        ext.queue_descr = build_mesh_queue_descr();
+        Ok(())
+    }).unwrap();
+    mesh.register_network(1, build_mesh_descr()).expect_err("network is already registered");
+    let mut count = 0;
+    mesh.iterate_networks(|nw_id, ext| {
+        assert_eq!(nw_id, 1);
+        assert_eq!(ext.descr.as_ref(), Some(&descr));
+        count += 1;
+        Ok(true)
+    }).unwrap();
+    assert_eq!(count, 1);
+    assert!(mesh.remove_network(1).unwrap());
+    assert!(mesh.is_empty());
+}
+
+#[test]
+fn test_mesh_out_queue_update() {
+    let old_root = 1u32.serialize().unwrap();
+    let new_root = 2u32.serialize().unwrap();
+    let descr = ConnectedNwOutDescr {
+        out_queue_update: HashUpdate::with_hashes(old_root.repr_hash(), new_root.repr_hash()),
+        exported: 150.into(),
+    };
+    assert_eq!(descr.apply_queue_update(&old_root.repr_hash()).unwrap(), new_root.repr_hash());
+    descr.apply_queue_update(&new_root.repr_hash()).expect_err("unknown queue root");
+    descr.verify_queue_update(&old_root, &new_root).unwrap();
+    descr.verify_queue_update(&old_root, &old_root).expect_err("wrong new queue root");
+    descr.verify_queue_update(&new_root, &new_root).expect_err("wrong old queue root");
+
+    let prev = ConnectedNwOutDescr { exported: 100.into(), ..ConnectedNwOutDescr::default() };
+    assert_eq!(descr.exported_since(&prev).unwrap(), 50.into());
+    prev.exported_since(&descr).expect_err("exported counter decreased");
+
+    let mut mesh = MeshHashesExt::default();
+    mesh.register_network(1, ConnectedNwDescrExt { queue_descr: descr, descr: None }).unwrap();
+    let mut prev_mesh = MeshHashesExt::default();
+    assert_eq!(mesh.exported_since(&prev_mesh, 1).unwrap(), 150.into());
+    prev_mesh.register_network(1, ConnectedNwDescrExt { queue_descr: prev, descr: None }).unwrap();
+    assert_eq!(mesh.exported_since(&prev_mesh, 1).unwrap(), 50.into());
+    mesh.exported_since(&prev_mesh, 2).expect_err("network is not registered");
+}
+
 #[test]
 fn test_shard_descr_mesh() {
     let mut descr = ShardDescr::with_params(42, 17, 25, UInt256::from([70; 32]), FutureSplitMerge::None);
@@ -165,6 +322,83 @@ fn test_shard_descr_mesh() {
 
 }
 
+#[test]
+fn test_register_shard_block() {
+    let basechain = ShardIdent::with_workchain_id(0).unwrap();
+    let (left, right) = basechain.split().unwrap();
+    let record = |shard: &ShardIdent, seq_no: u32, fees: u64| {
+        let mut descr = ShardDescr::with_params(seq_no, 0, 0, UInt256::from([seq_no as u8; 32]), FutureSplitMerge::None);
+        descr.fees_collected = CurrencyCollection::with_grams(fees);
+        McShardRecord::from_shard_descr(shard.clone(), descr)
+    };
+
+    let mut extra = McBlockExtra::default();
+    extra.shards_mut().add_workchain(0, 1, UInt256::default(), UInt256::default(), None).unwrap();
+    extra.register_shard_block(&record(&basechain, 1, 10), 2).unwrap();
+    let top = extra.shards().get_shard(&basechain).unwrap().unwrap();
+    assert_eq!((top.descr.seq_no, top.descr.reg_mc_seqno), (1, 2));
+    assert_eq!(extra.fee(&basechain).unwrap(), Some(CurrencyCollection::with_grams(10)));
+    extra.register_shard_block(&record(&basechain, 1, 10), 3).expect_err("block is not newer");
+    extra.register_shard_block(&record(&left, 2, 10), 3).expect_err("shard is not before split");
+    extra.register_shard_block(&record(&ShardIdent::masterchain(), 2, 10), 3).expect_err("masterchain");
+
+    extra.shards_mut().update_shard(&basechain, |mut descr| {
+        descr.before_split = true;
+        Ok(descr)
+    }).unwrap();
+    let mut extra_split = extra.clone();
+    extra_split.register_shard_block(&record(&left, 2, 20), 3).unwrap();
+    let left_top = extra_split.shards().get_shard(&left).unwrap().unwrap();
+    let right_top = extra_split.shards().get_shard(&right).unwrap().unwrap();
+    assert_eq!((left_top.descr.seq_no, left_top.descr.reg_mc_seqno), (2, 3));
+    assert_eq!((right_top.descr.seq_no, right_top.descr.before_split), (1, false));
+    extra_split.register_shard_block(&record(&right, 2, 30), 3).unwrap();
+    assert_eq!(extra_split.total_fee(), &CurrencyCollection::with_grams(60));
+
+    extra_split.register_shard_block(&record(&basechain, 3, 5), 4).expect_err("shards are not before merge");
+    for shard in [&left, &right] {
+        extra_split.shards_mut().update_shard(shard, |mut descr| {
+            descr.before_merge = true;
+            Ok(descr)
+        }).unwrap();
+    }
+    extra_split.register_shard_block(&record(&basechain, 3, 5), 4).unwrap();
+    let top = extra_split.shards().get_shard(&basechain).unwrap().unwrap();
+    assert_eq!((top.descr.seq_no, top.descr.reg_mc_seqno), (3, 4));
+    assert!(extra_split.shards().get_shard(&left).unwrap().is_none());
+}
+
+#[test]
+fn test_shard_hashes_top_blocks() {
+    let mut shards = ShardHashes::default();
+    assert!(shards.top_blocks().unwrap().is_empty());
+    for workchain_id in [0, -5, 7] {
+        let descr = ShardDescr::with_params(10, 1, 2, UInt256::from([workchain_id as u8; 32]), FutureSplitMerge::None);
+        shards.set(&workchain_id, &InRefValue(BinTree::with_item(&descr).unwrap())).unwrap();
+    }
+    let basechain = ShardIdent::with_workchain_id(0).unwrap();
+    let left = ShardDescr::with_params(11, 3, 4, UInt256::from([1; 32]), FutureSplitMerge::None);
+    let right = ShardDescr::with_params(12, 3, 4, UInt256::from([2; 32]), FutureSplitMerge::None);
+    shards.split_shard(&basechain, |_| Ok((left, right))).unwrap();
+    let (left_shard, right_shard) = basechain.split().unwrap();
+
+    let top_blocks = shards.top_blocks().unwrap();
+    let ids: Vec<_> = top_blocks.iter().map(|id| (id.shard().clone(), id.seq_no())).collect();
+    assert_eq!(ids, vec![
+        (ShardIdent::with_workchain_id(-5).unwrap(), 10),
+        (left_shard, 11),
+        (right_shard, 12),
+        (ShardIdent::with_workchain_id(7).unwrap(), 10),
+    ]);
+    assert_eq!(top_blocks[1].root_hash(), &UInt256::from([1; 32]));
+
+    let top_blocks = shards.top_blocks_for_workchains(&[7, 0, 42, 7]).unwrap();
+    assert_eq!(top_blocks.len(), 3);
+    assert_eq!(top_blocks[0].shard().workchain_id(), 0);
+    assert_eq!(top_blocks[2].shard().workchain_id(), 7);
+    assert!(shards.top_blocks_for_workchains(&[]).unwrap().is_empty());
+}
+
 #[test]
 fn test_mc_state_extra() {
     let mut extra = McStateExtra::default();
@@ -332,7 +566,15 @@ fn test_real_shard_hashes() {
     let block = Block::construct_from_file("src/tests/data/key_block_not_all_shardes.boc").unwrap();
     let extra = block.read_extra().unwrap().read_custom().unwrap().expect("need key block");
     let shards = extra.shards();
-    let mut count = shards.dump("shards");
+    let dump = shards.dump_to_string(DumpOptions::default()).unwrap();
+    println!("{}", dump);
+    let mut count = 0;
+    shards.iterate_shards(|shard, _descr| {
+        assert!(dump.contains(&format!("\n  shard {}:\n", shard)), "shard {} is not dumped", shard);
+        count += 1;
+        Ok(true)
+    }).unwrap();
+    assert_eq!(dump.lines().filter(|line| line.starts_with("workchain ")).count(), shards.len().unwrap());
     println!("total: {}", count);
 
     let mut result = vec![];
@@ -437,6 +679,118 @@ fn test_real_shard_hashes() {
     assert_eq!(*found_shard.unwrap().shard(), right_ancestor);
 }
 
+#[test]
+fn test_shard_hashes_index() {
+    let block = Block::construct_from_file("src/tests/data/key_block_not_all_shardes.boc").unwrap();
+    let extra = block.read_extra().unwrap().read_custom().unwrap().expect("need key block");
+    let mut shards = extra.shards().clone();
+    let mut index = ShardHashesIndex::with_shard_hashes(&shards).unwrap();
+    assert!(index.is_actual(&shards));
+    assert!(!index.is_empty());
+
+    let mut rng = rand::thread_rng();
+    for _ in 0..1000 {
+        let prefix = AccountIdPrefixFull { workchain_id: rng.gen_range(-1..=1), prefix: rng.gen() };
+        let expected = shards.find_shard_by_prefix(&prefix).unwrap();
+        assert_eq!(index.find_shard_by_prefix(&prefix), expected.as_ref());
+
+        let len = rng.gen_range(0..=MAX_SPLIT_DEPTH);
+        let shard = ShardIdent::with_prefix_len(len, prefix.workchain_id, prefix.prefix).unwrap();
+        let expected = shards.find_shard(&shard).unwrap();
+        assert_eq!(index.find_shard(&shard), expected.as_ref());
+        let expected = shards.get_shard(&shard).unwrap();
+        assert_eq!(index.get_shard(&shard), expected.as_ref());
+    }
+    for record in index.shards().to_vec() {
+        assert_eq!(index.get_shard(record.shard()), Some(&record));
+    }
+
+    let record = index.shards()[0].clone();
+    let descr = record.descr().clone();
+    shards.split_shard(record.shard(), |_| Ok((descr.clone(), descr))).unwrap();
+    assert!(!index.is_actual(&shards));
+    assert!(index.get_shard(record.shard()).is_some());
+
+    assert!(index.refresh(&shards).unwrap());
+    assert!(!index.refresh(&shards).unwrap());
+    assert!(index.get_shard(record.shard()).is_none());
+    let (left, right) = record.shard().split().unwrap();
+    assert!(index.get_shard(&left).is_some());
+    assert!(index.get_shard(&right).is_some());
+    assert_eq!(index.find_shard(record.shard()), None);
+}
+
+#[test]
+fn test_neighbor_index() {
+    let block = Block::construct_from_file("src/tests/data/key_block_not_all_shardes.boc").unwrap();
+    let extra = block.read_extra().unwrap().read_custom().unwrap().expect("need key block");
+    let mut shards = extra.shards().clone();
+    let mut index = NeighborIndex::with_shard_hashes(&shards).unwrap();
+    assert!(index.is_actual(&shards));
+
+    let tops = index.shards().shards().to_vec();
+    for record in &tops {
+        let mut expected = shards.get_neighbours(record.shard()).unwrap();
+        expected.sort_by_key(|record| (record.shard().workchain_id(), record.shard().shard_prefix_without_tag()));
+        let neighbors: Vec<_> = index.neighbors_of(record.shard()).into_iter().cloned().collect();
+        assert_eq!(neighbors, expected);
+        for other in &tops {
+            assert_eq!(
+                index.is_neighbor(record.shard(), other.shard()),
+                record.shard().is_neighbor_for(other.shard())
+            );
+        }
+    }
+    assert_eq!(index.neighbors_of(&ShardIdent::masterchain()).len(), tops.len());
+
+    // shards absent in the index are checked by scan
+    let (left, right) = tops[0].shard().split().unwrap();
+    assert_eq!(index.neighbors_of(&left).len(), shards.get_neighbours(&left).unwrap().len());
+    assert!(index.is_neighbor(&left, &right));
+
+    let descr = tops[0].descr().clone();
+    shards.split_shard(tops[0].shard(), |_| Ok((descr.clone(), descr))).unwrap();
+    assert!(!index.is_actual(&shards));
+    assert!(index.refresh(&shards).unwrap());
+    assert_eq!(index.shards().len(), tops.len() + 1);
+    assert!(index.neighbors_of(&left).iter().any(|record| record.shard() == &right));
+}
+
+#[test]
+fn test_calc_shard_cc_seqno_multi_level() {
+    let mut shards = ShardHashes::default();
+    shards.add_workchain(0, 1, UInt256::default(), UInt256::default(), None).unwrap();
+    let with_cc = |cc_seqno| {
+        let mut descr = ShardDescr::default();
+        descr.next_catchain_seqno = cc_seqno;
+        descr
+    };
+    let full = ShardIdent::with_workchain_id(0).unwrap();
+    let (left, right) = full.split().unwrap();
+    let (left_left, left_right) = left.split().unwrap();
+    shards.split_shard(&full, |_| Ok((with_cc(5), with_cc(7)))).unwrap();
+    shards.split_shard(&left, |_| Ok((with_cc(9), with_cc(3)))).unwrap();
+
+    assert_eq!(shards.calc_shard_cc_seqno(&left_right).unwrap(), 3);
+    assert_eq!(shards.calc_shard_cc_seqno(&right).unwrap(), 7);
+
+    // splits by one and several levels
+    let (child, _) = left_left.split().unwrap();
+    assert_eq!(shards.calc_shard_cc_seqno(&child).unwrap(), 9);
+    let (grandchild, _) = child.split().unwrap();
+    assert_eq!(shards.calc_shard_cc_seqno(&grandchild).unwrap(), 9);
+    let (_, right_right) = right.split().unwrap();
+    let (right_right_left, _) = right_right.split().unwrap();
+    assert_eq!(shards.calc_shard_cc_seqno(&right_right_left).unwrap(), 7);
+
+    // merges of one and several levels
+    assert_eq!(shards.calc_shard_cc_seqno(&left).unwrap(), 10);
+    assert_eq!(shards.calc_shard_cc_seqno(&full).unwrap(), 10);
+
+    shards.calc_shard_cc_seqno(&ShardIdent::masterchain()).expect_err("masterchain");
+    shards.calc_shard_cc_seqno(&ShardIdent::with_workchain_id(1).unwrap()).expect_err("no workchain");
+}
+
 #[test]
 fn test_serialization_shard_fees() {
     let mut shard_fees = ShardFees::default();
@@ -456,6 +810,113 @@ fn test_serialization_shard_fees() {
     write_read_and_assert(shard_fees);
 }
 
+#[test]
+fn test_shard_fees_reconcile_with_value_flow() {
+    let mut shard_fees = ShardFees::default();
+    for (wc, prefix) in [(0, 0x4000_0000_0000_0000), (0, 0xC000_0000_0000_0000), (1, SHARD_FULL)] {
+        shard_fees.store_shard_fees(
+            &ShardIdent::with_tagged_prefix(wc, prefix).unwrap(),
+            CurrencyCollection::with_grams(10),
+            CurrencyCollection::with_grams(3),
+        ).unwrap();
+    }
+    let totals = shard_fees.totals_by_workchain().unwrap();
+    assert_eq!(totals.len(), 2);
+    assert_eq!(totals[&0].fees, CurrencyCollection::with_grams(20));
+    assert_eq!(totals[&1].create, CurrencyCollection::with_grams(3));
+
+    let create_fee = CurrencyCollection::with_grams(100);
+    let mut value_flow = ValueFlow::default();
+    value_flow.fees_imported = CurrencyCollection::with_grams(30);
+    value_flow.created = CurrencyCollection::with_grams(109);
+    shard_fees.reconcile_with_value_flow(&value_flow, &create_fee).unwrap();
+    shard_fees.reconcile_with_value_flow(&value_flow, &CurrencyCollection::default())
+        .expect_err("block create fee is not taken into account");
+
+    value_flow.created = CurrencyCollection::with_grams(108);
+    let err = shard_fees.reconcile_with_value_flow(&value_flow, &create_fee).unwrap_err().to_string();
+    assert!(err.contains("created"), "{}", err);
+    assert!(!err.contains("fees_imported"), "{}", err);
+    assert!(err.contains("workchain 1"), "{}", err);
+}
+
+#[test]
+fn test_real_shard_fees_reconcile_with_value_flow() {
+    let block = Block::construct_from_file("src/tests/data/key_block_not_all_shardes.boc").unwrap();
+    let value_flow = block.read_value_flow().unwrap();
+    let extra = block.read_extra().unwrap().read_custom().unwrap().expect("need key block");
+    let create_fee = extra.config().unwrap().block_create_fees(true).unwrap();
+    let create_fee = CurrencyCollection::from_grams(create_fee);
+    extra.fees().reconcile_with_value_flow(&value_flow, &create_fee).unwrap();
+    if !create_fee.grams.is_zero() {
+        extra.fees().reconcile_with_value_flow(&value_flow, &CurrencyCollection::default())
+            .expect_err("created includes the masterchain block create fee");
+    }
+}
+
+#[test]
+fn test_key_blocks_in_range() {
+    let bytes = std::fs::read("src/tests/data/free-ton-mc-state-61884").unwrap();
+    let root = read_single_root_boc(&bytes).unwrap();
+    let shard_state = ShardStateUnsplit::construct_from_cell(root).unwrap();
+    let prev_blocks = &shard_state.read_custom().unwrap().unwrap().prev_blocks;
+
+    let mut all_key_blocks = Vec::new();
+    prev_blocks.iterate_with_keys_and_aug(|_seqno: u32, id, aug| {
+        if aug.key {
+            all_key_blocks.push(id.blk_ref);
+        }
+        Ok(true)
+    }).unwrap();
+    assert!(all_key_blocks.len() > 3);
+
+    assert_eq!(prev_blocks.key_blocks_in_range(0..u32::MAX).unwrap(), all_key_blocks);
+    assert!(prev_blocks.key_blocks_in_range(10..10).unwrap().is_empty());
+
+    let first = all_key_blocks[1].seq_no;
+    let last = all_key_blocks[3].seq_no;
+    let found = prev_blocks.key_blocks_in_range(first..last).unwrap();
+    assert_eq!(found, all_key_blocks[1..3]);
+    let found = prev_blocks.key_blocks_in_range(first + 1..last + 1).unwrap();
+    assert_eq!(found, all_key_blocks[2..4]);
+    let found = prev_blocks.key_blocks_in_range(first + 1..all_key_blocks[2].seq_no).unwrap();
+    assert!(found.is_empty());
+
+    assert!(OldMcBlocksInfo::default().key_blocks_in_range(0..u32::MAX).unwrap().is_empty());
+}
+
+#[test]
+fn test_find_block_by_lt() {
+    let bytes = std::fs::read("src/tests/data/free-ton-mc-state-61884").unwrap();
+    let root = read_single_root_boc(&bytes).unwrap();
+    let shard_state = ShardStateUnsplit::construct_from_cell(root).unwrap();
+    let prev_blocks = &shard_state.read_custom().unwrap().unwrap().prev_blocks;
+
+    let mut blocks = Vec::new();
+    prev_blocks.iterate_objects(|id| {
+        blocks.push(id.blk_ref);
+        Ok(true)
+    }).unwrap();
+    assert!(blocks.windows(2).all(|pair| pair[0].end_lt < pair[1].end_lt));
+
+    let first = blocks.first().unwrap();
+    let last = blocks.last().unwrap();
+    if first.end_lt > 0 {
+        assert_eq!(prev_blocks.find_block_by_lt(first.end_lt - 1).unwrap(), None);
+    }
+    assert_eq!(prev_blocks.find_block_by_lt(u64::MAX).unwrap().as_ref(), Some(last));
+    for index in (0..blocks.len()).step_by(997).chain([1, blocks.len() - 1]) {
+        let block = &blocks[index];
+        assert_eq!(prev_blocks.find_block_by_lt(block.end_lt).unwrap().as_ref(), Some(block));
+        if index + 1 < blocks.len() {
+            let lt = blocks[index + 1].end_lt - 1;
+            assert_eq!(prev_blocks.find_block_by_lt(lt).unwrap().as_ref(), Some(block));
+        }
+    }
+
+    assert_eq!(OldMcBlocksInfo::default().find_block_by_lt(u64::MAX).unwrap(), None);
+}
+
 #[test]
 fn test_get_next_prev_key_block() {
 
@@ -526,6 +987,59 @@ fn test_get_next_prev_key_block() {
     }
 }
 
+#[test]
+fn test_umulnexps32() {
+    // (x, k, rounded, truncated), exact values of x * exp(-k / 2^16) computed with 80 digits
+    const VECTORS: [(u64, u32, u64, u64); 20] = [
+        (1, 65535, 0, 0),
+        (3, 1048576, 0, 0),
+        (1000, 1, 1000, 999),
+        (1000, 32000, 614, 613),
+        (1000, 65536, 368, 367),
+        (65535, 4096, 61564, 61564),
+        (65535, 3145727, 0, 0),
+        (1048576, 1, 1048560, 1048560),
+        (1048576, 255, 1044504, 1044503),
+        (1048576, 256, 1044488, 1044487),
+        (123456789, 4096, 115976920, 115976920),
+        (123456789, 65536, 45417215, 45417214),
+        (4294967295, 255, 4278288085, 4278288085),
+        (4294967295, 32000, 2635736608, 2635736608),
+        (4294967295, 65537, 1580006059, 1580006059),
+        (1099511627783, 1, 1099494850695, 1099494850694),
+        (1099511627783, 100000, 239066672917, 239066672917),
+        (4503599627370497, 1978425, 349, 349),
+        (4503599627370497, 65537, 1656756433887149, 1656756433887148),
+        (72057594037927935, 65535, 26508911917640783, 26508911917640782),
+    ];
+    for (x, k, rounded, truncated) in VECTORS {
+        assert_eq!(umulnexps32(x, k, false), rounded, "x = {}, k = {}", x, k);
+        assert_eq!(umulnexps32(x, k, true), truncated, "x = {}, k = {}", x, k);
+    }
+
+    fn reference(x: u64, k: u32) -> f64 {
+        x as f64 * (k as f64 / -65536f64).exp()
+    }
+    for x in [1, 1000, 65535, 1 << 20, 123456789, u32::MAX as u64] {
+        for k in (0..48 << 16).step_by(997) {
+            let expected = reference(x, k);
+            let rounded = umulnexps32(x, k, false);
+            let truncated = umulnexps32(x, k, true);
+            assert!(truncated <= rounded && rounded <= truncated + 1);
+            if (expected.fract() - 0.5).abs() > 1e-3 {
+                assert_eq!(rounded, (expected + 0.5) as u64, "x = {}, k = {}", x, k);
+            }
+            if expected.fract() > 1e-3 && expected.fract() < 1.0 - 1e-3 {
+                assert_eq!(truncated, expected as u64, "x = {}, k = {}", x, k);
+            }
+        }
+        assert_eq!(umulnexps32(x, 0, true), x);
+        assert_eq!(umulnexps32(x, 48 << 16, false), 0);
+        assert_eq!(umulnexps32(x, u32::MAX, false), 0);
+    }
+    assert_eq!(umulnexps32(0, 1, false), 0);
+}
+
 #[test]
 fn test_counters() {
     let mut c = Counters::default();
@@ -536,6 +1050,145 @@ fn test_counters() {
     assert_eq!(c.total(), 4);
 }
 
+#[test]
+fn test_counters_ext() {
+    let mut legacy = Counters::default();
+    assert!(legacy.increase_by(3, 100500));
+    assert!(legacy.increase_by(1, 101000));
+    assert!(!legacy.is_ext());
+
+    let mut ext = legacy.clone().with_cnt256();
+    assert_eq!(ext.cnt256(), Some(legacy.cnt2048() >> 3));
+    assert_eq!(ext.to_legacy(), legacy);
+    assert!(ext.increase_by(1, 101100));
+    assert!(legacy.increase_by(1, 101100));
+    assert_eq!(ext.to_legacy(), legacy);
+    assert!(ext.cnt256().unwrap() < ext.cnt2048());
+
+    // short window decays faster
+    let mut decayed = ext.clone();
+    decayed.decay_to(101100 + 256);
+    assert!(decayed.cnt256().unwrap() < ext.cnt256().unwrap() / 2);
+    decayed.decay_to(101100 + 48 * 256);
+    assert_eq!(decayed.cnt256(), Some(0));
+    assert_eq!(decayed.total(), ext.total());
+
+    let sum = Counters::aggregate([&ext, &ext.clone()], 101200).unwrap();
+    assert_eq!(sum.total(), 2 * ext.total());
+    assert_eq!(sum.last_updated(), 101200);
+    assert!(sum.cnt256().is_some());
+    let sum = Counters::aggregate([&ext, &legacy], 101200).unwrap();
+    assert_eq!(sum.cnt256(), None);
+    assert_eq!(Counters::aggregate([], 101200), Some(Counters::default().with_cnt256()));
+
+    let stats = CreatorStats { mc_blocks: legacy.clone(), shard_blocks: Counters::default() };
+    write_read_and_assert(stats.clone());
+    let stats = stats.with_cnt256();
+    assert!(stats.is_ext());
+    write_read_and_assert(stats);
+
+    let stats = CreatorStats { mc_blocks: ext, shard_blocks: legacy };
+    assert!(stats.write_to_new_cell().is_err());
+}
+
+#[test]
+fn test_counters_merge() {
+    let mut left = Counters::default();
+    assert!(left.increase_by(2, 1000));
+    let mut right = Counters::default();
+    assert!(right.increase_by(1, 1500));
+
+    let mut expected_left = left.clone();
+    expected_left.decay_to(2000);
+    let mut expected_right = right.clone();
+    expected_right.decay_to(2000);
+
+    let mut merged = left.clone();
+    assert!(merged.merge(&right, 2000));
+    assert_eq!(merged.total(), 3);
+    assert_eq!(merged.last_updated(), 2000);
+    assert_eq!(merged.cnt2048(), expected_left.cnt2048() + expected_right.cnt2048());
+    assert_eq!(merged.cnt65536(), expected_left.cnt65536() + expected_right.cnt65536());
+
+    // merge is symmetric and merging with zero counters changes nothing but the time
+    let mut reversed = right.clone();
+    assert!(reversed.merge(&left, 2000));
+    assert_eq!(reversed, merged);
+    let mut with_zero = left.clone();
+    assert!(with_zero.merge(&Counters::default(), 1000));
+    assert_eq!(with_zero, left);
+
+    let overflow = Counters { last_updated: 1000, total: u64::MAX, ..Counters::default() };
+    let mut failed = left.clone();
+    assert!(!failed.merge(&overflow, 2000));
+    assert_eq!(failed, left);
+
+    let mut stats = CreatorStats { mc_blocks: left.clone(), shard_blocks: right.clone() };
+    let other = CreatorStats { mc_blocks: right, shard_blocks: left.clone() };
+    assert!(stats.merge(&other, 2000));
+    assert_eq!(stats.mc_blocks(), &merged);
+    assert_eq!(stats.shard_blocks(), &merged);
+
+    let broken = CreatorStats { mc_blocks: Counters::default(), shard_blocks: overflow };
+    let before = stats.clone();
+    assert!(!stats.merge(&broken, 2000));
+    assert_eq!(stats, before);
+}
+
+fn creator_stats(mc_blocks: u64, shard_blocks: u64, now: u32) -> CreatorStats {
+    let mut stats = CreatorStats::default();
+    if mc_blocks != 0 {
+        assert!(stats.mc_blocks.increase_by(mc_blocks, now));
+    }
+    if shard_blocks != 0 {
+        assert!(stats.shard_blocks.increase_by(shard_blocks, now));
+    }
+    stats
+}
+
+#[test]
+fn test_block_create_stats_queries() {
+    let mut stats = BlockCreateStats::default();
+    stats.counters.set(&UInt256::default(), &creator_stats(100, 100, 5000)).unwrap();
+    stats.counters.set(&UInt256::from([1; 32]), &creator_stats(1, 2, 1000)).unwrap();
+    stats.counters.set(&UInt256::from([2; 32]), &creator_stats(10, 5, 4000)).unwrap();
+    stats.counters.set(&UInt256::from([3; 32]), &creator_stats(0, 15, 5000)).unwrap();
+    stats.counters.set(&UInt256::from([4; 32]), &creator_stats(7, 0, 3000)).unwrap();
+
+    let top = stats.top_creators(10, 0).unwrap();
+    let keys: Vec<_> = top.iter().map(|(key, _)| key.clone()).collect();
+    assert_eq!(keys, vec![
+        UInt256::from([2; 32]), UInt256::from([3; 32]), UInt256::from([4; 32]), UInt256::from([1; 32])
+    ]);
+    assert_eq!(top[0].1.mc_blocks.total(), 10);
+
+    let top = stats.top_creators(2, 3500).unwrap();
+    assert_eq!(top.len(), 2);
+    assert_eq!(top[0].0, UInt256::from([2; 32]));
+    assert_eq!(top[1].0, UInt256::from([3; 32]));
+    assert!(stats.top_creators(0, 0).unwrap().is_empty());
+
+    assert_eq!(stats.total_blocks(0).unwrap(), (18, 22));
+    assert_eq!(stats.total_blocks(3500).unwrap(), (10, 20));
+    assert_eq!(stats.total_blocks(6000).unwrap(), (0, 0));
+
+    // nothing has decayed enough yet
+    assert_eq!(stats.prune_stale(5000).unwrap(), 0);
+    assert_eq!(stats.counters.len().unwrap(), 5);
+
+    // counters of the first creator decay to zero, others are still updated recently
+    let threshold = 1000 + 48 * 65536;
+    let recent = creator_stats(1, 1, threshold);
+    for key in [[2; 32], [3; 32]] {
+        stats.counters.set(&UInt256::from(key), &recent).unwrap();
+    }
+    assert_eq!(stats.prune_stale(threshold).unwrap(), 2);
+    assert!(!stats.counters.check_key(&UInt256::from([1; 32])).unwrap());
+    assert!(!stats.counters.check_key(&UInt256::from([4; 32])).unwrap());
+    assert!(stats.counters.check_key(&UInt256::from([2; 32])).unwrap());
+    assert!(stats.counters.check_key(&UInt256::default()).unwrap());
+}
+
 fn gen_collator() -> CollatorRange {
     let mut rng = rand::thread_rng();
     let collator = rng.gen_range(0..100);
@@ -593,6 +1246,101 @@ fn test_shard_collators() {
 
 }
 
+fn gen_validator_set(count: u32) -> ValidatorSet {
+    let list = (0..count).map(|_| {
+        let keypair = Ed25519KeyOption::generate().unwrap();
+        let key = SigPubKey::from_bytes(keypair.pub_key().unwrap()).unwrap();
+        ValidatorDescr::with_params(key, 1, None, None)
+    }).collect();
+    ValidatorSet::new(0, 100, 1, list).unwrap()
+}
+
+#[test]
+fn test_shard_collators_validate() {
+    let vset = gen_validator_set(3);
+    let range = |collator, start, finish| CollatorRange { collator, start, finish };
+    let mut collators = ShardCollators {
+        prev: range(0, 1, 10),
+        prev2: None,
+        current: range(1, 11, 20),
+        next: range(2, 21, 30),
+        next2: Some(range(0, 21, 30)),
+        updated_at: 0x12345678,
+    };
+    collators.validate(&vset).unwrap();
+    assert!(collators.current.contains(15));
+    assert!(!collators.current.contains(21));
+
+    collators.next2 = Some(range(3, 21, 30));
+    collators.validate(&vset).expect_err("collator index is out of range");
+    collators.next2 = None;
+
+    collators.current = range(1, 20, 11);
+    collators.validate(&vset).expect_err("range is inverted");
+
+    collators.current = range(1, 5, 20);
+    collators.validate(&vset).expect_err("prev overlaps current");
+
+    collators.current = range(1, 11, 25);
+    collators.validate(&vset).expect_err("current overlaps next");
+
+    collators.current = range(1, 10, 20);
+    collators.validate(&vset).expect_err("prev finishes at the start of current");
+    collators.current = range(1, 11, 21);
+    collators.validate(&vset).expect_err("next starts at the finish of current");
+
+    collators.current = range(1, 11, 20);
+    collators.prev2 = Some(range(2, 5, 11));
+    collators.validate(&vset).expect_err("prev2 overlaps current");
+    collators.prev2 = Some(range(2, 5, 10));
+    collators.next2 = Some(range(0, 15, 30));
+    collators.validate(&vset).expect_err("current overlaps next2");
+    collators.next2 = Some(range(0, 21, 25));
+    collators.validate(&vset).unwrap();
+}
+
+#[test]
+fn test_shard_collators_schedule() {
+    let range = |collator, start, finish| CollatorRange { collator, start, finish };
+    let mut collators = ShardCollators {
+        prev: range(0, 1, 10),
+        prev2: Some(range(2, 1, 10)),
+        current: range(1, 11, 20),
+        next: range(2, 21, 30),
+        next2: None,
+        updated_at: 100,
+    };
+    collators.validate_schedule(11).unwrap();
+    collators.validate_schedule(20).unwrap();
+    collators.validate_schedule(21).expect_err("seqno is out of current range");
+
+    let mut bad = collators.clone();
+    bad.prev2 = Some(range(2, 1, 9));
+    bad.validate_schedule(15).expect_err("gap between prev2 and current");
+    let mut bad = collators.clone();
+    bad.next = range(2, 20, 30);
+    bad.validate_schedule(15).expect_err("current overlaps next");
+    let mut bad = collators.clone();
+    bad.next2 = Some(range(0, 30, 21));
+    bad.validate_schedule(15).expect_err("next2 is inverted");
+
+    collators.rotate(range(0, 30, 40), 101).expect_err("gap between next and new next");
+    collators.rotate(range(0, 31, 30), 101).expect_err("new next is inverted");
+    collators.rotate(range(0, 31, 40), 101).unwrap();
+    assert_eq!(collators, ShardCollators {
+        prev: range(1, 11, 20),
+        prev2: None,
+        current: range(2, 21, 30),
+        next: range(0, 31, 40),
+        next2: None,
+        updated_at: 101,
+    });
+    collators.validate_schedule(25).unwrap();
+
+    collators.next2 = Some(range(1, 31, 40));
+    collators.rotate(range(0, 41, 50), 102).expect_err("next2 is set");
+}
+
 impl RefShardBlocks {
     pub fn collect_ref_shard_blocks(&self) -> Result<HashSet<(BlockIdExt, u64)>> {
         let mut res = HashSet::new();
@@ -768,4 +1516,422 @@ fn test_connected_network_descr() {
         gen_utime: 1234567890,
     };
     write_read_and_assert(cnd);
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_prev_blk_signatures() {
+    let keys: Vec<_> = (0..4).map(|_| Ed25519KeyOption::generate().unwrap()).collect();
+    let list = keys.iter().enumerate().map(|(i, keypair)| {
+        let key = SigPubKey::from_bytes(keypair.pub_key().unwrap()).unwrap();
+        ValidatorDescr::with_params(key, 10 + i as u64, None, None)
+    }).collect();
+    let vset = ValidatorSet::new(0, 100, 1, list).unwrap();
+
+    let root_hash = UInt256::rand();
+    let file_hash = UInt256::rand();
+    let data = Block::build_data_for_sign(&root_hash, &file_hash);
+    let sign = |i: usize, data: &[u8]| CryptoSignaturePair::with_params(
+        vset.list()[i].compute_node_id_short(),
+        CryptoSignature::from_bytes(&keys[i].sign(data).unwrap()).unwrap()
+    );
+
+    let mut extra = McBlockExtra::default();
+    let signatures = extra.prev_blk_signatures_mut();
+    assert_eq!(signatures.insert_unique(sign(2, &data), &vset).unwrap(), 0);
+    assert_eq!(signatures.insert_unique(sign(0, &data), &vset).unwrap(), 1);
+    signatures.insert_unique(sign(2, &data), &vset).expect_err("duplicated validator");
+    let stranger = CryptoSignaturePair::with_params(UInt256::rand(), CryptoSignature::default());
+    signatures.insert_unique(stranger.clone(), &vset).expect_err("unknown validator");
+    assert_eq!(signatures.len().unwrap(), 2);
+
+    assert_eq!(extra.check_prev_signatures(&vset, &root_hash, &file_hash).unwrap(), 22);
+    extra.check_prev_signatures(&vset, &file_hash, &root_hash).expect_err("signed other block");
+
+    let mut bad = extra.clone();
+    bad.prev_blk_signatures_mut().set(&2u16, &sign(0, &data)).unwrap();
+    bad.check_prev_signatures(&vset, &root_hash, &file_hash).expect_err("duplicated validator");
+
+    let mut bad = extra.clone();
+    bad.prev_blk_signatures_mut().set(&4u16, &sign(1, &data)).unwrap();
+    bad.check_prev_signatures(&vset, &root_hash, &file_hash).expect_err("index is out of range");
+
+    let mut bad = extra.clone();
+    bad.prev_blk_signatures_mut().set(&2u16, &stranger).unwrap();
+    bad.check_prev_signatures(&vset, &root_hash, &file_hash).expect_err("unknown validator");
+
+    let mut bad = extra;
+    bad.prev_blk_signatures_mut().set(&2u16, &sign(3, &root_hash.as_slice()[..])).unwrap();
+    bad.check_prev_signatures(&vset, &root_hash, &file_hash).expect_err("bad signature");
+}
+
+#[test]
+fn test_check_shard_fees() {
+    let descr = ShardDescr {
+        fees_collected: CurrencyCollection::with_grams(10),
+        funds_created: CurrencyCollection::with_grams(3),
+        ..ShardDescr::default()
+    };
+    let mut extra = McBlockExtra::default();
+    extra.shards_mut().set(&0i32, &InRefValue(BinTree::with_item(&descr).unwrap())).unwrap();
+    extra.check_shard_fees(true).unwrap();
+
+    let shard = ShardIdent::with_workchain_id(0).unwrap();
+    extra.fees_mut().store_shard_fees(
+        &shard, CurrencyCollection::with_grams(10), CurrencyCollection::with_grams(3)
+    ).unwrap();
+    extra.check_shard_fees(true).unwrap();
+
+    let mut bad = extra.clone();
+    bad.fees_mut().store_shard_fees(
+        &shard, CurrencyCollection::with_grams(11), CurrencyCollection::with_grams(3)
+    ).unwrap();
+    bad.check_shard_fees(false).unwrap();
+    bad.check_shard_fees(true).expect_err("fees differ from fees_collected");
+
+    let mut bad = extra.clone();
+    bad.fees_mut().store_shard_fees(
+        &shard, CurrencyCollection::with_grams(10), CurrencyCollection::with_grams(4)
+    ).unwrap();
+    bad.check_shard_fees(true).expect_err("created differ from funds_created");
+
+    let mut bad = extra;
+    bad.fees_mut().store_shard_fees(
+        &ShardIdent::with_workchain_id(1).unwrap(), CurrencyCollection::with_grams(1), CurrencyCollection::default()
+    ).unwrap();
+    bad.check_shard_fees(false).expect_err("shard is absent in shard hashes");
+}
+
+#[test]
+fn test_verify_total_fees() {
+    let descr = ShardDescr {
+        fees_collected: CurrencyCollection::with_grams(10),
+        funds_created: CurrencyCollection::with_grams(3),
+        ..ShardDescr::default()
+    };
+    let shard = ShardIdent::with_workchain_id(0).unwrap();
+    let mut extra = McBlockExtra::default();
+    extra.shards_mut().set(&0i32, &InRefValue(BinTree::with_item(&descr).unwrap())).unwrap();
+    extra.fees_mut().store_shard_fees(
+        &shard, CurrencyCollection::with_grams(10), CurrencyCollection::with_grams(3)
+    ).unwrap();
+
+    let create_fee = CurrencyCollection::with_grams(100);
+    let value_flow = ValueFlow {
+        fees_imported: CurrencyCollection::with_grams(10),
+        fees_collected: CurrencyCollection::with_grams(15),
+        created: CurrencyCollection::with_grams(103),
+        ..ValueFlow::default()
+    };
+    extra.verify_total_fees(&value_flow, &create_fee).unwrap();
+
+    let mut wrong_flow = value_flow.clone();
+    wrong_flow.created = CurrencyCollection::with_grams(3);
+    let err = extra.verify_total_fees(&wrong_flow, &create_fee).unwrap_err().to_string();
+    assert!(err.contains("created is 3"), "{}", err);
+
+    let mut wrong_flow = value_flow.clone();
+    wrong_flow.fees_collected = CurrencyCollection::with_grams(5);
+    let err = extra.verify_total_fees(&wrong_flow, &create_fee).unwrap_err().to_string();
+    assert!(err.contains("fees_imported") && err.contains("less than"), "{}", err);
+
+    let mut bad = extra.clone();
+    bad.fees_mut().store_shard_fees(
+        &shard, CurrencyCollection::with_grams(11), CurrencyCollection::with_grams(3)
+    ).unwrap();
+    let err = bad.verify_total_fees(&value_flow, &create_fee).unwrap_err().to_string();
+    assert!(err.contains("fees_collected 10 of its descr"), "{}", err);
+
+    let mut bad = extra;
+    bad.fees_mut().store_shard_fees(
+        &ShardIdent::with_workchain_id(1).unwrap(), CurrencyCollection::with_grams(1), CurrencyCollection::default()
+    ).unwrap();
+    let err = bad.verify_total_fees(&value_flow, &create_fee).unwrap_err().to_string();
+    assert!(err.contains("is absent in shard hashes"), "{}", err);
+}
+
+#[test]
+fn test_crypto_signatures_weight() {
+    let keys: Vec<_> = (0..4).map(|_| Ed25519KeyOption::generate().unwrap()).collect();
+    let list = keys.iter().enumerate().map(|(i, keypair)| {
+        let key = SigPubKey::from_bytes(keypair.pub_key().unwrap()).unwrap();
+        ValidatorDescr::with_params(key, 10 + i as u64, None, None)
+    }).collect();
+    let vset = ValidatorSet::new(0, 100, 1, list).unwrap();
+    let data = Block::build_data_for_sign(&UInt256::rand(), &UInt256::rand());
+    let sign = |i: usize| CryptoSignaturePair::with_params(
+        vset.list()[i].compute_node_id_short(),
+        CryptoSignature::from_bytes(&keys[i].sign(&data).unwrap()).unwrap()
+    );
+
+    let mut signatures = CryptoSignatures::default();
+    assert_eq!(signatures.total_weight(&vset).unwrap(), 0);
+    assert!(!signatures.is_quorum(&vset, &data).unwrap());
+
+    // total weight is 46, quorum needs more than 30.67
+    signatures.insert_unique(sign(3), &vset).unwrap();
+    signatures.insert_unique(sign(2), &vset).unwrap();
+    assert_eq!(signatures.total_weight(&vset).unwrap(), 25);
+    assert!(!signatures.is_quorum(&vset, &data).unwrap());
+    signatures.insert_unique(sign(0), &vset).unwrap();
+    assert_eq!(signatures.total_weight(&vset).unwrap(), 35);
+    assert!(signatures.is_quorum(&vset, &data).unwrap());
+    assert_eq!(signatures.verified_weight(&vset, &data).unwrap(), 35);
+    signatures.verified_weight(&vset, &data[1..]).expect_err("signed other data");
+    signatures.is_quorum(&vset, &data[1..]).expect_err("signed other data");
+
+    let mut forged = CryptoSignatures::default();
+    for i in 0..4 {
+        let pair = CryptoSignaturePair::with_params(vset.list()[i].compute_node_id_short(), CryptoSignature::default());
+        forged.insert_unique(pair, &vset).unwrap();
+    }
+    assert_eq!(forged.total_weight(&vset).unwrap(), 46);
+    forged.is_quorum(&vset, &data).expect_err("signatures are forged");
+
+    let mut bad = signatures.clone();
+    bad.set(&3u16, &sign(0)).unwrap();
+    bad.is_quorum(&vset, &data).expect_err("duplicated validator");
+
+    let mut bad = signatures;
+    bad.set(&3u16, &CryptoSignaturePair::with_params(UInt256::rand(), CryptoSignature::default())).unwrap();
+    bad.total_weight(&vset).expect_err("unknown validator");
+}
+
+#[test]
+fn test_crypto_signatures_verify_batch() {
+    let keys: Vec<_> = (0..5).map(|_| Ed25519KeyOption::generate().unwrap()).collect();
+    let list = keys.iter().map(|keypair| {
+        let key = SigPubKey::from_bytes(keypair.pub_key().unwrap()).unwrap();
+        ValidatorDescr::with_params(key, 10, None, None)
+    }).collect();
+    let vset = ValidatorSet::new(0, 100, 1, list).unwrap();
+    let data = Block::build_data_for_sign(&UInt256::rand(), &UInt256::rand());
+    let sign = |i: usize, data: &[u8]| CryptoSignaturePair::with_params(
+        vset.list()[i].compute_node_id_short(),
+        CryptoSignature::from_bytes(&keys[i].sign(data).unwrap()).unwrap()
+    );
+    let key_map: HashMap<UInt256, SigPubKey> = vset.list().iter()
+        .map(|vd| (vd.compute_node_id_short(), vd.public_key.clone()))
+        .collect();
+
+    let vset_keys = ValidatorSetKeys::new(&vset);
+
+    let mut signatures = CryptoSignatures::default();
+    signatures.verify_batch(&data, &vset_keys).expect_err("no signatures");
+    for i in 0..keys.len() {
+        signatures.insert_unique(sign(i, &data), &vset).unwrap();
+    }
+    signatures.verify_batch(&data, &vset_keys).unwrap();
+    signatures.verify_batch(&data, &key_map).unwrap();
+    signatures.verify_batch(&data[1..], &key_map).expect_err("signed other data");
+
+    let mut bad = signatures.clone();
+    bad.set(&2u16, &sign(2, &data[1..])).unwrap();
+    let err = bad.verify_batch(&data, &key_map).expect_err("one signature is bad");
+    assert!(err.to_string().contains("signature #2 "), "{}", err);
+
+    let mut partial_map = key_map;
+    partial_map.remove(&vset.list()[4].compute_node_id_short());
+    signatures.verify_batch(&data, &partial_map).expect_err("unknown validator");
+}
+
+#[test]
+fn test_mc_block_extra_semantic_eq() {
+    let build = |opts: u8, lt: u64| {
+        let msg = CommonMessage::Std(Message::with_ext_in_header(Default::default()));
+        let mut tr = if opts == SERDE_OPTS_COMMON_MESSAGE {
+            Transaction::with_common_msg_support(AccountId::from([1; 32]))
+        } else {
+            Transaction::with_address_and_status(AccountId::from([1; 32]), AccountStatus::AccStateNonexist)
+        };
+        tr.set_logical_time(lt);
+        tr.write_in_msg(Some(&msg)).unwrap();
+        let in_msg = InMsg::external(
+            ChildCell::with_struct_and_opts(&msg, opts).unwrap(),
+            ChildCell::with_struct_and_opts(&tr, opts).unwrap(),
+        );
+        let mut extra = if opts == SERDE_OPTS_COMMON_MESSAGE {
+            McBlockExtra::with_common_message_support()
+        } else {
+            McBlockExtra::default()
+        };
+        extra.write_mint_msg(Some(&in_msg)).unwrap();
+        extra
+    };
+
+    let plain = build(SERDE_OPTS_EMPTY, 10);
+    let common = build(SERDE_OPTS_COMMON_MESSAGE, 10);
+    assert_ne!(plain, common);
+    assert!(plain.semantic_eq(&common));
+    assert!(common.semantic_eq(&plain));
+    let restored = McBlockExtra::construct_from_cell_with_opts(
+        common.serialize_with_opts(SERDE_OPTS_COMMON_MESSAGE).unwrap(),
+        SERDE_OPTS_COMMON_MESSAGE,
+    ).unwrap();
+    assert!(restored.semantic_eq(&plain));
+
+    assert!(!plain.semantic_eq(&build(SERDE_OPTS_COMMON_MESSAGE, 11)));
+    let mut no_mint = common.clone();
+    no_mint.write_mint_msg(None).unwrap();
+    assert!(!no_mint.semantic_eq(&plain));
+    assert!(no_mint.semantic_eq(&McBlockExtra::default()));
+}
+
+#[test]
+fn test_mc_block_extra_extension() {
+    let mut mc_extra = build_mc_block_extra(SERDE_OPTS_COMMON_MESSAGE);
+    mc_extra.mesh_descr_mut().set(&7, &build_mesh_descr()).unwrap();
+    let cell = mc_extra.serialize_with_opts(SERDE_OPTS_COMMON_MESSAGE).unwrap();
+
+    let (tag, extension_cell) = McBlockExtra::read_extension_cell(cell.clone()).unwrap();
+    assert_eq!(tag, MC_BLOCK_EXTRA_TAG_3);
+    assert_eq!(extension_cell, mc_extra.extension_cell().unwrap());
+
+    let extension = McBlockExtra::read_extension(cell.clone()).unwrap();
+    assert_eq!(&extension.mesh, mc_extra.mesh_descr());
+    assert_eq!(extension.read_recover_create_msg().unwrap(), mc_extra.read_recover_create_msg().unwrap());
+    assert!(extension.unparsed.is_none());
+
+    let extension = McBlockExtra::read_extension(build_mc_block_extra(0).serialize().unwrap()).unwrap();
+    assert_eq!(extension.tag, MC_BLOCK_EXTRA_TAG);
+    assert!(extension.read_recover_create_msg().unwrap().is_some());
+
+    // newer tag: McBlockExtra can't be parsed but extension can
+    let mut slice = SliceData::load_cell(cell).unwrap();
+    slice.move_by(16).unwrap();
+    let mut builder = BuilderData::new();
+    builder.append_u16(0xdc77).unwrap();
+    builder.checked_append_references_and_data(&slice).unwrap();
+    let cell = builder.into_cell().unwrap();
+    assert!(McBlockExtra::construct_from_cell(cell.clone()).is_err());
+    let extension = McBlockExtra::read_extension(cell).unwrap();
+    assert_eq!(extension.tag, 0xdc77);
+    assert_eq!(extension.read_recover_create_msg().unwrap(), mc_extra.read_recover_create_msg().unwrap());
+    assert!(extension.unparsed.is_some());
+}
+
+#[test]
+fn test_shard_descr_display() {
+    let mut descr = ShardDescr::with_params(
+        15, 100, 200, UInt256::from([1; 32]), FutureSplitMerge::Split { split_utime: 1000, interval: 30 }
+    );
+    descr.gen_utime = 1700000000;
+    descr.want_split = true;
+    descr.before_merge = true;
+    assert_eq!(
+        descr.to_string(),
+        "seq_no: 15, gen_utime: 1700000000, lt: 100..200, flags: before_merge want_split"
+    );
+    descr.collators = Some(ShardCollators::default());
+
+    let verbose = format!("{:#}", descr);
+    assert!(verbose.contains("split_merge_at: split at 1000 in 30\n"));
+    assert!(verbose.contains(&format!("root_hash: {:x}\n", UInt256::from([1; 32]))));
+    assert!(verbose.contains("collators:\n  prev: 0 (0..0)\n"));
+    assert!(verbose.contains("  updated_at: 0\n"));
+
+    let shard = ShardIdent::with_tagged_prefix(BASE_WORKCHAIN_ID, 0x4000_0000_0000_0000).unwrap();
+    let record = McShardRecord::from_shard_descr(shard.clone(), descr.clone());
+    assert_eq!(record.to_string(), format!("{} {}", shard, descr));
+    let verbose = format!("{:#}", record);
+    assert!(verbose.starts_with(&format!("block_id: {}\nseq_no: 15\n", record.block_id)));
+    assert!(!format!("{}", ShardDescr::default()).contains('\n'));
+}
+
+#[test]
+fn test_split_shard_limits() {
+    let mut descr = WorkchainDescr::new();
+    descr.set_min_split(1).unwrap();
+    descr.set_max_split(2).unwrap();
+    let mut workchains = Workchains::default();
+    workchains.set(&0, &descr).unwrap();
+    let mut config = ConfigParams::new();
+    config.set_workchains(workchains).unwrap();
+
+    let limits = ShardSplitLimits::with_config(&config, 0).unwrap();
+    assert_eq!(limits, ShardSplitLimits { min_split: 1, max_split: 2, max_shards: 4 });
+    ShardSplitLimits::with_config(&config, 1).expect_err("unknown workchain");
+
+    let mut shards = ShardHashes::default();
+    shards.add_workchain(0, 1, UInt256::rand(), UInt256::rand(), None).unwrap();
+    let full = ShardIdent::with_workchain_id(0).unwrap();
+    shards.split_shard_with_config(&full, &config, |descr| Ok((descr.clone(), descr))).unwrap();
+    let (left, right) = full.split().unwrap();
+    shards.split_shard_with_config(&left, &config, |descr| Ok((descr.clone(), descr))).unwrap();
+    let (left_left, _) = left.split().unwrap();
+    shards.split_shard_with_config(&left_left, &config, |descr| Ok((descr.clone(), descr)))
+        .expect_err("max split depth");
+    shards.merge_shards_with_config(&full, &config, |descr, _| Ok(descr))
+        .expect_err("min split depth");
+
+    let limits = ShardSplitLimits { min_split: 0, max_split: 60, max_shards: 3 };
+    shards.split_shard_with_limits(&right, &limits, |descr| Ok((descr.clone(), descr)))
+        .expect_err("max shard count");
+    shards.merge_shards_with_config(&left, &config, |descr, _| Ok(descr)).unwrap();
+    shards.split_shard_with_limits(&right, &limits, |descr| Ok((descr.clone(), descr))).unwrap();
+    assert!(shards.get_shard(&right.split().unwrap().1).unwrap().is_some());
+}
+
+#[test]
+fn test_mc_shard_record_priority() {
+    let record = |prefix: u64, seq_no: u32, gen_utime: u32| {
+        let descr = ShardDescr { seq_no, gen_utime, root_hash: UInt256::from([seq_no as u8; 32]), ..Default::default() };
+        McShardRecord::from_shard_descr(ShardIdent::with_tagged_prefix(0, prefix).unwrap(), descr)
+    };
+    let mut records = vec![
+        record(0xC000_0000_0000_0000, 5, 100),
+        record(0x4000_0000_0000_0000, 7, 100),
+        record(0x4000_0000_0000_0000, 6, 100),
+        record(0xC000_0000_0000_0000, 4, 90),
+        record(0x4000_0000_0000_0000, 8, 110),
+    ];
+    let mut reversed = records.clone();
+    reversed.reverse();
+    records.sort_by_key(|r| Reverse(r.priority(120)));
+    reversed.sort_by_key(|r| Reverse(r.priority(120)));
+    assert_eq!(records, reversed);
+    let order: Vec<u32> = records.iter().map(|r| r.descr.seq_no).collect();
+    assert_eq!(order, vec![4, 6, 7, 5, 8]);
+
+    // block from the future has zero age
+    assert_eq!(record(SHARD_FULL, 1, 200).priority(120).0, 0);
+    assert!(records[0].block_id.collation_order_key() > records[1].block_id.collation_order_key());
+}
+
+#[test]
+fn test_mint_and_recover_msgs() {
+    let minter = AccountId::from([2; 32]);
+    let elector = AccountId::from([3; 32]);
+    let special_tr = |msg: &Message, account_id: &AccountId| {
+        let mut tr = Transaction::with_address_and_status(account_id.clone(), AccountStatus::AccStateActive);
+        tr.write_in_msg(Some(&CommonMessage::Std(msg.clone()))).unwrap();
+        tr
+    };
+
+    let mint = McBlockExtra::create_mint_msg(&minter, CurrencyCollection::with_grams(100), 1_000, 1_700_000_000).unwrap();
+    let header = mint.int_header().unwrap();
+    assert_eq!(header.src_ref().unwrap().address(), AccountId::from(UInt256::ZERO));
+    assert_eq!(header.dst, MsgAddressInt::with_standart(None, -1, minter.clone()).unwrap());
+    assert!(header.bounce && header.ihr_disabled);
+    assert_eq!(header.created_lt, 1_000);
+
+    let recover = McBlockExtra::create_recover_msg(CurrencyCollection::with_grams(5), &elector, 1_001, 1_700_000_000).unwrap();
+    assert_eq!(recover.int_header().unwrap().dst, MsgAddressInt::with_standart(None, -1, elector.clone()).unwrap());
+
+    for mut extra in [McBlockExtra::default(), McBlockExtra::with_common_message_support()] {
+        let in_msg = extra.set_mint_msg(&special_tr(&mint, &minter)).unwrap();
+        assert_eq!(extra.read_mint_msg().unwrap(), Some(in_msg.clone()));
+        assert_eq!(in_msg.read_message().unwrap(), mint);
+        extra.set_recover_create_msg(&special_tr(&recover, &elector)).unwrap();
+        assert!(extra.read_recover_create_msg().unwrap().is_some());
+        write_read_and_assert_with_opts(extra.clone(), extra.serde_opts()).unwrap();
+
+        extra.set_mint_msg(&special_tr(&mint, &elector)).expect_err("message is addressed to another account");
+        let mut msg = mint.clone();
+        if let Some(header) = msg.int_header_mut() {
+            header.fwd_fee = 1.into();
+        }
+        extra.set_mint_msg(&special_tr(&msg, &minter)).expect_err("message has forwarding fee");
+        let tr = Transaction::with_address_and_status(minter.clone(), AccountStatus::AccStateActive);
+        extra.set_mint_msg(&tr).expect_err("transaction has no inbound message");
+    }
+}
@@ -151,6 +151,78 @@ fn test_merkle_proof_with_subtrees() {
     );
 }
 
+#[test]
+fn test_merkle_proof_merge() {
+    let c1 = create_cell(&[1, 1, 1], &[]);
+    let c2 = create_cell(&[2, 2, 2], &[]);
+    let c3 = create_cell(&[3, 3, 3], &[&c1]);
+    let c4 = create_cell(&[4, 4, 4], &[&c2]);
+    let c5 = create_cell(&[5, 5, 5], &[&c1, &c2]);
+    let c6 = create_cell(&[6, 6, 6], &[&c3, &c4]);
+    let tree = create_cell(&[1], &[&c5, &c6]);
+
+    let proof_c5 = MerkleProof::create(&tree, |h| h == &tree.repr_hash() || h == &c5.repr_hash()).unwrap();
+    let proof_c3 = MerkleProof::create(
+        &tree,
+        |h| h == &tree.repr_hash() || h == &c6.repr_hash() || h == &c3.repr_hash()
+    ).unwrap();
+    let expected = MerkleProof::create(
+        &tree,
+        |h| h == &tree.repr_hash() || h == &c5.repr_hash() || h == &c6.repr_hash() || h == &c3.repr_hash()
+    ).unwrap();
+
+    let merged = MerkleProof::merge(&proof_c5, &proof_c3).unwrap();
+    assert_eq!(merged, expected);
+    assert_eq!(MerkleProof::merge(&proof_c3, &proof_c5).unwrap(), expected);
+    assert_eq!(MerkleProof::merge(&merged, &proof_c3).unwrap(), expected);
+
+    let virt_tree: Cell = merged.proof.virtualize(1);
+    assert_eq!(virt_tree.repr_hash(), tree.repr_hash());
+    assert_eq!(virt_tree.reference(0).unwrap().reference(1).unwrap().cell_type(), CellType::Ordinary); // c2
+    assert_eq!(virt_tree.reference(1).unwrap().reference(1).unwrap().cell_type(), CellType::PrunedBranch); // c4
+
+    let other = create_cell(&[2], &[&c5, &c6]);
+    let proof_other = MerkleProof::create(&other, |h| h == &other.repr_hash()).unwrap();
+    MerkleProof::merge(&proof_c5, &proof_other).expect_err("proofs of different roots");
+}
+
+#[test]
+fn test_merkle_proof_verify_against() {
+    let c1 = create_cell(&[1, 1, 1], &[]);
+    let c2 = create_cell(&[2, 2, 2], &[&c1]);
+    let c3 = create_cell(&[3, 3, 3], &[&c1]);
+    let tree = create_cell(&[1], &[&c2, &c3]);
+
+    let proof = MerkleProof::create(&tree, |h| h == &tree.repr_hash() || h == &c2.repr_hash()).unwrap();
+    proof.verify_against(&tree.repr_hash()).unwrap();
+
+    let err = proof.verify_against(&c2.repr_hash()).unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<MerkleProofError>(),
+        Some(&MerkleProofError::RootHashMismatch { expected: c2.repr_hash(), actual: tree.repr_hash() })
+    );
+
+    let mut wrong_depth = proof.clone();
+    wrong_depth.depth += 1;
+    let err = wrong_depth.verify_against(&tree.repr_hash()).unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<MerkleProofError>(),
+        Some(&MerkleProofError::InconsistentDepth { hash: tree.repr_hash(), depth: 3, expected: 2 })
+    );
+
+    // pruned branch made for nested Merkle cell
+    let pruned = MerkleUpdate::make_pruned_branch_cell(&c3, 1).unwrap().into_cell().unwrap();
+    let mut root = BuilderData::from_cell(&proof.proof).unwrap();
+    root.replace_reference_cell(1, pruned.clone());
+    let root = root.into_cell().unwrap();
+    let wrong_level = MerkleProof { hash: root.hash(0), depth: root.depth(0), proof: root };
+    let err = wrong_level.verify_against(&tree.repr_hash()).unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<MerkleProofError>(),
+        Some(&MerkleProofError::WrongPrunedBranchLevel { hash: pruned.repr_hash(), level: 1, merkle_depth: 0 })
+    );
+}
+
 #[test]
 fn test_merkle_proof_hi_hashes() {
     // Construct 2 trees & Merkle update for it
@@ -58,6 +58,52 @@ fn test_merkle_update() {
     assert_eq!(new_cell, updated_cell);
 }
 
+fn collect_update_old_hashes(cell: &Cell, hashes: &mut HashSet<UInt256>) {
+    if cell.cell_type() != CellType::PrunedBranch {
+        hashes.insert(cell.hash(0));
+        for child in cell.clone_references().iter() {
+            collect_update_old_hashes(child, hashes);
+        }
+    }
+}
+
+#[test]
+fn test_merkle_update_apply_for_pruned() {
+    let (old_cell, _) = ss_from_file("src/tests/data/7992DD77CEB677577A7D5A8B6F388CDA76B4D0DDE16FF5004C87215E6ADF84DD.boc");
+    let mut state = ShardStateUnsplit::construct_from_cell(old_cell.clone()).unwrap();
+    state.set_seq_no(state.seq_no() + 1);
+    state.set_gen_time(state.gen_time() + 5);
+    let mut shard_acc = None;
+    state.read_accounts().unwrap().iterate_objects(|acc| {
+        shard_acc = Some(acc);
+        Ok(false)
+    }).unwrap();
+    state.insert_account(&UInt256::from([0x55; 32]), &shard_acc.unwrap()).unwrap();
+    let new_cell = state.serialize().unwrap();
+
+    let mupd = MerkleUpdate::create(&old_cell, &new_cell).unwrap();
+
+    // old state contains only cells visited by the update, all the rest are pruned
+    let mut hashes = HashSet::new();
+    collect_update_old_hashes(&mupd.old, &mut hashes);
+    let pruned_old = MerkleProof::create(&old_cell, |hash| hashes.contains(hash)).unwrap().proof;
+    assert_ne!(pruned_old.repr_hash(), old_cell.repr_hash());
+    assert_eq!(pruned_old.hash(0), old_cell.repr_hash());
+
+    let pruned_new = mupd.apply_for_pruned(&pruned_old).unwrap();
+    assert_eq!(pruned_new.hash(0), new_cell.repr_hash());
+    assert!(pruned_new.level() > 0);
+
+    // full tree is also accepted
+    assert_eq!(mupd.apply_for_pruned(&old_cell).unwrap(), new_cell);
+    // wrong tree
+    assert!(mupd.apply_for_pruned(&pruned_new).is_err());
+
+    // update touching pruned part can not be applied
+    let pruned_old = MerkleProof::create(&old_cell, |hash| hash == &old_cell.repr_hash()).unwrap().proof;
+    assert!(mupd.apply_for_pruned(&pruned_old).is_err());
+}
+
 #[test]
 fn test_merkle_update_serialization() {
     let mut acc = generate_test_account_by_init_code_hash(false);
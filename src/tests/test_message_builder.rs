@@ -0,0 +1,108 @@
+/*
+* Copyright (C) 2019-2023 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use super::*;
+use crate::{AccountId, BuilderData, Cell, IBitstring};
+
+fn prices() -> MsgForwardPrices {
+    MsgForwardPrices {
+        lump_price: 1_000_000,
+        bit_price: 65_536,
+        cell_price: 6_553_600,
+        ihr_price_factor: 98_304,
+        first_frac: 21_845,
+        next_frac: 21_845,
+    }
+}
+
+fn dst() -> MsgAddressInt {
+    MsgAddressInt::with_standart(None, 0, AccountId::from([1; 32])).unwrap()
+}
+
+// chain of `len` cells with `bytes` unique bytes of data in every cell
+fn chain(len: usize, bytes: usize, seed: u8) -> Cell {
+    let mut cell = Cell::default();
+    for i in 0..len {
+        let mut builder = BuilderData::new();
+        builder.append_u8(seed).unwrap();
+        builder.append_u32(i as u32).unwrap();
+        builder.append_raw(&vec![0x55; bytes - 5], (bytes - 5) * 8).unwrap();
+        if i != 0 {
+            builder.checked_append_reference(cell).unwrap();
+        }
+        cell = builder.into_cell().unwrap();
+    }
+    cell
+}
+
+#[test]
+fn test_external_inbound_builder() {
+    let builder = MessageBuilder::external_inbound(dst(), None, None);
+    assert_eq!(builder.import_fee(&prices()).unwrap(), Grams::from(1_000_000));
+    let msg = builder.finalize(&prices()).unwrap();
+    assert_eq!(msg.ext_in_header().unwrap().dst, dst());
+    assert!(msg.body().is_none());
+
+    let mut state_init = StateInit::default();
+    state_init.set_code(chain(3, 20, 1));
+    let body = SliceData::load_cell(chain(2, 10, 2)).unwrap();
+    let builder = MessageBuilder::external_inbound(dst(), Some(body.clone()), Some(state_init.clone()));
+    let storage = StorageUsedShort::calculate_for_message_cell(&builder.message().serialize().unwrap()).unwrap();
+    assert!(storage.cells() >= 3);
+    let expected = 1_000_000 + storage.bits() + 100 * storage.cells();
+    assert_eq!(builder.import_fee(&prices()).unwrap(), Grams::from(expected));
+    let msg = builder.finalize(&prices()).unwrap();
+    assert_eq!(msg.state_init(), Some(&state_init));
+    assert_eq!(msg.body(), Some(body));
+
+    let deep = SliceData::load_cell(chain(MAX_EXT_MSG_DEPTH as usize + 1, 8, 3)).unwrap();
+    MessageBuilder::external_inbound(dst(), Some(deep), None).finalize(&prices()).expect_err("message is too deep");
+
+    let mut wide = BuilderData::new();
+    for seed in 0..4 {
+        wide.checked_append_reference(chain(140, 127, seed)).unwrap();
+    }
+    let wide = SliceData::load_builder(wide).unwrap();
+    MessageBuilder::external_inbound(dst(), Some(wide), None).finalize(&prices()).expect_err("message is too big");
+
+    let internal = MessageBuilder { msg: Message::with_int_header(Default::default()) };
+    internal.import_fee(&prices()).expect_err("internal message has no import fee");
+}
+
+#[test]
+fn test_internal_builder() {
+    let src = MsgAddressInt::with_standart(None, 0, AccountId::from([2; 32])).unwrap();
+    let value = CurrencyCollection::with_grams(5_000_000);
+    let msg = MessageBuilder::internal(src.clone(), dst(), value.clone()).finalize(&prices()).unwrap();
+    let header = msg.int_header().unwrap();
+    assert!(header.bounce && header.ihr_disabled && !header.bounced);
+    assert_eq!(header.src_ref(), Some(&src));
+    assert_eq!(header.value, value);
+    // only root cell: lump price without first_frac part
+    assert_eq!(header.fwd_fee, Grams::from(1_000_000 - 333_328));
+    assert_eq!(header.ihr_fee, Grams::zero());
+
+    let body = SliceData::load_cell(chain(2, 10, 2)).unwrap();
+    let builder = MessageBuilder::internal(src, dst(), value)
+        .with_bounce(false)
+        .with_ihr_disabled(false)
+        .with_body(body.clone());
+    let storage = StorageUsedShort::calculate_for_message_cell(&builder.message().serialize().unwrap()).unwrap();
+    let fwd_fee = 1_000_000 + storage.bits() as u128 + 100 * storage.cells() as u128;
+    let msg = builder.finalize(&prices()).unwrap();
+    let header = msg.int_header().unwrap();
+    assert!(!header.bounce);
+    assert_eq!(header.fwd_fee.as_u128(), fwd_fee - ((fwd_fee * 21_845) >> 16));
+    assert_eq!(header.ihr_fee.as_u128(), (fwd_fee * 98_304) >> 16);
+    assert_eq!(msg.body(), Some(body));
+}
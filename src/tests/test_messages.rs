@@ -381,3 +381,90 @@ fn test_msg_address_int_invalid() {
         .expect_err("MsgAddressInt should not be deserialized from None");
 }
 
+
+#[test]
+fn test_state_init_compute_address() {
+    let lib_code = SliceData::new(vec![0x71, 0x80]).into_cell();
+    let lib_hash = lib_code.repr_hash();
+    let mut data = vec![u8::from(CellType::LibraryReference)];
+    data.extend_from_slice(lib_hash.as_slice());
+    let mut lib_cell = BuilderData::with_raw(data, 264).unwrap();
+    lib_cell.set_type(CellType::LibraryReference);
+
+    let mut stinit = StateInit::default();
+    stinit.set_code(lib_cell.into_cell().unwrap());
+    stinit.set_data(SliceData::new(vec![0x55, 0x80]).into_cell());
+    let hash = stinit.serialize().unwrap().repr_hash();
+
+    assert_eq!(stinit.compute_address(0).unwrap(), MsgAddressInt::with_standart(None, 0, hash.clone().into()).unwrap());
+    assert_eq!(
+        stinit.compute_address(1000).unwrap(),
+        MsgAddressInt::with_variant(None, 1000, hash.clone().into()).unwrap()
+    );
+
+    stinit.hash_with_default_libs(|_| Ok(None)).expect_err("library is not resolved");
+    let wrong = SliceData::new(vec![0x72, 0x80]).into_cell();
+    stinit.hash_with_default_libs(|_| Ok(Some(wrong.clone()))).expect_err("library is resolved to other cell");
+    assert_eq!(stinit.hash_with_default_libs(|_| Ok(Some(lib_code.clone()))).unwrap(), hash);
+
+    // own libraries change the hash but resolve library cells
+    stinit.set_library_code(lib_code.clone(), false).unwrap();
+    let hash = stinit.hash_with_default_libs(|_| Ok(None)).unwrap();
+    assert_eq!(stinit.compute_address(0).unwrap().address(), AccountId::from(hash.clone()));
+
+    stinit.set_split_depth(Number5::new(4).unwrap());
+    let addr = stinit.compute_address(0).unwrap();
+    let hash = stinit.serialize().unwrap().repr_hash();
+    assert_eq!(addr.address(), AccountId::from(hash.clone()));
+    assert_eq!(addr.rewrite_pfx().unwrap().rewrite_pfx, SliceData::from_raw(hash.as_slice().to_vec(), 4));
+
+    stinit.set_special(TickTock::with_values(true, false));
+    stinit.compute_address(0).expect_err("special account in basechain");
+    stinit.compute_address(-1).unwrap();
+}
+
+#[test]
+fn test_anycast_rewriting() {
+    let anycast = AnycastInfo::with_rewrite_pfx(SliceData::from_raw(vec![0xA0], 4)).unwrap();
+    assert_eq!(anycast.prefix_u64().unwrap(), 0xA000_0000_0000_0000);
+
+    let addr = MsgAddressInt::with_standart(Some(anycast.clone()), 0, AccountId::from([0x12; 32])).unwrap();
+    addr.check_anycast().unwrap();
+    assert_eq!(addr.anycast(), Some(&anycast));
+    assert_eq!(addr.strip_anycast(), MsgAddressInt::standard(0, [0x12; 32]));
+    let mut rewritten = [0x12; 32];
+    rewritten[0] = 0xA2;
+    assert_eq!(addr.apply_anycast().unwrap(), MsgAddressInt::standard(0, rewritten));
+
+    let prefix = addr.delivery_prefix().unwrap();
+    assert_eq!(prefix, AccountIdPrefixFull::workchain(0, 0xA212_1212_1212_1212));
+    assert_eq!(prefix, AccountIdPrefixFull::prefix(&addr).unwrap());
+    assert_eq!(AccountIdPrefixFull::from_anycast(0, 0x1212_1212_1212_1212, &anycast).unwrap(), prefix);
+    let plain = MsgAddressInt::standard(0, [0x12; 32]);
+    assert_eq!(plain.delivery_prefix().unwrap(), AccountIdPrefixFull::workchain(0, 0x1212_1212_1212_1212));
+    assert_eq!(plain.apply_anycast().unwrap(), plain);
+
+    let addr = MsgAddressInt::with_variant(
+        Some(anycast.clone()), 1000, SliceData::from_raw(vec![0x12; 8], 64)
+    ).unwrap();
+    assert_eq!(
+        addr.apply_anycast().unwrap(),
+        MsgAddressInt::with_variant(None, 1000, SliceData::from_raw(rewritten[..8].to_vec(), 64)).unwrap()
+    );
+
+    let mut addr = plain.clone();
+    addr.set_anycast(Some(anycast.clone())).unwrap();
+    assert_eq!(addr.anycast(), Some(&anycast));
+    addr.set_anycast(None).unwrap();
+    assert_eq!(addr, plain);
+
+    let too_deep = AnycastInfo::with_rewrite_pfx(SliceData::from_raw(vec![0xFF; 4], 31)).unwrap();
+    too_deep.check(256).expect_err("depth is greater than 30");
+    addr.set_anycast(Some(too_deep)).expect_err("depth is greater than 30");
+    AnycastInfo::default().check(256).expect_err("zero depth");
+    let wrong_len = AnycastInfo { depth: Number5::new(5).unwrap(), rewrite_pfx: anycast.rewrite_pfx.clone() };
+    wrong_len.check(256).expect_err("rewrite_pfx length differs from depth");
+    let deep = AnycastInfo::with_rewrite_pfx(SliceData::from_raw(vec![0xFF; 3], 20)).unwrap();
+    let mut short = MsgAddressInt::with_variant(None, 1000, SliceData::from_raw(vec![0x12; 2], 16)).unwrap();
+    short.set_anycast(Some(deep)).expect_err("depth is greater than address length");
+}
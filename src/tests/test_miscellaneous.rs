@@ -15,9 +15,9 @@ use super::*;
 use std::str::FromStr;
 use crate::{
     write_read_and_assert,
-    Block, CurrencyCollection, InternalMessageHeader,
+    AccountIdPrefixFull, Block, CurrencyCollection, InternalMessageHeader,
     IntermediateAddress, ShardIdent,
-    MsgAddressInt, MsgEnvelope, Message, AccountId,
+    MsgAddressInt, MsgEnvelope, Message, AccountId, EnqueuedMsg,
 };
 
 #[test]
@@ -108,6 +108,75 @@ fn test_process_info() {
     }).unwrap();
 }
 
+fn enqueued_msg(lt: u64) -> EnqueuedMsg {
+    let src = AccountId::from_str("d78b3fd904191a09d111af6bd6aee2c891ee19edd419e40520e3312f68cbcec1").unwrap();
+    let dst = AccountId::from_str("9dd300cee029b9c799ef1c8317554a937c80aa475fb1324f0e22e80ac7a55ca3").unwrap();
+    let hdr = InternalMessageHeader::with_addresses_and_bounce(
+        MsgAddressInt::with_standart(None, 0, src).unwrap(),
+        MsgAddressInt::with_standart(None, 0, dst).unwrap(),
+        CurrencyCollection::with_grams(1000),
+        true);
+    let mut msg = Message::with_int_header(hdr);
+    msg.set_at_and_lt(0, lt);
+    let mut env = MsgEnvelope::with_message_and_fee(&msg, 100.into()).unwrap();
+    env.set_next_addr(IntermediateAddress::use_dest_bits(96).unwrap());
+    EnqueuedMsg::with_param(lt, &env).unwrap()
+}
+
+#[test]
+fn test_processed_info_already_processed() {
+    let end_lt = |lt: u64| move |mc_seqno: u32, prefix: &AccountIdPrefixFull| -> Result<u64> {
+        assert_eq!(mc_seqno, 2);
+        assert_eq!(prefix.prefix >> 56, 0xd7);
+        Ok(lt)
+    };
+    let mut info = ProcessedInfo::default();
+    info.set(
+        &ProcessedInfoKey::with_params(0x4000_0000_0000_0000, 1),
+        &ProcessedUpto::with_params(1000, UInt256::from([0xff; 32]), None),
+    ).unwrap();
+    assert!(!info.already_processed(&enqueued_msg(150), end_lt(300)).unwrap());
+
+    // next hop is in the shard of the record, but the current one is not
+    info.set(
+        &ProcessedInfoKey::with_params(0xa000_0000_0000_0000, 2),
+        &ProcessedUpto::with_params(200, UInt256::default(), None),
+    ).unwrap();
+    assert!(info.already_processed(&enqueued_msg(150), end_lt(300)).unwrap());
+    assert!(!info.already_processed(&enqueued_msg(150), end_lt(150)).unwrap());
+    assert!(!info.already_processed(&enqueued_msg(200), end_lt(300)).unwrap());
+    assert!(!info.already_processed(&enqueued_msg(250), end_lt(300)).unwrap());
+
+    // both current and next hop are in the shard of the record
+    info.set(
+        &ProcessedInfoKey::with_params(0xc000_0000_0000_0000, 3),
+        &ProcessedUpto::with_params(200, UInt256::default(), None),
+    ).unwrap();
+    assert!(info.already_processed(&enqueued_msg(150), end_lt(100)).unwrap());
+    assert!(!info.already_processed(&enqueued_msg(250), end_lt(300)).unwrap());
+}
+
+#[test]
+fn test_processed_info_compactify() {
+    let records = [
+        (0x8000_0000_0000_0000, 5, 300),
+        (0xc000_0000_0000_0000, 4, 200), // dominated by the first one
+        (0x4000_0000_0000_0000, 6, 100),
+        (0xc000_0000_0000_0000, 7, 50),
+    ];
+    let mut info = ProcessedInfo::default();
+    for (shard, mc_seqno, lt) in records {
+        info.set(
+            &ProcessedInfoKey::with_params(shard, mc_seqno),
+            &ProcessedUpto::with_params(lt, UInt256::from([1; 32]), None),
+        ).unwrap();
+    }
+    assert_eq!(info.compactify().unwrap(), 1);
+    assert_eq!(info.len().unwrap(), 3);
+    assert!(info.get(&ProcessedInfoKey::with_params(0xc000_0000_0000_0000, 4)).unwrap().is_none());
+    assert_eq!(info.compactify().unwrap(), 0);
+}
+
 #[test]
 fn test_find_shards_by_routing_custom() {
     // message from 0xd8... to 0x9C...
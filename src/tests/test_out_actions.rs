@@ -104,6 +104,32 @@ fn test_outactions_serialization() {
     assert_eq!(oa, oa_restored);
 }
 
+#[test]
+fn test_parse_out_actions() {
+    let oa = get_out_actions();
+    assert_eq!(parse_out_actions(oa.serialize().unwrap()).unwrap(), oa);
+    assert!(parse_out_actions(Cell::default()).unwrap().is_empty());
+
+    let mut too_many = OutActions::new();
+    for _ in 0..=MAX_OUT_ACTIONS {
+        too_many.push_back(OutAction::new_reserve(RESERVE_EXACTLY, CurrencyCollection::with_grams(1)));
+    }
+    validate_out_actions(&too_many).expect_err("too many actions");
+    parse_out_actions(too_many.serialize().unwrap()).expect_err("too many actions");
+    too_many.pop_back();
+    assert_eq!(parse_out_actions(too_many.serialize().unwrap()).unwrap().len(), MAX_OUT_ACTIONS);
+
+    let mut invalid = OutActions::new();
+    invalid.push_back(OutAction::new_send(SENDMSG_ALL_BALANCE | SENDMSG_REMAINING_MSG_BALANCE, Message::default()));
+    parse_out_actions(invalid.serialize().unwrap()).expect_err("invalid send mode");
+    let mut invalid = OutActions::new();
+    invalid.push_back(OutAction::new_reserve(16, CurrencyCollection::default()));
+    validate_out_actions(&invalid).expect_err("invalid reserve mode");
+    let mut invalid = OutActions::new();
+    invalid.push_back(OutAction::None);
+    validate_out_actions(&invalid).expect_err("empty action");
+}
+
 // TODO: move to anythere
 // #[test]
 // fn test_tvm_serialize_currency_collection() {
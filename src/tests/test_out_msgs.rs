@@ -14,6 +14,7 @@
 use super::*;
 use crate::{
     AccountStatus, HashUpdate, InMsgExternal, InternalMessageHeader, MsgAddressInt, 
+    ProcessedInfoKey, ProcessedUpto,
     StateInit, TickTock, TransactionDescr, write_read_and_assert,
     types::{Grams, Number5}
 };
@@ -391,6 +392,36 @@ fn test_out_msg_queue_and_info()
     write_read_and_assert(omq_info);
 }
 
+#[test]
+fn test_out_msg_queue_diff() {
+    let env = |n: u8| {
+        let msg = get_message_with_addrs(create_account_id(1), create_account_id(n));
+        MsgEnvelope::with_message_and_fee(&msg, Grams::one()).unwrap()
+    };
+    let mut old_queue = OutMsgQueue::default();
+    for n in 1..5 {
+        old_queue.insert(0, n as u64, &env(n), 10 + n as u64).unwrap();
+    }
+    let mut new_queue = old_queue.clone();
+    let removed = OutMsgQueueKey::with_workchain_id_and_prefix(0, 2, env(2).message_cell().repr_hash());
+    new_queue.remove(SliceData::load_builder(removed.write_to_new_cell().unwrap()).unwrap()).unwrap();
+    new_queue.insert(0, 7, &env(7), 17).unwrap();
+    new_queue.insert(-1, 8, &env(8), 18).unwrap();
+
+    let diff = OutMsgQueueDiff::with_queues(&old_queue, &new_queue).unwrap();
+    assert_eq!(diff.added().len().unwrap(), 2);
+    assert_eq!(diff.removed().len().unwrap(), 1);
+    assert!(diff.removed().get(&removed).unwrap().is_some());
+    let diff = write_read_and_assert(diff);
+
+    let mut queue = old_queue.clone();
+    diff.apply_to(&mut queue).unwrap();
+    assert_eq!(queue, new_queue);
+    diff.apply_to(&mut queue).expect_err("removed message is already applied");
+
+    assert!(OutMsgQueueDiff::with_queues(&new_queue, &new_queue).unwrap().is_empty());
+}
+
 #[test]
 fn test_enqueued_msg() {
     
@@ -533,4 +564,43 @@ fn test_outmsg_serde_with_cmnmsg_success() {
             };
         }
     }
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_out_msg_queue_clean_processed() {
+    let mut queue = OutMsgQueue::default();
+    for (n, prefix) in [0x1000_0000_0000_0000u64, 0x9000_0000_0000_0000u64].iter().enumerate() {
+        for lt in 10..13 {
+            let mut msg = get_message_with_addrs(create_account_id(n as u8), create_account_id(lt as u8));
+            msg.set_at_and_lt(0, lt);
+            let env = MsgEnvelope::with_message_and_fee(&msg, Grams::one()).unwrap();
+            queue.insert(0, *prefix, &env, lt).unwrap();
+        }
+    }
+    let mut info = OutMsgQueueInfo::with_params(queue, ProcessedInfo::default(), IhrPendingInfo::default());
+
+    // left neighbor has processed messages up to lt 11, right one has processed nothing
+    let mut processed = ProcessedInfo::default();
+    processed.set(
+        &ProcessedInfoKey::with_params(0x4000_0000_0000_0000, 1),
+        &ProcessedUpto::with_params(11, UInt256::from([0xFF; 32]), None),
+    ).unwrap();
+    processed.set(
+        &ProcessedInfoKey::with_params(0xC000_0000_0000_0000, 1),
+        &ProcessedUpto::with_params(11, UInt256::default(), None),
+    ).unwrap();
+
+    let stats = info.clean_processed(0, &processed, 1).unwrap();
+    assert_eq!((stats.deleted, stats.finished), (1, false));
+    assert_eq!(info.out_queue().len().unwrap(), 5);
+
+    // exactly the limit of delivered messages is left
+    let stats = info.clean_processed(0, &processed, 2).unwrap();
+    assert_eq!(stats, OutQueueCleanStats { checked: 5, deleted: 2, finished: true });
+    assert_eq!(info.out_queue().len().unwrap(), 3);
+
+    // other workchain
+    let stats = info.clean_processed(1, &processed, 10).unwrap();
+    assert_eq!(stats.deleted, 0);
+    write_read_and_assert(info);
+}
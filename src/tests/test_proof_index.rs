@@ -0,0 +1,91 @@
+/*
+* Copyright (C) 2019-2023 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use super::*;
+use crate::{
+    write_boc, write_read_and_assert, BlockExtra, MerkleUpdate, ShardIdent, UInt256, ValueFlow,
+};
+
+fn mc_block(seq_no: u32, prev_key_block_seqno: u32, key_block: bool) -> (BlockInfo, BlockProof) {
+    let mut info = BlockInfo::new();
+    info.set_shard(ShardIdent::masterchain());
+    info.set_seq_no(seq_no).unwrap();
+    info.set_key_block(key_block);
+    info.set_prev_key_block_seqno(prev_key_block_seqno);
+    let block = Block::with_params(
+        0, info.clone(), ValueFlow::default(), MerkleUpdate::default(), BlockExtra::new()
+    ).unwrap();
+    let root = block.serialize().unwrap();
+    let id = BlockIdExt::with_params(
+        ShardIdent::masterchain(),
+        seq_no,
+        root.repr_hash(),
+        UInt256::calc_file_hash(&write_boc(&root).unwrap()),
+    );
+    let proof = MerkleProof::create(&root, |_| true).unwrap();
+    (info, BlockProof::with_params(id, proof.serialize().unwrap(), None))
+}
+
+#[test]
+fn test_mc_proof_index() {
+    let mut index = McProofIndex::new();
+    assert!(index.proof_path(0, 0).is_err());
+
+    let (_, kb10) = mc_block(10, 0, true);
+    let (_, kb20) = mc_block(20, 10, true);
+    let (_, kb30) = mc_block(30, 20, true);
+    let (info35, block35) = mc_block(35, 30, false);
+
+    // not a key block
+    assert!(index.add_key_block_proof(&block35).is_err());
+    // proof for other block
+    let mut wrong = kb10.clone();
+    wrong.proof_for.root_hash = UInt256::rand();
+    assert!(index.add_key_block_proof(&wrong).is_err());
+
+    index.add_key_block_proof(&kb10).unwrap();
+    index.add_key_block_proof(&kb30).unwrap();
+    index.add_block_header(block35.proof_for.clone(), &info35).unwrap();
+    assert_eq!(index.key_blocks_count(), 2);
+    assert_eq!(index.last_block().unwrap().id, block35.proof_for);
+
+    // key block 20 is missing
+    assert!(index.proof_path(0, 15).is_err());
+    // key block 20 must refer to 10
+    let (_, forged20) = mc_block(20, 5, true);
+    assert!(index.add_key_block_proof(&forged20).is_err());
+    index.add_key_block_proof(&kb20).unwrap();
+    index.add_key_block_proof(&kb20).unwrap();
+    assert_eq!(index.prev_key_block(25).unwrap().id, kb20.proof_for);
+
+    let path = index.proof_path(0, 15).unwrap();
+    assert_eq!(path.key_blocks, vec![kb10.proof_for.clone()]);
+    assert_eq!(path.prover, kb20.proof_for);
+
+    let path = index.proof_path(0, 20).unwrap();
+    assert_eq!(path.prover, kb20.proof_for);
+
+    let path = index.proof_path(10, 33).unwrap();
+    assert_eq!(path.key_blocks, vec![kb20.proof_for.clone(), kb30.proof_for.clone()]);
+    assert_eq!(path.prover, block35.proof_for);
+
+    let path = index.proof_path(20, 5).unwrap();
+    assert!(path.key_blocks.is_empty());
+    assert_eq!(path.prover, kb20.proof_for);
+
+    assert!(index.proof_path(0, 36).is_err());
+
+    let index = write_read_and_assert(index);
+    assert_eq!(index.proof_path(0, 15).unwrap().prover, kb20.proof_for);
+    write_read_and_assert(McProofIndex::default());
+}
@@ -878,3 +878,161 @@ fn test_shards_heighbors() {
     assert!(shard1.is_neighbor_for(&shard2));
     assert!(!shard1.is_neighbor_for(&shard3));
 }
+
+#[test]
+fn test_build_account_proof() {
+    let bytes = std::fs::read(
+        "src/tests/data/7992DD77CEB677577A7D5A8B6F388CDA76B4D0DDE16FF5004C87215E6ADF84DD.boc"
+    ).unwrap();
+    let root = read_single_root_boc(bytes).unwrap();
+    let state = ShardStateUnsplit::construct_from_cell(root.clone()).unwrap();
+
+    let mut count = 0;
+    state.read_accounts().unwrap().iterate_with_keys(|id, shard_acc| {
+        let account = shard_acc.read_account()?;
+        let proof = state.build_account_proof(&SliceData::from(&id))?;
+        assert_eq!(proof.hash, root.repr_hash());
+        let block_id = crate::check_account_proof(&proof, &account)?;
+        assert_eq!(block_id.seq_no, state.seq_no());
+        count += 1;
+        Ok(count < 5)
+    }).unwrap();
+    assert!(count > 0);
+
+    let unknown = SliceData::from(&UInt256::from([0x11; 32]));
+    assert!(state.build_account_proof(&unknown).is_err());
+
+    // state with common messages keeps its options in the proof
+    let mut state = ShardStateUnsplit::with_ident_and_opts(ShardIdent::full(0), SERDE_OPTS_COMMON_MESSAGE);
+    let address = MsgAddressInt::with_standart(None, 0, AccountId::from([1; 32])).unwrap();
+    let account = Account::with_address_and_ballance(&address, &CurrencyCollection::with_grams(100));
+    state.insert_account(&UInt256::from([1; 32]), &ShardAccount::with_params(&account, UInt256::default(), 0).unwrap()).unwrap();
+    let proof = state.build_account_proof(&SliceData::from(&UInt256::from([1; 32]))).unwrap();
+    assert_eq!(proof.hash, state.serialize().unwrap().repr_hash());
+    let virt_state = ShardStateUnsplit::construct_from_cell(proof.proof.virtualize(1)).unwrap();
+    assert_eq!(virt_state.serde_opts(), SERDE_OPTS_COMMON_MESSAGE);
+}
+
+#[test]
+fn test_state_totals() {
+    let mut state = ShardStateUnsplit::default();
+    state.verify_totals().unwrap();
+
+    let account = |id: u8, grams: u64| {
+        let address = MsgAddressInt::with_standart(None, 0, AccountId::from([id; 32])).unwrap();
+        let account = Account::with_address_and_ballance(&address, &CurrencyCollection::with_grams(grams));
+        ShardAccount::with_params(&account, UInt256::default(), 0).unwrap()
+    };
+    let id1 = UInt256::from([1; 32]);
+    let id2 = UInt256::from([2; 32]);
+
+    assert!(state.update_account(&id1, &account(1, 100)).unwrap().is_none());
+    assert!(state.update_account(&id2, &account(2, 50)).unwrap().is_none());
+    assert_eq!(state.total_balance(), &CurrencyCollection::with_grams(150));
+    state.verify_totals().unwrap();
+
+    let prev = state.update_account(&id1, &account(1, 30)).unwrap().unwrap();
+    assert_eq!(prev, account(1, 100));
+    assert_eq!(state.total_balance(), &CurrencyCollection::with_grams(80));
+    state.verify_totals().unwrap();
+
+    assert_eq!(state.remove_account(&id2).unwrap().unwrap(), account(2, 50));
+    assert!(state.remove_account(&id2).unwrap().is_none());
+    assert_eq!(state.total_balance(), &CurrencyCollection::with_grams(30));
+    state.verify_totals().unwrap();
+
+    // drift is detected
+    state.insert_account(&id2, &account(2, 10)).unwrap();
+    state.verify_totals().expect_err("total balance is not updated");
+
+    state.add_validator_fees(&CurrencyCollection::with_grams(7)).unwrap();
+    state.withdraw_validator_fees(&CurrencyCollection::with_grams(5)).unwrap();
+    assert_eq!(state.total_validator_fees(), &CurrencyCollection::with_grams(2));
+    state.withdraw_validator_fees(&CurrencyCollection::with_grams(5)).expect_err("not enough fees");
+    assert_eq!(state.total_validator_fees(), &CurrencyCollection::with_grams(2));
+}
+
+#[test]
+fn test_shard_ident_ancestors_descendants() {
+    let full = ShardIdent::with_workchain_id(0).unwrap();
+    assert_eq!(full.ancestors().count(), 0);
+    assert_eq!(full.descendants(0).unwrap().collect::<Vec<_>>(), vec![full.clone()]);
+    let (left, right) = full.split().unwrap();
+    assert_eq!(full.descendants(1).unwrap().collect::<Vec<_>>(), vec![left.clone(), right.clone()]);
+
+    let shard = ShardIdent::with_tagged_prefix(0, 0x5400000000000000).unwrap();
+    let ancestors = shard.ancestors().collect::<Vec<_>>();
+    assert_eq!(ancestors.len(), shard.prefix_len() as usize);
+    assert_eq!(ancestors[0], shard.merge().unwrap());
+    assert_eq!(ancestors.last(), Some(&full));
+    assert!(ancestors.iter().all(|ancestor| ancestor.is_ancestor_for(&shard)));
+
+    let descendants = shard.descendants(3).unwrap().collect::<Vec<_>>();
+    assert_eq!(descendants.len(), 8);
+    for (i, descendant) in descendants.iter().enumerate() {
+        assert!(shard.is_ancestor_for(descendant));
+        assert_eq!(descendant.prefix_len(), shard.prefix_len() + 3);
+        if i > 0 {
+            assert!(descendants[i - 1].shard_prefix_with_tag() < descendant.shard_prefix_with_tag());
+        }
+    }
+    let (l, r) = shard.split().unwrap();
+    let (ll, _) = l.split().unwrap();
+    let (lll, _) = ll.split().unwrap();
+    let (_, rr) = r.split().unwrap();
+    let (_, rrr) = rr.split().unwrap();
+    assert_eq!(descendants[0], lll);
+    assert_eq!(descendants[7], rrr);
+
+    let deepest = ShardIdent::with_prefix_len(MAX_SPLIT_DEPTH, 0, 0x1234_5678_9abc_def0).unwrap();
+    assert_eq!(deepest.descendants(0).unwrap().count(), 1);
+    deepest.descendants(1).expect_err("max split depth");
+    shard.descendants(MAX_SPLIT_DEPTH).expect_err("max split depth");
+
+    let path = lll.path_from(&full).unwrap().collect::<Vec<_>>();
+    assert_eq!(path.len(), lll.prefix_len() as usize + 1);
+    assert_eq!(path[0], full);
+    assert_eq!(path[path.len() - 4], shard);
+    assert_eq!(path.last(), Some(&lll));
+    assert_eq!(shard.path_from(&shard).unwrap().collect::<Vec<_>>(), vec![shard.clone()]);
+    shard.path_from(&lll).expect_err("not an ancestor");
+    shard.path_from(&ShardIdent::with_workchain_id(-1).unwrap()).expect_err("other workchain");
+}
+
+#[test]
+fn test_state_libraries() {
+    let lib1 = BuilderData::with_raw(vec![1, 2, 3], 24).unwrap().into_cell().unwrap();
+    let lib2 = BuilderData::with_raw(vec![4, 5, 6], 24).unwrap().into_cell().unwrap();
+    let alice = AccountId::from([1; 32]);
+    let bob = AccountId::from([2; 32]);
+
+    let mut state = ShardStateUnsplit::with_ident(ShardIdent::with_workchain_id(0).unwrap());
+    state.add_library(lib1.clone(), &alice).expect_err("libraries are allowed only in masterchain");
+
+    let mut state = ShardStateUnsplit::with_ident(ShardIdent::masterchain());
+    assert!(state.add_library(lib1.clone(), &alice).unwrap());
+    assert!(!state.add_library(lib1.clone(), &alice).unwrap());
+    assert!(state.add_library(lib1.clone(), &bob).unwrap());
+    assert!(state.add_library(lib2.clone(), &bob).unwrap());
+    assert_eq!(state.libraries().len().unwrap(), 2);
+    let descr = state.libraries().get(&lib1.repr_hash()).unwrap().unwrap();
+    assert_eq!(descr.lib(), &lib1);
+    assert_eq!(descr.publishers().len().unwrap(), 2);
+    state.libraries().validate().unwrap();
+
+    assert!(!state.remove_publisher(&lib2.repr_hash(), &alice).unwrap());
+    assert!(state.remove_publisher(&lib1.repr_hash(), &alice).unwrap());
+    assert_eq!(state.libraries().get(&lib1.repr_hash()).unwrap().unwrap().publishers().len().unwrap(), 1);
+    assert!(state.remove_publisher(&lib1.repr_hash(), &bob).unwrap());
+    assert!(state.libraries().get(&lib1.repr_hash()).unwrap().is_none());
+    assert!(!state.remove_publisher(&lib1.repr_hash(), &bob).unwrap());
+    assert_eq!(state.libraries().len().unwrap(), 1);
+
+    let state = ShardStateUnsplit::construct_from_cell(state.serialize().unwrap()).unwrap();
+    state.libraries().validate().unwrap();
+
+    let mut libraries = Libraries::default();
+    libraries.set(&lib1.repr_hash(), &LibDescr::from_lib_data_by_publisher(lib2, alice.clone())).unwrap();
+    libraries.validate().expect_err("library is stored with wrong key");
+    libraries.add_library(lib1, &bob).expect_err("library is stored with wrong key");
+}
@@ -12,7 +12,7 @@
 */
 
 use crate::{
-    generate_test_account_by_init_code_hash, write_read_and_assert,
+    generate_test_account_by_init_code_hash, write_read_and_assert, MsgAddressInt, StateInit,
 };
 use super::*;
 
@@ -26,3 +26,125 @@ fn test_serialization_shard_account() {
     }
     write_read_and_assert(shard_acc);
 }
+
+fn account_with_data(id: u8, data_len: usize) -> (UInt256, ShardAccount) {
+    let account_id = UInt256::from([id; 32]);
+    let address = MsgAddressInt::with_standart(None, 0, AccountId::from([id; 32])).unwrap();
+    let mut data = BuilderData::new();
+    for i in 0..data_len {
+        let mut child = BuilderData::new();
+        child.append_u8(id).unwrap();
+        child.append_u32(i as u32).unwrap();
+        data.checked_append_reference(child.into_cell().unwrap()).unwrap();
+    }
+    let mut state_init = StateInit::default();
+    state_init.set_data(data.into_cell().unwrap());
+    let account = Account::active_by_init_code_hash(
+        address, CurrencyCollection::with_grams(100), 0, state_init, false
+    ).unwrap();
+    (account_id, ShardAccount::with_params(&account, UInt256::default(), 0).unwrap())
+}
+
+#[test]
+fn test_compare_account_sizes() {
+    let mut old_state = ShardStateUnsplit::default();
+    for (id, data_len) in [(1, 1), (2, 1), (3, 4)] {
+        let (account_id, account) = account_with_data(id, data_len);
+        old_state.insert_account(&account_id, &account).unwrap();
+    }
+    let mut new_state = old_state.clone();
+    assert!(compare_account_sizes(&old_state, &new_state).unwrap().is_empty());
+
+    // 1 grows, 2 is unchanged, 3 shrinks, 4 is new
+    for (id, data_len) in [(1, 3), (3, 2), (4, 1)] {
+        let (account_id, account) = account_with_data(id, data_len);
+        new_state.insert_account(&account_id, &account).unwrap();
+    }
+
+    let deltas = compare_account_sizes(&old_state, &new_state).unwrap();
+    let ids: Vec<_> = deltas.iter().map(|delta| delta.account_id.clone()).collect();
+    assert_eq!(ids, vec![UInt256::from([4; 32]), UInt256::from([1; 32]), UInt256::from([3; 32])]);
+    assert_eq!(deltas[0].old_used, StorageUsed::default());
+    assert!(deltas[0].bits_delta() > 80);
+    assert_eq!(deltas[1].cells_delta(), 2);
+    assert_eq!(deltas[1].bits_delta(), 80);
+    assert_eq!(deltas[2].cells_delta(), -2);
+    assert_eq!(deltas[2].bits_delta(), -80);
+
+    let deltas = compare_account_sizes(&new_state, &old_state).unwrap();
+    assert_eq!(deltas[0].account_id, UInt256::from([3; 32]));
+    assert_eq!(deltas[2].account_id, UInt256::from([4; 32]));
+    assert_eq!(deltas[2].new_used, StorageUsed::default());
+}
+
+#[test]
+fn test_iterate_range() {
+    let mut accounts = ShardAccounts::default();
+    let page = accounts.iterate_range(AccountId::from([0; 32]), 10).unwrap();
+    assert_eq!(page, ShardAccountsPage::default());
+
+    for id in (1..=20).map(|id| id * 10) {
+        let (account_id, account) = account_with_data(id, 0);
+        let aug = account.aug().unwrap();
+        accounts.set(&account_id, &account, &aug).unwrap();
+    }
+
+    let mut ids = Vec::new();
+    let mut from = Some(AccountId::from([0; 32]));
+    while let Some(start) = from {
+        let page = accounts.iterate_range(start.clone(), 3).unwrap();
+        assert!(page.accounts.len() <= 3);
+        assert!(page.accounts.iter().all(|(id, _)| id >= &start));
+        ids.extend(page.accounts.iter().map(|(id, _)| id.clone()));
+        from = page.next;
+    }
+    let mut expected = Vec::new();
+    accounts.iterate_with_keys(|id: UInt256, _| {
+        expected.push(AccountId::from(id));
+        Ok(true)
+    }).unwrap();
+    assert_eq!(ids.len(), 20);
+    assert_eq!(ids, expected);
+
+    // start between keys and on the exact key
+    let page = accounts.iterate_range(AccountId::from([15; 32]), 2).unwrap();
+    let ids: Vec<_> = page.accounts.iter().map(|(id, _)| id.clone()).collect();
+    assert_eq!(ids, vec![AccountId::from([20; 32]), AccountId::from([30; 32])]);
+    assert_eq!(page.next, Some(AccountId::from([40; 32])));
+    assert_eq!(page.accounts[0].1, accounts.account(&AccountId::from([20; 32])).unwrap().unwrap());
+
+    let page = accounts.iterate_range(AccountId::from([200; 32]), 2).unwrap();
+    assert_eq!(page.accounts.len(), 1);
+    assert_eq!(page.next, None);
+
+    let page = accounts.iterate_range(AccountId::from([201; 32]), 2).unwrap();
+    assert!(page.accounts.is_empty());
+
+    let page = accounts.iterate_range(AccountId::from([10; 32]), 0).unwrap();
+    assert!(page.accounts.is_empty());
+    assert_eq!(page.next, Some(AccountId::from([10; 32])));
+}
+
+#[test]
+fn test_check_augmentation() {
+    let mut accounts = ShardAccounts::default();
+    assert_eq!(accounts.check_augmentation().unwrap(), None);
+    for id in 1..=5 {
+        let (account_id, account) = account_with_data(id * 30, 0);
+        let aug = account.aug().unwrap();
+        accounts.set(&account_id, &account, &aug).unwrap();
+    }
+    assert_eq!(accounts.check_augmentation().unwrap(), None);
+
+    let (account_id, account) = account_with_data(90, 0);
+    let wrong_aug = DepthBalanceInfo::new(0, &CurrencyCollection::with_grams(1)).unwrap();
+    accounts.set_builder_serialized(
+        SliceData::from(&account_id), &account.write_to_new_cell().unwrap(), &wrong_aug
+    ).unwrap();
+    assert_eq!(accounts.full_balance(), &CurrencyCollection::with_grams(401));
+
+    let mismatch = accounts.check_augmentation().unwrap().unwrap();
+    assert_eq!(mismatch.key, SliceData::from(&account_id));
+    assert_eq!(mismatch.stored, wrong_aug);
+    assert_eq!(mismatch.computed, account.aug().unwrap());
+}
@@ -16,7 +16,8 @@ use std::{fs::File, io::Read};
 
 use crate::{
     Block, ShardIdent, TopBlockDescr, write_read_and_assert,
-    config_params::ConfigParamEnum, read_boc, Cell, UInt256,
+    config_params::{CatchainConfig, ConfigParamEnum}, read_boc, Cell, UInt256,
+    CryptoProvider, DefaultCryptoProvider, set_crypto_provider,
 };
 
 #[test]
@@ -466,3 +467,129 @@ fn test_check_block_signature() {
       assert!(result.is_err());
     }
 }
+
+#[test]
+fn test_block_proof_check_signatures() {
+    let keys: Vec<_> = (0..4).map(|_| Ed25519KeyOption::generate().unwrap()).collect();
+    let list: Vec<_> = keys.iter().enumerate().map(|(i, key)| {
+        let key = SigPubKey::from_bytes(key.pub_key().unwrap()).unwrap();
+        ValidatorDescr::with_params(key, 10 + i as u64, None, None)
+    }).collect();
+    let vset = ValidatorSet::new(0, u32::MAX, 4, list.clone()).unwrap();
+    let cc_seqno = 7;
+    let cc_config = CatchainConfig::default();
+    let hash_short = ValidatorSet::calc_subset_hash_short(&list, cc_seqno).unwrap();
+
+    let id = BlockIdExt::with_params(ShardIdent::masterchain(), 1, UInt256::rand(), UInt256::rand());
+    let data = Block::build_data_for_sign(&id.root_hash, &id.file_hash);
+    let sign = |signers: &[usize], hash_short: u32| {
+        let mut pure = BlockSignaturesPure::new();
+        for i in signers {
+            pure.add_sigpair(CryptoSignaturePair::with_params(
+                list[*i].compute_node_id_short(),
+                CryptoSignature::from_bytes(&keys[*i].sign(&data).unwrap()).unwrap(),
+            ));
+        }
+        let signatures = BlockSignatures::with_params(ValidatorBaseInfo::with_params(hash_short, cc_seqno), pure);
+        BlockProof::with_params(id.clone(), Cell::default(), Some(signatures))
+    };
+
+    let proof = sign(&[0, 1, 2, 3], hash_short);
+    assert_eq!(proof.check_signatures(&vset, &cc_config, 0).unwrap(), 46);
+    assert_eq!(proof.check_signatures(&vset, &cc_config, 100).unwrap(), 46);
+
+    // 36 of 46
+    let proof = sign(&[1, 2, 3], hash_short);
+    assert_eq!(proof.check_signatures(&vset, &cc_config, 0).unwrap(), 36);
+    assert!(proof.check_signatures(&vset, &cc_config, 80).is_err());
+    // 21 of 46
+    assert!(sign(&[0, 1], hash_short).check_signatures(&vset, &cc_config, 0).is_err());
+    // duplicated signature
+    assert!(sign(&[0, 1, 1, 2], hash_short).check_signatures(&vset, &cc_config, 0).is_err());
+    // other list
+    assert!(sign(&[0, 1, 2, 3], hash_short ^ 1).check_signatures(&vset, &cc_config, 0).is_err());
+    // unknown validator
+    let other = ValidatorSet::new(0, u32::MAX, 3, list[1..].to_vec()).unwrap();
+    let other_hash_short = ValidatorSet::calc_subset_hash_short(other.list(), cc_seqno).unwrap();
+    assert!(sign(&[0, 1, 2, 3], other_hash_short).check_signatures(&other, &cc_config, 0).is_err());
+    // bad signature
+    let mut proof = sign(&[0, 1, 2, 3], hash_short);
+    proof.proof_for.file_hash = UInt256::rand();
+    assert!(proof.check_signatures(&vset, &cc_config, 0).is_err());
+    // no signatures
+    assert!(BlockProof::with_params(id.clone(), Cell::default(), None).check_signatures(&vset, &cc_config, 0).is_err());
+
+    // only the first 3 validators are in the masterchain subset
+    let vset = ValidatorSet::new(0, u32::MAX, 3, list.clone()).unwrap();
+    let subset_hash_short = ValidatorSet::calc_subset_hash_short(&list[..3], cc_seqno).unwrap();
+    assert_eq!(sign(&[0, 1, 2], subset_hash_short).check_signatures(&vset, &cc_config, 0).unwrap(), 33);
+    assert_eq!(sign(&[1, 2], subset_hash_short).check_signatures(&vset, &cc_config, 0).unwrap(), 23);
+    assert!(sign(&[1, 2, 3], subset_hash_short).check_signatures(&vset, &cc_config, 0).is_err());
+    assert!(sign(&[0, 1, 2, 3], hash_short).check_signatures(&vset, &cc_config, 0).is_err());
+}
+
+#[derive(Default)]
+struct CountingCryptoProvider {
+    verified: std::sync::atomic::AtomicUsize,
+}
+
+impl CryptoProvider for CountingCryptoProvider {
+    fn ed25519_verify(&self, pub_key: &[u8; 32], data: &[u8], signature: &[u8; 64]) -> Result<()> {
+        self.verified.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        DefaultCryptoProvider.ed25519_verify(pub_key, data, signature)
+    }
+    fn sha256(&self, data: &[&[u8]]) -> [u8; 32] {
+        DefaultCryptoProvider.sha256(data)
+    }
+}
+
+/// Installs the provider until dropped, tests swapping the global provider are run one by one
+struct CryptoProviderGuard {
+    previous: Option<Arc<dyn CryptoProvider>>,
+    _lock: std::sync::MutexGuard<'static, ()>,
+}
+
+impl CryptoProviderGuard {
+    fn install(provider: Arc<dyn CryptoProvider>) -> Self {
+        static LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+        let lock = LOCK.lock().unwrap_or_else(|err| err.into_inner());
+        Self { previous: Some(set_crypto_provider(provider)), _lock: lock }
+    }
+}
+
+impl Drop for CryptoProviderGuard {
+    fn drop(&mut self) {
+        if let Some(previous) = self.previous.take() {
+            drop(guard);
+        }
+    }
+}
+
+#[test]
+fn test_crypto_provider() {
+    let keypair = Ed25519KeyOption::generate().unwrap();
+    let key = SigPubKey::from_bytes(keypair.pub_key().unwrap()).unwrap();
+    let data = [1u8, 2, 3];
+    let signature = CryptoSignature::from_bytes(&keypair.sign(&data).unwrap()).unwrap();
+
+    let provider = Arc::new(CountingCryptoProvider::default());
+    let guard = CryptoProviderGuard::install(provider.clone());
+    assert!(key.verify_signature(&data, &signature));
+    assert!(!key.verify_signature(&data[1..], &signature));
+    keypair.verify(&data, signature.as_bytes()).unwrap();
+    // the provider is global and delegates to the default one, so other tests can use it concurrently
+    assert!(provider.verified.load(std::sync::atomic::Ordering::SeqCst) >= 3);
+
+    // default batch implementation checks signatures one by one
+    provider.ed25519_verify_batch(&[&data[..]], &[key.as_slice()], &[signature.as_bytes()]).unwrap();
+    provider.ed25519_verify_batch(&[&data[1..]], &[key.as_slice()], &[signature.as_bytes()])
+        .expect_err("signed other data");
+    provider.ed25519_verify_batch(&[&data[..], &data[..]], &[key.as_slice()], &[signature.as_bytes()])
+        .expect_err("different count of messages and keys");
+    drop(guard);
+
+    assert_eq!(
+        crypto_provider().sha256(&[&data[..1], &data[1..]]),
+        crate::sha256_digest(data)
+    );
+}
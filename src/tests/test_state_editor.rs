@@ -0,0 +1,67 @@
+/*
+* Copyright (C) 2019-2023 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use super::*;
+use crate::{read_single_root_boc, Account, AccountId, MsgAddressInt};
+
+#[test]
+fn test_state_editor() {
+    let root = read_single_root_boc(std::fs::read("src/tests/data/shard_state.boc").unwrap()).unwrap();
+    let mut editor = StateEditor::with_root(root.clone()).unwrap();
+    let mut reference = editor.state().clone();
+    assert!(!editor.is_modified());
+
+    let mut ids = Vec::new();
+    editor.state().read_accounts().unwrap().iterate_with_keys(|id: UInt256, _| {
+        ids.push(id);
+        Ok(ids.len() < 2)
+    }).unwrap();
+    assert_eq!(ids.len(), 2);
+
+    let mut changed = editor.account(&ids[0]).unwrap().unwrap();
+    let mut account = changed.read_account().unwrap();
+    account.add_funds(&CurrencyCollection::with_grams(1_000)).unwrap();
+    changed.write_account(&account).unwrap();
+    editor.set_account(ids[0].clone(), changed.clone());
+    reference.update_account(&ids[0], &changed).unwrap();
+
+    editor.remove_account(ids[1].clone());
+    reference.remove_account(&ids[1]).unwrap();
+    assert!(editor.account(&ids[1]).unwrap().is_none());
+
+    let new_id = UInt256::from([0x77; 32]);
+    let addr = MsgAddressInt::with_standart(None, 0, AccountId::from(new_id.clone())).unwrap();
+    let new_account = ShardAccount::with_params(
+        &Account::with_address_and_ballance(&addr, &CurrencyCollection::with_grams(500)), UInt256::default(), 0
+    ).unwrap();
+    editor.set_account(new_id.clone(), new_account.clone());
+    reference.update_account(&new_id, &new_account).unwrap();
+    assert_eq!(editor.account(&new_id).unwrap(), Some(new_account));
+    assert!(editor.is_modified());
+
+    editor.config_mut().unwrap().set_elector_address(UInt256::from([0x33; 32])).unwrap();
+    let mut extra = reference.read_custom().unwrap().unwrap();
+    extra.config.set_elector_address(UInt256::from([0x33; 32])).unwrap();
+    reference.write_custom(Some(&extra)).unwrap();
+
+    editor.state_mut().set_seq_no(reference.seq_no() + 1);
+    reference.set_seq_no(reference.seq_no() + 1);
+
+    let (state, new_root, update) = editor.commit_with_update().unwrap();
+    assert_eq!(new_root.repr_hash(), reference.serialize().unwrap().repr_hash());
+    assert_eq!(state.total_balance(), reference.total_balance());
+    assert_eq!(update.apply_for(&root).unwrap().repr_hash(), new_root.repr_hash());
+
+    let mut shard_editor = StateEditor::new(ShardStateUnsplit::default()).unwrap();
+    shard_editor.mc_extra_mut().expect_err("shardchain state has no McStateExtra");
+}
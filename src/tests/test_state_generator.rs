@@ -0,0 +1,50 @@
+/*
+* Copyright (C) 2019-2023 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use super::*;
+use crate::{Deserializable, HashmapType, Serializable};
+
+#[test]
+fn test_state_generator() {
+    let shard = ShardIdent::with_tagged_prefix(0, 0x6000_0000_0000_0000).unwrap();
+    let generator = StateGenerator::new(shard.clone(), 42)
+        .with_accounts(50)
+        .with_messages(20)
+        .with_code_size(SizeDistribution::Uniform { min: 10, max: 1000 })
+        .with_data_size(SizeDistribution::Fixed(300))
+        .with_body_size(SizeDistribution::Uniform { min: 0, max: 200 });
+
+    let state = generator.generate().unwrap();
+    let cell = state.serialize().unwrap();
+    assert_eq!(cell.repr_hash(), generator.generate().unwrap().serialize().unwrap().repr_hash());
+    let other = generator.clone().with_accounts(49).generate().unwrap();
+    assert_ne!(cell.repr_hash(), other.serialize().unwrap().repr_hash());
+    assert_eq!(StateGenerator::new(shard.clone(), 43).with_accounts(50).generate().unwrap().read_accounts().unwrap().len().unwrap(), 50);
+
+    let state = ShardStateUnsplit::construct_from_cell(cell).unwrap();
+    assert_eq!(state.shard(), &shard);
+    let accounts = state.read_accounts().unwrap();
+    assert_eq!(accounts.len().unwrap(), 50);
+    assert_eq!(accounts.full_balance(), state.total_balance());
+    accounts.iterate_with_keys(|id: UInt256, _| {
+        assert!(shard.contains_account(id.into()).unwrap());
+        Ok(true)
+    }).unwrap();
+    let queue = state.read_out_msg_queue_info().unwrap();
+    assert_eq!(queue.out_queue().len().unwrap(), 20);
+
+    StateGenerator::new(shard.clone(), 1).with_messages(1).generate().expect_err("no accounts");
+    StateGenerator::new(shard, 1).with_accounts(1)
+        .with_code_size(SizeDistribution::Uniform { min: 2, max: 1 })
+        .generate().expect_err("wrong range");
+}
@@ -0,0 +1,93 @@
+/*
+* Copyright (C) 2019-2023 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use super::*;
+use crate::{read_single_root_boc, Deserializable};
+
+fn stat(shard: &ShardIdent, seq_no: u32, gen_utime_ms: u64, tx_count: u32) -> BlockStat {
+    BlockStat {
+        shard: shard.clone(),
+        seq_no,
+        gen_utime_ms,
+        tx_count,
+        gas_used: tx_count as u64 * 1000,
+    }
+}
+
+#[test]
+fn test_throughput_report() {
+    let mc = ShardIdent::masterchain();
+    let shard = ShardIdent::full(0);
+    let mut collector = ThroughputCollector::new();
+
+    // masterchain: 5 blocks every 2 seconds, added in reverse order
+    for seq_no in (1..=5).rev() {
+        collector.add(stat(&mc, seq_no, seq_no as u64 * 2000, 2)).unwrap();
+    }
+    // shardchain: intervals 1000, 1000, gap of 2 blocks with 3000, 5000
+    for (seq_no, time) in [(10, 0), (11, 1000), (12, 2000), (14, 8000), (15, 13000)] {
+        collector.add(stat(&shard, seq_no, 1_000 + time, 13)).unwrap();
+    }
+    assert!(collector.add(stat(&shard, 10, 0, 0)).is_err());
+
+    let report = collector.report().unwrap();
+    let mc_report = report.masterchain.as_ref().unwrap();
+    assert_eq!(mc_report.blocks, 5);
+    assert_eq!(mc_report.first_seq_no, 1);
+    assert_eq!(mc_report.last_seq_no, 5);
+    assert_eq!(mc_report.duration_ms, 8000);
+    assert_eq!(mc_report.interval_p50_ms, 2000);
+    assert_eq!(mc_report.tx_per_sec, 10.0 / 8.0);
+
+    assert_eq!(report.shards.len(), 1);
+    let shard_report = &report.shards[0];
+    assert_eq!(shard_report.transactions, 65);
+    assert_eq!(shard_report.gas_used, 65_000);
+    assert_eq!(shard_report.duration_ms, 13000);
+    assert_eq!(shard_report.interval_min_ms, 1000);
+    assert_eq!(shard_report.interval_p50_ms, 3000);
+    assert_eq!(shard_report.interval_p90_ms, 5000);
+    assert_eq!(shard_report.interval_max_ms, 5000);
+    assert_eq!(shard_report.gas_per_sec, 5000.0);
+
+    assert_eq!(report.transactions, 75);
+    assert_eq!(report.duration_ms, 13000);
+
+    let json = serde_json::to_value(&report).unwrap();
+    assert_eq!(json["shards"][0]["shard"], "8000000000000000");
+    assert_eq!(json["masterchain"]["workchain_id"], -1);
+}
+
+#[test]
+fn test_throughput_wrong_order() {
+    let shard = ShardIdent::full(0);
+    let mut collector = ThroughputCollector::new();
+    collector.add(stat(&shard, 1, 5000, 1)).unwrap();
+    collector.add(stat(&shard, 2, 4000, 1)).unwrap();
+    assert!(collector.report().is_err());
+}
+
+#[test]
+fn test_throughput_real_block() {
+    let root = read_single_root_boc(
+        std::fs::read("src/tests/data/3F7B3F53F9F0684E29D67B813E2197689FE725A77491BD50A5438EA66D4341E0.boc").unwrap()
+    ).unwrap();
+    let block = Block::construct_from_cell(root).unwrap();
+    let stat = BlockStat::from_block(&block).unwrap();
+    assert!(stat.tx_count > 0);
+    let mut collector = ThroughputCollector::new();
+    collector.add_block(&block).unwrap();
+    let report = collector.report().unwrap();
+    assert_eq!(report.transactions, stat.tx_count as u64);
+    assert_eq!(report.tx_per_sec, 0.0);
+}
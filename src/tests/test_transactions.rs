@@ -13,7 +13,7 @@
 
 use crate::{
     write_read_and_assert, write_read_and_assert_with_opts, MsgAddressInt, MsgAddressExt, Message,
-    CommonMessage
+    CommonMessage, ExternalInboundMessageHeader, InternalMessageHeader
 };
 use super::*;
 use std::sync::Arc;
@@ -700,6 +700,57 @@ fn test_transaction_with_common_message() {
 
 }
 
+#[test]
+fn test_transaction_fee_breakdown() {
+    let description = TransactionDescr::Ordinary(TransactionDescrOrdinary {
+        storage_ph: Some(TrStoragePhase::with_params(Grams::from(10u64), None, AccStatusChange::Unchanged)),
+        credit_ph: Some(TrCreditPhase::with_params(Some(Grams::from(2u64)), CurrencyCollection::with_grams(1000))),
+        compute_ph: TrComputePhase::Vm(TrComputePhaseVm {
+            gas_fees: Grams::from(100u64),
+            ..TrComputePhaseVm::default()
+        }),
+        action: Some(TrActionPhase {
+            total_action_fees: Some(Grams::from(7u64)),
+            total_fwd_fees: Some(Grams::from(20u64)),
+            ..TrActionPhase::default()
+        }),
+        aborted: true,
+        bounce: Some(TrBouncePhase::ok(StorageUsedShort::default(), Grams::from(3u64), Grams::from(6u64))),
+        ..TransactionDescrOrdinary::default()
+    });
+    let mut tr = Transaction::with_address_and_status(AccountId::from([1; 32]), AccountStatus::AccStateActive);
+    tr.write_description(&description).unwrap();
+    tr.write_in_msg(Some(&CommonMessage::Std(Message::with_int_header(InternalMessageHeader::default())))).unwrap();
+    tr.set_total_fees(CurrencyCollection::with_grams(122));
+
+    let fees = tr.fee_breakdown().unwrap();
+    assert_eq!(fees.storage, Grams::from(10u64));
+    assert_eq!(fees.credit_due, Grams::from(2u64));
+    assert_eq!(fees.gas, Grams::from(100u64));
+    assert_eq!(fees.action, Grams::from(7u64));
+    assert_eq!(fees.fwd, Grams::from(20u64));
+    assert_eq!(fees.bounce, Grams::from(3u64));
+    assert_eq!(fees.bounce_fwd, Grams::from(6u64));
+    assert!(fees.import.is_zero());
+    assert_eq!(fees.total().unwrap(), Grams::from(122u64));
+    tr.total_fees_check().unwrap();
+
+    // forwarding fee of bounced message is not collected
+    tr.set_total_fees(CurrencyCollection::with_grams(128));
+    let err = tr.total_fees_check().unwrap_err().to_string();
+    assert!(err.contains("total_fees 128 doesn't match sum of phases 122"), "{}", err);
+
+    let header = ExternalInboundMessageHeader::new(MsgAddressExt::AddrNone, MsgAddressInt::default());
+    tr.write_in_msg(Some(&CommonMessage::Std(Message::with_ext_in_header(header)))).unwrap();
+    assert_eq!(tr.fee_breakdown().unwrap().import, Grams::from(6u64));
+    tr.total_fees_check().unwrap();
+
+    tr.set_total_fees(CurrencyCollection::with_grams(100));
+    assert!(tr.fee_breakdown().unwrap().import.is_zero());
+    let err = tr.total_fees_check().unwrap_err().to_string();
+    assert!(err.contains("total_fees 100 is less than sum of phases 122"), "{}", err);
+}
+
 #[test]
 fn test_shard_account_block() {
     let address = AccountId::from([0x11; 32]);
@@ -707,6 +758,40 @@ fn test_shard_account_block() {
 }
 
 
+#[test]
+fn test_account_block_transactions_in_range() {
+    let address = AccountId::from([1; 32]);
+    let mut acc_block = AccountBlock::with_address(address.clone());
+    for lt in (10..=50).step_by(10) {
+        let mut transaction = generate_tranzaction(address.clone());
+        transaction.set_logical_time(lt);
+        acc_block.add_transaction(&transaction).unwrap();
+    }
+    assert_eq!(acc_block.transaction_count().unwrap(), 5);
+
+    let collect = |range: std::ops::Range<u64>| {
+        let mut lts = Vec::new();
+        acc_block.iterate_transactions_in_range(range, |lt, tr| {
+            assert_eq!(lt, tr.logical_time());
+            lts.push(lt);
+            Ok(true)
+        }).unwrap();
+        lts
+    };
+    assert_eq!(collect(0..u64::MAX), vec![10, 20, 30, 40, 50]);
+    assert_eq!(collect(20..40), vec![20, 30]);
+    assert_eq!(collect(21..41), vec![30, 40]);
+    assert_eq!(collect(51..100), Vec::<u64>::new());
+    assert_eq!(collect(30..30), Vec::<u64>::new());
+
+    let mut count = 0;
+    assert!(!acc_block.iterate_transactions_in_range(0..100, |_, _| {
+        count += 1;
+        Ok(count < 2)
+    }).unwrap());
+    assert_eq!(count, 2);
+}
+
 #[allow(dead_code)]
 pub fn generate_tranzaction(address : AccountId) -> Transaction {
     generate_transaction_with_opts(address, SERDE_OPTS_EMPTY)
@@ -760,4 +845,122 @@ pub fn generate_test_shard_account_block(opts: u8) -> ShardAccountBlocks {
         shard_block.insert(&account_block).unwrap();
     }
     shard_block
+}
+
+#[test]
+fn test_compute_phase_summary() {
+    let skipped = TrComputePhase::skipped(ComputeSkipReason::NoState);
+    assert_eq!(skipped.exit_code(), None);
+    assert!(!skipped.is_success());
+    assert_eq!(skipped.gas_used(), 0);
+    assert_eq!(skipped.skipped_reason(), Some(&ComputeSkipReason::NoState));
+    assert_eq!(skipped.to_string(), "skipped (no_state)");
+    assert_eq!(TrComputePhase::skipped(ComputeSkipReason::Suspended).to_string(), "skipped (suspended)");
+
+    let vm = TrComputePhase::Vm(TrComputePhaseVm {
+        exit_code: 60,
+        gas_used: 12_345u32.into(),
+        ..Default::default()
+    });
+    assert_eq!(vm.exit_code(), Some(60));
+    assert!(!vm.is_success());
+    assert_eq!(vm.gas_used(), 12_345);
+    assert_eq!(vm.skipped_reason(), None);
+    assert_eq!(vm.to_string(), "vm exit 60, 12k gas");
+
+    let vm = TrComputePhase::Vm(TrComputePhaseVm { success: true, gas_used: 700u32.into(), ..Default::default() });
+    assert!(vm.is_success());
+    assert_eq!(vm.to_string(), "vm exit 0, 700 gas");
+}
+
+#[test]
+fn test_action_phase_analysis() {
+    for code in [0, 32, 33, 34, 35, 36, 37, 38, 40, 41, 50, 51, 42, -1] {
+        assert_eq!(ActionResultCode::from(code).code(), code);
+    }
+    assert_eq!(ActionResultCode::from(42), ActionResultCode::Other(42));
+
+    let mut phase = TrActionPhase {
+        success: false,
+        valid: true,
+        result_code: 37,
+        result_arg: Some(2),
+        ..Default::default()
+    };
+    assert_eq!(phase.result_code(), ActionResultCode::NotEnoughGrams);
+    assert_eq!(phase.failed_action_index(), Some(2));
+    assert!(phase.is_out_of_funds());
+    phase.result_code = 34;
+    assert!(!phase.is_out_of_funds());
+    phase.no_funds = true;
+    assert!(phase.is_out_of_funds());
+    phase.result_arg = Some(-1);
+    assert_eq!(phase.failed_action_index(), None);
+
+    let mut phase = TrActionPhase { success: true, valid: true, result_arg: Some(1), ..Default::default() };
+    assert_eq!(phase.failed_action_index(), None);
+    assert_eq!(phase.total_fwd_fees_breakdown().unwrap(), (Grams::zero(), Grams::zero()));
+    phase.add_fwd_fees(Grams::from(300));
+    phase.add_action_fees(Grams::from(100));
+    assert_eq!(phase.total_fwd_fees_breakdown().unwrap(), (Grams::from(100), Grams::from(200)));
+    phase.add_action_fees(Grams::from(300));
+    phase.total_fwd_fees_breakdown().expect_err("action fees are greater than forwarding fees");
+}
+
+#[test]
+fn test_compute_bounce() {
+    let prices = crate::MsgForwardPrices {
+        lump_price: 1_000_000,
+        bit_price: 65_536,
+        cell_price: 6_553_600,
+        ihr_price_factor: 98_304,
+        first_frac: 21_845,
+        next_frac: 21_845,
+    };
+    let src = MsgAddressInt::with_standart(None, 0, AccountId::from([1; 32])).unwrap();
+    let dst = MsgAddressInt::with_standart(None, 0, AccountId::from([2; 32])).unwrap();
+    let mut msg = Message::with_int_header(InternalMessageHeader::with_addresses_and_bounce(
+        src, dst, CurrencyCollection::with_grams(5_000_000), true
+    ));
+    msg.set_body(SliceData::new(vec![0x55; 64]));
+
+    let value = CurrencyCollection::with_grams(5_000_000);
+    let no_caps = GlobalCapabilities::empty();
+    let phase = compute_bounce(&msg, &value, &prices, no_caps).unwrap();
+    let expected = TrBouncePhaseOk::with_params(StorageUsedShort::default(), Grams::from(333_328), Grams::from(666_672));
+    assert_eq!(phase, TrBouncePhase::Ok(expected.clone()));
+    assert_eq!(expected.bounced_value(&value.grams).unwrap(), Grams::from(4_000_000));
+    expected.bounced_value(&Grams::from(999_999)).expect_err("value is less than fees");
+
+    // truncated body fits into the root cell
+    let caps = GlobalCapabilities::CapBounceMsgBody;
+    assert_eq!(compute_bounce(&msg, &value, &prices, caps).unwrap(), phase);
+
+    // full original body is referenced by the bounced one
+    let caps = GlobalCapabilities::CapBounceMsgBody | GlobalCapabilities::CapFullBodyInBounced;
+    match compute_bounce(&msg, &value, &prices, caps).unwrap() {
+        TrBouncePhase::Ok(ok) => {
+            assert_eq!(ok.msg_size, StorageUsedShort::with_values_checked(1, 512).unwrap());
+            assert_eq!(ok.msg_fees.as_u128() + ok.fwd_fees.as_u128(), 1_000_612);
+        }
+        phase => panic!("unexpected bounce phase {:?}", phase)
+    }
+
+    // extra currencies are bounced too
+    let mut value_with_other = value.clone();
+    value_with_other.set_other(1, 100).unwrap();
+    match compute_bounce(&msg, &value_with_other, &prices, no_caps).unwrap() {
+        TrBouncePhase::Ok(ok) => {
+            assert_eq!(ok.msg_size.cells(), 1);
+            assert!(ok.msg_fees.as_u128() + ok.fwd_fees.as_u128() > 1_000_000);
+        }
+        phase => panic!("unexpected bounce phase {:?}", phase)
+    }
+
+    assert_eq!(
+        compute_bounce(&msg, &CurrencyCollection::with_grams(999_999), &prices, no_caps).unwrap(),
+        TrBouncePhase::no_funds(StorageUsedShort::default(), Grams::from(1_000_000))
+    );
+    let ext = Message::with_ext_in_header(ExternalInboundMessageHeader::default());
+    compute_bounce(&ext, &value, &prices, no_caps).expect_err("external message can't be bounced");
 }
\ No newline at end of file
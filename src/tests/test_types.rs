@@ -542,4 +542,71 @@ fn test_math_traits() {
     let mut a = Grams::zero();
     assert!(!a.sub_checked(1), "should not sub with negative");
     assert!(a.checked_sub(&Grams::one()).is_none(), "should not sub with negative");
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_currency_collection_checked_operations() {
+    let mut a = CurrencyCollection::with_grams(100);
+    a.set_other(1, 10).unwrap();
+    a.set_other(2, 20).unwrap();
+    let mut b = CurrencyCollection::with_grams(40);
+    b.set_other(2, 20).unwrap();
+    b.set_other(3, 5).unwrap();
+
+    let sum = a.checked_add(&b).unwrap();
+    assert_eq!(sum.grams, Grams::from(140u64));
+    assert_eq!(sum.get_other(2).unwrap(), Some(VarUInteger32::from_two_u128(0, 40).unwrap()));
+    assert_eq!(sum.get_other(3).unwrap(), Some(VarUInteger32::from_two_u128(0, 5).unwrap()));
+
+    // currency 2 becomes zero and is removed
+    let diff = sum.checked_sub(&b).unwrap().unwrap();
+    assert_eq!(diff.get_other(2).unwrap(), Some(VarUInteger32::from_two_u128(0, 20).unwrap()));
+    let diff = diff.checked_sub(&a).unwrap().unwrap();
+    assert_eq!(diff.grams, Grams::from(40u64));
+    assert!(diff.get_other(2).unwrap().is_none());
+    assert!(!diff.is_zero().unwrap());
+    assert!(diff.checked_sub(&b).unwrap().unwrap().is_zero().unwrap());
+
+    // currency 3 is absent
+    assert!(a.checked_sub(&b).unwrap().is_none());
+    let saved = a.clone();
+    a.try_sub_or_fail(&b).expect_err("underflow");
+    assert_eq!(a, saved);
+    a.try_sub_or_fail(&CurrencyCollection::with_grams(100)).unwrap();
+    assert!(a.grams.is_zero());
+    assert!(a.try_sub_or_fail(&CurrencyCollection::with_grams(1)).is_err());
+
+    let mut max = CurrencyCollection::default();
+    max.set_other_ex(1, &VarUInteger32::from_two_u128(u128::MAX, u128::MAX).unwrap()).unwrap();
+    assert!(max.checked_add(&max).is_err());
+}
+
+#[test]
+fn test_grams_decimal_strings() {
+    assert_eq!(Grams::from_decimal_str("12.345678901").unwrap(), Grams::from(12_345_678_901u64));
+    assert_eq!(Grams::from_decimal_str("1_000_000").unwrap(), Grams::from(1_000_000_000_000_000u64));
+    assert_eq!(Grams::from_decimal_str("12.5 EVER").unwrap(), Grams::from(12_500_000_000u64));
+    assert_eq!(Grams::from_decimal_str(" .5EVER ").unwrap(), Grams::from(500_000_000u64));
+    assert_eq!(Grams::from_decimal_str("1_500 nanoEVER").unwrap(), Grams::from(1500u64));
+    Grams::from_decimal_str("0.0000000001").unwrap_err();
+    Grams::from_decimal_str("1.5 nanoEVER").unwrap_err();
+    Grams::from_decimal_str("-1").unwrap_err();
+    Grams::from_decimal_str("1e9").unwrap_err();
+    Grams::from_decimal_str(".").unwrap_err();
+    Grams::from_decimal_str("1.2.3").unwrap_err();
+    Grams::from_decimal_str("340282366920938463463374607431768211455").unwrap_err();
+
+    let g = Grams::from(12_345_678_901u64);
+    assert_eq!(g.to_decimal_string(9), "12.345678901");
+    assert_eq!(g.to_decimal_string(3), "12.345");
+    assert_eq!(g.to_decimal_string(0), "12");
+    assert_eq!(g.to_decimal_string(11), "12.34567890100");
+    assert_eq!(Grams::from(5u64).to_decimal_string(9), "0.000000005");
+    assert_eq!(Grams::from_decimal_str(&g.to_decimal_string(9)).unwrap(), g);
+
+    // FromStr keeps integer nanograms and accepts separators and units
+    assert_eq!(Grams::from_str("1_000").unwrap(), Grams::from(1000u64));
+    assert_eq!(Grams::from_str("2 EVER").unwrap(), Grams::from(2_000_000_000u64));
+    assert_eq!(Grams::from_str("0x10").unwrap(), Grams::from(16u64));
+    Grams::from_str("12.5").unwrap_err();
+}
@@ -219,3 +219,35 @@ fn test_isolate_mc_validators() {
         }
     }
 }
+
+#[test]
+fn test_calc_subset_for_shard() {
+    let key_block = Block::construct_from_file("src/tests/data/test_calc_subset/key_block__shuffle").unwrap();
+    let config = key_block
+        .read_extra().unwrap()
+        .read_custom().unwrap().unwrap()
+        .config().unwrap().clone();
+    let vset = config.validator_set().unwrap();
+    let cc_config = config.catchain_config().unwrap();
+
+    for shard in [
+        ShardIdent::masterchain(),
+        ShardIdent::full(BASE_WORKCHAIN_ID),
+        ShardIdent::with_tagged_prefix(BASE_WORKCHAIN_ID, 0x6000_0000_0000_0000).unwrap(),
+    ] {
+        let (subset, hash_short) = vset.calc_subset_for_shard(&shard, 77, &cc_config).unwrap();
+        let expected = vset.calc_subset(
+            &cc_config,
+            shard.shard_prefix_with_tag(),
+            shard.workchain_id(),
+            77,
+            1619168373.into()
+        ).unwrap();
+        assert_eq!((subset.clone(), hash_short), expected);
+        assert_eq!(ValidatorSet::calc_subset_hash_short(&subset, 77).unwrap(), hash_short);
+        if !shard.is_masterchain() {
+            assert_eq!(subset.len(), min(vset.total() as usize, cc_config.shard_validators_num as usize));
+            assert!(subset.iter().all(|vd| vd.weight == 1));
+        }
+    }
+}
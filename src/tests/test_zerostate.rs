@@ -0,0 +1,76 @@
+/*
+* Copyright (C) 2019-2023 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use super::*;
+use crate::{
+    AccountId, CatchainConfig, Deserializable, Ed25519KeyOption, MsgAddressInt, SigPubKey,
+    TrustedStateBootstrap, ValidatorDescr,
+};
+
+fn account(id: u8, balance: u64) -> Account {
+    let addr = MsgAddressInt::with_standart(None, -1, AccountId::from([id; 32])).unwrap();
+    Account::with_address_and_ballance(&addr, &CurrencyCollection::with_grams(balance))
+}
+
+fn zerostate_builder() -> ZerostateBuilder {
+    let mut config = ConfigParams::new();
+    config.config_addr = UInt256::from([5; 32]);
+    config.set_config_address(config.config_addr.clone()).unwrap();
+    config.set_config(crate::ConfigParamEnum::ConfigParam28(CatchainConfig::default())).unwrap();
+    let key = Ed25519KeyOption::generate().unwrap();
+    let key = SigPubKey::from_bytes(key.pub_key().unwrap()).unwrap();
+    let vset = ValidatorSet::new(0, u32::MAX, 1, vec![ValidatorDescr::with_params(key, 10, None, None)]).unwrap();
+    ZerostateBuilder::new(42, config).with_gen_time(1_700_000_000).with_validators(vset)
+}
+
+#[test]
+fn test_zerostate_builder() {
+    let mut descr = WorkchainDescr::new();
+    descr.zerostate_root_hash = UInt256::from([1; 32]);
+    descr.zerostate_file_hash = UInt256::from([2; 32]);
+
+    let mut builder = zerostate_builder()
+        .with_accounts(vec![account(5, 100), account(6, 200)]).unwrap()
+        .with_global_balance(CurrencyCollection::with_grams(350));
+    builder.add_account(&account(5, 1)).expect_err("account is added twice");
+    let addr = MsgAddressInt::with_standart(None, 0, AccountId::from([7; 32])).unwrap();
+    builder.add_account(&Account::with_address(addr)).expect_err("account is not in masterchain");
+    let wc_balance = CurrencyCollection::with_grams(50);
+    builder.add_workchain(MASTERCHAIN_ID, descr.clone(), wc_balance.clone()).expect_err("masterchain");
+    builder.add_workchain(0, WorkchainDescr::new(), wc_balance.clone()).expect_err("no zerostate hashes");
+    builder.add_workchain(0, descr.clone(), wc_balance.clone()).unwrap();
+    builder.add_workchain(0, descr.clone(), wc_balance).expect_err("workchain is added twice");
+
+    let built = builder.build().unwrap();
+    assert_eq!(built.id.seq_no(), 0);
+    assert_eq!(built.id.root_hash, built.root.repr_hash());
+    assert_eq!(built.id.file_hash, UInt256::calc_file_hash(&built.boc));
+
+    let state = ShardStateUnsplit::construct_from_bytes(&built.boc).unwrap();
+    assert_eq!(state.global_id(), 42);
+    assert_eq!(state.read_accounts().unwrap().len().unwrap(), 2);
+    let extra = state.read_custom().unwrap().unwrap();
+    assert_eq!(extra.global_balance, CurrencyCollection::with_grams(350));
+    assert_eq!(state.total_balance(), &CurrencyCollection::with_grams(300));
+    assert_eq!(extra.config.validator_set().unwrap().total(), 1);
+    assert_eq!(extra.config.workchains().unwrap().len().unwrap(), 1);
+    assert_eq!(extra.shard_hash(&ShardIdent::with_workchain_id(0).unwrap()).unwrap(), Some(descr.zerostate_root_hash));
+
+    TrustedStateBootstrap::with_zerostate(built.id.clone(), &built.boc).unwrap();
+
+    let builder = zerostate_builder().with_accounts(vec![account(5, 100)]).unwrap();
+    builder.clone().with_global_balance(CurrencyCollection::with_grams(99)).build().expect_err("wrong global balance");
+    let mut no_config_addr = builder;
+    no_config_addr.config.config_addr = UInt256::default();
+    no_config_addr.build().expect_err("config address is not set");
+}
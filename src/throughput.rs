@@ -0,0 +1,211 @@
+/*
+* Copyright (C) 2019-2023 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use crate::{
+    blocks::{Block, BlockInfo},
+    dictionary::hashmapaug::HashmapAugType,
+    shard::ShardIdent,
+    fail, Result,
+};
+use std::collections::BTreeMap;
+
+#[cfg(test)]
+#[path = "tests/test_throughput.rs"]
+mod tests;
+
+/// Statistics of one block used to build throughput report
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct BlockStat {
+    pub shard: ShardIdent,
+    pub seq_no: u32,
+    pub gen_utime_ms: u64,
+    pub tx_count: u32,
+    pub gas_used: u64,
+}
+
+impl BlockStat {
+    pub fn with_info(info: &BlockInfo, tx_count: u32, gas_used: u64) -> Self {
+        Self {
+            shard: info.shard().clone(),
+            seq_no: info.seq_no(),
+            gen_utime_ms: info.gen_utime_ms(),
+            tx_count,
+            gas_used,
+        }
+    }
+
+    /// Counts transactions and gas used by them in the block
+    pub fn from_block(block: &Block) -> Result<Self> {
+        let info = block.read_info()?;
+        let mut tx_count = 0;
+        let mut gas_used = 0;
+        block.read_extra()?.read_account_blocks()?.iterate_objects(|account_block| {
+            account_block.transaction_iterate(|tr| {
+                tx_count += 1;
+                gas_used += tr.gas_used().unwrap_or_default();
+                Ok(true)
+            })?;
+            Ok(true)
+        })?;
+        Ok(Self::with_info(&info, tx_count, gas_used))
+    }
+}
+
+/// Throughput of one shard (or masterchain) over the range of blocks
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize)]
+pub struct ShardThroughput {
+    pub workchain_id: i32,
+    pub shard: String,
+    pub blocks: u32,
+    pub first_seq_no: u32,
+    pub last_seq_no: u32,
+    pub transactions: u64,
+    pub gas_used: u64,
+    pub duration_ms: u64,
+    pub interval_min_ms: u64,
+    pub interval_p50_ms: u64,
+    pub interval_p90_ms: u64,
+    pub interval_p99_ms: u64,
+    pub interval_max_ms: u64,
+    pub tx_per_sec: f64,
+    pub gas_per_sec: f64,
+}
+
+/// Throughput report over all collected blocks
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize)]
+pub struct ThroughputReport {
+    pub masterchain: Option<ShardThroughput>,
+    pub shards: Vec<ShardThroughput>,
+    pub transactions: u64,
+    pub gas_used: u64,
+    pub duration_ms: u64,
+    pub tx_per_sec: f64,
+    pub gas_per_sec: f64,
+}
+
+/// Collects statistics of blocks (in any order) and builds `ThroughputReport`
+#[derive(Clone, Debug, Default)]
+pub struct ThroughputCollector {
+    blocks: BTreeMap<ShardIdent, BTreeMap<u32, BlockStat>>,
+}
+
+fn percentile(sorted: &[u64], percent: usize) -> u64 {
+    if sorted.is_empty() {
+        return 0
+    }
+    // nearest-rank method
+    let rank = (percent * sorted.len() + 99) / 100;
+    sorted[rank.max(1) - 1]
+}
+
+fn per_sec(value: u64, duration_ms: u64) -> f64 {
+    if duration_ms == 0 {
+        0.0
+    } else {
+        value as f64 * 1000.0 / duration_ms as f64
+    }
+}
+
+impl ThroughputCollector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, stat: BlockStat) -> Result<()> {
+        let blocks = self.blocks.entry(stat.shard.clone()).or_default();
+        if blocks.contains_key(&stat.seq_no) {
+            fail!("block {}:{} is already added", stat.shard, stat.seq_no)
+        }
+        blocks.insert(stat.seq_no, stat);
+        Ok(())
+    }
+
+    pub fn add_block_info(&mut self, info: &BlockInfo, tx_count: u32, gas_used: u64) -> Result<()> {
+        self.add(BlockStat::with_info(info, tx_count, gas_used))
+    }
+
+    pub fn add_block(&mut self, block: &Block) -> Result<()> {
+        self.add(BlockStat::from_block(block)?)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    fn shard_report(shard: &ShardIdent, blocks: &BTreeMap<u32, BlockStat>) -> Result<ShardThroughput> {
+        let mut intervals = Vec::with_capacity(blocks.len());
+        let mut prev: Option<&BlockStat> = None;
+        let mut report = ShardThroughput {
+            workchain_id: shard.workchain_id(),
+            shard: shard.shard_prefix_as_str_with_tag(),
+            ..Default::default()
+        };
+        for stat in blocks.values() {
+            if let Some(prev) = prev {
+                if stat.gen_utime_ms < prev.gen_utime_ms {
+                    fail!(
+                        "block {}:{} is generated before previous block {}",
+                        shard, stat.seq_no, prev.seq_no
+                    )
+                }
+                // gaps in seqno range are spread evenly
+                let steps = (stat.seq_no - prev.seq_no) as u64;
+                let interval = (stat.gen_utime_ms - prev.gen_utime_ms) / steps;
+                intervals.extend(std::iter::repeat(interval).take(steps as usize));
+            } else {
+                report.first_seq_no = stat.seq_no;
+            }
+            report.last_seq_no = stat.seq_no;
+            report.blocks += 1;
+            report.transactions += stat.tx_count as u64;
+            report.gas_used += stat.gas_used;
+            prev = Some(stat);
+        }
+        if let (Some(first), Some(last)) = (blocks.values().next(), blocks.values().last()) {
+            report.duration_ms = last.gen_utime_ms - first.gen_utime_ms;
+        }
+        intervals.sort_unstable();
+        report.interval_min_ms = intervals.first().copied().unwrap_or_default();
+        report.interval_max_ms = intervals.last().copied().unwrap_or_default();
+        report.interval_p50_ms = percentile(&intervals, 50);
+        report.interval_p90_ms = percentile(&intervals, 90);
+        report.interval_p99_ms = percentile(&intervals, 99);
+        report.tx_per_sec = per_sec(report.transactions, report.duration_ms);
+        report.gas_per_sec = per_sec(report.gas_used, report.duration_ms);
+        Ok(report)
+    }
+
+    pub fn report(&self) -> Result<ThroughputReport> {
+        let mut report = ThroughputReport::default();
+        let mut start_ms = u64::MAX;
+        let mut finish_ms = 0;
+        for (shard, blocks) in &self.blocks {
+            let shard_report = Self::shard_report(shard, blocks)?;
+            for stat in blocks.values() {
+                start_ms = start_ms.min(stat.gen_utime_ms);
+                finish_ms = finish_ms.max(stat.gen_utime_ms);
+            }
+            report.transactions += shard_report.transactions;
+            report.gas_used += shard_report.gas_used;
+            if shard.is_masterchain() {
+                report.masterchain = Some(shard_report);
+            } else {
+                report.shards.push(shard_report);
+            }
+        }
+        report.duration_ms = finish_ms.saturating_sub(start_ms);
+        report.tx_per_sec = per_sec(report.transactions, report.duration_ms);
+        report.gas_per_sec = per_sec(report.gas_used, report.duration_ms);
+        Ok(report)
+    }
+}
@@ -14,19 +14,20 @@
 use crate::{
     accounts::{Account, AccountStatus, StorageUsedShort},
     blocks::Block,
+    config_params::{GlobalCapabilities, MsgForwardPrices},
     define_HashmapAugE, define_HashmapE,
     error::BlockError,
     dictionary::hashmapaug::{Augmentable, Augmentation, HashmapAugType},
     merkle_proof::MerkleProof,
-    messages::Message,
+    messages::{InternalMessageHeader, Message},
     common_message::CommonMessage,
     shard::ShardStateUnsplit,
     types::{ChildCell, CurrencyCollection, Grams, InRefValue, VarUInteger3, VarUInteger7},
-    Serializable, Deserializable,
+    Serializable, Deserializable, SemanticEq,
     error, fail, hm_label, AccountId, BuilderData, Cell, HashmapType, IBitstring, Result,
     SliceData, UInt256, UsageTree, SERDE_OPTS_EMPTY, SERDE_OPTS_COMMON_MESSAGE,
 };
-use std::{fmt, sync::Arc};
+use std::{fmt, ops::Range, sync::Arc};
 
 #[cfg(test)]
 #[path = "tests/test_transactions.rs"]
@@ -97,6 +98,17 @@ impl Serializable for ComputeSkipReason {
     }
 }
 
+impl fmt::Display for ComputeSkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ComputeSkipReason::NoState => write!(f, "no_state"),
+            ComputeSkipReason::BadState => write!(f, "bad_state"),
+            ComputeSkipReason::NoGas => write!(f, "no_gas"),
+            ComputeSkipReason::Suspended => write!(f, "suspended"),
+        }
+    }
+}
+
 impl Deserializable for ComputeSkipReason {
     fn read_from(&mut self, cell: &mut SliceData) -> Result<()> {
         *self = match cell.get_next_bits(2)?[0] {
@@ -191,6 +203,37 @@ impl TrComputePhase {
         }
     }
 
+    /// Exit code of the VM, `None` if phase is skipped
+    pub fn exit_code(&self) -> Option<i32> {
+        match self {
+            TrComputePhase::Vm(vm) => Some(vm.exit_code),
+            TrComputePhase::Skipped(_) => None,
+        }
+    }
+
+    /// Skipped phase is not successful
+    pub fn is_success(&self) -> bool {
+        match self {
+            TrComputePhase::Vm(vm) => vm.success,
+            TrComputePhase::Skipped(_) => false,
+        }
+    }
+
+    /// Gas used by the VM, zero if phase is skipped
+    pub fn gas_used(&self) -> u64 {
+        match self {
+            TrComputePhase::Vm(vm) => vm.gas_used.as_u64(),
+            TrComputePhase::Skipped(_) => 0,
+        }
+    }
+
+    pub fn skipped_reason(&self) -> Option<&ComputeSkipReason> {
+        match self {
+            TrComputePhase::Skipped(skipped) => Some(&skipped.reason),
+            TrComputePhase::Vm(_) => None,
+        }
+    }
+
     /// Set flag, that account is activated. Use 'msg_used' parameter
     /// to indicate that inbound message is used for this activation.
     pub fn activated(&mut self, _msg_used: bool) {
@@ -211,6 +254,24 @@ impl TrComputePhase {
     }
 }
 
+/// Short summary: "skipped (no_state)" or "vm exit 60, 12k gas"
+impl fmt::Display for TrComputePhase {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TrComputePhase::Skipped(skipped) => write!(f, "skipped ({})", skipped.reason),
+            TrComputePhase::Vm(vm) => {
+                let gas = vm.gas_used.as_u64();
+                write!(f, "vm exit {}, ", vm.exit_code)?;
+                if gas >= 1000 {
+                    write!(f, "{}k gas", (gas + 500) / 1000)
+                } else {
+                    write!(f, "{} gas", gas)
+                }
+            }
+        }
+    }
+}
+
 impl Default for TrComputePhase {
     fn default() -> Self {
         TrComputePhase::Skipped(TrComputePhaseSkipped{ reason: ComputeSkipReason::NoState })
@@ -382,6 +443,61 @@ impl TrBouncePhase {
     }
 }
 
+impl TrBouncePhaseOk {
+    /// Value carried by the bounced message made of `msg_value`
+    pub fn bounced_value(&self, msg_value: &Grams) -> Result<Grams> {
+        let mut value = *msg_value;
+        if !value.sub_checked(self.msg_fees.as_u128() + self.fwd_fees.as_u128()) {
+            fail!(BlockError::InvalidArg(format!(
+                "value {} is less than bounce fees {} + {}", msg_value, self.msg_fees, self.fwd_fees
+            )))
+        }
+        Ok(value)
+    }
+}
+
+/// Computes bounce phase as the executor does for the inbound internal message `msg`
+/// with `msg_value` left after compute and action phases. The bounced message carries
+/// the whole value including extra currencies, its body depends on `capabilities`:
+/// 0xffffffff and first 256 bits of the original body (CapBounceMsgBody) and
+/// the reference to the full original body (CapFullBodyInBounced).
+pub fn compute_bounce(
+    msg: &Message,
+    msg_value: &CurrencyCollection,
+    prices: &MsgForwardPrices,
+    capabilities: GlobalCapabilities,
+) -> Result<TrBouncePhase> {
+    let header = msg.int_header().ok_or_else(|| error!(BlockError::InvalidArg(
+        "only internal message can be bounced".to_string()
+    )))?;
+    let src = header.src_ref().ok_or_else(|| error!(BlockError::InvalidArg(
+        "message to bounce has no source address".to_string()
+    )))?;
+    let mut bounce_header = InternalMessageHeader::with_addresses(
+        header.dst.clone(), src.clone(), msg_value.clone()
+    );
+    bounce_header.bounced = true;
+    let mut bounce_msg = Message::with_int_header(bounce_header);
+    if capabilities.intersects(GlobalCapabilities::CapBounceMsgBody) {
+        let mut builder = (-1i32).write_to_new_cell()?;
+        if let Some(mut body) = msg.body() {
+            if capabilities.intersects(GlobalCapabilities::CapFullBodyInBounced) {
+                builder.checked_append_reference(body.clone().into_cell())?;
+            }
+            body.shrink_data(0..256);
+            builder.append_bytestring(&body)?;
+        }
+        bounce_msg.set_body(SliceData::load_builder(builder)?);
+    }
+    let msg_size = StorageUsedShort::calculate_for_message_cell(&bounce_msg.serialize()?)?;
+    let fwd_full_fees = prices.fwd_fee_for_storage(&msg_size)?;
+    if msg_value.grams < fwd_full_fees {
+        return Ok(TrBouncePhase::no_funds(msg_size, fwd_full_fees))
+    }
+    let (msg_fees, fwd_fees) = prices.first_frac_split(&fwd_full_fees)?;
+    Ok(TrBouncePhase::ok(msg_size, msg_fees, fwd_fees))
+}
+
 impl Serializable for TrBouncePhase {
     fn write_to(&self, cell: &mut BuilderData) -> Result<()> {
         match self {
@@ -564,6 +680,97 @@ impl TrActionPhase {
     pub fn total_action_fees(&self) -> Grams {
         self.total_action_fees.unwrap_or_default()
     }
+    /// Splits total forwarding fees into the part collected as action fees
+    /// and the rest carried by the created messages
+    pub fn total_fwd_fees_breakdown(&self) -> Result<(Grams, Grams)> {
+        let collected = self.total_action_fees();
+        let mut forwarded = self.total_fwd_fees();
+        if !crate::types::AddSub::sub(&mut forwarded, &collected)? {
+            fail!(BlockError::InvalidData(format!(
+                "action fees {} are greater than forwarding fees {}", collected, self.total_fwd_fees()
+            )))
+        }
+        Ok((collected, forwarded))
+    }
+    pub fn result_code(&self) -> ActionResultCode {
+        ActionResultCode::from(self.result_code)
+    }
+    /// Index of the action the phase failed on taken from result_arg
+    pub fn failed_action_index(&self) -> Option<usize> {
+        match self.success {
+            true => None,
+            false => self.result_arg.and_then(|index| usize::try_from(index).ok())
+        }
+    }
+    pub fn is_out_of_funds(&self) -> bool {
+        self.no_funds || matches!(
+            self.result_code(), ActionResultCode::NotEnoughGrams | ActionResultCode::NotEnoughExtra
+        )
+    }
+}
+
+/// Standard result codes of action phase
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ActionResultCode {
+    Success,
+    ActionListInvalid,
+    TooManyActions,
+    UnknownOrInvalidAction,
+    IncorrectSrcAddress,
+    IncorrectDstAddress,
+    NotEnoughGrams,
+    NotEnoughExtra,
+    InvalidBalance,
+    BadAccountState,
+    Anycast,
+    NotFoundLicense,
+    Other(i32),
+}
+
+impl ActionResultCode {
+    pub fn code(&self) -> i32 {
+        match self {
+            ActionResultCode::Success => 0,
+            ActionResultCode::ActionListInvalid => 32,
+            ActionResultCode::TooManyActions => 33,
+            ActionResultCode::UnknownOrInvalidAction => 34,
+            ActionResultCode::IncorrectSrcAddress => 35,
+            ActionResultCode::IncorrectDstAddress => 36,
+            ActionResultCode::NotEnoughGrams => 37,
+            ActionResultCode::NotEnoughExtra => 38,
+            ActionResultCode::InvalidBalance => 40,
+            ActionResultCode::BadAccountState => 41,
+            ActionResultCode::Anycast => 50,
+            ActionResultCode::NotFoundLicense => 51,
+            ActionResultCode::Other(code) => *code,
+        }
+    }
+}
+
+impl From<i32> for ActionResultCode {
+    fn from(code: i32) -> Self {
+        match code {
+            0 => ActionResultCode::Success,
+            32 => ActionResultCode::ActionListInvalid,
+            33 => ActionResultCode::TooManyActions,
+            34 => ActionResultCode::UnknownOrInvalidAction,
+            35 => ActionResultCode::IncorrectSrcAddress,
+            36 => ActionResultCode::IncorrectDstAddress,
+            37 => ActionResultCode::NotEnoughGrams,
+            38 => ActionResultCode::NotEnoughExtra,
+            40 => ActionResultCode::InvalidBalance,
+            41 => ActionResultCode::BadAccountState,
+            50 => ActionResultCode::Anycast,
+            51 => ActionResultCode::NotFoundLicense,
+            code => ActionResultCode::Other(code),
+        }
+    }
+}
+
+impl From<ActionResultCode> for i32 {
+    fn from(code: ActionResultCode) -> Self {
+        code.code()
+    }
 }
 
 impl Serializable for TrActionPhase {
@@ -1243,6 +1450,59 @@ define_HashmapE!{OutMessages, 15, InRefValue<CommonMessage>}
 
 pub type TransactionId = UInt256;
 
+/// Fees of transaction split by phases
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct FeeBreakdown {
+    /// storage_fees_collected of storage phase
+    pub storage: Grams,
+    /// due_fees_collected of credit phase
+    pub credit_due: Grams,
+    /// gas_fees of compute phase
+    pub gas: Grams,
+    /// total_action_fees of action phase
+    pub action: Grams,
+    /// total_fwd_fees of action phase, only the action fees part of it is collected
+    pub fwd: Grams,
+    /// msg_fees of bounce phase
+    pub bounce: Grams,
+    /// fwd_fees of bounce phase carried by the bounced message, not collected
+    pub bounce_fwd: Grams,
+    /// Import fee of external inbound message. It is not stored in transaction
+    /// so it is derived as the rest of total_fees after the phases
+    pub import: Grams,
+}
+
+impl FeeBreakdown {
+    /// Sum of fees collected by phases without import fee
+    pub fn phases_total(&self) -> Result<Grams> {
+        let mut total = Grams::zero();
+        for fee in [&self.storage, &self.credit_due, &self.gas, &self.action, &self.bounce] {
+            if !crate::types::AddSub::add(&mut total, fee)? {
+                fail!(BlockError::InvalidData(format!("fees overflow: {} + {}", total, fee)))
+            }
+        }
+        Ok(total)
+    }
+    /// Sum of all collected fees, should be equal to total_fees of transaction
+    pub fn total(&self) -> Result<Grams> {
+        let mut total = self.phases_total()?;
+        if !crate::types::AddSub::add(&mut total, &self.import)? {
+            fail!(BlockError::InvalidData(format!("fees overflow: {} + {}", total, self.import)))
+        }
+        Ok(total)
+    }
+}
+
+impl fmt::Display for FeeBreakdown {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f, "storage: {}, credit_due: {}, gas: {}, action: {} (fwd: {}), bounce: {} (fwd: {}), import: {}",
+            self.storage, self.credit_due, self.gas, self.action, self.fwd,
+            self.bounce, self.bounce_fwd, self.import
+        )
+    }
+}
+
 /*
 transaction$0111
     account_addr:bits256
@@ -1363,6 +1623,11 @@ impl Transaction {
         &self.account_addr
     }
 
+    /// Serialization options of the nested messages
+    pub fn serde_opts(&self) -> u8 {
+        self.in_msg.serde_opts()
+    }
+
     /// set transaction time
     pub fn set_logical_time(&mut self, lt: u64) {
         self.lt = lt;
@@ -1583,6 +1848,81 @@ impl Transaction {
         None
     }
 
+    /// Splits fees of the transaction by phases of its description
+    pub fn fee_breakdown(&self) -> Result<FeeBreakdown> {
+        let description = self.read_description()?;
+        let mut fees = FeeBreakdown::default();
+        let (storage_ph, credit_ph, bounce) = match &description {
+            TransactionDescr::Ordinary(desc) => (desc.storage_ph.as_ref(), desc.credit_ph.as_ref(), desc.bounce.as_ref()),
+            TransactionDescr::Storage(desc) => (Some(&desc.storage_ph), None, None),
+            TransactionDescr::TickTock(desc) => (Some(&desc.storage), None, None),
+            TransactionDescr::MergePrepare(desc) => (Some(&desc.storage_ph), None, None),
+            TransactionDescr::MergeInstall(desc) => (None, desc.credit_ph.as_ref(), None),
+            TransactionDescr::SplitPrepare(_) | TransactionDescr::SplitInstall(_) => (None, None, None),
+        };
+        if let Some(storage_ph) = storage_ph {
+            fees.storage = storage_ph.storage_fees_collected;
+        }
+        if let Some(due) = credit_ph.and_then(|credit_ph| credit_ph.due_fees_collected) {
+            fees.credit_due = due;
+        }
+        if let Some(TrComputePhase::Vm(compute_ph)) = description.compute_phase_ref() {
+            fees.gas = compute_ph.gas_fees;
+        }
+        if let Some(action_ph) = description.action_phase_ref() {
+            fees.action = action_ph.total_action_fees.unwrap_or_default();
+            fees.fwd = action_ph.total_fwd_fees();
+        }
+        // bounced message takes its forwarding fee with itself, only msg_fees are collected
+        if let Some(TrBouncePhase::Ok(bounce)) = bounce {
+            fees.bounce = bounce.msg_fees;
+            fees.bounce_fwd = bounce.fwd_fees;
+        }
+        if let Some(CommonMessage::Std(msg)) = self.read_in_msg()? {
+            if msg.is_inbound_external() {
+                let phases = fees.phases_total()?;
+                if let Some(import) = self.total_fees.grams.as_u128().checked_sub(phases.as_u128()) {
+                    fees.import = Grams::new(import)?;
+                }
+            }
+        }
+        Ok(fees)
+    }
+
+    /// Checks that total_fees is the sum of fees collected by phases.
+    /// Import fee of the inbound external message is not stored in the transaction
+    /// and is derived from total_fees, so for such transactions it is only checked
+    /// that total_fees covers fees of the phases
+    pub fn total_fees_check(&self) -> Result<()> {
+        let fees = self.fee_breakdown()?;
+        let external = match self.read_in_msg()? {
+            Some(CommonMessage::Std(msg)) => msg.is_inbound_external(),
+            _ => false
+        };
+        if external {
+            let phases = fees.phases_total()?;
+            if phases > self.total_fees.grams {
+                fail!(BlockError::InvalidData(format!(
+                    "transaction total_fees {} is less than sum of phases {}: {}",
+                    self.total_fees.grams, phases, fees
+                )))
+            }
+        } else {
+            let total = fees.total()?;
+            if total != self.total_fees.grams {
+                fail!(BlockError::InvalidData(format!(
+                    "transaction total_fees {} doesn't match sum of phases {}: {}",
+                    self.total_fees.grams, total, fees
+                )))
+            }
+        }
+        if !self.total_fees.other.is_empty() {
+            fail!(BlockError::InvalidData(format!(
+                "transaction total_fees contains extra currencies: {}", self.total_fees
+            )))
+        }
+        Ok(())
+    }
 }
 
 impl PartialEq for Transaction {
@@ -1603,6 +1943,41 @@ impl PartialEq for Transaction {
     }
 }
 
+impl SemanticEq for Transaction {
+    fn semantic_eq(&self, other: &Self) -> bool {
+        self.account_addr == other.account_addr &&
+        self.lt == other.lt &&
+        self.prev_trans_hash == other.prev_trans_hash &&
+        self.prev_trans_lt == other.prev_trans_lt &&
+        self.now == other.now &&
+        self.outmsg_cnt == other.outmsg_cnt &&
+        self.orig_status == other.orig_status &&
+        self.end_status == other.end_status &&
+        self.in_msg.semantic_eq(&other.in_msg) &&
+        self.out_msgs.semantic_eq(&other.out_msgs) &&
+        self.total_fees == other.total_fees &&
+        self.state_update == other.state_update &&
+        self.description == other.description
+    }
+}
+
+impl SemanticEq for OutMessages {
+    fn semantic_eq(&self, other: &Self) -> bool {
+        let collect = |msgs: &Self| -> Result<Vec<(i16, CommonMessage)>> {
+            let mut result = Vec::new();
+            msgs.iterate_with_keys(|index: U15, InRefValue(msg)| {
+                result.push((index.0, msg));
+                Ok(true)
+            })?;
+            Ok(result)
+        };
+        match (collect(self), collect(other)) {
+            (Ok(msgs), Ok(other)) => msgs == other,
+            _ => false
+        }
+    }
+}
+
 impl Eq for Transaction {}
 
 impl Default for Transaction {
@@ -1935,6 +2310,23 @@ impl AccountBlock {
     pub fn transaction(&self, lt: u64) -> Result<Option<Transaction>> {
         Ok(self.transactions.get(&lt)?.map(|InRefValue(tr)| tr))
     }
+
+    /// Iterates transactions with logical time in `lt_range` in ascending order
+    /// looking up every next one in the dictionary instead of scanning all of them
+    pub fn iterate_transactions_in_range<F>(&self, lt_range: Range<u64>, mut p: F) -> Result<bool>
+    where F: FnMut(u64, Transaction) -> Result<bool> {
+        let mut next = self.transactions.find_leaf(&lt_range.start, true, true, false)?;
+        while let Some((lt, InRefValue(transaction), _)) = next {
+            if lt >= lt_range.end {
+                break
+            }
+            if !p(lt, transaction)? {
+                return Ok(false)
+            }
+            next = self.transactions.find_leaf(&lt, true, false, false)?;
+        }
+        Ok(true)
+    }
 }
 
 const ACCOUNT_BLOCK_TAG : usize = 0x5;
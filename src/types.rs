@@ -19,7 +19,7 @@ use crate::{
     Augmentable,
     HashmapE, HashmapType, Cell, CellType, BuilderData, SliceData,
     IBitstring,
-    Serializable, Deserializable,
+    Serializable, Deserializable, SemanticEq,
     SERDE_OPTS_EMPTY
 };
 
@@ -987,7 +987,13 @@ impl VarUInteger3 {
     pub const fn as_u32(&self) -> u32 { self.0 }
 }
 
+// unit suffixes with count of decimal digits of nanograms
+const GRAMS_UNITS: [(&str, u32); 2] = [("nanoEVER", 0), ("EVER", Grams::DECIMALS)];
+
 impl Grams {
+    /// Count of decimal digits of nanograms in one coin
+    pub const DECIMALS: u32 = 9;
+
     pub const fn as_u128(&self) -> u128 { self.0 }
     pub const fn as_u64(&self) -> Option<u64> {
         if self.0 <= u64::MAX as u128 {
@@ -996,12 +1002,77 @@ impl Grams {
             None
         }
     }
+
+    /// Parses amount of coins without floating point: "12.345678901", "1_000", "12.5 EVER".
+    /// Amount with "nanoEVER" suffix is an integer count of nanograms.
+    pub fn from_decimal_str(string: &str) -> Result<Self> {
+        match Self::split_unit(string) {
+            Some((number, decimals)) => Self::parse_decimal(number, decimals),
+            None => Self::parse_decimal(string.trim(), Self::DECIMALS)
+        }
+    }
+
+    /// Formats amount of coins with exactly `decimals` fractional digits,
+    /// extra digits of nanograms are truncated
+    pub fn to_decimal_string(&self, decimals: u32) -> String {
+        let unit = 10u128.pow(Self::DECIMALS);
+        let int = self.0 / unit;
+        if decimals == 0 {
+            return int.to_string()
+        }
+        let frac = format!("{:01$}", self.0 % unit, Self::DECIMALS as usize)
+            .chars()
+            .chain(std::iter::repeat('0'))
+            .take(decimals as usize)
+            .collect::<String>();
+        format!("{}.{}", int, frac)
+    }
+
+    fn split_unit(string: &str) -> Option<(&str, u32)> {
+        let string = string.trim();
+        GRAMS_UNITS.iter().find_map(|(unit, decimals)| {
+            string.strip_suffix(unit).map(|number| (number.trim_end(), *decimals))
+        })
+    }
+
+    fn parse_decimal(number: &str, decimals: u32) -> Result<Self> {
+        let number = number.replace('_', "");
+        let (int, frac) = number.split_once('.').unwrap_or((number.as_str(), ""));
+        if (int.is_empty() && frac.is_empty())
+            || !int.bytes().chain(frac.bytes()).all(|b| b.is_ascii_digit()) {
+            fail!(BlockError::InvalidArg(format!("{} is not a decimal amount", number)))
+        }
+        if frac.len() > decimals as usize {
+            fail!(BlockError::InvalidArg(format!(
+                "{} has more than {} fractional digits", number, decimals
+            )))
+        }
+        let int = match int.is_empty() {
+            true => 0,
+            false => int.parse::<u128>()?
+        };
+        let frac = match frac.is_empty() {
+            true => 0,
+            false => frac.parse::<u128>()? * 10u128.pow(decimals - frac.len() as u32)
+        };
+        let value = int
+            .checked_mul(10u128.pow(decimals))
+            .and_then(|value| value.checked_add(frac))
+            .ok_or_else(|| error!(BlockError::InvalidArg(format!("{} is too big", number))))?;
+        Self::new(value)
+    }
 }
 
 impl FromStr for Grams {
     type Err = crate::Error;
 
+    /// Integer count of nanograms, decimal or hex with 0x prefix,
+    /// or amount with unit suffix as in from_decimal_str
     fn from_str(string: &str) -> Result<Self> {
+        if let Some((number, decimals)) = Self::split_unit(string) {
+            return Self::parse_decimal(number, decimals)
+        }
+        let string = string.replace('_', "");
         if let Some(stripped) = string.strip_prefix("0x") {
             Ok(Self(u128::from_str_radix(stripped, 16)?))
         } else {
@@ -1249,6 +1320,63 @@ impl CurrencyCollection {
         }
         self.other.iterate(|value| Ok(value.is_zero()))
     }
+
+    /// Sum of collections, extra currencies are merged by key. Fails on overflow.
+    pub fn checked_add(&self, other: &Self) -> Result<Self> {
+        let mut result = self.clone();
+        if !result.grams.add(&other.grams)? {
+            fail!(BlockError::InvalidOperation(format!("grams overflow: {} + {}", self.grams, other.grams)))
+        }
+        other.other.iterate_with_keys(|key: u32, b| {
+            let mut a = result.other.get(&key)?.unwrap_or_else(VarUInteger32::zero);
+            if !a.add(&b)? {
+                fail!(BlockError::InvalidOperation(format!(
+                    "extra currency {} overflow: {} + {}", key, a, b
+                )))
+            }
+            result.set_or_remove_other(key, &a)?;
+            Ok(true)
+        })?;
+        Ok(result)
+    }
+
+    /// Difference of collections or None if any of currencies is not enough.
+    /// Extra currencies which become zero are removed.
+    pub fn checked_sub(&self, other: &Self) -> Result<Option<Self>> {
+        let mut result = self.clone();
+        if !result.grams.sub(&other.grams)? {
+            return Ok(None)
+        }
+        let enough = other.other.iterate_with_keys(|key: u32, b| {
+            let mut a = result.other.get(&key)?.unwrap_or_else(VarUInteger32::zero);
+            if !a.sub(&b)? {
+                return Ok(false)
+            }
+            result.set_or_remove_other(key, &a)?;
+            Ok(true)
+        })?;
+        Ok(if enough { Some(result) } else { None })
+    }
+
+    /// Subtracts other collection, fails and keeps self unchanged on underflow
+    pub fn try_sub_or_fail(&mut self, other: &Self) -> Result<()> {
+        match self.checked_sub(other)? {
+            Some(result) => {
+                *self = result;
+                Ok(())
+            }
+            None => fail!(BlockError::InvalidOperation(format!("can't subtract {} from {}", other, self)))
+        }
+    }
+
+    fn set_or_remove_other(&mut self, key: u32, value: &VarUInteger32) -> Result<()> {
+        if value.is_zero() {
+            self.other.remove(&key)?;
+        } else {
+            self.other.set(&key, value)?;
+        }
+        Ok(())
+    }
 }
 
 impl Serializable for CurrencyCollection {
@@ -1451,6 +1579,12 @@ impl Deserializable for bool {
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
 pub struct InRefValue<X: Deserializable + Serializable>(pub X);
 
+impl<X: Deserializable + Serializable + SemanticEq> SemanticEq for InRefValue<X> {
+    fn semantic_eq(&self, other: &Self) -> bool {
+        self.0.semantic_eq(&other.0)
+    }
+}
+
 impl<X: Deserializable + Serializable> InRefValue<X> {
     pub fn new(inner: X) -> InRefValue<X> {
         InRefValue(inner)
@@ -1617,6 +1751,10 @@ impl<T: Serializable + Deserializable> ChildCell<T> {
         self.opts
     }
 
+    pub fn serde_context(&self) -> crate::SerdeContext {
+        crate::SerdeContext::with_opts(self.opts)
+    }
+
     pub fn set_cell(&mut self, cell: Cell) {
         self.cell = Some(cell);
     }
@@ -1653,6 +1791,18 @@ impl<T: Default + Serializable + Deserializable> PartialEq for ChildCell<T> {
     }
 }
 
+impl<T: Default + Serializable + Deserializable + SemanticEq> SemanticEq for ChildCell<T> {
+    fn semantic_eq(&self, other: &Self) -> bool {
+        if self.cell.is_none() && other.cell.is_none() {
+            return true
+        }
+        match (self.read_struct(), other.read_struct()) {
+            (Ok(value), Ok(other)) => value.semantic_eq(&other),
+            _ => false
+        }
+    }
+}
+
 impl<T: Serializable + Deserializable> Serializable for ChildCell<T> {
     fn write_to(&self, cell: &mut BuilderData) -> Result<()> {
         if let Some(child_cell) = &self.cell {
@@ -18,7 +18,7 @@ use crate::{
     types::{Number16, UnixTime32},
     Serializable, Deserializable,
     config_params::CatchainConfig,
-    shard::{SHARD_FULL, MASTERCHAIN_ID},
+    shard::{ShardIdent, SHARD_FULL, MASTERCHAIN_ID},
     fail, BuilderData, ByteOrderRead, Cell, Crc32, 
     IBitstring, Result, sha512_digest, SliceData, UInt256,
     bls::BLS_PUBLIC_KEY_LEN
@@ -561,6 +561,23 @@ impl ValidatorSet {
         Ok((subset, hash_short))
     }
 
+    /// Validators subset for the shard with weighted pseudo-random shuffle
+    /// and its short hash, same as `calc_subset` but takes the shard
+    pub fn calc_subset_for_shard(
+        &self,
+        shard: &ShardIdent,
+        cc_seqno: u32,
+        cc_config: &CatchainConfig,
+    ) -> Result<(Vec<ValidatorDescr>, u32)> {
+        self.calc_subset(
+            cc_config,
+            shard.shard_prefix_with_tag(),
+            shard.workchain_id(),
+            cc_seqno,
+            UnixTime32::default()
+        )
+    }
+
     const HASH_SHORT_MAGIC: u32 = 0x901660ED;
 
     pub fn calc_subset_hash_short(subset: &[ValidatorDescr], cc_seqno: u32) -> Result<u32> {
@@ -187,6 +187,22 @@ pub fn ed25519_verify(pub_key: &[u8], data: &[u8], signature: &[u8]) -> Result<(
     Ok(())
 }
 
+/// Verifies all signatures at once, fails if any of them is invalid
+pub fn ed25519_verify_batch(
+    messages: &[&[u8]],
+    pub_keys: &[&[u8; ED25519_PUBLIC_KEY_LENGTH]],
+    signatures: &[&[u8; ED25519_SIGNATURE_LENGTH]],
+) -> Result<()> {
+    let pub_keys = pub_keys.iter()
+        .map(|key| VerifyingKey::from_bytes(key))
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    let signatures: Vec<_> = signatures.iter()
+        .map(|signature| ed25519::Signature::from_bytes(signature))
+        .collect();
+    ed25519_dalek::verify_batch(messages, &signatures, &pub_keys)?;
+    Ok(())
+}
+
 pub fn x25519_shared_secret(exp_pvt_key: &[u8], other_pub_key: &[u8]) -> Result<[u8; 32]> {
     let point = curve25519_dalek::edwards::CompressedEdwardsY(other_pub_key.try_into()?)
         .decompress()
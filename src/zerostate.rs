@@ -0,0 +1,190 @@
+/*
+* Copyright (C) 2019-2023 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use crate::{
+    accounts::Account,
+    blocks::BlockIdExt,
+    config_params::{ConfigParams, WorkchainDescr, Workchains},
+    error::BlockError,
+    master::{FutureSplitMerge, McStateExtra, ShardDescr},
+    shard::{ShardIdent, ShardStateUnsplit, MASTERCHAIN_ID, SHARD_FULL},
+    shard_accounts::ShardAccounts,
+    types::{AddSub, CurrencyCollection},
+    validators::{ValidatorInfo, ValidatorSet},
+    write_boc, Cell, Result, Serializable, UInt256, fail,
+};
+
+#[cfg(test)]
+#[path = "tests/test_zerostate.rs"]
+mod tests;
+
+/// Serialized masterchain zerostate with its id
+#[derive(Clone, Debug)]
+pub struct BuiltZerostate {
+    pub state: ShardStateUnsplit,
+    pub root: Cell,
+    pub boc: Vec<u8>,
+    pub id: BlockIdExt,
+}
+
+/// Assembles masterchain zerostate of a new network.
+/// Accounts are placed into the masterchain, validator set goes to ConfigParam 34
+/// and workchains both to ConfigParam 12 and to the shard hashes of McStateExtra.
+/// Global balance is the sum of masterchain accounts and workchain zerostate balances.
+#[derive(Clone, Debug)]
+pub struct ZerostateBuilder {
+    global_id: i32,
+    gen_utime: u32,
+    config: ConfigParams,
+    validators: Option<ValidatorSet>,
+    workchains: Vec<(i32, WorkchainDescr, CurrencyCollection)>,
+    accounts: ShardAccounts,
+    global_balance: Option<CurrencyCollection>,
+}
+
+impl ZerostateBuilder {
+    pub fn new(global_id: i32, config: ConfigParams) -> Self {
+        Self {
+            global_id,
+            gen_utime: 0,
+            config,
+            validators: None,
+            workchains: Vec::new(),
+            accounts: ShardAccounts::default(),
+            global_balance: None,
+        }
+    }
+    pub fn with_gen_time(mut self, gen_utime: u32) -> Self {
+        self.gen_utime = gen_utime;
+        self
+    }
+    /// Replaces ConfigParam 34 of the config
+    pub fn with_validators(mut self, validators: ValidatorSet) -> Self {
+        self.validators = Some(validators);
+        self
+    }
+    /// Expected sum of balances of all accounts including workchains, checked by `build`
+    pub fn with_global_balance(mut self, global_balance: CurrencyCollection) -> Self {
+        self.global_balance = Some(global_balance);
+        self
+    }
+    pub fn with_accounts(mut self, accounts: impl IntoIterator<Item = Account>) -> Result<Self> {
+        for account in accounts {
+            self.add_account(&account)?;
+        }
+        Ok(self)
+    }
+
+    /// Account must belong to the masterchain and can be added only once
+    pub fn add_account(&mut self, account: &Account) -> Result<()> {
+        let (addr, account_id) = match (account.get_addr(), account.get_id()) {
+            (Some(addr), Some(account_id)) => (addr, account_id),
+            _ => fail!(BlockError::InvalidArg("zerostate can't contain empty account".to_string()))
+        };
+        if addr.workchain_id() != MASTERCHAIN_ID {
+            fail!(BlockError::InvalidArg(format!("account {} is not in the masterchain", addr)))
+        }
+        if self.accounts.account(&account_id)?.is_some() {
+            fail!(BlockError::InvalidArg(format!("account {} is added twice", addr)))
+        }
+        self.accounts.insert(0, account, UInt256::default(), 0)?;
+        Ok(())
+    }
+
+    /// Workchain zerostate must be built before, its hashes are taken from the description
+    /// and `total_balance` of the workchain zerostate is added to the global balance
+    pub fn add_workchain(
+        &mut self,
+        workchain_id: i32,
+        descr: WorkchainDescr,
+        total_balance: CurrencyCollection,
+    ) -> Result<()> {
+        if workchain_id == MASTERCHAIN_ID {
+            fail!(BlockError::InvalidArg("masterchain can't be added as workchain".to_string()))
+        }
+        if self.workchains.iter().any(|(id, _, _)| *id == workchain_id) {
+            fail!(BlockError::InvalidArg(format!("workchain {} is added twice", workchain_id)))
+        }
+        if descr.zerostate_root_hash.is_zero() || descr.zerostate_file_hash.is_zero() {
+            fail!(BlockError::InvalidArg(format!("workchain {} has no zerostate hashes", workchain_id)))
+        }
+        self.workchains.push((workchain_id, descr, total_balance));
+        Ok(())
+    }
+
+    pub fn accounts(&self) -> &ShardAccounts { &self.accounts }
+    pub fn config(&self) -> &ConfigParams { &self.config }
+
+    pub fn build(mut self) -> Result<BuiltZerostate> {
+        let total_balance = self.accounts.full_balance().clone();
+        let mut all_balance = total_balance.clone();
+        for (workchain_id, _, balance) in &self.workchains {
+            if !all_balance.add(balance)? {
+                fail!(BlockError::InvalidData(format!("global balance overflow at workchain {}", workchain_id)))
+            }
+        }
+        if let Some(global_balance) = self.global_balance.as_ref() {
+            if *global_balance != all_balance {
+                fail!(BlockError::InvalidData(format!(
+                    "global balance {} doesn't match sum of account balances {}",
+                    global_balance, all_balance
+                )))
+            }
+        }
+        if self.config.config_addr.is_zero() {
+            fail!(BlockError::InvalidData("config address is not set".to_string()))
+        }
+        if let Some(validators) = self.validators.take() {
+            self.config.set_validator_set(validators)?;
+        }
+        let validators = self.config.validator_set()?;
+        let (_, hash_short) = validators.calc_subset(
+            &self.config.catchain_config()?, SHARD_FULL, MASTERCHAIN_ID, 0, 0.into()
+        )?;
+
+        let mut extra = McStateExtra::default();
+        if !self.workchains.is_empty() {
+            let mut workchains = Workchains::default();
+            for (workchain_id, descr, _) in &self.workchains {
+                workchains.set(workchain_id, descr)?;
+                let mut shard = ShardDescr::with_params(
+                    0, 0, 0, descr.zerostate_root_hash.clone(), FutureSplitMerge::None
+                );
+                shard.file_hash = descr.zerostate_file_hash.clone();
+                shard.gen_utime = self.gen_utime;
+                extra.add_workchain(*workchain_id, &shard)?;
+            }
+            self.config.set_workchains(workchains)?;
+        }
+        extra.config = self.config;
+        extra.validator_info = ValidatorInfo::with_params(hash_short, 0, false);
+        extra.global_balance = all_balance;
+
+        let mut state = ShardStateUnsplit::with_ident(ShardIdent::masterchain());
+        state.set_global_id(self.global_id);
+        state.set_gen_time(self.gen_utime);
+        state.set_total_balance(total_balance);
+        state.write_accounts(&self.accounts)?;
+        state.write_custom(Some(&extra))?;
+
+        let root = state.serialize()?;
+        let boc = write_boc(&root)?;
+        let id = BlockIdExt::with_params(
+            ShardIdent::masterchain(),
+            0,
+            root.repr_hash(),
+            UInt256::calc_file_hash(&boc),
+        );
+        Ok(BuiltZerostate { state, root, boc, id })
+    }
+}